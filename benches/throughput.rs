@@ -1,46 +1,9 @@
 use std::fmt::Write;
 use std::hint::black_box;
 
+use cor::bench::{generate_log_batch, generate_log_line};
 use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
 
-/// Generate a realistic JSON log line of approximately the given size.
-///
-/// Produces lines resembling real structured-logging output from frameworks
-/// like logrus, zap, slog, pino, etc.
-fn generate_log_line(variant: usize) -> String {
-    match variant % 6 {
-        0 => {
-            // logrus-style (~220 bytes)
-            r#"{"time":"2026-01-15T10:30:00.123Z","level":"info","msg":"request completed","method":"GET","path":"/api/v1/users","status":200,"latency_ms":42,"user_id":"usr_abc123","request_id":"req_xyz789"}"#.to_string()
-        }
-        1 => {
-            // zap-style with nested object (~300 bytes)
-            r#"{"ts":1768473000.123,"level":"debug","caller":"server/handler.go:42","msg":"processing request","http":{"method":"POST","url":"/api/v1/orders","status":201},"user":"john@example.com","duration":"15.2ms","trace_id":"abc123def456"}"#.to_string()
-        }
-        2 => {
-            // slog-style (~250 bytes)
-            r#"{"time":"2026-01-15T10:30:01.456Z","level":"WARN","msg":"high memory usage detected","source":"monitor","component":"health-checker","memory_mb":1842,"threshold_mb":1500,"hostname":"prod-web-03"}"#.to_string()
-        }
-        3 => {
-            // pino-style with numeric level (~280 bytes)
-            r#"{"level":30,"time":1768473000456,"pid":12345,"hostname":"api-server-01","msg":"database query executed","query":"SELECT * FROM users WHERE active = true","duration_ms":23,"rows_returned":150,"connection_pool":"primary"}"#.to_string()
-        }
-        4 => {
-            // bunyan-style (~320 bytes)
-            r#"{"v":0,"name":"myapp","hostname":"prod-01","pid":9876,"level":50,"msg":"connection pool exhausted","time":"2026-01-15T10:30:02.789Z","src":{"file":"db/pool.rs","line":142},"pool_size":20,"active_connections":20,"waiting_requests":15}"#.to_string()
-        }
-        _ => {
-            // structlog-style (~350 bytes)
-            r#"{"event":"payment processed","level":"info","timestamp":"2026-01-15T10:30:03.012Z","logger":"payments.processor","amount":99.99,"currency":"USD","customer_id":"cust_12345","payment_method":"card","transaction_id":"txn_abcdef123456","processing_time_ms":234}"#.to_string()
-        }
-    }
-}
-
-/// Generate a batch of log lines as a single string (newline-delimited).
-fn generate_log_batch(count: usize) -> Vec<String> {
-    (0..count).map(generate_log_line).collect()
-}
-
 fn bench_parse_and_format(c: &mut Criterion) {
     owo_colors::set_override(false);
     let config = cor::Config::default();
@@ -78,6 +41,14 @@ fn bench_parse_only(c: &mut Criterion) {
         });
     });
 
+    group.bench_function("parse_ref_1k_lines", |b| {
+        b.iter(|| {
+            for line in &lines {
+                let _ = cor::parse_record_ref(black_box(line), &config);
+            }
+        });
+    });
+
     group.finish();
 }
 
@@ -212,6 +183,35 @@ fn bench_embedded_json(c: &mut Criterion) {
     group.finish();
 }
 
+fn bench_field_heavy(c: &mut Criterion) {
+    let config = cor::Config::default();
+
+    // 40 extra fields per line, half of them nested one level.
+    let lines: Vec<String> = (0..1000)
+        .map(|i| {
+            let mut line = format!(r#"{{"level":"info","msg":"request {i}""#);
+            for f in 0..20 {
+                let _ = write!(line, r#","field_{f}":{i},"group_{f}":{{"k":"v{f}"}}"#);
+            }
+            line.push('}');
+            line
+        })
+        .collect();
+
+    let mut group = c.benchmark_group("field_heavy");
+    group.throughput(Throughput::Elements(lines.len() as u64));
+
+    group.bench_function("parse_40_fields_1k_lines", |b| {
+        b.iter(|| {
+            for line in &lines {
+                let _ = cor::parse_line(black_box(line), &config);
+            }
+        });
+    });
+
+    group.finish();
+}
+
 criterion_group!(
     benches,
     bench_parse_and_format,
@@ -220,5 +220,6 @@ criterion_group!(
     bench_line_sizes,
     bench_level_filtering,
     bench_embedded_json,
+    bench_field_heavy,
 );
 criterion_main!(benches);
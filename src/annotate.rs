@@ -0,0 +1,228 @@
+//! Annotation banners (`##cor:mark` control lines and `--mark-fifo`).
+//!
+//! Other tooling can inject a visible, timestamped banner into the output
+//! stream — e.g. a deploy script announcing `deploy v1.2.3` — either by
+//! writing a `##cor:mark <text>` line into the input or by writing text to
+//! the FIFO given with `--mark-fifo`. Control lines never reach the parser,
+//! and are only honored in local inputs: network clients can't inject them.
+
+use std::fmt::Write as _;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+use owo_colors::OwoColorize;
+use owo_colors::Stream::Stdout;
+
+use crate::config::Config;
+use crate::timestamp::Timestamp;
+
+/// Prefix of an annotation control line.
+pub const MARK_PREFIX: &str = "##cor:mark";
+
+/// Text of a `##cor:mark <text>` control line, or `None` for other lines.
+pub fn parse_mark(line: &str) -> Option<&str> {
+    let rest = line.strip_prefix(MARK_PREFIX)?;
+    if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    Some(rest.trim())
+}
+
+/// Render an annotation banner for `text`, received at `time`.
+///
+/// The time uses the configured timestamp format and timezone; it is left
+/// out with `--stable` so output stays byte-stable.
+pub fn format_banner(text: &str, time: jiff::Timestamp, config: &Config, out: &mut String) {
+    let text: String = text.chars().filter(|c| !c.is_control()).collect();
+    let rule = config.glyphs().rule;
+    let mut banner = String::from(rule);
+    if !config.stable {
+        let time = Timestamp {
+            value: time,
+            original: String::new(),
+        };
+        let time = time.format_localized(&config.timestamp_format, &config.timezone, config.locale);
+        let _ = write!(banner, " {time}");
+    }
+    if !text.is_empty() {
+        let _ = write!(banner, " {text}");
+    }
+    let _ = write!(banner, " {rule}");
+    let _ = write!(
+        out,
+        "{}",
+        banner.if_supports_color(Stdout, |t| t.bold().magenta().to_string())
+    );
+}
+
+/// Annotations arriving on a `--mark-fifo` FIFO.
+///
+/// A FIFO created by [`MarkFifo::spawn`] is removed again when this is
+/// dropped; one that already existed is left alone.
+#[derive(Debug)]
+pub struct MarkFifo {
+    rx: Receiver<String>,
+    created: Option<PathBuf>,
+}
+
+impl MarkFifo {
+    /// Read annotations written to the FIFO at `path` on a background
+    /// thread.
+    ///
+    /// The FIFO is created when missing; an existing path that is not a
+    /// FIFO is an `InvalidInput` error. Writers may come and go: the FIFO
+    /// is reopened after each writer closes it. Lines may carry the
+    /// `##cor:mark` prefix or be plain text.
+    pub fn spawn(path: &Path) -> io::Result<Self> {
+        let created = match fs::metadata(path) {
+            Ok(metadata) if is_fifo(&metadata) => None,
+            Ok(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "exists and is not a FIFO",
+                ));
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                make_fifo(path)?;
+                Some(PathBuf::from(path))
+            }
+            Err(e) => return Err(e),
+        };
+        let (tx, rx) = mpsc::channel();
+        let path = PathBuf::from(path);
+        thread::spawn(move || {
+            // Opening blocks until a writer appears.
+            while let Ok(file) = File::open(&path) {
+                for line in BufReader::new(file).lines() {
+                    let Ok(line) = line else { break };
+                    let text = parse_mark(&line).unwrap_or(&line).trim().to_string();
+                    if tx.send(text).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+        Ok(Self { rx, created })
+    }
+
+    /// Annotations received so far, without waiting.
+    pub fn try_iter(&self) -> mpsc::TryIter<'_, String> {
+        self.rx.try_iter()
+    }
+}
+
+impl Drop for MarkFifo {
+    fn drop(&mut self) {
+        if let Some(ref path) = self.created {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+#[cfg(unix)]
+fn is_fifo(metadata: &fs::Metadata) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+
+    metadata.file_type().is_fifo()
+}
+
+#[cfg(not(unix))]
+const fn is_fifo(_metadata: &fs::Metadata) -> bool {
+    false
+}
+
+#[cfg(unix)]
+fn make_fifo(path: &Path) -> io::Result<()> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = CString::new(path.as_os_str().as_bytes())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    // SAFETY: `c_path` is a valid NUL-terminated string for the duration of the call.
+    if unsafe { libc::mkfifo(c_path.as_ptr(), 0o600) } == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+#[cfg(not(unix))]
+fn make_fifo(_path: &Path) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "FIFOs are only supported on Unix",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_mark() {
+        assert_eq!(
+            parse_mark("##cor:mark deploy v1.2.3"),
+            Some("deploy v1.2.3")
+        );
+        assert_eq!(parse_mark("##cor:mark"), Some(""));
+        assert_eq!(parse_mark("##cor:markdown"), None);
+        assert_eq!(parse_mark("  ##cor:mark indented"), None);
+        assert_eq!(parse_mark("{\"msg\":\"##cor:mark\"}"), None);
+    }
+
+    #[test]
+    fn test_banner_includes_time_unless_stable() {
+        owo_colors::set_override(false);
+        let time: jiff::Timestamp = "2026-01-15T10:30:00Z".parse().unwrap();
+        let mut config = Config {
+            timestamp_format: "%H:%M:%S".to_string(),
+            ..Config::default()
+        };
+        let mut out = String::new();
+        format_banner("deploy\x1b v1", time, &config, &mut out);
+        assert_eq!(out, "──── 10:30:00 deploy v1 ────");
+
+        config.apply_stable();
+        out.clear();
+        format_banner("deploy", time, &config, &mut out);
+        assert_eq!(out, "──── deploy ────");
+
+        config.ascii = true;
+        out.clear();
+        format_banner("deploy", time, &config, &mut out);
+        assert_eq!(out, "---- deploy ----");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_fifo_annotations() {
+        use std::io::Write;
+        use std::time::Duration;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("marks");
+        let fifo = MarkFifo::spawn(&path).unwrap();
+        let mut writer = std::fs::OpenOptions::new().write(true).open(&path).unwrap();
+        writeln!(writer, "##cor:mark deploy v2").unwrap();
+        writeln!(writer, "rollback").unwrap();
+        let timeout = Duration::from_secs(5);
+        assert_eq!(fifo.rx.recv_timeout(timeout).unwrap(), "deploy v2");
+        assert_eq!(fifo.rx.recv_timeout(timeout).unwrap(), "rollback");
+        drop(fifo);
+        assert!(!path.exists(), "the FIFO cor created is removed");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_fifo_refuses_regular_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("marks");
+        fs::write(&path, "keep").unwrap();
+        let err = MarkFifo::spawn(&path).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "keep");
+    }
+}
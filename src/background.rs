@@ -0,0 +1,201 @@
+//! Terminal background color detection.
+//!
+//! The background is queried with `OSC 11`, which most modern terminals
+//! answer with `rgb:RRRR/GGGG/BBBB`. The query is followed by a primary
+//! device attributes request (`CSI c`) that every terminal answers, so a
+//! terminal ignoring `OSC 11` ends the wait early instead of running into
+//! the timeout. The exchange goes through `/dev/tty`, leaving stdin and
+//! stdout to the log stream.
+//!
+//! Terminals that don't answer may still describe their colors in
+//! `COLORFGBG` (`fg;bg`, set by rxvt, Konsole, and others), which is used
+//! as a fallback. A reply arriving after the timeout is read and dropped
+//! before the terminal mode is restored, so it never shows up as input.
+
+use std::time::Duration;
+
+use crate::theme::{Rgb, ansi_rgb};
+
+/// How long to wait for the terminal to answer.
+pub const QUERY_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// How long to keep reading, after the timeout, for a reply that is late;
+/// it is discarded rather than left to show up on the terminal.
+const DRAIN_TIMEOUT: Duration = Duration::from_millis(50);
+
+/// Background color of the terminal: queried, or else from `COLORFGBG`.
+pub fn detect(timeout: Duration) -> Option<Rgb> {
+    query(timeout).or_else(from_colorfgbg)
+}
+
+/// Background color described by `COLORFGBG`, without querying.
+pub fn from_colorfgbg() -> Option<Rgb> {
+    parse_colorfgbg(&std::env::var("COLORFGBG").ok()?)
+}
+
+/// Ask the controlling terminal for its background color.
+#[cfg(unix)]
+pub fn query(timeout: Duration) -> Option<Rgb> {
+    use std::fs::OpenOptions;
+    use std::os::fd::AsRawFd;
+
+    let tty = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/tty")
+        .ok()?;
+    let fd = tty.as_raw_fd();
+    // SAFETY: termios is plain data, filled in by tcgetattr.
+    let mut saved: libc::termios = unsafe { std::mem::zeroed() };
+    // SAFETY: fd is open for the lifetime of `tty`.
+    if unsafe { libc::tcgetattr(fd, &raw mut saved) } != 0 {
+        return None;
+    }
+    // Unbuffered and without echo, so the reply is neither shown nor held
+    // back until a newline.
+    let mut raw = saved;
+    raw.c_lflag &= !(libc::ICANON | libc::ECHO);
+    raw.c_cc[libc::VMIN] = 0;
+    raw.c_cc[libc::VTIME] = 0;
+    // SAFETY: as above; `raw` is a valid termios.
+    if unsafe { libc::tcsetattr(fd, libc::TCSANOW, &raw const raw) } != 0 {
+        return None;
+    }
+    let reply = exchange(&tty, timeout).map(|mut reply| {
+        if !device_attributes_seen(&reply) {
+            // A slow terminal may still answer: wait a little longer so the
+            // reply is read here instead of echoed once the mode is restored.
+            collect(&tty, &mut reply, DRAIN_TIMEOUT);
+        }
+        reply
+    });
+    // SAFETY: restores the settings read above; TCSAFLUSH also discards
+    // whatever part of a reply arrived but was not read.
+    unsafe { libc::tcsetattr(fd, libc::TCSAFLUSH, &raw const saved) };
+    parse_reply(&reply?)
+}
+
+/// Background detection needs a Unix terminal.
+#[cfg(not(unix))]
+pub const fn query(_timeout: Duration) -> Option<Rgb> {
+    None
+}
+
+/// Send the queries and collect the reply until the device attributes
+/// arrive or `timeout` passes.
+#[cfg(unix)]
+fn exchange(tty: &std::fs::File, timeout: Duration) -> Option<Vec<u8>> {
+    use std::io::Write;
+
+    let mut tty = tty;
+    tty.write_all(b"\x1b]11;?\x1b\\\x1b[c").ok()?;
+    let mut reply = Vec::new();
+    collect(tty, &mut reply, timeout);
+    Some(reply)
+}
+
+/// Read from `tty` into `reply` until the device attributes answer is in
+/// or `timeout` passes.
+#[cfg(unix)]
+fn collect(tty: &std::fs::File, reply: &mut Vec<u8>, timeout: Duration) {
+    use std::io::Read;
+    use std::os::fd::AsRawFd;
+    use std::time::Instant;
+
+    let mut tty = tty;
+    let deadline = Instant::now() + timeout;
+    let mut chunk = [0; 256];
+    while !device_attributes_seen(reply) {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let millis = i32::try_from(remaining.as_millis()).unwrap_or(i32::MAX);
+        if millis == 0 {
+            break;
+        }
+        let mut pollfd = libc::pollfd {
+            fd: tty.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        // SAFETY: one valid pollfd.
+        if unsafe { libc::poll(&raw mut pollfd, 1, millis) } <= 0 {
+            break;
+        }
+        match tty.read(&mut chunk) {
+            Ok(0) | Err(_) => break,
+            Ok(n) => reply.extend_from_slice(&chunk[..n]),
+        }
+    }
+}
+
+/// Whether `reply` contains a complete `CSI ? ... c` device attributes answer.
+fn device_attributes_seen(reply: &[u8]) -> bool {
+    reply
+        .windows(3)
+        .position(|w| w == b"\x1b[?")
+        .is_some_and(|start| reply[start..].contains(&b'c'))
+}
+
+/// Color in an `OSC 11 ; rgb:R/G/B` reply, with 1 to 4 hex digits per
+/// channel.
+pub fn parse_reply(reply: &[u8]) -> Option<Rgb> {
+    const MARKER: &[u8] = b"]11;rgb:";
+    let start = reply.windows(MARKER.len()).position(|w| w == MARKER)? + MARKER.len();
+    let body = &reply[start..];
+    let end = body.iter().position(|&b| b == 0x07 || b == 0x1b)?;
+    let body = std::str::from_utf8(&body[..end]).ok()?;
+    let mut channels = body.split('/').map(|hex| {
+        if hex.is_empty() || hex.len() > 4 {
+            return None;
+        }
+        let value = u32::from_str_radix(hex, 16).ok()?;
+        let max = (1u32 << (4 * hex.len())) - 1;
+        u8::try_from(value * 255 / max).ok()
+    });
+    let rgb = Rgb(channels.next()??, channels.next()??, channels.next()??);
+    channels.next().is_none().then_some(rgb)
+}
+
+/// Background in a `COLORFGBG` value: its last field, an ANSI color index
+/// (`15;0`, or `0;default;15` as set by some terminals).
+pub fn parse_colorfgbg(value: &str) -> Option<Rgb> {
+    let index = value.rsplit(';').next()?.trim().parse().ok()?;
+    ansi_rgb(index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_reply() {
+        let reply = b"\x1b]11;rgb:ffff/ffff/dddd\x1b\\\x1b[?62;22c";
+        assert_eq!(parse_reply(reply), Some(Rgb(255, 255, 221)));
+        assert_eq!(
+            parse_reply(b"\x1b]11;rgb:1e/1e/2e\x07"),
+            Some(Rgb(30, 30, 46))
+        );
+        assert_eq!(
+            parse_reply(b"\x1b]11;rgb:f/0/8\x07"),
+            Some(Rgb(255, 0, 136))
+        );
+        // Only the device attributes: the terminal ignored OSC 11.
+        assert_eq!(parse_reply(b"\x1b[?1;2c"), None);
+        assert_eq!(parse_reply(b"\x1b]11;rgb:zz/00/00\x07"), None);
+        assert_eq!(parse_reply(b"\x1b]11;rgb:00/00\x07"), None);
+    }
+
+    #[test]
+    fn test_parse_colorfgbg() {
+        assert_eq!(parse_colorfgbg("15;0"), Some(Rgb(0, 0, 0)));
+        assert_eq!(parse_colorfgbg("0;default;15"), Some(Rgb(255, 255, 255)));
+        assert!(parse_colorfgbg("0;7").is_some_and(Rgb::is_light));
+        assert_eq!(parse_colorfgbg("15;default"), None);
+        assert_eq!(parse_colorfgbg("0;231"), None);
+    }
+
+    #[test]
+    fn test_device_attributes_seen() {
+        assert!(device_attributes_seen(b"\x1b]11;rgb:0/0/0\x07\x1b[?62;22c"));
+        assert!(!device_attributes_seen(b"\x1b]11;rgb:0/0/0\x07\x1b[?62;2"));
+    }
+}
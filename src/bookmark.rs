@@ -0,0 +1,160 @@
+//! Record bookmarking (`--mark-on`).
+//!
+//! Records matching a [`Expr`] are remembered with their input position so
+//! users can jump back into the raw log later. Each bookmark is rendered as
+//! one tab-separated line:
+//!
+//! ```text
+//! <source>:<line>\t<byte offset>\t<raw line>
+//! ```
+//!
+//! Byte offsets count every byte of the input, `\r\n` endings included.
+//! With a side file, bookmarks are appended as they happen; otherwise they
+//! are collected and printed as an index on stderr when input is exhausted.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::expr::Expr;
+use crate::parser::LogRecord;
+
+/// Position of a record in its input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bookmark {
+    /// Input name (`-` for stdin).
+    pub source: String,
+    /// 1-indexed line number of the record's first line.
+    pub line: usize,
+    /// Byte offset of the record's first line.
+    pub offset: u64,
+    /// The raw input line (first line for reassembled records).
+    pub raw: String,
+}
+
+impl Bookmark {
+    fn write_to(&self, out: &mut impl Write) -> io::Result<()> {
+        writeln!(
+            out,
+            "{}:{}\t{}\t{}",
+            self.source, self.line, self.offset, self.raw
+        )
+    }
+}
+
+/// Collects bookmarks for records matching an expression.
+#[derive(Debug)]
+pub struct Bookmarks {
+    expr: Expr,
+    side_file: Option<File>,
+    pending: Vec<Bookmark>,
+}
+
+impl Bookmarks {
+    /// Bookmark records matching `expr`, appending to `side_file` when given.
+    pub fn new(expr: Expr, side_file: Option<&Path>) -> io::Result<Self> {
+        let side_file = side_file
+            .map(|path| OpenOptions::new().create(true).append(true).open(path))
+            .transpose()?;
+        Ok(Self {
+            expr,
+            side_file,
+            pending: Vec::new(),
+        })
+    }
+
+    /// Record a bookmark if `record` matches the expression.
+    pub fn observe(
+        &mut self,
+        record: &LogRecord,
+        source: &str,
+        line: usize,
+        offset: u64,
+        raw: &str,
+    ) -> io::Result<()> {
+        if !self.expr.matches(record) {
+            return Ok(());
+        }
+        let bookmark = Bookmark {
+            source: source.to_string(),
+            line,
+            offset,
+            raw: raw.lines().next().unwrap_or_default().to_string(),
+        };
+        if let Some(ref mut file) = self.side_file {
+            return bookmark.write_to(file);
+        }
+        self.pending.push(bookmark);
+        Ok(())
+    }
+
+    /// Bookmarks collected so far (empty when writing to a side file).
+    pub fn pending(&self) -> &[Bookmark] {
+        &self.pending
+    }
+
+    /// Write the collected index to `out`.
+    pub fn write_index(&self, out: &mut impl Write) -> io::Result<()> {
+        for bookmark in &self.pending {
+            bookmark.write_to(out)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::parser::{LineKind, parse_line};
+
+    fn record(line: &str) -> LogRecord {
+        match parse_line(line, &Config::default()) {
+            LineKind::Json(record) => record,
+            other => panic!("expected JSON record, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_collects_matching_records() {
+        let mut marks = Bookmarks::new(Expr::parse("level>=error").unwrap(), None).unwrap();
+        let info = r#"{"level":"info","msg":"ok"}"#;
+        let error = r#"{"level":"error","msg":"boom"}"#;
+        marks.observe(&record(info), "-", 1, 0, info).unwrap();
+        marks.observe(&record(error), "-", 2, 28, error).unwrap();
+
+        assert_eq!(marks.pending().len(), 1);
+        let mut out = Vec::new();
+        marks.write_index(&mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            format!("-:2\t28\t{error}\n")
+        );
+    }
+
+    #[test]
+    fn test_appends_to_side_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("marks.tsv");
+        let line = r#"{"level":"fatal","msg":"down"}"#;
+        let mut marks = Bookmarks::new(Expr::parse("level>=error").unwrap(), Some(&path)).unwrap();
+        marks
+            .observe(&record(line), "app.log", 7, 512, line)
+            .unwrap();
+        drop(marks);
+
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            format!("app.log:7\t512\t{line}\n")
+        );
+    }
+
+    #[test]
+    fn test_reassembled_record_keeps_first_line() {
+        let mut marks = Bookmarks::new(Expr::parse("level>=error").unwrap(), None).unwrap();
+        let raw = "{\"level\":\"error\",\"msg\":\"a\nb\"}";
+        let sanitized = r#"{"level":"error","msg":"a\nb"}"#;
+        marks.observe(&record(sanitized), "-", 3, 10, raw).unwrap();
+        assert_eq!(marks.pending()[0].raw, "{\"level\":\"error\",\"msg\":\"a");
+    }
+}
@@ -0,0 +1,377 @@
+//! Live record broadcasting (`--serve-ws`).
+//!
+//! Serves the records cor renders, as normalized JSON (see
+//! [`LogRecord::to_normalized_json`](crate::parser::LogRecord::to_normalized_json)),
+//! to any number of clients on one port:
+//!
+//! - WebSocket clients (a `GET` with `Upgrade: websocket`) receive one text
+//!   frame per record.
+//! - Any other `GET` is answered as a Server-Sent Events stream with one
+//!   `data:` event per record, e.g. `curl -N http://127.0.0.1:9700/`.
+//!
+//! Only records that pass filters and rules are sent. Each client gets a
+//! queue of [`CLIENT_QUEUE`] records drained by its own writer thread, or a
+//! task with the `async` feature, so sending never waits on a client. A
+//! client whose queue overflows, whose writes stall for [`WRITE_TIMEOUT`],
+//! or who disconnects is dropped; one that does not finish its request
+//! within [`HANDSHAKE_TIMEOUT`] is never added. Messages sent by WebSocket
+//! clients are ignored.
+
+use std::io::{self, Write};
+#[cfg(not(feature = "async"))]
+use std::net::TcpStream;
+use std::net::{SocketAddr, TcpListener};
+#[cfg(not(feature = "async"))]
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex, PoisonError};
+#[cfg(not(feature = "async"))]
+use std::thread;
+use std::time::Duration;
+
+#[cfg(feature = "async")]
+use tokio::sync::mpsc::{self, Receiver};
+
+use crate::ingest::{ACCEPT_BACKOFF, Request, respond};
+
+/// How long a write to one client may block before the client is dropped.
+pub const WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// How long a client may take to send its request.
+pub const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Records queued for one client before it is dropped.
+pub const CLIENT_QUEUE: usize = 1024;
+
+/// GUID appended to the client key in the WebSocket handshake (RFC 6455).
+const WEBSOCKET_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// Sending half of a client's record queue.
+#[cfg(not(feature = "async"))]
+type Queue = mpsc::SyncSender<Arc<str>>;
+#[cfg(feature = "async")]
+type Queue = mpsc::Sender<Arc<str>>;
+
+/// How records are framed for a client.
+#[derive(Debug, Clone, Copy)]
+enum Client {
+    WebSocket,
+    EventStream,
+}
+
+impl Client {
+    fn encode(self, json: &str) -> Vec<u8> {
+        match self {
+            Self::WebSocket => text_frame(json),
+            Self::EventStream => format!("data: {json}\n\n").into_bytes(),
+        }
+    }
+}
+
+/// Sends records to every connected client.
+#[derive(Debug)]
+pub struct Broadcaster {
+    addr: SocketAddr,
+    /// Queues of the connected clients' writers.
+    clients: Arc<Mutex<Vec<Queue>>>,
+}
+
+impl Broadcaster {
+    /// Bind `addr` (`HOST:PORT`) and accept clients on a background thread,
+    /// or as tasks on the shared runtime with the `async` feature.
+    pub fn bind(addr: &str) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        let addr = listener.local_addr()?;
+        let clients = Arc::new(Mutex::new(Vec::new()));
+        let accepted = Arc::clone(&clients);
+        #[cfg(feature = "async")]
+        {
+            let runtime = crate::runtime::handle().map_err(io::Error::other)?;
+            listener.set_nonblocking(true)?;
+            let listener = {
+                let _guard = runtime.enter();
+                tokio::net::TcpListener::from_std(listener)?
+            };
+            runtime.spawn(async move {
+                loop {
+                    let Ok((stream, _)) = listener.accept().await else {
+                        tokio::time::sleep(ACCEPT_BACKOFF).await;
+                        continue;
+                    };
+                    let clients = Arc::clone(&accepted);
+                    tokio::spawn(async move {
+                        let _ = serve_async(stream, &clients).await;
+                    });
+                }
+            });
+        }
+        #[cfg(not(feature = "async"))]
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(stream) = stream else {
+                    thread::sleep(ACCEPT_BACKOFF);
+                    continue;
+                };
+                let clients = Arc::clone(&accepted);
+                thread::spawn(move || {
+                    let _ = serve(stream, &clients);
+                });
+            }
+        });
+        Ok(Self { addr, clients })
+    }
+
+    /// The bound address (useful with port 0).
+    pub const fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Number of connected clients.
+    pub fn client_count(&self) -> usize {
+        lock(&self.clients).len()
+    }
+
+    /// Queue one JSON record for every client, dropping clients whose queue
+    /// is full or whose connection has ended.
+    pub fn send(&self, json: &str) {
+        let json = Arc::<str>::from(json);
+        lock(&self.clients).retain(|tx| tx.try_send(Arc::clone(&json)).is_ok());
+    }
+}
+
+fn lock<T>(clients: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    clients.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Answer a client's `GET` and, if it stays connected, register it and
+/// write its records until it falls behind or goes away.
+#[cfg(not(feature = "async"))]
+fn serve(stream: TcpStream, clients: &Mutex<Vec<Queue>>) -> io::Result<()> {
+    stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+    stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+    let mut writer = stream.try_clone()?;
+    let request = Request::read(&mut io::BufReader::new(stream))?;
+    let (response, client) = answer(&request);
+    writer.write_all(&response)?;
+    writer.flush()?;
+    let client = client.ok_or(io::ErrorKind::InvalidInput)?;
+    let (tx, rx): (_, Receiver<Arc<str>>) = mpsc::sync_channel(CLIENT_QUEUE);
+    lock(clients).push(tx);
+    for json in rx {
+        writer.write_all(&client.encode(&json))?;
+    }
+    Ok(())
+}
+
+/// Answer and serve a client on the shared runtime, like [`serve`].
+#[cfg(feature = "async")]
+async fn serve_async(stream: tokio::net::TcpStream, clients: &Mutex<Vec<Queue>>) -> io::Result<()> {
+    use tokio::io::AsyncWriteExt;
+    use tokio::time::timeout;
+
+    let mut stream = tokio::io::BufReader::new(stream);
+    let request = timeout(HANDSHAKE_TIMEOUT, Request::read_async(&mut stream))
+        .await
+        .map_err(|_| io::ErrorKind::TimedOut)??;
+    let mut stream = stream.into_inner();
+    let (response, client) = answer(&request);
+    let write = async {
+        stream.write_all(&response).await?;
+        stream.flush().await
+    };
+    timeout(WRITE_TIMEOUT, write)
+        .await
+        .map_err(|_| io::ErrorKind::TimedOut)??;
+    let client = client.ok_or(io::ErrorKind::InvalidInput)?;
+    let (tx, mut rx): (_, Receiver<Arc<str>>) = mpsc::channel(CLIENT_QUEUE);
+    lock(clients).push(tx);
+    while let Some(json) = rx.recv().await {
+        timeout(WRITE_TIMEOUT, stream.write_all(&client.encode(&json)))
+            .await
+            .map_err(|_| io::ErrorKind::TimedOut)??;
+    }
+    Ok(())
+}
+
+/// The response to a client's request, and how to frame its records if it
+/// stays connected.
+fn answer(request: &Request) -> (Vec<u8>, Option<Client>) {
+    let mut response = Vec::new();
+    if request.method != "GET" {
+        let _ = respond(&mut response, "405 Method Not Allowed");
+        return (response, None);
+    }
+    let upgrade = request
+        .header("upgrade")
+        .is_some_and(|v| v.eq_ignore_ascii_case("websocket"));
+    if let (true, Some(key)) = (upgrade, request.header("sec-websocket-key")) {
+        let _ = write!(
+            response,
+            "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+             Sec-WebSocket-Accept: {}\r\n\r\n",
+            accept_key(key)
+        );
+        return (response, Some(Client::WebSocket));
+    }
+    let _ = write!(
+        response,
+        "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\n\
+         Access-Control-Allow-Origin: *\r\n\r\n"
+    );
+    (response, Some(Client::EventStream))
+}
+
+/// `Sec-WebSocket-Accept` value for a client's `Sec-WebSocket-Key`.
+fn accept_key(key: &str) -> String {
+    let mut sha = sha1_smol::Sha1::new();
+    sha.update(key.as_bytes());
+    sha.update(WEBSOCKET_GUID.as_bytes());
+    base64(&sha.digest().bytes())
+}
+
+/// An unmasked, unfragmented WebSocket text frame.
+fn text_frame(text: &str) -> Vec<u8> {
+    let payload = text.as_bytes();
+    let mut frame = Vec::with_capacity(payload.len() + 10);
+    frame.push(0x81); // FIN + text opcode
+    let len = payload.len();
+    match (u8::try_from(len), u16::try_from(len)) {
+        (Ok(short), _) if short <= 125 => frame.push(short),
+        (_, Ok(medium)) => {
+            frame.push(126);
+            frame.extend_from_slice(&medium.to_be_bytes());
+        }
+        _ => {
+            frame.push(127);
+            frame.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(payload);
+    frame
+}
+
+/// Standard base64 with padding.
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = u32::from(b[0]) << 16 | u32::from(b[1]) << 8 | u32::from(b[2]);
+        for (i, shift) in [18, 12, 6, 0].into_iter().enumerate() {
+            if i <= chunk.len() {
+                out.push(char::from(ALPHABET[(n >> shift) as usize & 0x3F]));
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read};
+    use std::net::TcpStream;
+    use std::thread;
+    use std::time::Instant;
+
+    #[test]
+    fn test_accept_key_matches_rfc_example() {
+        // Example handshake from RFC 6455, section 1.3.
+        assert_eq!(
+            accept_key("dGhlIHNhbXBsZSBub25jZQ=="),
+            "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+        );
+    }
+
+    #[test]
+    fn test_text_frame_lengths() {
+        assert_eq!(text_frame("hi"), [0x81, 2, b'h', b'i']);
+        let frame = text_frame(&"x".repeat(300));
+        assert_eq!(&frame[..4], [0x81, 126, 0x01, 0x2C]);
+        assert_eq!(frame.len(), 304);
+    }
+
+    fn wait_for_clients(broadcaster: &Broadcaster, n: usize) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while broadcaster.client_count() < n {
+            assert!(Instant::now() < deadline, "client never registered");
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn test_broadcasts_to_websocket_and_sse_clients() {
+        let broadcaster = Broadcaster::bind("127.0.0.1:0").unwrap();
+        let addr = broadcaster.local_addr();
+
+        let mut ws = TcpStream::connect(addr).unwrap();
+        ws.write_all(
+            b"GET / HTTP/1.1\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+              Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\nSec-WebSocket-Version: 13\r\n\r\n",
+        )
+        .unwrap();
+        let mut sse = TcpStream::connect(addr).unwrap();
+        sse.write_all(b"GET /events HTTP/1.1\r\n\r\n").unwrap();
+        wait_for_clients(&broadcaster, 2);
+
+        broadcaster.send(r#"{"msg":"hi"}"#);
+
+        let mut ws = BufReader::new(ws);
+        let mut response = String::new();
+        while response != "\r\n" {
+            response.clear();
+            ws.read_line(&mut response).unwrap();
+            if response.starts_with("HTTP/1.1") {
+                assert!(response.contains("101"), "got: {response}");
+            }
+        }
+        let mut frame = [0u8; 14];
+        ws.read_exact(&mut frame).unwrap();
+        assert_eq!(&frame[..2], [0x81, 12]);
+        assert_eq!(&frame[2..], br#"{"msg":"hi"}"#);
+
+        let mut sse = BufReader::new(sse);
+        let mut lines = Vec::new();
+        for _ in 0..6 {
+            let mut line = String::new();
+            sse.read_line(&mut line).unwrap();
+            lines.push(line);
+        }
+        assert!(lines[1].contains("text/event-stream"), "got: {lines:?}");
+        assert_eq!(lines[5], "data: {\"msg\":\"hi\"}\n");
+    }
+
+    #[test]
+    fn test_drops_stalled_clients_without_waiting() {
+        let broadcaster = Broadcaster::bind("127.0.0.1:0").unwrap();
+        let mut sse = TcpStream::connect(broadcaster.local_addr()).unwrap();
+        sse.write_all(b"GET / HTTP/1.1\r\n\r\n").unwrap();
+        wait_for_clients(&broadcaster, 1);
+
+        // The client never reads: its socket buffers fill, then its queue.
+        let record = format!(r#"{{"msg":"{}"}}"#, "x".repeat(64 * 1024));
+        let start = Instant::now();
+        for _ in 0..CLIENT_QUEUE * 4 {
+            broadcaster.send(&record);
+        }
+        assert!(start.elapsed() < WRITE_TIMEOUT, "send waited on the client");
+        assert_eq!(broadcaster.client_count(), 0);
+    }
+
+    #[test]
+    fn test_silent_clients_are_not_registered() {
+        let broadcaster = Broadcaster::bind("127.0.0.1:0").unwrap();
+        let mut silent = TcpStream::connect(broadcaster.local_addr()).unwrap();
+        silent
+            .set_read_timeout(Some(HANDSHAKE_TIMEOUT * 2))
+            .unwrap();
+        // The server hangs up once the handshake times out.
+        assert_eq!(silent.read(&mut [0; 16]).unwrap(), 0);
+        assert_eq!(broadcaster.client_count(), 0);
+    }
+}
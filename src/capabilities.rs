@@ -0,0 +1,92 @@
+//! Machine-readable description of this build (`--capabilities`).
+//!
+//! Wrapper scripts and editor plugins run `cor --capabilities` to learn
+//! what the installed binary supports — input readers, output formats,
+//! themes, and the cargo features it was built with — before invoking it.
+//! [`SCHEMA_VERSION`] changes only when keys are removed or change meaning;
+//! new keys may appear at any time.
+
+use clap::ValueEnum;
+use serde_json::{Map, Value, json};
+
+use crate::cli::{OutputFormat, Preset};
+use crate::icons::IconSet;
+use crate::pipeline::Stage;
+use crate::theme::Palette;
+
+/// Version of the layout of the capabilities object.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Line formats the parser recognizes, as named in the output.
+const INPUT_FORMATS: &[&str] = &[
+    "json",
+    "embedded_json",
+    "syslog_rfc5424",
+    "cri",
+    "klog",
+    "docker_json_file",
+    "gelf",
+    "plain_text",
+];
+
+/// Optional cargo features and whether this build has them.
+const FEATURES: &[(&str, bool)] = &[
+    ("completions", cfg!(feature = "completions")),
+    ("net", cfg!(feature = "net")),
+    ("tls", cfg!(feature = "tls")),
+    ("async", cfg!(feature = "async")),
+    ("simd", cfg!(feature = "simd")),
+    ("mmap", cfg!(feature = "mmap")),
+    ("parquet", cfg!(feature = "parquet")),
+    ("trace", cfg!(feature = "trace")),
+];
+
+/// The capabilities object.
+pub fn capabilities() -> Value {
+    let features: Map<String, Value> = FEATURES
+        .iter()
+        .map(|&(name, enabled)| (name.to_string(), enabled.into()))
+        .collect();
+    json!({
+        "name": env!("CARGO_PKG_NAME"),
+        "version": env!("CARGO_PKG_VERSION"),
+        "schema_version": SCHEMA_VERSION,
+        "input_formats": INPUT_FORMATS,
+        "output_formats": value_names::<OutputFormat>(),
+        "themes": value_names::<Palette>(),
+        "icon_sets": value_names::<IconSet>(),
+        "presets": value_names::<Preset>(),
+        "pipeline_stages": Stage::ALL.map(Stage::name),
+        "features": features,
+    })
+}
+
+/// Command-line names of the values of `T`.
+fn value_names<T: ValueEnum>() -> Vec<String> {
+    T::value_variants()
+        .iter()
+        .filter_map(ValueEnum::to_possible_value)
+        .map(|value| value.get_name().to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capabilities_lists_values_and_features() {
+        let caps = capabilities();
+        assert_eq!(caps["schema_version"], SCHEMA_VERSION);
+        assert_eq!(caps["version"], env!("CARGO_PKG_VERSION"));
+        assert!(
+            caps["output_formats"]
+                .as_array()
+                .unwrap()
+                .contains(&"flat-json".into())
+        );
+        assert_eq!(caps["themes"][0], "default");
+        assert_eq!(caps["pipeline_stages"][3], "infer_level");
+        assert_eq!(caps["features"]["net"], cfg!(feature = "net"));
+    }
+}
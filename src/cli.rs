@@ -3,7 +3,23 @@
 //! Uses [`clap`] derive macros for argument parsing. All flags are documented
 //! in the contract specification at `specs/001-log-colorizer/contracts/cli.md`.
 
-use clap::{Parser, ValueEnum};
+use std::time::Duration;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+use crate::dedup::DedupKey;
+use crate::elapsed::{ElapsedMode, Origin};
+use crate::expr::Expr;
+use crate::highlight::Highlight;
+use crate::icons::IconSet;
+#[cfg(feature = "net")]
+use crate::ingest::ListenAddr;
+use crate::locale::Locale;
+#[cfg(feature = "net")]
+use crate::net::Endpoint;
+use crate::sink::SinkSpec;
+use crate::theme::Palette;
+use crate::timestamp::validate_format;
 
 /// Colorize JSON-structured log lines from stdin.
 ///
@@ -15,9 +31,17 @@ use clap::{Parser, ValueEnum};
 pub struct Cli {
     /// Control color output.
     ///
-    /// `auto` enables colors only when stdout is a TTY and `NO_COLOR` is unset.
-    #[arg(short = 'c', long, value_enum, default_value_t = ColorMode::Auto)]
-    pub color: ColorMode,
+    /// `auto` (the default) enables colors only when stdout is a TTY and
+    /// `NO_COLOR` is unset.
+    #[arg(short = 'c', long, value_enum)]
+    pub color: Option<ColorMode>,
+
+    /// Apply a curated bundle of options at once.
+    ///
+    /// Presets are applied after the config file and before any other CLI
+    /// flag, so explicit flags always win over the preset.
+    #[arg(long, value_enum)]
+    pub preset: Option<Preset>,
 
     /// Minimum severity level to display.
     ///
@@ -26,16 +50,44 @@ pub struct Cli {
     pub level: Option<String>,
 
     /// Override the JSON key used for the log message field.
-    #[arg(short = 'm', long)]
-    pub message_key: Option<String>,
+    ///
+    /// Accepts a comma-separated fallback list (e.g. `event,msg`); the first
+    /// key present in a record wins.
+    #[arg(short = 'm', long, value_name = "KEYS", value_delimiter = ',')]
+    pub message_key: Option<Vec<String>>,
 
-    /// Override the JSON key used for the log level field.
+    /// Compose the message from several fields, joined in order
+    /// (e.g. `msg,error_detail`). Used fields are removed from the extras.
+    #[arg(
+        long,
+        value_name = "KEYS",
+        value_delimiter = ',',
+        conflicts_with = "message_key"
+    )]
+    pub message_fields: Option<Vec<String>>,
+
+    /// Separator placed between `--message-fields` values [default: ": "].
+    #[arg(long, value_name = "SEP", requires = "message_fields")]
+    pub message_separator: Option<String>,
+
+    /// Keep detected timestamp/level/message/logger/caller/error keys in the
+    /// extra-field list, e.g. to verify what auto-detection picked.
     #[arg(long)]
-    pub level_key: Option<String>,
+    pub keep_extracted: bool,
+
+    /// Override the JSON key used for the log level field.
+    ///
+    /// Accepts a comma-separated fallback list (e.g. `event,msg`); the first
+    /// key present in a record wins.
+    #[arg(long, value_name = "KEYS", value_delimiter = ',')]
+    pub level_key: Option<Vec<String>>,
 
     /// Override the JSON key used for the timestamp field.
-    #[arg(short = 't', long)]
-    pub timestamp_key: Option<String>,
+    ///
+    /// Accepts a comma-separated fallback list (e.g. `event,msg`); the first
+    /// key present in a record wins.
+    #[arg(short = 't', long, value_name = "KEYS", value_delimiter = ',')]
+    pub timestamp_key: Option<Vec<String>>,
 
     /// Override the JSON key used for the logger name field.
     #[arg(long)]
@@ -80,12 +132,52 @@ pub struct Cli {
     )]
     pub no_extra: bool,
 
+    /// Hide the logger name shown between the level badge and the message.
+    #[arg(long)]
+    pub hide_logger: bool,
+
     /// Output filtered lines as JSON instead of colorized text.
     ///
-    /// Non-JSON lines are suppressed in this mode.
+    /// Non-JSON lines are suppressed in this mode. With colors on (a
+    /// terminal, or `--color=always`) keys, strings, and numbers are
+    /// highlighted in place; otherwise each line is written unchanged.
     #[arg(short = 'j', long)]
     pub json: bool,
 
+    /// Output format: colorized text, the input JSON, flat JSON, or CSV/TSV.
+    ///
+    /// `flat-json` emits one object per record with normalized `ts`, `level`,
+    /// and `msg` plus the extra fields under their dot-notation keys, ready
+    /// for CSV and SQL import tools. `csv` and `tsv` write a header row and
+    /// one row of the `--fields` columns per record. `json` is the same as
+    /// `--json`.
+    #[arg(long, value_name = "FORMAT", conflicts_with = "json")]
+    pub output_format: Option<OutputFormat>,
+
+    /// Columns of `--output-format csv|tsv` (comma-separated).
+    ///
+    /// `ts`, `level`, `msg`, `logger`, `caller`, `error`, or an extra field
+    /// in dot notation, e.g. `--fields ts,level,msg,status`. Defaults to
+    /// `ts,level,msg`.
+    #[arg(long, value_name = "COLUMNS", value_delimiter = ',')]
+    pub fields: Option<Vec<String>>,
+
+    /// Output JSON like `--json`, indented and with colored keys, strings,
+    /// and numbers, for reading whole payloads.
+    ///
+    /// Also indents `--output-format=flat-json`.
+    #[arg(long)]
+    pub json_pretty: bool,
+
+    /// Add a `_cor` object to normalized JSON output telling where each
+    /// record came from: source, line number, parser, preset, and whether it
+    /// was reassembled from several lines.
+    ///
+    /// Applies to `--output-format=flat-json` and the JSON sent to
+    /// `--output` sinks.
+    #[arg(long)]
+    pub provenance: bool,
+
     /// Render extra fields inline on the same line as the message.
     ///
     /// Format: `key=val key=val` after the message instead of one-per-line.
@@ -99,6 +191,21 @@ pub struct Cli {
     #[arg(short = 'M', long)]
     pub max_field_length: Option<usize>,
 
+    /// Which part of a long value to keep: head (the start), middle (the
+    /// start and end, `abc…xyz`), or tail (the end, for long paths)
+    /// [default: head].
+    #[arg(long, value_enum, value_name = "MODE")]
+    pub truncate_mode: Option<TruncateMode>,
+
+    /// Show at most N extra fields per record, followed by a
+    /// `… +K more fields` marker. Set to `0` to show every field.
+    #[arg(long, value_name = "N")]
+    pub max_fields: Option<usize>,
+
+    /// Show every extra field, overriding `max_fields` from the config file.
+    #[arg(long, conflicts_with = "max_fields")]
+    pub expand_all: bool,
+
     /// Number of blank lines between each log entry.
     ///
     /// Set to `0` for compact output with no gaps.
@@ -108,24 +215,140 @@ pub struct Cli {
     /// Timestamp display format (strftime-compatible).
     ///
     /// Overrides the format from config file or the default `%Y-%m-%dT%H:%M:%S%.3f`.
-    #[arg(short = 'T', long)]
+    /// Invalid patterns are rejected at startup.
+    #[arg(short = 'T', long, value_name = "FMT", value_parser = parse_timestamp_format_arg)]
     pub timestamp_format: Option<String>,
 
     /// Minimum width for extra field key alignment (right-justified).
     #[arg(long)]
     pub key_min_width: Option<usize>,
 
-    /// Filter lines where any field value matches a regex pattern.
-    #[arg(short = 'G', long)]
+    /// Only show records matching a field expression, e.g.
+    /// `status >= 500 && method == "POST"`. Combine alternatives with `||`;
+    /// repeated `--where` flags must all match.
+    #[arg(short = 'w', long = "where", value_name = "EXPR", value_parser = Expr::parse)]
+    pub where_filter: Vec<Expr>,
+
+    /// Only show records whose field equals a value (`key=value`) or
+    /// differs from it (`key!=value`). The value is matched literally;
+    /// repeated `--filter` flags must all match.
+    #[arg(long, value_name = "KEY=VALUE", value_parser = Expr::parse_filter)]
+    pub filter: Vec<Expr>,
+
+    /// Only show records where a field's value differs from the last one
+    /// seen, turning a chatty stream into a change log of that field.
+    #[arg(long, value_name = "FIELD")]
+    pub watch_field: Option<String>,
+
+    /// Append a dim `(seen N×)` to values of these fields seen before in
+    /// the session, without hiding anything [default fields: msg, error].
+    #[arg(
+        long,
+        value_name = "FIELDS",
+        num_args = 0..=1,
+        require_equals = true,
+        value_delimiter = ',',
+        default_missing_value = "msg,error"
+    )]
+    pub seen_counts: Option<Vec<String>>,
+
+    /// Show only the first record of each distinct message and count the
+    /// repeats, summarized on stderr at exit. Key by `fingerprint` to
+    /// ignore numbers and IDs in the message, or by a field name.
+    #[arg(
+        long,
+        value_name = "KEY",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "message",
+        value_parser = DedupKey::parse
+    )]
+    pub first_only: Option<DedupKey>,
+
+    /// Show only a random fraction of records (`0.1` or `10%`), to tame
+    /// chatty debug streams while following. Records at `--sample-level`
+    /// or above and raw lines are always shown.
+    #[arg(long, value_name = "RATE", value_parser = crate::sample::parse_rate)]
+    pub sample: Option<f64>,
+
+    /// Lowest level never sampled out by `--sample` [default: error].
+    #[arg(long, value_name = "LEVEL", value_parser = parse_level_arg, requires = "sample")]
+    pub sample_level: Option<String>,
+
+    /// Show at most this many entries per second (`100/s`) or minute
+    /// (`1000/m`); the rest are dropped and counted in a `… N lines
+    /// suppressed` marker, so a log storm cannot flood the terminal. Off
+    /// under `--stable`.
+    #[arg(long, value_name = "RATE", value_parser = crate::throttle::Rate::parse)]
+    pub throttle: Option<crate::throttle::Rate>,
+
+    /// Only show records whose field is one of a set of values
+    /// (`status=404,500,503`).
+    #[arg(long = "in", value_name = "FIELD=V1,V2", value_parser = Expr::parse_in)]
+    pub in_set: Vec<Expr>,
+
+    /// Hide records whose field is one of a set of values
+    /// (`path=/health,/metrics`).
+    #[arg(long, value_name = "FIELD=V1,V2", value_parser = Expr::parse_not_in)]
+    pub not_in: Vec<Expr>,
+
+    /// Only show records whose numeric field is at least a value
+    /// (`status=500`). String-encoded numbers count; other values don't.
+    #[arg(long, value_name = "FIELD=NUM", value_parser = Expr::parse_min)]
+    pub min: Vec<Expr>,
+
+    /// Only show records whose numeric field is at most a value
+    /// (`latency_ms=100`).
+    #[arg(long, value_name = "FIELD=NUM", value_parser = Expr::parse_max)]
+    pub max: Vec<Expr>,
+
+    /// Filter lines where any field value matches a regex pattern. Matches
+    /// in the message are highlighted.
+    #[arg(short = 'G', long, value_name = "PATTERN")]
     pub grep: Option<String>,
 
+    /// Hide lines where any field value matches a regex pattern (the
+    /// inverse of `--grep`).
+    #[arg(long, value_name = "PATTERN")]
+    pub grep_v: Option<String>,
+
+    /// Color substrings matching a regex in messages and field values
+    /// (e.g. `req-[0-9a-f]+:cyan`); yellow unless a color name, `#rrggbb`,
+    /// or 256-color index follows the last `:`. Repeatable; nothing is
+    /// filtered out.
+    #[arg(long, value_name = "REGEX[:COLOR]", value_parser = Highlight::parse)]
+    pub highlight: Vec<Highlight>,
+
+    /// Tint records matching a field expression (e.g. `request_id=abc`)
+    /// and dim all others instead of hiding them.
+    #[arg(long, value_name = "EXPR", value_parser = Expr::parse)]
+    pub focus: Option<Expr>,
+
+    /// Show records that fail the filters (`--where`, `--filter`,
+    /// `--level`, `--grep`, ...) in dark grey instead of hiding them, so
+    /// matches keep their surrounding context. They are shown on stdout
+    /// only, never in `--split-by` files or `--output` sinks.
+    #[arg(long)]
+    pub dim_unmatched: bool,
+
+    /// Regex with named groups applied to the text before embedded JSON
+    /// (e.g. `^(?P<pod>\S+) (?P<stream>stdout|stderr) `). Captures become
+    /// extra fields usable in filters, rules, and `--split-by`; the matched
+    /// text is dropped from the displayed prefix.
+    #[arg(long, value_name = "REGEX")]
+    pub prefix_pattern: Option<String>,
+
     /// Timezone for timestamp display.
     ///
     /// Use `local` for system timezone, or an IANA name like `Europe/Berlin`.
     /// Default: UTC.
-    #[arg(short = 'z', long)]
+    #[arg(short = 'z', long, visible_alias = "tz", value_name = "TZ")]
     pub timezone: Option<String>,
 
+    /// Display timestamps in the system timezone (same as `--timezone local`).
+    #[arg(long, conflicts_with = "timezone")]
+    pub local: bool,
+
     /// Path to configuration file.
     #[arg(long)]
     pub config: Option<std::path::PathBuf>,
@@ -137,13 +360,445 @@ pub struct Cli {
     #[arg(short = 'v', long)]
     pub verbose: bool,
 
+    /// Exit with status 4 after processing if any line that looked like
+    /// JSON failed to parse, reporting how many and where.
+    #[arg(long)]
+    pub strict: bool,
+
+    /// Emit cor's own diagnostics — parse failures, dropped lines,
+    /// reassembly outcomes, and a closing summary with filter counts — on
+    /// stderr in this format.
+    #[arg(long, value_enum, value_name = "FORMAT")]
+    pub diag: Option<DiagFormat>,
+
+    /// Write `--diag` output to this inherited file descriptor instead of
+    /// stderr (e.g. `--diag-fd 3 3>diag.ndjson`); must be 3 or above.
+    #[cfg(unix)]
+    #[arg(long, value_name = "FD", requires = "diag")]
+    pub diag_fd: Option<i32>,
+
+    /// Trace cor's own pipeline on stderr: timing spans for reading,
+    /// parsing, formatting, and writing each line.
+    #[cfg(feature = "trace")]
+    #[arg(long)]
+    pub debug_cor: bool,
+
+    /// Always attempt multi-line JSON reassembly, even for line shapes that
+    /// recently failed to reassemble.
+    ///
+    /// By default, `cor` remembers the shape of lines whose reassembly failed
+    /// on malformed JSON and passes similar lines straight through to avoid
+    /// repeated buffering.
+    #[arg(long)]
+    pub no_reassembly_cache: bool,
+
+    /// Maximum number of continuation lines buffered while reassembling
+    /// multi-line JSON [default: 200].
+    #[arg(long, value_name = "N")]
+    pub max_continuation_lines: Option<usize>,
+
+    /// Maximum number of bytes buffered while reassembling multi-line JSON
+    /// [default: 1048576]. Set to `0` to disable the byte cap.
+    #[arg(long, value_name = "BYTES")]
+    pub max_continuation_bytes: Option<usize>,
+
+    /// Give up on multi-line reassembly after this long (e.g. `500ms`, `2s`).
+    ///
+    /// Buffered lines are emitted as raw text once the cutoff passes, so an
+    /// incomplete JSON object cannot hold back live output indefinitely.
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration_arg)]
+    pub continuation_timeout: Option<Duration>,
+
+    /// Flush lines held for multi-line reassembly as raw text when no new
+    /// input arrives for this long (e.g. `200ms`).
+    ///
+    /// Unlike `--continuation-timeout`, the idle window restarts with every
+    /// continuation line, so a steadily arriving traceback is still reassembled.
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration_arg)]
+    pub continuation_idle: Option<Duration>,
+
+    /// Exit when no input arrives for this long (e.g. `30s`).
+    ///
+    /// Exits with `--timeout-exit-code` so scripts can tell a hung producer
+    /// apart from a clean end of input.
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration_arg)]
+    pub timeout: Option<Duration>,
+
+    /// Exit code used when `--timeout` expires [default: 124].
+    #[arg(long, value_name = "CODE", requires = "timeout")]
+    pub timeout_exit_code: Option<u8>,
+
+    /// Stop at end of input [default: true].
+    ///
+    /// With `false`, keep waiting for more input after EOF, e.g. for a FIFO
+    /// whose writer restarts. With several inputs, only the last is waited
+    /// on. Combine with `--timeout` to bound the wait.
+    #[arg(long, value_name = "BOOL", action = clap::ArgAction::Set)]
+    pub exit_on_eof: Option<bool>,
+
+    /// Show the offset from the first record's timestamp (`+00:03.412`):
+    /// `only` in place of the timestamp (the default with a bare
+    /// `--elapsed`), or `beside` it.
+    #[arg(
+        long,
+        value_enum,
+        value_name = "MODE",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "only"
+    )]
+    pub elapsed: Option<ElapsedMode>,
+
+    /// Show timestamps relative to the `first` record (the default with a
+    /// bare `--relative`) or the `previous` one, in place of the wall-clock
+    /// time unless `--elapsed=beside` keeps both.
+    #[arg(
+        long,
+        value_enum,
+        value_name = "FROM",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "first"
+    )]
+    pub relative: Option<Origin>,
+
+    /// Append a sparkline of a numeric field's last values to each record
+    /// carrying it (e.g. `--sparkline latency_ms`).
+    #[arg(long, value_name = "FIELD")]
+    pub sparkline: Option<String>,
+
+    /// Hour clock for timestamps: 12 (with AM/PM) or 24 [default: 24].
+    #[arg(long, value_enum, value_name = "HOURS")]
+    pub clock: Option<ClockMode>,
+
+    /// Language for day/month names in timestamps (`%a`, `%A`, `%b`, `%B`).
+    #[arg(long, value_enum)]
+    pub locale: Option<Locale>,
+
+    /// Quote string values: auto (only when they contain spaces or special
+    /// characters), always, or never [default: never].
+    #[arg(long, value_enum, value_name = "MODE")]
+    pub quote_values: Option<QuoteMode>,
+
+    /// Print control characters and escape sequences verbatim.
+    ///
+    /// By default, control characters other than newline and tab (e.g. ANSI
+    /// escape sequences injected into log messages) are shown escaped in
+    /// field values, and non-color escape sequences (OSC titles, clipboard
+    /// writes, DCS, cursor movement) are stripped from raw passthrough lines.
+    #[arg(long)]
+    pub raw_control_chars: bool,
+
+    /// Draw ASCII-only markers (`...` for truncation, `-` rules) for
+    /// terminals without Unicode. Enabled automatically when the locale
+    /// (`LC_ALL`, `LC_CTYPE`, `LANG`) is not UTF-8.
+    #[arg(long)]
+    pub ascii: bool,
+
+    /// Icons before level badges and known field clusters (http, db, k8s):
+    /// emoji, Nerd Font glyphs, or none [default: none].
+    #[arg(long, value_enum, value_name = "SET")]
+    pub icons: Option<IconSet>,
+
+    /// Color palette; the color-blind friendly ones keep levels apart
+    /// without relying on red and green [default: default].
+    #[arg(long, value_enum, value_name = "PALETTE")]
+    pub theme: Option<Palette>,
+
+    /// Don't ask the terminal for its background color (OSC 11); themes
+    /// are then only adapted from `COLORFGBG`. The query is also skipped
+    /// with `--theme`, `NO_COLOR`, or colors off.
+    #[arg(long)]
+    pub no_detect_background: bool,
+
+    /// Output buffering: line (flush after each record, for live streams),
+    /// block (flush when a 64 KiB buffer fills, for batch throughput), or
+    /// none (flush after every write) [default: line].
+    #[arg(long, value_enum, value_name = "MODE")]
+    pub buffer_mode: Option<BufferMode>,
+
+    /// Byte-stable output for golden-file tests: UTC timestamps, no colors,
+    /// and no time-dependent behavior. Overrides conflicting settings.
+    #[arg(long)]
+    pub stable: bool,
+
+    /// Bookmark records matching an expression (e.g. `level>=error`).
+    ///
+    /// Each match is recorded as `<source>:<line><TAB><byte offset><TAB><raw line>`
+    /// and printed to stderr once input is exhausted, or appended to `--mark-file`.
+    #[arg(long, value_name = "EXPR")]
+    pub mark_on: Option<String>,
+
+    /// Append bookmarks to this file as they happen instead of stderr.
+    #[arg(long, value_name = "PATH", requires = "mark_on")]
+    pub mark_file: Option<std::path::PathBuf>,
+
+    /// Stop with exit code 3 as soon as a record matches an expression
+    /// (`level>=error`), after writing it and flushing the output. Repeated
+    /// flags are alternatives; display filters do not apply.
+    #[arg(long, value_name = "EXPR", value_parser = Expr::parse)]
+    pub fail_on: Vec<Expr>,
+
+    /// Like `--fail-on`, for input lines matching a regex (`panic`),
+    /// JSON or not.
+    #[arg(long, value_name = "PATTERN")]
+    pub fail_on_grep: Option<String>,
+
+    /// Remap failure exit codes as `NAME=CODE,...`, for names config (1),
+    /// io (2), fail-on-level (3), and strict (4).
+    #[arg(long, value_name = "MAP", value_parser = crate::exit::ExitCodeMap::parse)]
+    pub exit_code_map: Option<crate::exit::ExitCodeMap>,
+
+    /// Keep a live count of WARN/ERROR/FATAL records, redrawn every
+    /// second in the terminal `title` (the default with a bare
+    /// `--live-counts`) or as a status line on `stderr`.
+    #[arg(
+        long,
+        value_enum,
+        value_name = "WHERE",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "title"
+    )]
+    pub live_counts: Option<crate::counters::CounterTarget>,
+
+    /// Ring the terminal bell (on stderr, when it is a terminal) for each
+    /// record shown at this level or above, e.g. `--bell-on error` while following in a
+    /// background terminal.
+    #[arg(long, value_name = "LEVEL", value_parser = parse_level_arg)]
+    pub bell_on: Option<String>,
+
+    /// Show a desktop notification (`notify-send`, or `osascript` on macOS)
+    /// for records shown at this level or above, at most one every few
+    /// seconds.
+    #[arg(long, value_name = "LEVEL", value_parser = parse_level_arg)]
+    pub notify_on: Option<String>,
+
+    /// For the first N entries (default 5), print on stderr which parser
+    /// read the line, which keys the fields came from, which filters and
+    /// rules fired, and why a record was dropped.
+    #[arg(
+        long,
+        value_name = "N",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "5"
+    )]
+    pub explain: Option<usize>,
+
+    /// After input ends, print a summary to stderr: lines read, records
+    /// per level, malformed JSON, the time span covered, and the most
+    /// frequent field keys.
+    #[arg(long)]
+    pub stats: bool,
+
+    /// Instead of printing records, report every JSON field seen with its
+    /// record count and value types.
+    #[arg(long)]
+    pub discover_fields: bool,
+
+    /// Include up to N distinct redacted sample values per field in the
+    /// `--discover-fields` report (letters and digits masked, shape kept).
+    #[arg(long, value_name = "N", requires = "discover_fields")]
+    pub examples: Option<usize>,
+
+    /// Print a timestamped banner for every line written to this FIFO
+    /// (created if missing), e.g. `echo "deploy v1.2.3" > PATH`. Input lines
+    /// of the form `##cor:mark <text>` are shown the same way.
+    #[arg(long, value_name = "PATH")]
+    pub mark_fifo: Option<std::path::PathBuf>,
+
+    /// Write records into one file per value of this field (e.g. `service`).
+    ///
+    /// Records without the field, and non-JSON lines, still go to stdout.
+    #[arg(long, value_name = "FIELD")]
+    pub split_by: Option<String>,
+
+    /// Directory for `--split-by` output files [default: .].
+    #[arg(long, value_name = "DIR", requires = "split_by")]
+    pub split_dir: Option<std::path::PathBuf>,
+
+    /// Read logs from a TCP producer, e.g. `tcp://localhost:5170` (repeatable).
+    ///
+    /// Prefix with `LABEL=` to name the connection; lines are shown with
+    /// their connection's label. Dropped connections are retried with
+    /// exponential backoff. Use `tls://` for TLS (requires the `tls` feature).
+    #[cfg(feature = "net")]
+    #[arg(
+        long,
+        value_name = "[LABEL=]tcp://HOST:PORT",
+        value_parser = Endpoint::parse,
+        conflicts_with = "files",
+        group = "network"
+    )]
+    pub connect: Vec<Endpoint>,
+
+    /// Accept logs `POST`ed as NDJSON, e.g. `http://0.0.0.0:8080/ingest`.
+    ///
+    /// Both `Content-Length` and chunked bodies are accepted; each request
+    /// is answered with `202 Accepted` once its lines have been queued.
+    /// Use `https://` for TLS with `--tls-cert` and `--tls-key` (requires
+    /// the `tls` feature).
+    #[cfg(feature = "net")]
+    #[arg(
+        long,
+        value_name = "http://HOST:PORT/PATH",
+        value_parser = ListenAddr::parse,
+        conflicts_with_all = ["files", "connect"],
+        group = "network"
+    )]
+    pub listen: Option<ListenAddr>,
+
+    /// Broadcast rendered records as normalized JSON to WebSocket and
+    /// Server-Sent Events clients on this address (e.g. `127.0.0.1:9700`).
+    #[cfg(feature = "net")]
+    #[arg(long, value_name = "HOST:PORT")]
+    pub serve_ws: Option<String>,
+
+    /// Also send rendered records to another output: `file:PATH` (plain
+    /// text), `webhook:URL` (NDJSON batches), or `loki:URL` (Loki push API).
+    /// Repeatable; stdout is still written.
+    #[arg(long, value_name = "SINK", value_parser = SinkSpec::parse)]
+    pub output: Vec<SinkSpec>,
+
+    /// Also write the normalized records to a Parquet file, for analysis
+    /// in SQL engines and dataframe libraries.
+    #[cfg(feature = "parquet")]
+    #[arg(long, value_name = "PATH")]
+    pub export_parquet: Option<std::path::PathBuf>,
+
+    /// Columns of the `--export-parquet` file as `FIELD:TYPE,...`, with
+    /// types string, int, float, bool, or timestamp. Inferred from the
+    /// first records by default.
+    #[cfg(feature = "parquet")]
+    #[arg(
+        long,
+        value_name = "SCHEMA",
+        requires = "export_parquet",
+        value_parser = crate::parquet_export::Schema::parse
+    )]
+    pub parquet_schema: Option<crate::parquet_export::Schema>,
+
+    /// CA certificates (PEM) for verifying `tls://` endpoints, replacing
+    /// the bundled Mozilla roots. With `--listen https://`, clients must
+    /// present a certificate signed by one of them.
+    #[cfg(feature = "tls")]
+    #[arg(long, value_name = "PATH", requires = "network")]
+    pub tls_ca: Option<std::path::PathBuf>,
+
+    /// Client certificate chain (PEM) for mutual TLS, or the certificate
+    /// served by `--listen https://`.
+    #[cfg(feature = "tls")]
+    #[arg(long, value_name = "PATH", requires_all = ["network", "tls_key"])]
+    pub tls_cert: Option<std::path::PathBuf>,
+
+    /// Private key (PEM) for `--tls-cert`.
+    #[cfg(feature = "tls")]
+    #[arg(long, value_name = "PATH", requires = "tls_cert")]
+    pub tls_key: Option<std::path::PathBuf>,
+
+    /// Record raw input lines with their arrival times to this file, for
+    /// `cor replay`.
+    #[arg(long, value_name = "FILE")]
+    pub record: Option<std::path::PathBuf>,
+
+    /// Print what this build supports — input and output formats, themes,
+    /// compiled-in features — as JSON and exit, for wrapper scripts and
+    /// editor plugins.
+    #[arg(long)]
+    pub capabilities: bool,
+
     /// Generate shell completions and exit.
+    #[cfg(feature = "completions")]
     #[arg(long, value_enum)]
     pub completions: Option<clap_complete::Shell>,
 
+    /// Read regular file arguments by memory-mapping them instead of
+    /// through a read buffer. With `--level`, records below the level are
+    /// skipped in place without being copied. The files must not be
+    /// truncated while cor reads them.
+    #[cfg(feature = "mmap")]
+    #[arg(long, conflicts_with_all = ["follow", "jobs"])]
+    pub mmap: bool,
+
+    /// Parse and format offline input on N worker threads, keeping the
+    /// output order. 0 uses one thread per CPU. Live input (`--follow`,
+    /// network inputs, deadlines) and `--sort` are processed on one thread,
+    /// with a warning.
+    #[arg(long, value_name = "N", default_value_t = 1)]
+    pub jobs: usize,
+
+    /// Keep reading file arguments as they grow, like `tail -F`: truncated
+    /// files are reread from the start and rotated files are reopened.
+    #[arg(short = 'f', long)]
+    pub follow: bool,
+
+    /// Read each input to the end and show its records in timestamp order,
+    /// for exports that arrive unsorted. Ties keep their input order, and
+    /// lines without a timestamp stay after the line they followed.
+    #[arg(long, conflicts_with = "follow")]
+    pub sort: bool,
+
+    /// Prefix each entry with the name of the file it came from (`-` for stdin).
+    #[arg(short = 'H', long)]
+    pub with_filename: bool,
+
     /// Input files to process (reads stdin if none given, `-` for explicit stdin).
     #[arg()]
     pub files: Vec<std::path::PathBuf>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+impl Cli {
+    /// Whether any `--connect` endpoints were given.
+    pub const fn has_connections(&self) -> bool {
+        #[cfg(feature = "net")]
+        return !self.connect.is_empty();
+        #[cfg(not(feature = "net"))]
+        false
+    }
+
+    /// Whether input comes from the network (`--listen` or `--connect`)
+    /// rather than files or stdin.
+    pub const fn network_input(&self) -> bool {
+        #[cfg(feature = "net")]
+        return self.listen.is_some() || self.has_connections();
+        #[cfg(not(feature = "net"))]
+        false
+    }
+}
+
+/// Subcommands. Display flags go before the subcommand name, e.g.
+/// `cor --level warn replay session.cor`.
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Replay a session recorded with `--record`, with its original timing.
+    Replay(ReplayArgs),
+    /// Measure throughput of the full pipeline on synthetic log lines.
+    Bench(BenchArgs),
+    /// Write a sidecar index (`FILE.coridx`) that lets later runs with
+    /// `--level` or `--where` filters skip blocks of FILE without a match.
+    Index(IndexArgs),
+}
+
+/// Arguments for `cor index`.
+#[derive(Debug, Args)]
+pub struct IndexArgs {
+    /// Log file to index.
+    pub file: std::path::PathBuf,
+}
+
+/// Arguments for `cor replay`.
+#[derive(Debug, Args)]
+pub struct ReplayArgs {
+    /// Recording to replay.
+    pub file: std::path::PathBuf,
+
+    /// Playback speed factor; `0` replays without delays.
+    #[arg(long, default_value_t = 1.0, value_parser = parse_speed_arg)]
+    pub speed: f64,
 }
 
 /// Color output mode.
@@ -157,6 +812,86 @@ pub enum ColorMode {
     Never,
 }
 
+/// Shape of the records written to stdout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Colorized text.
+    Text,
+    /// The input JSON, unchanged.
+    Json,
+    /// One flat JSON object per record with normalized `ts`, `level`, `msg`.
+    FlatJson,
+    /// Comma-separated values of the `--fields` columns, with a header row.
+    Csv,
+    /// Tab-separated values of the `--fields` columns, with a header row.
+    Tsv,
+}
+
+/// Hour clock used when displaying timestamps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ClockMode {
+    /// 12-hour clock with an AM/PM marker.
+    #[value(name = "12")]
+    H12,
+    /// 24-hour clock.
+    #[value(name = "24")]
+    H24,
+}
+
+/// Format of `--diag` diagnostics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum DiagFormat {
+    /// One JSON object per event (NDJSON).
+    Json,
+}
+
+/// Buffering of formatted output on stdout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum BufferMode {
+    /// Flush at the end of each record.
+    Line,
+    /// Flush when the buffer fills and at exit.
+    Block,
+    /// Flush after every write.
+    None,
+}
+
+/// Quoting policy for string field values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum QuoteMode {
+    /// Quote values containing whitespace, quotes, `=`, or control characters.
+    Auto,
+    /// Quote every string value.
+    Always,
+    /// Print string values verbatim.
+    Never,
+}
+
+/// Which part of a value longer than `--max-field-length` is kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TruncateMode {
+    /// Keep the start: `abcdef…`.
+    Head,
+    /// Keep the start and the end: `abc…xyz`.
+    Middle,
+    /// Keep the end: `…uvwxyz`.
+    Tail,
+}
+
+/// Named option bundles selectable with `--preset`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Preset {
+    /// Kubernetes streams: `kubectl logs --prefix` pod and container as
+    /// fields, compact, time-only timestamps, runtime noise hidden.
+    K8s,
+    /// CI logs: no colors, no gaps, inline fields, untruncated values.
+    Ci,
+    /// Local development: local timezone, time-only timestamps, parse errors shown.
+    Dev,
+    /// Just the essentials: no extra fields, no gaps.
+    Minimal,
+}
+
 /// Parse level argument as case-insensitive string.
 fn parse_level_arg(s: &str) -> Result<String, String> {
     let lower = s.to_lowercase();
@@ -168,6 +903,53 @@ fn parse_level_arg(s: &str) -> Result<String, String> {
     }
 }
 
+/// Validate a strftime pattern for `--timestamp-format`.
+fn parse_timestamp_format_arg(s: &str) -> Result<String, String> {
+    validate_format(s).map(|()| s.to_string())
+}
+
+/// Arguments for `cor bench`.
+#[derive(Debug, Args)]
+pub struct BenchArgs {
+    /// Number of synthetic lines to process.
+    #[arg(long, default_value_t = 100_000)]
+    pub lines: usize,
+}
+
+/// Parse a `cor replay --speed` factor.
+fn parse_speed_arg(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(speed) if speed.is_finite() && speed >= 0.0 => Ok(speed),
+        _ => Err(format!(
+            "invalid speed '{s}': expected a non-negative number"
+        )),
+    }
+}
+
+/// Parse a duration like `250ms`, `2s`, `5m`, or `1h`. Bare numbers are seconds.
+pub fn parse_duration_arg(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    let split = s
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    let value: f64 = number
+        .parse()
+        .map_err(|_| format!("invalid duration '{s}': expected e.g. 500ms, 2s, 5m"))?;
+    let seconds = match unit.trim() {
+        "ms" => value / 1000.0,
+        "" | "s" => value,
+        "m" => value * 60.0,
+        "h" => value * 3600.0,
+        other => {
+            return Err(format!(
+                "invalid duration unit '{other}': expected one of ms, s, m, h"
+            ));
+        }
+    };
+    Duration::try_from_secs_f64(seconds).map_err(|e| format!("invalid duration '{s}': {e}"))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -192,4 +974,27 @@ mod tests {
         let err = parse_level_arg("critical").unwrap_err();
         assert!(err.contains("invalid level"));
     }
+
+    #[test]
+    fn test_parse_duration_arg_units() {
+        assert_eq!(
+            parse_duration_arg("250ms").unwrap(),
+            Duration::from_millis(250)
+        );
+        assert_eq!(parse_duration_arg("2s").unwrap(), Duration::from_secs(2));
+        assert_eq!(parse_duration_arg("3").unwrap(), Duration::from_secs(3));
+        assert_eq!(
+            parse_duration_arg("1.5s").unwrap(),
+            Duration::from_millis(1500)
+        );
+        assert_eq!(parse_duration_arg("5m").unwrap(), Duration::from_mins(5));
+        assert_eq!(parse_duration_arg("1h").unwrap(), Duration::from_hours(1));
+    }
+
+    #[test]
+    fn test_parse_duration_arg_invalid() {
+        assert!(parse_duration_arg("").is_err());
+        assert!(parse_duration_arg("fast").is_err());
+        assert!(parse_duration_arg("10days").is_err());
+    }
 }
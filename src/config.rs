@@ -6,18 +6,42 @@
 //! 3. Built-in defaults
 
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
+use clap::ValueEnum;
 use serde::Deserialize;
 
-use crate::cli::{Cli, ColorMode};
+use crate::cli::parse_duration_arg;
+use crate::cli::{
+    BufferMode, Cli, ClockMode, ColorMode, OutputFormat, Preset, QuoteMode, TruncateMode,
+};
+use crate::dedup::DedupKey;
+use crate::elapsed::{ElapsedMode, Origin, Thresholds};
 use crate::error::CorError;
-use crate::level::{Level, color_name_to_style};
+use crate::expr::Expr;
+use crate::glyphs::{self, Glyphs};
+use crate::highlight::Highlight;
+use crate::icons::{IconSet, Icons};
+use crate::klog;
+use crate::level::Level;
+use crate::locale::Locale;
+use crate::pipeline::Stage;
+use crate::rules::{Rule, RuleConfig};
+use crate::sample;
+use crate::table::{self, Table};
+use crate::theme::{Overrides, Paint, Palette, Theme};
+use crate::throttle::Rate;
+use crate::timestamp::{to_twelve_hour, validate_format};
 
 /// Runtime configuration merged from defaults, config file, and CLI arguments.
 ///
-/// Use [`Config::from_cli`] to build from parsed CLI arguments, or
-/// [`Config::default`] for built-in defaults (useful in tests and benchmarks).
+/// Use [`Config::from_cli`] to build from parsed CLI arguments,
+/// [`Config::builder`] to set options from code, or [`Config::default`]
+/// for built-in defaults (useful in tests and benchmarks).
+///
+/// A `Config` is `Send + Sync` and never changes while formatting, so it can
+/// be shared by reference or in an [`Arc`] by any number of threads.
 #[allow(clippy::struct_excessive_bools)]
 #[derive(Debug, Clone)]
 pub struct Config {
@@ -25,12 +49,18 @@ pub struct Config {
     pub color_mode: ColorMode,
     /// Minimum log level to display; lines below this are suppressed.
     pub min_level: Option<Level>,
-    /// Custom JSON key for the message field (overrides alias table).
-    pub message_key: Option<String>,
-    /// Custom JSON key for the level field (overrides alias table).
-    pub level_key: Option<String>,
-    /// Custom JSON key for the timestamp field (overrides alias table).
-    pub timestamp_key: Option<String>,
+    /// Custom JSON keys for the message field, tried in order (overrides alias table).
+    pub message_key: Option<Vec<String>>,
+    /// Fields joined (in order) to form the displayed message.
+    pub message_fields: Option<Vec<String>>,
+    /// Separator between `message_fields` values.
+    pub message_separator: String,
+    /// Keep detected fields in the extras instead of removing them.
+    pub keep_extracted: bool,
+    /// Custom JSON keys for the level field, tried in order (overrides alias table).
+    pub level_key: Option<Vec<String>>,
+    /// Custom JSON keys for the timestamp field, tried in order (overrides alias table).
+    pub timestamp_key: Option<Vec<String>>,
     /// Custom JSON key for the logger name field (overrides alias table).
     pub logger_key: Option<String>,
     /// Custom JSON key for the caller/source field (overrides alias table).
@@ -41,30 +71,155 @@ pub struct Config {
     pub include_fields: Option<Vec<String>>,
     /// Blacklist of extra fields to hide (mutually exclusive with `include_fields`).
     pub exclude_fields: Option<Vec<String>>,
+    /// Per-level include/exclude rules replacing the config file's global
+    /// ones for records of that level; command-line lists still apply.
+    pub level_fields: Option<Arc<HashMap<Level, FieldFilter>>>,
     /// Output raw JSON instead of colorized text (for piping to other tools).
     pub json_output: bool,
+    /// With `json_output`, emit flat JSON with normalized `ts`/`level`/`msg`
+    /// instead of the input JSON.
+    pub flat_json: bool,
+    /// With `json_output`, emit CSV/TSV rows of the chosen columns instead.
+    pub table: Option<Table>,
+    /// Indent and highlight JSON output.
+    pub json_pretty: bool,
+    /// Add a `_cor` provenance object to normalized JSON output.
+    pub provenance: bool,
+    /// The `--preset` applied, reported in provenance.
+    pub preset: Option<Preset>,
     /// Maximum character length for extra field values before truncation. 0 = no limit.
     pub max_field_length: usize,
+    /// Which part of a truncated value is kept.
+    pub truncate_mode: TruncateMode,
+    /// Extra fields shown per record before the rest are folded into a
+    /// `+N more fields` marker (0 = no limit).
+    pub max_fields: usize,
     /// Timestamp display format string (strftime-compatible).
     pub timestamp_format: String,
     /// Custom level name aliases mapping string → [`Level`].
-    pub level_aliases: Option<HashMap<String, Level>>,
+    pub level_aliases: Option<Arc<HashMap<String, Level>>>,
     /// Number of blank lines inserted between each log entry. 0 = compact (no gaps).
     pub line_gap: usize,
     /// Minimum width for extra field key alignment (right-justified).
     pub key_min_width: usize,
-    /// Custom colors for log level badges (maps level → color name).
+    /// Custom colors for log level badges as configured (maps level → color
+    /// spec); they are drawn from `theme`, which includes them.
     pub level_colors: Option<HashMap<Level, String>>,
     /// Hide all extra fields, showing only timestamp/level/logger/message/caller/error.
     pub no_extra: bool,
+    /// Don't show the logger name (`--hide-logger`).
+    pub hide_logger: bool,
     /// Render extra fields inline on the same line as the message.
     pub single_line: bool,
     /// Timezone for timestamp display (default: UTC).
     pub timezone: jiff::tz::TimeZone,
+    /// Show the offset from the first record instead of or beside the timestamp.
+    pub elapsed: Option<ElapsedMode>,
+    /// Record the elapsed column measures from (`--relative`).
+    pub elapsed_origin: Origin,
+    /// Gaps between records that turn the elapsed column yellow and red.
+    pub elapsed_thresholds: Thresholds,
+    /// Numeric field drawn as a sparkline after each record (`--sparkline`).
+    pub sparkline: Option<String>,
+    /// Hour clock for timestamp display; 12-hour rewrites `timestamp_format`.
+    pub clock: ClockMode,
+    /// Language for day and month names in timestamps.
+    pub locale: Locale,
+    /// Field expressions a record must all match to be shown (`--where`,
+    /// `--filter`, `--in`, `--not-in`, `--min`, `--max`).
+    pub where_filter: Vec<Expr>,
+    /// Only show records changing this field's value (`--watch-field`).
+    pub watch_field: Option<String>,
+    /// Fields whose repeated values get a `(seen N×)` counter (`--seen-counts`).
+    pub seen_counts: Option<Vec<String>>,
+    /// Show only the first record per key (`--first-only`).
+    pub first_only: Option<DedupKey>,
+    /// Fraction of records below `sample_level` shown (`--sample`).
+    pub sample: Option<f64>,
+    /// Lowest level `sample` never drops.
+    pub sample_level: Level,
+    /// Most entries written to stdout per window (`--throttle`).
+    pub throttle: Option<Rate>,
     /// Regex pattern to filter lines by field values.
     pub grep_pattern: Option<regex::Regex>,
+    /// Regex pattern hiding lines whose field values match (`--grep-v`).
+    pub grep_exclude: Option<regex::Regex>,
+    /// Patterns colored in messages and field values (`--highlight`).
+    pub highlights: Vec<Highlight>,
+    /// Records ending the run with a failure (`--fail-on`); any may match.
+    pub fail_on: Vec<Expr>,
+    /// Input lines ending the run with a failure (`--fail-on-grep`).
+    pub fail_on_grep: Option<regex::Regex>,
+    /// Regex whose named groups turn the embedded-JSON prefix into fields.
+    pub prefix_pattern: Option<regex::Regex>,
     /// Show parse errors for lines that look like JSON but fail to parse.
     pub verbose: bool,
+    /// Exit non-zero after processing if any line that looked like JSON
+    /// failed to parse.
+    pub strict: bool,
+    /// Quoting policy for string field values.
+    pub quote_values: QuoteMode,
+    /// Produce byte-stable output (see [`Config::apply_stable`]).
+    pub stable: bool,
+    /// Time year-less klog dates are placed against; `None` reads the
+    /// clock. Pinned to [`klog::STABLE_NOW`] by `--stable`.
+    pub year_reference: Option<jiff::Timestamp>,
+    /// Print control characters and escape sequences verbatim instead of
+    /// escaping (field values) or stripping (raw lines) them.
+    pub raw_control_chars: bool,
+    /// Draw ASCII-only markers (see [`crate::glyphs`]).
+    pub ascii: bool,
+    /// Ask the terminal for its background color to adapt the theme.
+    pub detect_background: bool,
+    /// When formatted output is flushed to stdout.
+    pub buffer_mode: BufferMode,
+    /// Level and field-cluster icons; none unless opted in.
+    pub icons: Icons,
+    /// Colors of the formatted output.
+    pub theme: Theme,
+    /// `[theme]` element colors, kept when `--theme` switches the palette.
+    pub theme_overrides: Overrides,
+    /// Transform stages run on each JSON object, in order (`pipeline`).
+    pub pipeline: Vec<Stage>,
+    /// Skip multi-line reassembly for line shapes that recently failed to reassemble.
+    pub reassembly_cache: bool,
+    /// Maximum number of continuation lines buffered during multi-line reassembly.
+    pub max_continuation_lines: usize,
+    /// Maximum number of bytes buffered during multi-line reassembly. 0 = no limit.
+    pub max_continuation_bytes: usize,
+    /// Give up on multi-line reassembly once this much time has passed.
+    pub continuation_timeout: Option<std::time::Duration>,
+    /// Flush held continuation lines as raw once input has been idle this long.
+    pub continuation_idle: Option<std::time::Duration>,
+    /// Exit when no input has arrived for this long.
+    pub input_timeout: Option<std::time::Duration>,
+    /// Exit code used when `input_timeout` expires.
+    pub timeout_exit_code: u8,
+    /// Stop at end of input; when `false`, keep polling for more.
+    pub exit_on_eof: bool,
+    /// Conditional formatting rules from `[[rules]]`, in file order.
+    pub rules: Vec<Rule>,
+    /// Tint records matching this expression and dim the rest.
+    pub focus: Option<Expr>,
+    /// Draw records failing the filters in dark grey instead of hiding them.
+    pub dim_unmatched: bool,
+    /// Bookmark records matching this expression.
+    pub mark_on: Option<Expr>,
+    /// Append bookmarks to this file instead of printing an index to stderr.
+    pub mark_file: Option<PathBuf>,
+    /// Write records into one file per value of this field.
+    pub split_by: Option<String>,
+    /// Directory for `split_by` output files.
+    pub split_dir: PathBuf,
+}
+
+/// Include/exclude rule for extra fields.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct FieldFilter {
+    /// Only show these fields.
+    pub include: Option<Vec<String>>,
+    /// Hide these fields.
+    pub exclude: Option<Vec<String>>,
 }
 
 impl Default for Config {
@@ -73,6 +228,9 @@ impl Default for Config {
             color_mode: ColorMode::Auto,
             min_level: None,
             message_key: None,
+            message_fields: None,
+            message_separator: ": ".to_string(),
+            keep_extracted: false,
             level_key: None,
             timestamp_key: None,
             logger_key: None,
@@ -80,18 +238,72 @@ impl Default for Config {
             error_key: None,
             include_fields: None,
             exclude_fields: None,
+            level_fields: None,
             json_output: false,
+            flat_json: false,
+            table: None,
+            json_pretty: false,
+            provenance: false,
+            preset: None,
             max_field_length: 120,
+            truncate_mode: TruncateMode::Head,
+            max_fields: 0,
             timestamp_format: "%Y-%m-%dT%H:%M:%S%.3f".to_string(),
             level_aliases: None,
             line_gap: 1,
             key_min_width: 25,
             level_colors: None,
             no_extra: false,
+            hide_logger: false,
             single_line: false,
             timezone: jiff::tz::TimeZone::UTC,
+            elapsed: None,
+            elapsed_origin: Origin::default(),
+            elapsed_thresholds: Thresholds::default(),
+            sparkline: None,
+            clock: ClockMode::H24,
+            locale: Locale::En,
+            where_filter: Vec::new(),
+            watch_field: None,
+            seen_counts: None,
+            first_only: None,
+            sample: None,
+            sample_level: sample::DEFAULT_LEVEL,
+            throttle: None,
             grep_pattern: None,
+            grep_exclude: None,
+            highlights: Vec::new(),
+            fail_on: Vec::new(),
+            fail_on_grep: None,
+            prefix_pattern: None,
             verbose: false,
+            strict: false,
+            quote_values: QuoteMode::Never,
+            stable: false,
+            year_reference: None,
+            raw_control_chars: false,
+            ascii: false,
+            detect_background: true,
+            buffer_mode: BufferMode::Line,
+            icons: Icons::default(),
+            theme: Theme::default(),
+            theme_overrides: Overrides::default(),
+            pipeline: Stage::DEFAULT.to_vec(),
+            reassembly_cache: true,
+            max_continuation_lines: 200,
+            max_continuation_bytes: 1024 * 1024,
+            continuation_timeout: None,
+            continuation_idle: None,
+            input_timeout: None,
+            timeout_exit_code: 124,
+            exit_on_eof: true,
+            rules: Vec::new(),
+            focus: None,
+            dim_unmatched: false,
+            mark_on: None,
+            mark_file: None,
+            split_by: None,
+            split_dir: PathBuf::from("."),
         }
     }
 }
@@ -104,6 +316,12 @@ impl Config {
         // Start with defaults
         let mut config = Self::default();
 
+        // A non-UTF-8 locale is an ambient default, like the TTY check for
+        // colors; stable output ignores it.
+        if !cli.stable {
+            config.ascii = glyphs::locale_wants_ascii(|name| std::env::var(name).ok());
+        }
+
         // Load config file: explicit --config must exist, default path is optional.
         let explicit_config = cli.config.is_some();
         let config_path = cli.config.clone().unwrap_or_else(Self::default_config_path);
@@ -118,68 +336,304 @@ impl Config {
             )));
         }
 
+        // Presets sit between the config file and explicit CLI flags.
+        if let Some(preset) = cli.preset {
+            config.apply_preset(preset);
+        }
+
+        config.apply_cli(cli)?;
+
+        if config.clock == ClockMode::H12 {
+            config.timestamp_format = to_twelve_hour(&config.timestamp_format);
+        }
+
+        // Stable mode wins over everything that could vary between runs.
+        if cli.stable {
+            config.apply_stable();
+        }
+        Ok(config)
+    }
+
+    /// Apply explicit CLI flags on top of the config file and preset.
+    fn apply_cli(&mut self, cli: &Cli) -> Result<(), CorError> {
         // CLI overrides (CLI takes precedence over config file)
-        config.color_mode = cli.color;
+        if let Some(color) = cli.color {
+            self.color_mode = color;
+        }
 
         if let Some(ref level_str) = cli.level {
-            config.min_level = Level::from_str_loose(level_str);
+            self.min_level = Level::from_str_loose(level_str);
         }
 
         // CLI key overrides replace config file settings
         if let Some(ref key) = cli.message_key {
-            config.message_key = Some(key.clone());
+            self.message_key = Some(key.clone());
+        }
+        if let Some(ref fields) = cli.message_fields {
+            self.message_fields = Some(fields.clone());
+        }
+        if let Some(ref sep) = cli.message_separator {
+            self.message_separator.clone_from(sep);
+        }
+        if cli.keep_extracted {
+            self.keep_extracted = true;
         }
         if let Some(ref key) = cli.level_key {
-            config.level_key = Some(key.clone());
+            self.level_key = Some(key.clone());
         }
         if let Some(ref key) = cli.timestamp_key {
-            config.timestamp_key = Some(key.clone());
+            self.timestamp_key = Some(key.clone());
         }
         if let Some(ref key) = cli.logger_key {
-            config.logger_key = Some(key.clone());
+            self.logger_key = Some(key.clone());
         }
         if let Some(ref key) = cli.caller_key {
-            config.caller_key = Some(key.clone());
+            self.caller_key = Some(key.clone());
         }
         if let Some(ref key) = cli.error_key {
-            config.error_key = Some(key.clone());
+            self.error_key = Some(key.clone());
         }
-        if let Some(ref fields) = cli.include_fields {
-            config.include_fields = Some(fields.clone());
+        self.apply_cli_fields(cli);
+
+        self.apply_cli_format(cli);
+        if cli.no_extra {
+            self.no_extra = true;
         }
-        if let Some(ref fields) = cli.exclude_fields {
-            config.exclude_fields = Some(fields.clone());
+        if cli.hide_logger {
+            self.hide_logger = true;
         }
-
-        config.json_output = cli.json;
-        config.no_extra = cli.no_extra;
         if cli.single_line {
-            config.single_line = true;
+            self.single_line = true;
+        }
+        if cli.verbose {
+            self.verbose = true;
+        }
+        if cli.strict {
+            self.strict = true;
+        }
+        if let Some(ref pattern) = cli.grep {
+            self.grep_pattern = Some(
+                regex::Regex::new(pattern)
+                    .map_err(|e| CorError::Config(format!("invalid grep pattern: {e}")))?,
+            );
+        }
+        if let Some(ref pattern) = cli.grep_v {
+            self.grep_exclude = Some(
+                regex::Regex::new(pattern)
+                    .map_err(|e| CorError::Config(format!("invalid grep-v pattern: {e}")))?,
+            );
+        }
+        if let Some(ref pattern) = cli.fail_on_grep {
+            self.fail_on_grep = Some(
+                regex::Regex::new(pattern)
+                    .map_err(|e| CorError::Config(format!("invalid fail-on-grep pattern: {e}")))?,
+            );
+        }
+        self.fail_on.clone_from(&cli.fail_on);
+        self.highlights.extend(cli.highlight.iter().cloned());
+        self.where_filter.clone_from(&cli.where_filter);
+        self.focus.clone_from(&cli.focus);
+        self.dim_unmatched = cli.dim_unmatched;
+        self.watch_field.clone_from(&cli.watch_field);
+        self.seen_counts.clone_from(&cli.seen_counts);
+        self.first_only.clone_from(&cli.first_only);
+        self.sample = cli.sample;
+        self.throttle = cli.throttle;
+        if let Some(level) = cli.sample_level.as_deref().and_then(Level::from_str_loose) {
+            self.sample_level = level;
+        }
+        self.where_filter.extend(
+            [&cli.filter, &cli.in_set, &cli.not_in, &cli.min, &cli.max]
+                .into_iter()
+                .flatten()
+                .cloned(),
+        );
+        if let Some(ref pattern) = cli.prefix_pattern {
+            self.prefix_pattern = Some(compile_prefix_pattern(pattern)?);
         }
-        config.verbose = cli.verbose;
+        self.apply_cli_reassembly(cli);
+        self.apply_cli_input(cli);
+        self.apply_cli_display(cli)?;
+        self.apply_cli_outputs(cli)?;
+
+        Ok(())
+    }
+
+    /// Apply CLI flags choosing the output format.
+    fn apply_cli_format(&mut self, cli: &Cli) {
+        if cli.json {
+            self.json_output = true;
+        }
+        if cli.json_pretty {
+            self.json_output = true;
+            self.json_pretty = true;
+        }
+        match cli.output_format {
+            Some(OutputFormat::Json) => self.json_output = true,
+            Some(OutputFormat::FlatJson) => {
+                // JSON Lines: no blank lines between records.
+                self.json_output = true;
+                self.flat_json = true;
+                self.line_gap = 0;
+            }
+            Some(format @ (OutputFormat::Csv | OutputFormat::Tsv)) => {
+                self.json_output = true;
+                self.line_gap = 0;
+                self.table = Some(Table {
+                    delimiter: if format == OutputFormat::Csv {
+                        ','
+                    } else {
+                        '\t'
+                    },
+                    columns: cli
+                        .fields
+                        .clone()
+                        .unwrap_or_else(|| table::DEFAULT_COLUMNS.map(String::from).to_vec()),
+                });
+            }
+            Some(OutputFormat::Text) | None => {}
+        }
+        if cli.provenance {
+            self.provenance = true;
+        }
+    }
+
+    /// Apply CLI flags controlling multi-line JSON reassembly.
+    const fn apply_cli_reassembly(&mut self, cli: &Cli) {
+        if cli.no_reassembly_cache {
+            self.reassembly_cache = false;
+        }
+        if let Some(lines) = cli.max_continuation_lines {
+            self.max_continuation_lines = lines;
+        }
+        if let Some(bytes) = cli.max_continuation_bytes {
+            self.max_continuation_bytes = bytes;
+        }
+        if let Some(timeout) = cli.continuation_timeout {
+            self.continuation_timeout = Some(timeout);
+        }
+        if let Some(idle) = cli.continuation_idle {
+            self.continuation_idle = Some(idle);
+        }
+    }
+
+    /// Apply CLI flags controlling how input is waited for.
+    const fn apply_cli_input(&mut self, cli: &Cli) {
+        if let Some(timeout) = cli.timeout {
+            self.input_timeout = Some(timeout);
+        }
+        if let Some(code) = cli.timeout_exit_code {
+            self.timeout_exit_code = code;
+        }
+        if let Some(exit) = cli.exit_on_eof {
+            self.exit_on_eof = exit;
+        }
+    }
+
+    /// Apply CLI flags controlling how records are rendered.
+    fn apply_cli_display(&mut self, cli: &Cli) -> Result<(), CorError> {
         if let Some(max_len) = cli.max_field_length {
-            config.max_field_length = max_len;
+            self.max_field_length = max_len;
+        }
+        if let Some(mode) = cli.truncate_mode {
+            self.truncate_mode = mode;
+        }
+        if let Some(max_fields) = cli.max_fields {
+            self.max_fields = max_fields;
+        }
+        if cli.expand_all {
+            self.max_fields = 0;
         }
         if let Some(gap) = cli.line_gap {
-            config.line_gap = gap;
+            self.line_gap = gap;
         }
         if let Some(ref fmt) = cli.timestamp_format {
-            config.timestamp_format.clone_from(fmt);
+            self.timestamp_format.clone_from(fmt);
         }
         if let Some(width) = cli.key_min_width {
-            config.key_min_width = width;
+            self.key_min_width = width;
+        }
+        if let Some(mode) = cli.elapsed {
+            self.elapsed = Some(mode);
+        }
+        if let Some(origin) = cli.relative {
+            self.elapsed_origin = origin;
+            self.elapsed.get_or_insert(ElapsedMode::Only);
+        }
+        self.sparkline.clone_from(&cli.sparkline);
+        if let Some(clock) = cli.clock {
+            self.clock = clock;
+        }
+        if let Some(locale) = cli.locale {
+            self.locale = locale;
         }
         if let Some(ref tz_str) = cli.timezone {
-            config.timezone = parse_timezone(tz_str)?;
+            self.timezone = parse_timezone(tz_str)?;
+        } else if cli.local {
+            self.timezone = jiff::tz::TimeZone::system();
         }
-        if let Some(ref pattern) = cli.grep {
-            config.grep_pattern = Some(
-                regex::Regex::new(pattern)
-                    .map_err(|e| CorError::Config(format!("invalid grep pattern: {e}")))?,
+        if cli.raw_control_chars {
+            self.raw_control_chars = true;
+        }
+        if cli.ascii {
+            self.ascii = true;
+        }
+        if cli.no_detect_background {
+            self.detect_background = false;
+        }
+        if let Some(mode) = cli.buffer_mode {
+            self.buffer_mode = mode;
+        }
+        if let Some(set) = cli.icons {
+            self.icons.set = set;
+        }
+        if let Some(palette) = cli.theme {
+            self.theme = Theme::palette(palette).with_overrides(&self.theme_overrides);
+        }
+        if let Some(mode) = cli.quote_values {
+            self.quote_values = mode;
+        }
+        Ok(())
+    }
+
+    /// Apply CLI flags for side outputs (bookmarks, split files).
+    fn apply_cli_outputs(&mut self, cli: &Cli) -> Result<(), CorError> {
+        if let Some(ref expr) = cli.mark_on {
+            self.mark_on = Some(
+                Expr::parse(expr)
+                    .map_err(|e| CorError::Config(format!("invalid --mark-on expression: {e}")))?,
             );
         }
+        self.mark_file.clone_from(&cli.mark_file);
+        self.split_by.clone_from(&cli.split_by);
+        if let Some(ref dir) = cli.split_dir {
+            self.split_dir.clone_from(dir);
+        }
+        Ok(())
+    }
 
-        Ok(config)
+    /// Whether reassembly has a time-based cutoff, so input must be read
+    /// through a source that can wait with a deadline.
+    pub const fn has_continuation_deadline(&self) -> bool {
+        self.continuation_timeout.is_some() || self.continuation_idle.is_some()
+    }
+
+    /// Whether input must be read through a source that can wait with a
+    /// deadline (reassembly cutoffs or `--timeout`).
+    pub const fn needs_deadline(&self) -> bool {
+        self.has_continuation_deadline() || self.input_timeout.is_some()
+    }
+
+    /// Whether values of `field` get `--seen-counts` counters; `msg` and
+    /// `message` both name the message.
+    pub fn counts_seen(&self, field: &str) -> bool {
+        self.seen_counts.as_ref().is_some_and(|fields| {
+            fields.iter().any(|f| {
+                f == field
+                    || (matches!(field, "msg" | "message")
+                        && matches!(f.as_str(), "msg" | "message"))
+            })
+        })
     }
 
     /// Default config file path: `$XDG_CONFIG_HOME/cor/config.toml` or `~/.config/cor/config.toml`.
@@ -196,8 +650,107 @@ impl Config {
         }
     }
 
+    /// Apply a named [`Preset`] bundle on top of the current settings.
+    pub fn apply_preset(&mut self, preset: Preset) {
+        self.preset = Some(preset);
+        match preset {
+            Preset::K8s => {
+                self.line_gap = 0;
+                self.continuation_timeout = Some(std::time::Duration::from_secs(1));
+                self.continuation_idle = Some(std::time::Duration::from_millis(200));
+                self.timestamp_format = "%H:%M:%S%.3f".to_string();
+                self.exclude_fields = Some(
+                    ["pid", "hostname", "v"]
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect(),
+                );
+                // `kubectl logs --prefix` labels: `[pod/<pod>/<container>] `.
+                self.prefix_pattern = compile_prefix_pattern(K8S_PREFIX_PATTERN).ok();
+            }
+            Preset::Ci => {
+                self.color_mode = ColorMode::Never;
+                self.line_gap = 0;
+                self.single_line = true;
+                self.max_field_length = 0;
+            }
+            Preset::Dev => {
+                self.timezone = jiff::tz::TimeZone::system();
+                self.timestamp_format = "%H:%M:%S%.3f".to_string();
+                self.verbose = true;
+            }
+            Preset::Minimal => {
+                self.no_extra = true;
+                self.line_gap = 0;
+            }
+        }
+    }
+
+    /// Make output depend only on the input bytes.
+    ///
+    /// Ambient state is pinned: timestamps render in UTC regardless of the
+    /// system or configured timezone, colors are off regardless of TTY
+    /// detection, time-based reassembly cutoffs are disabled so slow
+    /// producers can't change how records are grouped, `--throttle`, which
+    /// drops entries by wall-clock windows, is off, and klog dates get
+    /// their year from a fixed reference instead of today.
+    pub fn apply_stable(&mut self) {
+        self.stable = true;
+        self.timezone = jiff::tz::TimeZone::UTC;
+        self.color_mode = ColorMode::Never;
+        self.continuation_timeout = None;
+        self.continuation_idle = None;
+        self.throttle = None;
+        self.year_reference = Some(klog::STABLE_NOW);
+    }
+
+    /// Apply the `[levels]` alias table from the config file.
+    fn apply_file_levels(&mut self, levels: HashMap<String, String>) {
+        let mut aliases = HashMap::new();
+        for (key, value) in levels {
+            if let Some(level) = Level::from_str_loose(&value) {
+                aliases.insert(key.to_lowercase(), level);
+            }
+        }
+        if !aliases.is_empty() {
+            self.level_aliases = Some(Arc::new(aliases));
+        }
+    }
+
+    /// Apply the `[icons]` table: `set` picks the built-in set, other keys
+    /// override single icons.
+    fn apply_file_icons(&mut self, mut icons: HashMap<String, String>) {
+        if let Some(set) = icons.remove("set") {
+            self.icons.set = IconSet::from_str(&set, true).unwrap_or_default();
+        }
+        self.icons.overrides = icons;
+    }
+
+    /// Apply the clock, locale, and timezone settings of the config file.
+    fn apply_file_time(&mut self, file: &FileConfig) {
+        if let Some(clock) = file.clock {
+            self.clock = if clock == 12 {
+                ClockMode::H12
+            } else {
+                ClockMode::H24
+            };
+        }
+
+        if let Some(locale) = file.locale.as_deref().and_then(Locale::from_str_loose) {
+            self.locale = locale;
+        }
+
+        if let Some(ref tz_str) = file.timezone
+            && let Ok(tz) = parse_timezone(tz_str)
+        {
+            self.timezone = tz;
+        }
+    }
+
     /// Apply settings from a parsed config file.
     fn apply_file_config(&mut self, file: FileConfig) {
+        self.apply_file_time(&file);
+
         if let Some(color) = file.color {
             self.color_mode = match color.as_str() {
                 "always" => ColorMode::Always,
@@ -214,10 +767,30 @@ impl Config {
             self.timestamp_format = format;
         }
 
+        if let Some(fields) = file.message_fields {
+            self.message_fields = Some(fields);
+        }
+
+        if let Some(sep) = file.message_separator {
+            self.message_separator = sep;
+        }
+
         if let Some(max_len) = file.max_field_length {
             self.max_field_length = max_len;
         }
 
+        if let Some(mode) = file.truncate_mode {
+            self.truncate_mode = match mode.as_str() {
+                "middle" => TruncateMode::Middle,
+                "tail" => TruncateMode::Tail,
+                _ => TruncateMode::Head,
+            };
+        }
+
+        if let Some(max_fields) = file.max_fields {
+            self.max_fields = max_fields;
+        }
+
         if let Some(gap) = file.line_gap {
             self.line_gap = gap;
         }
@@ -230,60 +803,491 @@ impl Config {
             self.single_line = single_line;
         }
 
-        if let Some(ref tz_str) = file.timezone
-            && let Ok(tz) = parse_timezone(tz_str)
-        {
-            self.timezone = tz;
+        if let Some(quote) = file.quote_values {
+            self.quote_values = match quote.as_str() {
+                "auto" => QuoteMode::Auto,
+                "always" => QuoteMode::Always,
+                _ => QuoteMode::Never,
+            };
+        }
+
+        if let Some(ascii) = file.ascii {
+            self.ascii = ascii;
+        }
+
+        if let Some(detect) = file.detect_background {
+            self.detect_background = detect;
+        }
+
+        if let Some(icons) = file.icons {
+            self.apply_file_icons(icons);
+        }
+
+        self.apply_file_patterns(file.highlight, file.prefix_pattern);
+
+        if let Some(elapsed) = file.elapsed {
+            self.apply_file_elapsed(elapsed);
+        }
+
+        if let Some(theme) = file.theme {
+            self.apply_file_theme(theme);
+        }
+
+        if let Some(mode) = file.buffer_mode {
+            self.buffer_mode = match mode.as_str() {
+                "block" => BufferMode::Block,
+                "none" => BufferMode::None,
+                _ => BufferMode::Line,
+            };
+        }
+
+        if let Some(keys) = file.keys {
+            self.apply_file_keys(keys);
+        }
+
+        if let Some(levels) = file.levels {
+            self.apply_file_levels(levels);
+        }
+
+        if let Some(fields) = file.fields {
+            self.apply_file_fields(fields);
+        }
+
+        if let Some(json_pretty) = file.json_pretty {
+            self.json_pretty = json_pretty;
+        }
+
+        if let Some(provenance) = file.provenance {
+            self.provenance = provenance;
+        }
+
+        if let Some(pipeline) = file.pipeline {
+            self.pipeline = pipeline;
+        }
+
+        if let Some(rules) = file.rules {
+            // Already validated in `FileConfig::load`.
+            self.rules = rules
+                .into_iter()
+                .filter_map(|r| Rule::compile(r).ok())
+                .collect();
+        }
+
+        if let Some(colors) = file.colors {
+            self.apply_file_colors(colors);
+        }
+    }
+}
+
+impl Config {
+    /// Apply the `[colors]` table of level badge colors. Unknown levels and
+    /// invalid colors are skipped, keeping the theme's.
+    fn apply_file_colors(&mut self, colors: HashMap<String, String>) {
+        for (level_str, color) in colors {
+            if let Some(level) = Level::from_str_loose(&level_str) {
+                self.set_level_color(level, &color);
+            }
+        }
+    }
+
+    /// Draw the `level` badge bold in `color`: an ANSI name, `#rrggbb`, or
+    /// a 256-color index. Returns `false`, changing nothing, when the color
+    /// is invalid.
+    fn set_level_color(&mut self, level: Level, color: &str) -> bool {
+        let Some(paint) = Paint::parse(color).ok().filter(|p| p.fg.is_some()) else {
+            return false;
+        };
+        self.theme_overrides.set_level(
+            level,
+            Paint {
+                bold: true,
+                ..paint
+            },
+        );
+        self.theme = self.theme.clone().with_overrides(&self.theme_overrides);
+        self.level_colors
+            .get_or_insert_default()
+            .insert(level, color.to_lowercase());
+        true
+    }
+
+    /// Apply `theme`: a palette name, or a `[theme]` table.
+    fn apply_file_theme(&mut self, theme: ThemeConfig) {
+        let table = match theme {
+            ThemeConfig::Palette(palette) => ThemeTable {
+                palette: Some(palette),
+                ..ThemeTable::default()
+            },
+            ThemeConfig::Table(table) => *table,
+        };
+        let palette = table
+            .palette
+            .as_deref()
+            .map(|name| Palette::from_str(name, true).unwrap_or_default())
+            .unwrap_or_default();
+        // Already validated in `FileConfig::load`.
+        self.theme_overrides = table.overrides().unwrap_or_default();
+        self.theme = Theme::palette(palette).with_overrides(&self.theme_overrides);
+    }
+
+    /// Apply the `highlight` and `prefix_pattern` regexes.
+    fn apply_file_patterns(&mut self, highlight: Option<Vec<String>>, prefix: Option<String>) {
+        // Already validated in `FileConfig::load`.
+        if let Some(patterns) = highlight {
+            self.highlights = patterns
+                .iter()
+                .filter_map(|p| Highlight::parse(p).ok())
+                .collect();
+        }
+        if let Some(pattern) = prefix {
+            self.prefix_pattern = compile_prefix_pattern(&pattern).ok();
+        }
+    }
+
+    /// Apply the `[elapsed]` latency thresholds.
+    fn apply_file_elapsed(&mut self, elapsed: ElapsedConfig) {
+        // Already validated in `FileConfig::load`.
+        if let Some(warn) = elapsed.warn.and_then(|d| parse_duration_arg(&d).ok()) {
+            self.elapsed_thresholds.warn = warn;
+        }
+        if let Some(slow) = elapsed.slow.and_then(|d| parse_duration_arg(&d).ok()) {
+            self.elapsed_thresholds.slow = slow;
+        }
+    }
+
+    /// Apply the `[keys]` table of field key overrides.
+    fn apply_file_keys(&mut self, keys: KeysConfig) {
+        if let Some(msg) = keys.message {
+            self.message_key = Some(msg.into_vec());
+        }
+        if let Some(lvl) = keys.level {
+            self.level_key = Some(lvl.into_vec());
+        }
+        if let Some(ts) = keys.timestamp {
+            self.timestamp_key = Some(ts.into_vec());
+        }
+        if let Some(logger) = keys.logger {
+            self.logger_key = Some(logger);
+        }
+        if let Some(caller) = keys.caller {
+            self.caller_key = Some(caller);
+        }
+        if let Some(error) = keys.error {
+            self.error_key = Some(error);
+        }
+    }
+
+    /// Apply the `[fields]` table: global include/exclude plus `[fields.<level>]` overrides.
+    fn apply_file_fields(&mut self, fields: FieldsConfig) {
+        if fields.include.is_some() {
+            self.include_fields = fields.include;
+        }
+        if fields.exclude.is_some() {
+            self.exclude_fields = fields.exclude;
+        }
+        let level_fields: HashMap<Level, FieldFilter> = fields
+            .levels
+            .into_iter()
+            .filter_map(|(name, rule)| Level::from_str_loose(&name).map(|level| (level, rule)))
+            .collect();
+        if !level_fields.is_empty() {
+            self.level_fields = Some(Arc::new(level_fields));
+        }
+    }
+
+    /// Apply `--include-fields`/`--exclude-fields`. They replace the global
+    /// lists and narrow every `[fields.<level>]` rule, so they hold for
+    /// records of all levels.
+    fn apply_cli_fields(&mut self, cli: &Cli) {
+        let (include, exclude) = (cli.include_fields.as_deref(), cli.exclude_fields.as_deref());
+        if let Some(fields) = include {
+            self.include_fields = Some(fields.to_vec());
+        }
+        if let Some(fields) = exclude {
+            self.exclude_fields = Some(fields.to_vec());
+        }
+        let Some(ref mut level_fields) = self.level_fields else {
+            return;
+        };
+        for rule in Arc::make_mut(level_fields).values_mut() {
+            if let Some(include) = include {
+                rule.include = Some(match rule.include.take() {
+                    Some(own) => own.into_iter().filter(|f| include.contains(f)).collect(),
+                    None => include.to_vec(),
+                });
+            }
+            if let Some(exclude) = exclude {
+                rule.exclude
+                    .get_or_insert_default()
+                    .extend(exclude.iter().cloned());
+            }
+        }
+    }
+
+    /// Icons to draw, or `None` when off or ASCII output is requested.
+    pub fn icons(&self) -> Option<&Icons> {
+        (self.icons.enabled() && !self.ascii).then_some(&self.icons)
+    }
+
+    /// Glyphs for output markers: ASCII-only with `--ascii`.
+    pub const fn glyphs(&self) -> &'static Glyphs {
+        if self.ascii {
+            &glyphs::ASCII
+        } else {
+            &glyphs::UNICODE
+        }
+    }
+
+    /// Whether the extra field `key` is shown for a record at `level`.
+    ///
+    /// A `[fields.<level>]` rule replaces the global include/exclude lists.
+    pub fn allows_field(&self, level: Option<Level>, key: &str) -> bool {
+        let (include, exclude) = match level.and_then(|l| self.level_fields.as_ref()?.get(&l)) {
+            Some(rule) => (rule.include.as_deref(), rule.exclude.as_deref()),
+            None => (
+                self.include_fields.as_deref(),
+                self.exclude_fields.as_deref(),
+            ),
+        };
+        include.is_none_or(|fields| fields.iter().any(|f| f == key))
+            && !exclude.is_some_and(|fields| fields.iter().any(|f| f == key))
+    }
+}
+
+/// Builder for a [`Config`], for library users who would otherwise write
+/// struct literals. Settings are checked by [`ConfigBuilder::build`].
+///
+/// ```
+/// use cor::{Config, Level};
+///
+/// let config = Config::builder()
+///     .min_level(Level::Warn)
+///     .exclude_fields(["pid", "hostname"])
+///     .level_color(Level::Error, "#ff5f5f")
+///     .grep("timeout|refused")
+///     .build()?;
+/// assert_eq!(config.min_level, Some(Level::Warn));
+/// # Ok::<(), cor::CorError>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+#[must_use]
+pub struct ConfigBuilder {
+    config: Config,
+    timezone: Option<String>,
+    grep: Option<String>,
+    grep_v: Option<String>,
+    prefix_pattern: Option<String>,
+    level_colors: Vec<(Level, String)>,
+}
+
+impl Config {
+    /// Start a [`ConfigBuilder`] from the built-in defaults.
+    pub fn builder() -> ConfigBuilder {
+        ConfigBuilder::default()
+    }
+}
+
+impl ConfigBuilder {
+    /// Color output mode.
+    pub const fn color_mode(mut self, mode: ColorMode) -> Self {
+        self.config.color_mode = mode;
+        self
+    }
+
+    /// Hide records below `level`.
+    pub const fn min_level(mut self, level: Level) -> Self {
+        self.config.min_level = Some(level);
+        self
+    }
+
+    /// Keys of the message field, tried in order.
+    pub fn message_key<S: Into<String>>(mut self, keys: impl IntoIterator<Item = S>) -> Self {
+        self.config.message_key = Some(keys.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Keys of the level field, tried in order.
+    pub fn level_key<S: Into<String>>(mut self, keys: impl IntoIterator<Item = S>) -> Self {
+        self.config.level_key = Some(keys.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Keys of the timestamp field, tried in order.
+    pub fn timestamp_key<S: Into<String>>(mut self, keys: impl IntoIterator<Item = S>) -> Self {
+        self.config.timestamp_key = Some(keys.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Only show these extra fields. Cannot be combined with
+    /// [`exclude_fields`](Self::exclude_fields).
+    pub fn include_fields<S: Into<String>>(mut self, fields: impl IntoIterator<Item = S>) -> Self {
+        self.config.include_fields = Some(fields.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Hide these extra fields. Cannot be combined with
+    /// [`include_fields`](Self::include_fields).
+    pub fn exclude_fields<S: Into<String>>(mut self, fields: impl IntoIterator<Item = S>) -> Self {
+        self.config.exclude_fields = Some(fields.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Hide all extra fields.
+    pub const fn no_extra(mut self, no_extra: bool) -> Self {
+        self.config.no_extra = no_extra;
+        self
+    }
+
+    /// Render extra fields inline as `key=val`.
+    pub const fn single_line(mut self, single_line: bool) -> Self {
+        self.config.single_line = single_line;
+        self
+    }
+
+    /// Output the input JSON instead of colorized text.
+    pub const fn json_output(mut self, json_output: bool) -> Self {
+        self.config.json_output = json_output;
+        self
+    }
+
+    /// Truncate extra field values longer than `max_len` characters
+    /// (0 = no limit).
+    pub const fn max_field_length(mut self, max_len: usize) -> Self {
+        self.config.max_field_length = max_len;
+        self
+    }
+
+    /// Which part of a truncated value is kept.
+    pub const fn truncate_mode(mut self, mode: TruncateMode) -> Self {
+        self.config.truncate_mode = mode;
+        self
+    }
+
+    /// Fold extra fields past the first `max` (0 = no limit).
+    pub const fn max_fields(mut self, max: usize) -> Self {
+        self.config.max_fields = max;
+        self
+    }
+
+    /// Blank lines between entries.
+    pub const fn line_gap(mut self, gap: usize) -> Self {
+        self.config.line_gap = gap;
+        self
+    }
+
+    /// Minimum width of extra field keys.
+    pub const fn key_min_width(mut self, width: usize) -> Self {
+        self.config.key_min_width = width;
+        self
+    }
+
+    /// Quoting policy for string field values.
+    pub const fn quote_values(mut self, mode: QuoteMode) -> Self {
+        self.config.quote_values = mode;
+        self
+    }
+
+    /// Draw ASCII-only markers.
+    pub const fn ascii(mut self, ascii: bool) -> Self {
+        self.config.ascii = ascii;
+        self
+    }
+
+    /// Timestamp display format (strftime).
+    pub fn timestamp_format(mut self, format: impl Into<String>) -> Self {
+        self.config.timestamp_format = format.into();
+        self
+    }
+
+    /// Timezone for timestamps: `local`, `UTC`, or an IANA name.
+    pub fn timezone(mut self, tz: impl Into<String>) -> Self {
+        self.timezone = Some(tz.into());
+        self
+    }
+
+    /// Only show entries with a field value matching this regex.
+    pub fn grep(mut self, pattern: impl Into<String>) -> Self {
+        self.grep = Some(pattern.into());
+        self
+    }
+
+    /// Hide entries with a field value matching this regex.
+    pub fn grep_v(mut self, pattern: impl Into<String>) -> Self {
+        self.grep_v = Some(pattern.into());
+        self
+    }
+
+    /// Regex whose named groups turn the embedded-JSON prefix into fields.
+    pub fn prefix_pattern(mut self, pattern: impl Into<String>) -> Self {
+        self.prefix_pattern = Some(pattern.into());
+        self
+    }
+
+    /// Badge color of `level`: an ANSI name, `#rrggbb`, or a 256-color
+    /// index, drawn bold.
+    pub fn level_color(mut self, level: Level, color: impl Into<String>) -> Self {
+        self.level_colors.push((level, color.into()));
+        self
+    }
+
+    /// Check the settings and build the [`Config`].
+    ///
+    /// # Errors
+    ///
+    /// [`CorError::Config`] for conflicting field filters, an invalid
+    /// timestamp format, timezone, regex, or color.
+    pub fn build(self) -> Result<Config, CorError> {
+        let mut config = self.config;
+        if config.include_fields.is_some() && config.exclude_fields.is_some() {
+            return Err(CorError::Config(
+                "include_fields and exclude_fields are mutually exclusive".to_string(),
+            ));
+        }
+        validate_format(&config.timestamp_format).map_err(CorError::Config)?;
+        if let Some(ref tz) = self.timezone {
+            config.timezone = parse_timezone(tz)?;
+        }
+        if let Some(ref pattern) = self.grep {
+            config.grep_pattern = Some(
+                regex::Regex::new(pattern)
+                    .map_err(|e| CorError::Config(format!("invalid grep pattern: {e}")))?,
+            );
+        }
+        if let Some(ref pattern) = self.grep_v {
+            config.grep_exclude = Some(
+                regex::Regex::new(pattern)
+                    .map_err(|e| CorError::Config(format!("invalid grep-v pattern: {e}")))?,
+            );
         }
-
-        if let Some(keys) = file.keys {
-            if let Some(msg) = keys.message {
-                self.message_key = Some(msg);
-            }
-            if let Some(lvl) = keys.level {
-                self.level_key = Some(lvl);
-            }
-            if let Some(ts) = keys.timestamp {
-                self.timestamp_key = Some(ts);
-            }
-            if let Some(logger) = keys.logger {
-                self.logger_key = Some(logger);
-            }
-            if let Some(caller) = keys.caller {
-                self.caller_key = Some(caller);
-            }
-            if let Some(error) = keys.error {
-                self.error_key = Some(error);
-            }
+        if let Some(ref pattern) = self.prefix_pattern {
+            config.prefix_pattern = Some(compile_prefix_pattern(pattern)?);
         }
-
-        if let Some(levels) = file.levels {
-            let mut aliases = HashMap::new();
-            for (key, value) in levels {
-                if let Some(level) = Level::from_str_loose(&value) {
-                    aliases.insert(key.to_lowercase(), level);
-                }
-            }
-            if !aliases.is_empty() {
-                self.level_aliases = Some(aliases);
+        for (level, color) in self.level_colors {
+            if !config.set_level_color(level, &color) {
+                return Err(CorError::Config(format!(
+                    "invalid color '{color}' for level {}",
+                    level.name()
+                )));
             }
         }
+        Ok(config)
+    }
+}
 
-        if let Some(colors) = file.colors {
-            let mut level_colors = HashMap::new();
-            for (level_str, color) in colors {
-                if let Some(level) = Level::from_str_loose(&level_str) {
-                    // Validate color name
-                    if color_name_to_style(&color).is_some() {
-                        level_colors.insert(level, color.to_lowercase());
-                    }
-                }
-            }
-            if !level_colors.is_empty() {
-                self.level_colors = Some(level_colors);
-            }
-        }
+/// The line prefix `kubectl logs --prefix` writes, read by the `k8s`
+/// preset into `pod` and `container` fields.
+const K8S_PREFIX_PATTERN: &str = r"^\[pod/(?P<pod>[^/\]]+)/(?P<container>[^\]]+)\] ";
+
+/// Compile a `--prefix-pattern`, which must name at least one group.
+fn compile_prefix_pattern(pattern: &str) -> Result<regex::Regex, CorError> {
+    let re = regex::Regex::new(pattern)
+        .map_err(|e| CorError::Config(format!("invalid prefix pattern: {e}")))?;
+    if re.capture_names().flatten().next().is_none() {
+        return Err(CorError::Config(format!(
+            "prefix pattern '{pattern}' has no named groups (use `(?P<name>...)`)"
+        )));
     }
+    Ok(re)
 }
 
 /// Parse a timezone string into a [`jiff::tz::TimeZone`].
@@ -299,39 +1303,234 @@ fn parse_timezone(s: &str) -> Result<jiff::tz::TimeZone, CorError> {
 }
 
 /// Config file structure (TOML deserialization).
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 struct FileConfig {
     color: Option<String>,
     level: Option<String>,
     timestamp_format: Option<String>,
+    message_fields: Option<Vec<String>>,
+    message_separator: Option<String>,
     max_field_length: Option<usize>,
+    truncate_mode: Option<String>,
+    max_fields: Option<usize>,
     line_gap: Option<usize>,
     key_min_width: Option<usize>,
     single_line: Option<bool>,
+    quote_values: Option<String>,
+    ascii: Option<bool>,
+    detect_background: Option<bool>,
+    buffer_mode: Option<String>,
+    /// `[icons]` table: `set` plus icon overrides by level or cluster name.
+    icons: Option<HashMap<String, String>>,
+    elapsed: Option<ElapsedConfig>,
+    theme: Option<ThemeConfig>,
+    highlight: Option<Vec<String>>,
+    prefix_pattern: Option<String>,
     timezone: Option<String>,
+    clock: Option<u8>,
+    locale: Option<String>,
     keys: Option<KeysConfig>,
     levels: Option<HashMap<String, String>>,
+    fields: Option<FieldsConfig>,
     colors: Option<HashMap<String, String>>,
+    rules: Option<Vec<RuleConfig>>,
+    pipeline: Option<Vec<Stage>>,
+    json_pretty: Option<bool>,
+    provenance: Option<bool>,
+}
+
+/// `[fields]` table: global `include`/`exclude` and per-level `[fields.<level>]` rules.
+#[derive(Debug, Default, Deserialize)]
+struct FieldsConfig {
+    include: Option<Vec<String>>,
+    exclude: Option<Vec<String>>,
+    #[serde(flatten)]
+    levels: HashMap<String, FieldFilter>,
+}
+
+/// `[elapsed]` table: latency thresholds for the `--elapsed` column.
+#[derive(Debug, Default, Deserialize)]
+struct ElapsedConfig {
+    warn: Option<String>,
+    slow: Option<String>,
 }
 
+/// `theme`: a palette name, or a `[theme]` table with element colors.
 #[derive(Debug, Deserialize)]
-struct KeysConfig {
-    message: Option<String>,
-    level: Option<String>,
+#[serde(untagged)]
+enum ThemeConfig {
+    Palette(String),
+    Table(Box<ThemeTable>),
+}
+
+/// `[theme]` table: a base `palette` and color specs per element.
+#[derive(Debug, Default, Deserialize)]
+struct ThemeTable {
+    palette: Option<String>,
     timestamp: Option<String>,
     logger: Option<String>,
+    prefix: Option<String>,
+    tag: Option<String>,
+    message: Option<String>,
+    caller: Option<String>,
+    keys: Option<String>,
+    values: Option<String>,
+    error: Option<String>,
+    raw: Option<String>,
+    json_string: Option<String>,
+    json_number: Option<String>,
+    json_literal: Option<String>,
+}
+
+impl ThemeTable {
+    /// Parse the element color specs.
+    fn overrides(&self) -> Result<Overrides, String> {
+        let paint = |key: &str, spec: &Option<String>| {
+            spec.as_deref()
+                .map(Paint::parse)
+                .transpose()
+                .map_err(|e| format!("theme.{key}: {e}"))
+        };
+        Ok(Overrides {
+            timestamp: paint("timestamp", &self.timestamp)?,
+            logger: paint("logger", &self.logger)?,
+            prefix: paint("prefix", &self.prefix)?,
+            tag: paint("tag", &self.tag)?,
+            message: paint("message", &self.message)?,
+            caller: paint("caller", &self.caller)?,
+            keys: paint("keys", &self.keys)?,
+            values: paint("values", &self.values)?,
+            error: paint("error", &self.error)?,
+            raw: paint("raw", &self.raw)?,
+            json_string: paint("json_string", &self.json_string)?,
+            json_number: paint("json_number", &self.json_number)?,
+            json_literal: paint("json_literal", &self.json_literal)?,
+            ..Overrides::default()
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct KeysConfig {
+    message: Option<KeyList>,
+    level: Option<KeyList>,
+    timestamp: Option<KeyList>,
+    logger: Option<String>,
     caller: Option<String>,
     error: Option<String>,
 }
 
+/// A key override: a single key (`"event"`, or comma-separated
+/// `"event,msg"`) or an ordered array of fallback keys.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum KeyList {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl KeyList {
+    fn into_vec(self) -> Vec<String> {
+        match self {
+            Self::One(keys) => keys.split(',').map(|k| k.trim().to_string()).collect(),
+            Self::Many(keys) => keys,
+        }
+    }
+}
+
 impl FileConfig {
     fn load(path: &PathBuf) -> Result<Self, CorError> {
         let content = std::fs::read_to_string(path).map_err(|e| {
             CorError::Config(format!("cannot read config file {}: {e}", path.display()))
         })?;
         let config: Self = toml::from_str(&content)?;
+        config.validate(path, &content)?;
         Ok(config)
     }
+
+    /// Reject values that would otherwise only fail once per log line,
+    /// pointing at the offending line of the config file.
+    fn validate(&self, path: &Path, content: &str) -> Result<(), CorError> {
+        if let Some(ref format) = self.timestamp_format
+            && let Err(e) = validate_format(format)
+        {
+            let location = key_line(content, "timestamp_format").map_or_else(
+                || path.display().to_string(),
+                |line| format!("{}:{line}", path.display()),
+            );
+            return Err(CorError::Config(format!("{location}: {e}")));
+        }
+        if let Some(ref pattern) = self.prefix_pattern
+            && let Err(CorError::Config(e)) = compile_prefix_pattern(pattern)
+        {
+            let location = key_line(content, "prefix_pattern").map_or_else(
+                || path.display().to_string(),
+                |line| format!("{}:{line}", path.display()),
+            );
+            return Err(CorError::Config(format!("{location}: {e}")));
+        }
+        for (index, pattern) in self.highlight.iter().flatten().enumerate() {
+            if let Err(e) = Highlight::parse(pattern) {
+                let location = key_line(content, "highlight").map_or_else(
+                    || path.display().to_string(),
+                    |line| format!("{}:{line}", path.display()),
+                );
+                return Err(CorError::Config(format!(
+                    "{location}: highlight[{index}]: {e}"
+                )));
+            }
+        }
+        if let Some(ThemeConfig::Table(ref table)) = self.theme
+            && let Err(e) = table.overrides()
+        {
+            return Err(CorError::Config(format!("{}: {e}", path.display())));
+        }
+        if let Some(ref elapsed) = self.elapsed {
+            for (key, value) in [("warn", &elapsed.warn), ("slow", &elapsed.slow)] {
+                if let Some(value) = value
+                    && let Err(e) = parse_duration_arg(value)
+                {
+                    return Err(CorError::Config(format!(
+                        "{}: elapsed.{key}: {e}",
+                        path.display()
+                    )));
+                }
+            }
+        }
+        if let Some(ref fields) = self.fields
+            && let Some(name) = fields
+                .levels
+                .keys()
+                .find(|name| Level::from_str_loose(name).is_none())
+        {
+            return Err(CorError::Config(format!(
+                "{}: fields.{name}: unknown level; expected one of trace, debug, info, warn, error, fatal",
+                path.display()
+            )));
+        }
+        for (index, rule) in self.rules.iter().flatten().enumerate() {
+            if let Err(e) = Rule::compile(rule.clone()) {
+                return Err(CorError::Config(format!(
+                    "{}: rules[{index}]: {e}",
+                    path.display()
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 1-indexed line of the first top-level `key = ...` assignment in a TOML document.
+fn key_line(content: &str, key: &str) -> Option<usize> {
+    content
+        .lines()
+        .take_while(|line| !line.trim_start().starts_with('['))
+        .position(|line| {
+            line.trim_start()
+                .strip_prefix(key)
+                .is_some_and(|rest| rest.trim_start().starts_with('='))
+        })
+        .map(|index| index + 1)
 }
 
 #[cfg(test)]
@@ -393,7 +1592,7 @@ mod tests {
             single_line: None,
             timezone: None,
             keys: Some(KeysConfig {
-                message: Some("event".to_string()),
+                message: Some(KeyList::One("event".to_string())),
                 level: None,
                 timestamp: None,
                 logger: None,
@@ -406,12 +1605,13 @@ mod tests {
                 m
             }),
             colors: None,
+            ..FileConfig::default()
         };
 
         config.apply_file_config(file_config);
         assert_eq!(config.color_mode, ColorMode::Never);
         assert_eq!(config.min_level, Some(Level::Error));
-        assert_eq!(config.message_key.as_deref(), Some("event"));
+        assert_eq!(config.message_key, Some(vec!["event".to_string()]));
         assert_eq!(config.max_field_length, 80);
         assert_eq!(config.line_gap, 3);
         assert_eq!(config.key_min_width, 30);
@@ -474,6 +1674,7 @@ mod tests {
             keys: None,
             levels: None,
             colors: None,
+            ..FileConfig::default()
         };
         config.apply_file_config(file_config);
         assert_eq!(config.color_mode, ColorMode::Auto);
@@ -505,6 +1706,7 @@ mod tests {
                 m
             }),
             colors: None,
+            ..FileConfig::default()
         };
         config.apply_file_config(file_config);
         let aliases = config.level_aliases.unwrap();
@@ -535,6 +1737,7 @@ mod tests {
                 m
             }),
             colors: None,
+            ..FileConfig::default()
         };
         config.apply_file_config(file_config);
         assert!(
@@ -563,6 +1766,7 @@ mod tests {
                 m.insert("error".to_string(), "bright_red".to_string());
                 m
             }),
+            ..FileConfig::default()
         };
         config.apply_file_config(file_config);
         let colors = config.level_colors.unwrap();
@@ -590,6 +1794,7 @@ mod tests {
                 m.insert("error".to_string(), "red".to_string()); // valid
                 m
             }),
+            ..FileConfig::default()
         };
         config.apply_file_config(file_config);
         let colors = config.level_colors.unwrap();
@@ -620,6 +1825,7 @@ mod tests {
                 m.insert("error".to_string(), "neon".to_string());
                 m
             }),
+            ..FileConfig::default()
         };
         config.apply_file_config(file_config);
         assert!(
@@ -649,6 +1855,7 @@ mod tests {
                 m.insert("warn".to_string(), "yellow".to_string()); // valid
                 m
             }),
+            ..FileConfig::default()
         };
         config.apply_file_config(file_config);
         let colors = config.level_colors.unwrap();
@@ -678,6 +1885,7 @@ mod tests {
             }),
             levels: None,
             colors: None,
+            ..FileConfig::default()
         };
         config.apply_file_config(file_config);
         assert_eq!(config.logger_key.as_deref(), Some("service"));
@@ -704,6 +1912,7 @@ mod tests {
                 m.insert("fatal".to_string(), "purple".to_string());
                 m
             }),
+            ..FileConfig::default()
         };
         config.apply_file_config(file_config);
         let colors = config.level_colors.unwrap();
@@ -747,6 +1956,7 @@ mod tests {
             keys: None,
             levels: None,
             colors: None,
+            ..FileConfig::default()
         };
         config.apply_file_config(file_config);
         assert_eq!(
@@ -755,4 +1965,445 @@ mod tests {
             "unrecognized color value should default to Auto"
         );
     }
+
+    #[test]
+    fn test_apply_file_config_quote_values() {
+        let mut config = Config::default();
+        config.apply_file_config(FileConfig {
+            quote_values: Some("auto".to_string()),
+            ..FileConfig::default()
+        });
+        assert_eq!(config.quote_values, QuoteMode::Auto);
+    }
+
+    #[test]
+    fn test_builder_validates() {
+        let config = Config::builder()
+            .include_fields(["status"])
+            .level_color(Level::Warn, "#FF8700")
+            .timezone("Europe/Berlin")
+            .build()
+            .unwrap();
+        assert!(config.allows_field(None, "status"));
+        assert!(!config.allows_field(None, "pid"));
+        assert_eq!(config.level_colors.unwrap()[&Level::Warn], "#ff8700");
+
+        let err = |builder: ConfigBuilder| builder.build().unwrap_err().to_string();
+        assert!(
+            err(Config::builder()
+                .include_fields(["a"])
+                .exclude_fields(["b"]))
+            .contains("mutually exclusive")
+        );
+        assert!(
+            err(Config::builder().level_color(Level::Error, "not-a-color")).contains("not-a-color")
+        );
+        assert!(err(Config::builder().grep("(")).contains("invalid grep pattern"));
+        assert!(err(Config::builder().timezone("Mars/Olympus")).contains("invalid timezone"));
+    }
+
+    #[test]
+    fn test_file_config_pipeline() {
+        let file_config: FileConfig =
+            toml::from_str(r#"pipeline = ["redact", "unwrap_docker"]"#).unwrap();
+        let mut config = Config::default();
+        config.apply_file_config(file_config);
+        assert_eq!(config.pipeline, [Stage::Redact, Stage::UnwrapDocker]);
+
+        let err = toml::from_str::<FileConfig>(r#"pipeline = ["unwrap_dokcer"]"#).unwrap_err();
+        assert!(err.to_string().contains("unknown variant"));
+    }
+
+    #[test]
+    fn test_apply_file_config_truncate_mode() {
+        let mut config = Config::default();
+        config.apply_file_config(FileConfig {
+            truncate_mode: Some("tail".to_string()),
+            ..FileConfig::default()
+        });
+        assert_eq!(config.truncate_mode, TruncateMode::Tail);
+    }
+
+    #[test]
+    fn test_apply_file_config_buffer_mode() {
+        let mut config = Config::default();
+        config.apply_file_config(FileConfig {
+            buffer_mode: Some("block".to_string()),
+            ..FileConfig::default()
+        });
+        assert_eq!(config.buffer_mode, BufferMode::Block);
+    }
+
+    #[test]
+    fn test_apply_file_config_theme() {
+        let mut config = Config::default();
+        config.apply_file_config(FileConfig {
+            theme: Some(ThemeConfig::Palette("Protanopia".to_string())),
+            ..FileConfig::default()
+        });
+        assert_eq!(config.theme, Theme::palette(Palette::Protanopia));
+    }
+
+    #[test]
+    fn test_apply_file_config_theme_table() {
+        let toml_str = r#"
+[theme]
+palette = "deuteranopia"
+message = "bold"
+keys = "cyan"
+raw = "dimmed"
+"#;
+        let mut config = Config::default();
+        config.apply_file_config(toml::from_str(toml_str).unwrap());
+        let palette = Theme::palette(Palette::Deuteranopia);
+        assert_eq!(config.theme.levels, palette.levels);
+        assert_eq!(config.theme.message, Paint::parse("bold").unwrap());
+        assert_eq!(
+            config.theme.key("http.status"),
+            Paint::parse("cyan").unwrap()
+        );
+        assert_eq!(config.theme.raw, Paint::parse("dimmed").unwrap());
+        assert_eq!(config.theme.timestamp, palette.timestamp);
+
+        // Switching the palette from the CLI keeps the element colors.
+        let cli = Cli::parse_from(["cor", "--theme", "protanopia"]);
+        config.apply_cli(&cli).unwrap();
+        assert_eq!(
+            config.theme.levels,
+            Theme::palette(Palette::Protanopia).levels
+        );
+        assert_eq!(config.theme.message, Paint::parse("bold").unwrap());
+    }
+
+    #[test]
+    fn test_theme_table_rejects_unknown_color() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "[theme]\nkeys = \"bold teal\"\n").unwrap();
+        let err = FileConfig::load(&path).unwrap_err().to_string();
+        assert!(
+            err.contains("theme.keys: unknown color or style 'teal'"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_file_config_highlight_patterns() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "line_gap = 0\nhighlight = [\"ok\", \"(:red\"]\n").unwrap();
+        let err = FileConfig::load(&path).unwrap_err().to_string();
+        assert!(
+            err.contains("config.toml:2: highlight[1]: invalid highlight pattern '('"),
+            "{err}"
+        );
+
+        let file_config: FileConfig = toml::from_str("highlight = [\"req-\\\\d+:cyan\"]").unwrap();
+        let mut config = Config::default();
+        config.apply_file_config(file_config);
+        assert_eq!(config.highlights.len(), 1);
+        assert_eq!(config.highlights[0].regex.as_str(), r"req-\d+");
+    }
+
+    #[test]
+    fn test_prefix_pattern_needs_named_groups() {
+        assert!(compile_prefix_pattern(r"^(?P<pod>\S+) ").is_ok());
+        let err = compile_prefix_pattern(r"^(\S+) ").unwrap_err();
+        assert!(err.to_string().contains("no named groups"), "{err}");
+        assert!(compile_prefix_pattern("(").is_err());
+    }
+
+    #[test]
+    fn test_file_config_elapsed_thresholds() {
+        let file_config: FileConfig = toml::from_str("[elapsed]\nwarn = \"500ms\"\n").unwrap();
+        let mut config = Config::default();
+        config.apply_file_config(file_config);
+        assert_eq!(
+            config.elapsed_thresholds.warn,
+            std::time::Duration::from_millis(500)
+        );
+        assert_eq!(config.elapsed_thresholds.slow, Thresholds::default().slow);
+
+        let bad: FileConfig = toml::from_str("[elapsed]\nslow = \"soon\"\n").unwrap();
+        let err = bad.validate(Path::new("c.toml"), "").unwrap_err();
+        assert!(err.to_string().contains("elapsed.slow"), "{err}");
+    }
+
+    #[test]
+    fn test_apply_file_config_icons() {
+        let mut config = Config::default();
+        config.apply_file_config(FileConfig {
+            icons: Some(HashMap::from([
+                ("set".to_string(), "nerd".to_string()),
+                ("error".to_string(), "!".to_string()),
+            ])),
+            ..FileConfig::default()
+        });
+        assert_eq!(config.icons.set, IconSet::Nerd);
+        assert_eq!(config.icons.level(Level::Error), "!");
+        config.ascii = true;
+        assert!(config.icons().is_none());
+    }
+
+    #[test]
+    fn test_stable_pins_ambient_settings() {
+        let cli = Cli::parse_from([
+            "cor",
+            "--stable",
+            "--color=always",
+            "--timezone=local",
+            "--preset=k8s",
+            "--throttle=2/s",
+        ]);
+        let config = Config::from_cli(&cli).unwrap();
+        assert!(config.stable);
+        assert!(config.throttle.is_none());
+        assert_eq!(config.year_reference, Some(klog::STABLE_NOW));
+        assert_eq!(config.color_mode, ColorMode::Never);
+        assert_eq!(config.timezone, jiff::tz::TimeZone::UTC);
+        assert!(!config.has_continuation_deadline());
+    }
+
+    #[test]
+    fn test_clock_12_rewrites_timestamp_format() {
+        let cli = Cli::parse_from(["cor", "--clock=12", "--timestamp-format=%H:%M"]);
+        let config = Config::from_cli(&cli).unwrap();
+        assert_eq!(config.timestamp_format, "%I:%M %p");
+    }
+
+    #[test]
+    fn test_file_config_clock_and_locale() {
+        let file_config: FileConfig =
+            toml::from_str("clock = 12\nlocale = \"de_DE.UTF-8\"").unwrap();
+        let mut config = Config::default();
+        config.apply_file_config(file_config);
+        assert_eq!(config.clock, ClockMode::H12);
+        assert_eq!(config.locale, Locale::De);
+    }
+
+    #[test]
+    fn test_load_rejects_invalid_timestamp_format_with_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "level = \"info\"\n\ntimestamp_format = \"%H %\"\n").unwrap();
+        let err = FileConfig::load(&path).unwrap_err().to_string();
+        assert!(err.contains(&format!("{}:3:", path.display())), "{err}");
+        assert!(err.contains("invalid timestamp format"), "{err}");
+    }
+
+    #[test]
+    fn test_key_line_ignores_tables() {
+        let content = "color = \"never\"\n[keys]\ntimestamp_format = \"x\"\n";
+        assert_eq!(key_line(content, "color"), Some(1));
+        assert_eq!(key_line(content, "timestamp_format"), None);
+    }
+
+    #[test]
+    fn test_file_config_key_fallback_lists() {
+        let toml_str = r#"
+            [keys]
+            message = ["event", "description"]
+            level = "severity, lvl"
+        "#;
+        let mut config = Config::default();
+        config.apply_file_config(toml::from_str(toml_str).unwrap());
+        assert_eq!(
+            config.message_key,
+            Some(vec!["event".to_string(), "description".to_string()])
+        );
+        assert_eq!(
+            config.level_key,
+            Some(vec!["severity".to_string(), "lvl".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_cli_key_fallback_list() {
+        let cli = Cli::parse_from(["cor", "--message-key=event,msg,description"]);
+        let config = Config::from_cli(&cli).unwrap();
+        assert_eq!(
+            config.message_key,
+            Some(vec![
+                "event".to_string(),
+                "msg".to_string(),
+                "description".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn test_file_config_message_fields() {
+        let toml_str = r#"
+            message_fields = ["msg", "error_detail"]
+            message_separator = " - "
+        "#;
+        let mut config = Config::default();
+        config.apply_file_config(toml::from_str(toml_str).unwrap());
+        assert_eq!(
+            config.message_fields,
+            Some(vec!["msg".to_string(), "error_detail".to_string()])
+        );
+        assert_eq!(config.message_separator, " - ");
+    }
+
+    #[test]
+    fn test_file_config_per_level_fields() {
+        let toml_str = r#"
+            [fields]
+            exclude = ["payload"]
+
+            [fields.error]
+            include = ["payload", "stack"]
+        "#;
+        let mut config = Config::default();
+        config.apply_file_config(toml::from_str(toml_str).unwrap());
+        assert_eq!(config.exclude_fields, Some(vec!["payload".to_string()]));
+
+        assert!(!config.allows_field(Some(Level::Info), "payload"));
+        assert!(config.allows_field(Some(Level::Info), "port"));
+
+        assert!(config.allows_field(Some(Level::Error), "payload"));
+        assert!(config.allows_field(Some(Level::Error), "stack"));
+        assert!(!config.allows_field(Some(Level::Error), "port"));
+
+        // Records without a level use the global rule
+        assert!(!config.allows_field(None, "payload"));
+    }
+
+    #[test]
+    fn test_cli_fields_apply_on_top_of_level_rules() {
+        let toml_str = r#"
+            [fields.error]
+            include = ["payload", "stack"]
+
+            [fields.warn]
+            exclude = ["payload"]
+        "#;
+        let mut config = Config::default();
+        config.apply_file_config(toml::from_str(toml_str).unwrap());
+        let cli = Cli::parse_from(["cor", "--exclude-fields", "stack"]);
+        config.apply_cli(&cli).unwrap();
+
+        assert!(config.allows_field(Some(Level::Error), "payload"));
+        assert!(!config.allows_field(Some(Level::Error), "stack"));
+        assert!(!config.allows_field(Some(Level::Warn), "stack"));
+        assert!(!config.allows_field(Some(Level::Warn), "payload"));
+        assert!(!config.allows_field(Some(Level::Info), "stack"));
+
+        let mut config = Config::default();
+        config.apply_file_config(toml::from_str(toml_str).unwrap());
+        let cli = Cli::parse_from(["cor", "--include-fields", "stack,port"]);
+        config.apply_cli(&cli).unwrap();
+        assert!(config.allows_field(Some(Level::Error), "stack"));
+        assert!(!config.allows_field(Some(Level::Error), "payload"));
+        assert!(!config.allows_field(Some(Level::Error), "port"));
+        assert!(config.allows_field(Some(Level::Warn), "port"));
+        assert!(!config.allows_field(Some(Level::Warn), "user"));
+    }
+
+    #[test]
+    fn test_file_config_rejects_unknown_level_fields() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "[fields.eror]\ninclude = [\"stack\"]\n").unwrap();
+        let err = FileConfig::load(&path).unwrap_err().to_string();
+        assert!(err.contains("fields.eror: unknown level"), "{err}");
+    }
+
+    #[test]
+    fn test_file_config_rules() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(
+            &path,
+            "[[rules]]\nmatch = 'status >= 500'\ncolor = \"red\"\n\n[[rules]]\nmatch = 'path == \"/healthz\"'\nhide = true\n",
+        )
+        .unwrap();
+        let mut config = Config::default();
+        config.apply_file_config(FileConfig::load(&path).unwrap());
+        assert_eq!(config.rules.len(), 2);
+
+        std::fs::write(&path, "[[rules]]\nmatch = 'status'\n").unwrap();
+        let err = FileConfig::load(&path).unwrap_err().to_string();
+        assert!(err.contains("rules[0]"), "{err}");
+    }
+
+    #[test]
+    fn test_background_detection_opt_out() {
+        assert!(Config::default().detect_background);
+        let mut config = Config::default();
+        config.apply_file_config(toml::from_str("detect_background = false").unwrap());
+        assert!(!config.detect_background);
+
+        let cli = Cli::parse_from(["cor", "--no-detect-background"]);
+        let mut config = Config::default();
+        config.apply_cli(&cli).unwrap();
+        assert!(!config.detect_background);
+    }
+
+    #[test]
+    fn test_preset_ci_bundle() {
+        let cli = Cli::parse_from(["cor", "--preset=ci"]);
+        let config = Config::from_cli(&cli).unwrap();
+        assert_eq!(config.color_mode, ColorMode::Never);
+        assert_eq!(config.line_gap, 0);
+        assert!(config.single_line);
+        assert_eq!(config.max_field_length, 0);
+    }
+
+    #[test]
+    fn test_preset_overridden_by_explicit_flags() {
+        let cli = Cli::parse_from(["cor", "--preset=ci", "--color=always", "--line-gap=2"]);
+        let config = Config::from_cli(&cli).unwrap();
+        assert_eq!(config.color_mode, ColorMode::Always);
+        assert_eq!(config.line_gap, 2);
+        // Untouched preset settings still apply
+        assert!(config.single_line);
+    }
+
+    #[test]
+    fn test_preset_minimal_and_k8s() {
+        let mut config = Config::default();
+        config.apply_preset(Preset::Minimal);
+        assert!(config.no_extra);
+        assert_eq!(config.line_gap, 0);
+
+        let mut config = Config::default();
+        config.apply_preset(Preset::K8s);
+        assert_eq!(config.timestamp_format, "%H:%M:%S%.3f");
+    }
+
+    #[test]
+    fn test_preset_k8s_hides_runtime_noise() {
+        let mut config = Config::default();
+        config.apply_preset(Preset::K8s);
+        for field in ["pid", "hostname", "v"] {
+            assert!(!config.allows_field(Some(Level::Info), field), "{field}");
+        }
+        assert!(config.allows_field(Some(Level::Info), "status"));
+    }
+
+    #[test]
+    fn test_preset_k8s_reads_kubectl_prefix() {
+        use crate::parser::{LineKind, parse_line};
+
+        let mut config = Config::default();
+        config.apply_preset(Preset::K8s);
+        let line = r#"[pod/api-7f9c4/app] {"level":"info","msg":"ready"}"#;
+        let LineKind::EmbeddedJson { prefix, record } = parse_line(line, &config) else {
+            panic!("expected embedded JSON");
+        };
+        // The label becomes fields and leaves the displayed prefix.
+        assert_eq!(prefix, "");
+        assert_eq!(record.extra["pod"], "api-7f9c4");
+        assert_eq!(record.extra["container"], "app");
+    }
+
+    #[test]
+    fn test_preset_dev_enables_verbose() {
+        let mut config = Config::default();
+        config.apply_preset(Preset::Dev);
+        assert!(config.verbose);
+        assert_eq!(config.timestamp_format, "%H:%M:%S%.3f");
+    }
 }
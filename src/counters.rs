@@ -0,0 +1,184 @@
+//! Live level counters (`--live-counts`).
+//!
+//! While input streams in, [`LiveCounts`] keeps a running count of the
+//! WARN, ERROR, and FATAL records seen so far and renders it at most once
+//! per [`INTERVAL`], either as the terminal title or as a status line
+//! redrawn in place on stderr. Neither touches stdout, so piped output is
+//! unaffected. Counters are only shown when stderr is a terminal; the
+//! title is saved on the first draw and restored by [`LiveCounts::finish`],
+//! and the status line is cleared before each entry written to stdout.
+
+use std::time::{Duration, Instant};
+
+use clap::ValueEnum;
+
+use crate::level::Level;
+
+/// Clears the current terminal line.
+const CLEAR_LINE: &str = "\r\x1b[2K";
+
+/// Push and pop the window title on the terminal's title stack (xterm).
+const SAVE_TITLE: &str = "\x1b[22;0t";
+const RESTORE_TITLE: &str = "\x1b[23;0t";
+
+/// How often the counters are redrawn.
+pub const INTERVAL: Duration = Duration::from_secs(1);
+
+/// Where the counters are shown.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum CounterTarget {
+    /// The terminal window title (OSC 0 on stderr).
+    #[default]
+    Title,
+    /// A status line on stderr, redrawn in place.
+    Stderr,
+}
+
+/// Running WARN/ERROR/FATAL counts.
+#[derive(Debug, Clone)]
+pub struct LiveCounts {
+    target: CounterTarget,
+    warn: usize,
+    error: usize,
+    fatal: usize,
+    /// When the counters were last drawn, and whether they changed since.
+    drawn: Option<Instant>,
+    changed: bool,
+    /// Whether the title was saved, or the status line is on screen.
+    shown: bool,
+}
+
+impl LiveCounts {
+    /// Counters shown on `target`.
+    pub const fn new(target: CounterTarget) -> Self {
+        Self {
+            target,
+            warn: 0,
+            error: 0,
+            fatal: 0,
+            drawn: None,
+            changed: true,
+            shown: false,
+        }
+    }
+
+    /// Count a record at `level`.
+    pub const fn observe(&mut self, level: Option<Level>) {
+        let counter = match level {
+            Some(Level::Warn) => &mut self.warn,
+            Some(Level::Error) => &mut self.error,
+            Some(Level::Fatal) => &mut self.fatal,
+            _ => return,
+        };
+        *counter += 1;
+        self.changed = true;
+    }
+
+    /// The text to write to stderr if the counters are due for a redraw at
+    /// `now`: changed, and not drawn within the last [`INTERVAL`].
+    pub fn redraw(&mut self, now: Instant) -> Option<String> {
+        if !self.changed || self.drawn.is_some_and(|at| now < at + INTERVAL) {
+            return None;
+        }
+        self.drawn = Some(now);
+        self.changed = false;
+        let save = if self.target == CounterTarget::Title && !self.shown {
+            SAVE_TITLE
+        } else {
+            ""
+        };
+        self.shown = true;
+        Some(format!("{save}{}", self.render()))
+    }
+
+    /// The text clearing the status line before an entry is written to
+    /// stdout, if one is on screen. It is redrawn with the next redraw.
+    pub fn clear(&mut self) -> Option<&'static str> {
+        if self.target != CounterTarget::Stderr || !self.shown {
+            return None;
+        }
+        self.shown = false;
+        self.changed = true;
+        self.drawn = None;
+        Some(CLEAR_LINE)
+    }
+
+    /// The final redraw at end of input: a status line is drawn once more
+    /// and ended, so later stderr output starts on a fresh line; a title is
+    /// restored to what it was before the first draw.
+    pub fn finish(&mut self) -> String {
+        match self.target {
+            CounterTarget::Stderr => format!("{}\n", self.render()),
+            CounterTarget::Title if self.shown => RESTORE_TITLE.to_string(),
+            CounterTarget::Title => String::new(),
+        }
+    }
+
+    fn render(&self) -> String {
+        let counts = format!(
+            "WARN {} ERROR {} FATAL {}",
+            self.warn, self.error, self.fatal
+        );
+        match self.target {
+            CounterTarget::Title => format!("\x1b]0;cor: {counts}\x07"),
+            CounterTarget::Stderr => format!("{CLEAR_LINE}cor: {counts}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redraws_at_most_once_per_interval() {
+        let mut counts = LiveCounts::new(CounterTarget::Stderr);
+        let start = Instant::now();
+        assert_eq!(
+            counts.redraw(start).as_deref(),
+            Some("\r\x1b[2Kcor: WARN 0 ERROR 0 FATAL 0")
+        );
+        counts.observe(Some(Level::Error));
+        counts.observe(Some(Level::Info));
+        assert_eq!(counts.redraw(start + Duration::from_millis(500)), None);
+        assert_eq!(
+            counts.redraw(start + INTERVAL).as_deref(),
+            Some("\r\x1b[2Kcor: WARN 0 ERROR 1 FATAL 0")
+        );
+        // Nothing changed since.
+        assert_eq!(counts.redraw(start + INTERVAL * 3), None);
+    }
+
+    #[test]
+    fn test_title_saved_and_restored() {
+        let mut counts = LiveCounts::new(CounterTarget::Title);
+        assert_eq!(counts.finish(), "");
+        counts.observe(Some(Level::Warn));
+        counts.observe(Some(Level::Fatal));
+        let start = Instant::now();
+        assert_eq!(
+            counts.redraw(start).as_deref(),
+            Some("\x1b[22;0t\x1b]0;cor: WARN 1 ERROR 0 FATAL 1\x07")
+        );
+        counts.observe(Some(Level::Warn));
+        assert_eq!(
+            counts.redraw(start + INTERVAL).as_deref(),
+            Some("\x1b]0;cor: WARN 2 ERROR 0 FATAL 1\x07")
+        );
+        assert_eq!(counts.clear(), None);
+        assert_eq!(counts.finish(), "\x1b[23;0t");
+    }
+
+    #[test]
+    fn test_status_line_cleared_before_output() {
+        let mut counts = LiveCounts::new(CounterTarget::Stderr);
+        assert_eq!(counts.clear(), None);
+        let start = Instant::now();
+        assert!(counts.redraw(start).is_some());
+        assert_eq!(counts.clear(), Some("\r\x1b[2K"));
+        assert_eq!(counts.clear(), None);
+        // Redrawn right away, without waiting out the interval.
+        assert!(counts.redraw(start).is_some());
+        assert_eq!(counts.finish(), "\r\x1b[2Kcor: WARN 0 ERROR 0 FATAL 0\n");
+    }
+}
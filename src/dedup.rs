@@ -0,0 +1,222 @@
+//! First-occurrence-only mode (`--first-only`).
+//!
+//! Retry storms and crash loops repeat the same record hundreds of times.
+//! [`Dedup`] lets only the first record with a given key through and counts
+//! the rest; the suppressed counts are summarized on stderr at exit.
+//!
+//! Records are keyed by their message, by a [`fingerprint`] of it that
+//! ignores numbers and IDs, or by the value of a field. Raw lines and
+//! records without the key are always shown.
+
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+use crate::expr::field_text;
+use crate::parser::LogRecord;
+use crate::stats::write_top;
+
+/// Number of keys listed in the summary.
+const SUMMARY_KEYS: usize = 10;
+
+/// Distinct keys remembered; records with keys first seen after that are
+/// always shown, which keeps memory bounded on high-cardinality keys.
+const MAX_KEYS: usize = 100_000;
+
+/// What makes two records repeats of each other.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DedupKey {
+    /// The exact message.
+    Message,
+    /// The message with numbers and IDs masked.
+    Fingerprint,
+    /// The value of a field.
+    Field(String),
+}
+
+impl DedupKey {
+    /// Parse `message`, `fingerprint`, or a field name.
+    pub fn parse(input: &str) -> Result<Self, String> {
+        match input.trim() {
+            "" => Err("expected message, fingerprint, or a field name".to_string()),
+            "message" | "msg" => Ok(Self::Message),
+            "fingerprint" => Ok(Self::Fingerprint),
+            field => Ok(Self::Field(field.to_string())),
+        }
+    }
+
+    fn of(&self, record: &LogRecord) -> Option<String> {
+        match self {
+            Self::Message => record.message.clone(),
+            Self::Fingerprint => record.message.as_deref().map(fingerprint),
+            Self::Field(field) => field_text(record, field),
+        }
+    }
+}
+
+/// The message with every word containing a digit (numbers, hex IDs,
+/// UUIDs, `req-42`) replaced by `#`, so `retry 3 of 5` and `retry 4 of 5`
+/// share a fingerprint.
+pub fn fingerprint(message: &str) -> String {
+    let mut out = String::with_capacity(message.len());
+    let mut word = String::new();
+    let is_word = |c: char| c.is_alphanumeric() || c == '_' || c == '-';
+    let flush = |word: &mut String, out: &mut String| {
+        if word.chars().any(|c| c.is_ascii_digit()) {
+            out.push('#');
+        } else {
+            out.push_str(word);
+        }
+        word.clear();
+    };
+    for c in message.chars() {
+        if is_word(c) {
+            word.push(c);
+        } else {
+            flush(&mut word, &mut out);
+            out.push(c);
+        }
+    }
+    flush(&mut word, &mut out);
+    out
+}
+
+/// Keys seen so far and how often each was suppressed.
+#[derive(Debug)]
+pub struct Dedup {
+    key: DedupKey,
+    /// Order of first occurrence and repeats, per key.
+    seen: HashMap<String, (usize, usize)>,
+}
+
+impl Dedup {
+    pub fn new(key: DedupKey) -> Self {
+        Self {
+            key,
+            seen: HashMap::new(),
+        }
+    }
+
+    /// Key of `record`; records without one are always shown.
+    pub fn key(&self, record: &LogRecord) -> Option<String> {
+        self.key.of(record)
+    }
+
+    /// Whether `key` is seen for the first time; repeats are counted.
+    pub fn first(&mut self, key: String) -> bool {
+        if let Some((_, repeats)) = self.seen.get_mut(&key) {
+            *repeats += 1;
+            return false;
+        }
+        if self.seen.len() < MAX_KEYS {
+            let order = self.seen.len();
+            self.seen.insert(key, (order, 0));
+        }
+        true
+    }
+
+    /// Write the suppressed counts, most repeated first. Nothing is
+    /// written when nothing was suppressed.
+    pub fn write_summary(&self, out: &mut impl Write) -> io::Result<()> {
+        let mut repeated: Vec<(&String, usize, usize)> = self
+            .seen
+            .iter()
+            .filter(|(_, (_, n))| *n > 0)
+            .map(|(key, &(order, n))| (key, order, n))
+            .collect();
+        if repeated.is_empty() {
+            return Ok(());
+        }
+        // Ties keep the order of first occurrence.
+        repeated.sort_by_key(|&(_, order, n)| (std::cmp::Reverse(n), order));
+        let total: usize = repeated.iter().map(|&(_, _, n)| n).sum();
+        writeln!(
+            out,
+            "cor: suppressed {total} repeats of {} entries",
+            repeated.len()
+        )?;
+        let rows = repeated.iter().map(|&(key, _, n)| (n, key.as_str()));
+        write_top(out, rows, SUMMARY_KEYS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::parser::{LineKind, parse_line};
+
+    fn record(line: &str) -> LogRecord {
+        match parse_line(line, &Config::default()) {
+            LineKind::Json(record) => record,
+            other => panic!("expected JSON record, got {other:?}"),
+        }
+    }
+
+    /// Whether the record in `line` is shown.
+    fn shown(dedup: &mut Dedup, line: &str) -> bool {
+        dedup.key(&record(line)).is_none_or(|key| dedup.first(key))
+    }
+
+    #[test]
+    fn test_fingerprint_masks_numbers_and_ids() {
+        assert_eq!(fingerprint("retry 3 of 5"), "retry # of #");
+        assert_eq!(
+            fingerprint("request req-8f3a failed after 120ms"),
+            "request # failed after #"
+        );
+        assert_eq!(fingerprint("connection refused"), "connection refused");
+    }
+
+    #[test]
+    fn test_first_occurrence_by_message() {
+        let mut dedup = Dedup::new(DedupKey::Message);
+        assert!(shown(&mut dedup, r#"{"msg":"boom"}"#));
+        assert!(!shown(&mut dedup, r#"{"msg":"boom","attempt":2}"#));
+        assert!(shown(&mut dedup, r#"{"msg":"other"}"#));
+        // No message, no key: always shown.
+        assert!(shown(&mut dedup, r#"{"attempt":3}"#));
+        assert!(shown(&mut dedup, r#"{"attempt":3}"#));
+    }
+
+    #[test]
+    fn test_keyed_by_field_and_fingerprint() {
+        let mut dedup = Dedup::new(DedupKey::parse("host").unwrap());
+        assert!(shown(&mut dedup, r#"{"msg":"a","host":"db1"}"#));
+        assert!(!shown(&mut dedup, r#"{"msg":"b","host":"db1"}"#));
+
+        let mut dedup = Dedup::new(DedupKey::parse("fingerprint").unwrap());
+        assert!(shown(&mut dedup, r#"{"msg":"retry 1 of 5"}"#));
+        assert!(!shown(&mut dedup, r#"{"msg":"retry 2 of 5"}"#));
+    }
+
+    #[test]
+    fn test_keys_past_the_cap_are_always_shown() {
+        let mut dedup = Dedup::new(DedupKey::Message);
+        for n in 0..MAX_KEYS {
+            assert!(dedup.first(n.to_string()));
+        }
+        assert!(dedup.first("late".to_string()));
+        assert!(dedup.first("late".to_string()));
+        assert!(!dedup.first("0".to_string()));
+    }
+
+    #[test]
+    fn test_summary() {
+        let mut dedup = Dedup::new(DedupKey::Message);
+        for msg in ["a", "b", "b", "a", "b", "c"] {
+            shown(&mut dedup, &format!(r#"{{"msg":"{msg}"}}"#));
+        }
+        let mut out = Vec::new();
+        dedup.write_summary(&mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "cor: suppressed 3 repeats of 2 entries\n       2  b\n       1  a\n"
+        );
+
+        let mut out = Vec::new();
+        Dedup::new(DedupKey::Message)
+            .write_summary(&mut out)
+            .unwrap();
+        assert!(out.is_empty());
+    }
+}
@@ -0,0 +1,214 @@
+//! Machine-readable diagnostics (`--diag json`).
+//!
+//! cor's own observations about the input — parse failures, dropped lines,
+//! multi-line reassembly outcomes, and a closing summary with filter counts
+//! — are written as one JSON object per line, tagged by `event`. They go to
+//! stderr, or to an inherited file descriptor with `--diag-fd`, so wrapper
+//! tooling can monitor cor without scraping human-oriented messages.
+//!
+//! Diagnostics are best effort: a failing diagnostics stream never stops
+//! log processing.
+
+use std::io::{self, Write};
+
+use serde::Serialize;
+
+/// Why a multi-line reassembly gave up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GaveUp {
+    /// A continuation deadline passed.
+    Timeout,
+    /// The line or byte limit was reached.
+    Limit,
+    /// The input ended first.
+    Eof,
+}
+
+/// Running totals reported in the closing `summary` event.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct Counts {
+    /// Entries processed (a reassembled record counts once).
+    pub entries: usize,
+    /// Entries parsed as JSON records.
+    pub records: usize,
+    /// Entries passed through as raw text.
+    pub raw: usize,
+    /// Entries suppressed by filters or rules.
+    pub filtered: usize,
+    /// Lines that looked like JSON but failed to parse.
+    pub malformed: usize,
+    /// Lines skipped before processing (e.g. invalid UTF-8).
+    pub dropped: usize,
+    /// Records assembled from several lines.
+    pub reassembled: usize,
+}
+
+/// One diagnostics event.
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event<'a> {
+    /// A line that looked like JSON failed to parse.
+    ParseError {
+        source: &'a str,
+        line: usize,
+        error: &'a str,
+    },
+    /// A line was skipped without being processed.
+    Dropped {
+        source: &'a str,
+        line: usize,
+        reason: &'a str,
+    },
+    /// A record was assembled from `lines` input lines.
+    Reassembled {
+        source: &'a str,
+        line: usize,
+        lines: usize,
+    },
+    /// Reassembly gave up; the `lines` buffered lines were emitted as raw.
+    ReassemblyFailed {
+        source: &'a str,
+        line: usize,
+        lines: usize,
+        reason: GaveUp,
+    },
+    /// A problem that did not stop processing.
+    Warning { message: &'a str },
+    /// Totals at the end of the run.
+    Summary(Counts),
+}
+
+/// Writes diagnostics events as NDJSON.
+pub struct Diagnostics {
+    out: Box<dyn Write + Send>,
+    /// Totals for the closing summary.
+    pub counts: Counts,
+}
+
+impl std::fmt::Debug for Diagnostics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Diagnostics")
+            .field("counts", &self.counts)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Diagnostics {
+    /// Diagnostics written to `out`.
+    pub fn new(out: impl Write + Send + 'static) -> Self {
+        Self {
+            out: Box::new(out),
+            counts: Counts::default(),
+        }
+    }
+
+    /// Diagnostics written to stderr.
+    pub fn stderr() -> Self {
+        Self::new(io::stderr())
+    }
+
+    /// Diagnostics written to the inherited file descriptor `fd`.
+    ///
+    /// The standard streams 0 to 2 are refused: the writer takes ownership
+    /// of the descriptor and closes it when dropped.
+    #[cfg(unix)]
+    pub fn from_fd(fd: i32) -> io::Result<Self> {
+        use std::os::fd::FromRawFd;
+
+        if (0..=2).contains(&fd) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "file descriptor {fd} is a standard stream; diagnostics go to stderr by default"
+                ),
+            ));
+        }
+        // SAFETY: fcntl only queries the descriptor's flags.
+        if fd < 0 || unsafe { libc::fcntl(fd, libc::F_GETFD) } == -1 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("file descriptor {fd} is not open"),
+            ));
+        }
+        // SAFETY: the descriptor is open, and nothing else in cor uses it.
+        let file = unsafe { std::fs::File::from_raw_fd(fd) };
+        Ok(Self::new(io::LineWriter::new(file)))
+    }
+
+    /// Write `event` as one JSON line.
+    pub fn emit(&mut self, event: &Event<'_>) {
+        if let Ok(mut json) = serde_json::to_vec(event) {
+            json.push(b'\n');
+            let _ = self.out.write_all(&json);
+        }
+    }
+
+    /// Write the closing summary and flush.
+    pub fn finish(&mut self) {
+        self.emit(&Event::Summary(self.counts));
+        let _ = self.out.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    /// Writer sharing its buffer with the test.
+    #[derive(Clone, Default)]
+    struct Shared(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_events_are_tagged_ndjson() {
+        let buf = Shared::default();
+        let mut diag = Diagnostics::new(buf.clone());
+        diag.emit(&Event::ParseError {
+            source: "app.log",
+            line: 3,
+            error: "expected value",
+        });
+        diag.emit(&Event::ReassemblyFailed {
+            source: "-",
+            line: 7,
+            lines: 2,
+            reason: GaveUp::Timeout,
+        });
+        diag.counts.records = 4;
+        diag.finish();
+
+        let out = String::from_utf8(buf.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(
+            lines[0],
+            r#"{"event":"parse_error","source":"app.log","line":3,"error":"expected value"}"#
+        );
+        assert_eq!(
+            lines[1],
+            r#"{"event":"reassembly_failed","source":"-","line":7,"lines":2,"reason":"timeout"}"#
+        );
+        let summary: serde_json::Value = serde_json::from_str(lines[2]).unwrap();
+        assert_eq!(summary["event"], "summary");
+        assert_eq!(summary["records"], 4);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_from_fd_refuses_standard_streams() {
+        for fd in 0..=2 {
+            let err = Diagnostics::from_fd(fd).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        }
+    }
+}
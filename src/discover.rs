@@ -0,0 +1,237 @@
+//! Field discovery report (`--discover-fields`).
+//!
+//! Instead of printing records, collect every JSON field path seen in the
+//! input with how often it appears and which value types it holds. With
+//! `--examples N`, up to `N` distinct sample values per field are kept in
+//! masked form: letters and digits are masked (`a`, `A`, `9`) while
+//! punctuation and length are preserved, so the report shows value shapes
+//! without leaking the values themselves.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{self, Write};
+
+use serde_json::Value;
+
+use crate::glyphs::Glyphs;
+use crate::parser::LogRecord;
+
+/// Longest masked example shown, in characters.
+const MAX_EXAMPLE_CHARS: usize = 40;
+
+/// Width of the TYPES column when examples follow it.
+const TYPES_WIDTH: usize = 17;
+
+/// Per-field statistics.
+#[derive(Debug, Default)]
+struct FieldStats {
+    /// Records containing the field.
+    count: usize,
+    /// JSON types seen for the field.
+    types: BTreeSet<&'static str>,
+    /// Distinct masked sample values, in order of first appearance.
+    examples: Vec<String>,
+}
+
+/// Accumulates the field discovery report.
+#[derive(Debug)]
+pub struct FieldReport {
+    records: usize,
+    /// Stats keyed by dotted field path (e.g. `http.status`).
+    fields: BTreeMap<String, FieldStats>,
+    /// Distinct examples kept per field.
+    examples: usize,
+    glyphs: &'static Glyphs,
+}
+
+impl FieldReport {
+    /// New report keeping up to `examples` masked sample values per field,
+    /// marking truncated examples with `glyphs.ellipsis`.
+    pub const fn new(examples: usize, glyphs: &'static Glyphs) -> Self {
+        Self {
+            records: 0,
+            fields: BTreeMap::new(),
+            examples,
+            glyphs,
+        }
+    }
+
+    /// Add the fields of `record`, as they appear in its raw JSON.
+    pub fn observe(&mut self, record: &LogRecord) {
+        let Ok(Value::Object(map)) = serde_json::from_str::<Value>(&record.raw_json) else {
+            return;
+        };
+        self.records += 1;
+        for (key, value) in &map {
+            self.observe_value(key.clone(), value);
+        }
+    }
+
+    fn observe_value(&mut self, path: String, value: &Value) {
+        if let Value::Object(map) = value
+            && !map.is_empty()
+        {
+            for (key, value) in map {
+                self.observe_value(format!("{path}.{key}"), value);
+            }
+            return;
+        }
+        let limit = self.examples;
+        let glyphs = self.glyphs;
+        let stats = self.fields.entry(path).or_default();
+        stats.count += 1;
+        stats.types.insert(type_name(value));
+        if stats.examples.len() < limit {
+            let example = value_shape(value, glyphs);
+            if !stats.examples.contains(&example) {
+                stats.examples.push(example);
+            }
+        }
+    }
+
+    /// Write the report as an aligned table.
+    pub fn write(&self, out: &mut impl Write) -> io::Result<()> {
+        let width = self
+            .fields
+            .keys()
+            .map(|k| k.chars().count())
+            .max()
+            .unwrap_or(0)
+            .max("FIELD".len());
+        write!(out, "{:<width$}  {:>7}  ", "FIELD", "RECORDS")?;
+        if self.examples > 0 {
+            writeln!(out, "{:<TYPES_WIDTH$}  EXAMPLES", "TYPES")?;
+        } else {
+            writeln!(out, "TYPES")?;
+        }
+        for (path, stats) in &self.fields {
+            let types = stats.types.iter().copied().collect::<Vec<_>>().join("|");
+            write!(out, "{path:<width$}  {:>7}  ", stats.count)?;
+            if self.examples > 0 {
+                writeln!(out, "{types:<TYPES_WIDTH$}  {}", stats.examples.join(", "))?;
+            } else {
+                writeln!(out, "{types}")?;
+            }
+        }
+        writeln!(
+            out,
+            "{} fields in {} records",
+            self.fields.len(),
+            self.records
+        )
+    }
+}
+
+const fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "bool",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// The shape of `value`: `"user-42"` becomes `"aaaa-99"`.
+///
+/// Unlike the `redact` pipeline stage, which blanks sensitive fields in
+/// records, this keeps the form of every value for the report.
+///
+/// Booleans and null carry no sensitive detail and are shown as is; arrays
+/// are summarized by length. Long values are cut off with `glyphs.ellipsis`.
+pub fn value_shape(value: &Value, glyphs: &Glyphs) -> String {
+    match value {
+        Value::Null | Value::Bool(_) => value.to_string(),
+        Value::Number(n) => mask(&n.to_string(), glyphs),
+        Value::String(s) => format!("\"{}\"", mask(s, glyphs)),
+        Value::Array(items) => format!("[{} items]", items.len()),
+        Value::Object(_) => "{}".to_string(),
+    }
+}
+
+fn mask(s: &str, glyphs: &Glyphs) -> String {
+    let mut masked: String = s
+        .chars()
+        .take(MAX_EXAMPLE_CHARS)
+        .map(|c| match c {
+            c if c.is_ascii_digit() => '9',
+            c if c.is_uppercase() => 'A',
+            c if c.is_alphabetic() => 'a',
+            c if c.is_control() => ' ',
+            c => c,
+        })
+        .collect();
+    if s.chars().nth(MAX_EXAMPLE_CHARS).is_some() {
+        masked.push_str(glyphs.ellipsis);
+    }
+    masked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::glyphs;
+    use crate::parser::{LineKind, parse_line};
+
+    fn report(lines: &[&str], examples: usize) -> String {
+        let mut report = FieldReport::new(examples, &glyphs::UNICODE);
+        for line in lines {
+            if let LineKind::Json(record) = parse_line(line, &Config::default()) {
+                report.observe(&record);
+            }
+        }
+        let mut out = Vec::new();
+        report.write(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_value_shape_masks_letters_and_digits() {
+        let g = &glyphs::UNICODE;
+        assert_eq!(
+            value_shape(&"user-42@Example.com".into(), g),
+            "\"aaaa-99@Aaaaaaa.aaa\""
+        );
+        assert_eq!(value_shape(&serde_json::json!(-3.25), g), "-9.99");
+        assert_eq!(value_shape(&serde_json::json!(true), g), "true");
+        assert_eq!(value_shape(&serde_json::json!([1, 2]), g), "[2 items]");
+        let long = "x".repeat(50);
+        assert_eq!(
+            value_shape(&long.clone().into(), g),
+            format!("\"{}…\"", "a".repeat(40))
+        );
+        assert_eq!(
+            value_shape(&long.into(), &glyphs::ASCII),
+            format!("\"{}...\"", "a".repeat(40))
+        );
+    }
+
+    #[test]
+    fn test_report_counts_types_and_examples() {
+        let out = report(
+            &[
+                r#"{"msg":"a","http":{"status":200}}"#,
+                r#"{"msg":"bb","http":{"status":"OK"}}"#,
+                r#"{"msg":"cc"}"#,
+            ],
+            2,
+        );
+        let lines: Vec<&str> = out.lines().collect();
+        assert!(lines[0].starts_with("FIELD"), "{out}");
+        assert!(lines[0].ends_with("EXAMPLES"), "{out}");
+        assert!(lines[1].starts_with("http.status"), "{out}");
+        assert!(lines[1].contains("number|string"), "{out}");
+        assert!(lines[1].ends_with("999, \"AA\""), "{out}");
+        assert!(lines[2].starts_with("msg"), "{out}");
+        assert!(lines[2].ends_with("\"a\", \"aa\""), "{out}");
+        assert_eq!(lines[3], "2 fields in 3 records");
+    }
+
+    #[test]
+    fn test_report_without_examples() {
+        let out = report(&[r#"{"msg":"secret"}"#], 0);
+        assert!(!out.contains("EXAMPLES"));
+        assert!(!out.contains("aaaaaa"));
+    }
+}
@@ -0,0 +1,158 @@
+//! Elapsed time column (`--elapsed`).
+//!
+//! In job and CI logs the wall-clock time rarely matters; the offset from
+//! the start of the log does. [`Elapsed`] remembers the timestamp of the
+//! first record read — shown or filtered out — and renders every later record's offset from it as
+//! `+MM:SS.mmm` (`+H:MM:SS.mmm` past an hour).
+//!
+//! With `--relative=previous` the column shows the offset from the previous
+//! record instead, which makes latency gaps readable at a glance.
+//!
+//! The column is colored by the time since the previous record, so slow
+//! steps in build and test logs stand out: green below the `warn`
+//! threshold, yellow below `slow`, red beyond.
+
+use std::fmt::Write as _;
+use std::time::Duration;
+
+use clap::ValueEnum;
+
+/// Where the elapsed column is shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ElapsedMode {
+    /// In place of the timestamp.
+    Only,
+    /// After the timestamp.
+    Beside,
+}
+
+/// What the elapsed column measures from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Origin {
+    /// The first record.
+    #[default]
+    First,
+    /// The previous record.
+    Previous,
+}
+
+/// Tracks the first and previous timestamps that offsets are measured from.
+#[derive(Debug, Clone, Default)]
+pub struct Elapsed {
+    origin: Option<jiff::Timestamp>,
+    previous: Option<jiff::Timestamp>,
+}
+
+impl Elapsed {
+    /// Whether no timestamp has been tracked yet.
+    pub const fn is_empty(&self) -> bool {
+        self.origin.is_none() && self.previous.is_none()
+    }
+
+    /// Measure offsets from `time` unless an origin is already set.
+    pub fn start(&mut self, time: jiff::Timestamp) {
+        self.origin.get_or_insert(time);
+    }
+
+    /// Offsets of `time`, in milliseconds, from the first timestamp passed
+    /// in and from the one passed in just before.
+    pub fn track(&mut self, time: jiff::Timestamp) -> (i64, i64) {
+        let origin = *self.origin.get_or_insert(time);
+        let previous = self.previous.replace(time).unwrap_or(time);
+        (
+            time.as_millisecond() - origin.as_millisecond(),
+            time.as_millisecond() - previous.as_millisecond(),
+        )
+    }
+}
+
+/// How long a record took to follow the previous one, relative to the
+/// [`Thresholds`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pace {
+    /// Below the `warn` threshold.
+    Fast,
+    /// Below the `slow` threshold.
+    Warn,
+    /// At or beyond the `slow` threshold.
+    Slow,
+}
+
+/// Latency budget coloring the elapsed column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Thresholds {
+    /// Gaps at least this long are yellow.
+    pub warn: Duration,
+    /// Gaps at least this long are red.
+    pub slow: Duration,
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Self {
+            warn: Duration::from_secs(1),
+            slow: Duration::from_secs(10),
+        }
+    }
+}
+
+impl Thresholds {
+    /// Pace of a record that came `delta_ms` after the previous one.
+    pub fn pace(&self, delta_ms: i64) -> Pace {
+        let delta = Duration::from_millis(delta_ms.unsigned_abs());
+        if delta >= self.slow {
+            Pace::Slow
+        } else if delta >= self.warn {
+            Pace::Warn
+        } else {
+            Pace::Fast
+        }
+    }
+}
+
+/// Render a millisecond offset as `+MM:SS.mmm`, or `+H:MM:SS.mmm` past an hour.
+pub fn format_offset(ms: i64) -> String {
+    let sign = if ms < 0 { '-' } else { '+' };
+    let ms = ms.unsigned_abs();
+    let (hours, minutes) = (ms / 3_600_000, ms / 60_000 % 60);
+    let (seconds, millis) = (ms / 1000 % 60, ms % 1000);
+    let mut out = String::from(sign);
+    if hours > 0 {
+        let _ = write!(out, "{hours}:");
+    }
+    let _ = write!(out, "{minutes:02}:{seconds:02}.{millis:03}");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_offset() {
+        assert_eq!(format_offset(0), "+00:00.000");
+        assert_eq!(format_offset(3_412), "+00:03.412");
+        assert_eq!(format_offset(61_005), "+01:01.005");
+        assert_eq!(format_offset(3_723_004), "+1:02:03.004");
+        assert_eq!(format_offset(-1_500), "-00:01.500");
+    }
+
+    #[test]
+    fn test_offsets_from_first_and_previous_record() {
+        let mut elapsed = Elapsed::default();
+        let t0: jiff::Timestamp = "2026-01-15T10:30:00Z".parse().unwrap();
+        let t1: jiff::Timestamp = "2026-01-15T10:30:03.412Z".parse().unwrap();
+        let t2: jiff::Timestamp = "2026-01-15T10:30:04Z".parse().unwrap();
+        assert_eq!(elapsed.track(t1), (0, 0));
+        assert_eq!(elapsed.track(t0), (-3_412, -3_412));
+        assert_eq!(elapsed.track(t2), (588, 4_000));
+    }
+
+    #[test]
+    fn test_threshold_paces() {
+        let thresholds = Thresholds::default();
+        assert_eq!(thresholds.pace(999), Pace::Fast);
+        assert_eq!(thresholds.pace(1_000), Pace::Warn);
+        assert_eq!(thresholds.pace(-12_000), Pace::Slow);
+    }
+}
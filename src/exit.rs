@@ -0,0 +1,129 @@
+//! Exit codes.
+//!
+//! | Code | Meaning |
+//! |------|---------|
+//! | 0 | Success |
+//! | 1 | Configuration or setup error (bad config file, flag value, or output) |
+//! | 2 | I/O error (unreadable input file, read or write failure) |
+//! | 3 | An entry matched `--fail-on` or `--fail-on-grep` |
+//! | 4 | Malformed JSON lines under `--strict` |
+//! | 124 | `--timeout` expired (`--timeout-exit-code`) |
+//! | 128 + N | Stopped by signal N after a clean shutdown |
+//!
+//! Scripts can move the failure codes out of the way of their own with
+//! `--exit-code-map strict=20,io=21`. Command-line usage errors are reported
+//! by the argument parser before the map is read and always exit with 2.
+
+use std::process::ExitCode;
+use std::sync::OnceLock;
+
+/// A failure with its own exit code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Failure {
+    Config,
+    Io,
+    FailOnLevel,
+    Strict,
+}
+
+impl Failure {
+    const ALL: [Self; 4] = [Self::Config, Self::Io, Self::FailOnLevel, Self::Strict];
+
+    /// Name used in `--exit-code-map`.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Config => "config",
+            Self::Io => "io",
+            Self::FailOnLevel => "fail-on-level",
+            Self::Strict => "strict",
+        }
+    }
+
+    /// Exit code without remapping.
+    pub const fn default_code(self) -> u8 {
+        match self {
+            Self::Config => 1,
+            Self::Io => 2,
+            Self::FailOnLevel => 3,
+            Self::Strict => 4,
+        }
+    }
+
+    /// Exit code after the map installed with [`remap`].
+    pub fn exit_code(self) -> ExitCode {
+        ExitCode::from(
+            MAP.get()
+                .map_or_else(|| self.default_code(), |map| map.code(self)),
+        )
+    }
+}
+
+/// Remapped exit codes from `--exit-code-map`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExitCodeMap(Vec<(Failure, u8)>);
+
+impl ExitCodeMap {
+    /// Parse `NAME=CODE,...`, e.g. `fail-on-level=10,strict=11`.
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let mut map = Vec::new();
+        for entry in s.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (name, code) = entry.split_once('=').ok_or_else(|| {
+                format!("invalid exit code mapping '{entry}': expected NAME=CODE")
+            })?;
+            let failure = Failure::ALL
+                .into_iter()
+                .find(|f| f.name() == name.trim())
+                .ok_or_else(|| {
+                    let names: Vec<_> = Failure::ALL.iter().map(|f| f.name()).collect();
+                    format!(
+                        "unknown exit code '{name}': expected one of {}",
+                        names.join(", ")
+                    )
+                })?;
+            let code = code
+                .trim()
+                .parse()
+                .map_err(|_| format!("invalid exit code '{code}': expected 0-255"))?;
+            map.push((failure, code));
+        }
+        Ok(Self(map))
+    }
+
+    /// Code for `failure`; the last mapping wins.
+    pub fn code(&self, failure: Failure) -> u8 {
+        self.0
+            .iter()
+            .rev()
+            .find(|(f, _)| *f == failure)
+            .map_or_else(|| failure.default_code(), |(_, code)| *code)
+    }
+}
+
+static MAP: OnceLock<ExitCodeMap> = OnceLock::new();
+
+/// Use `map` for every later [`Failure::exit_code`]. Only the first call
+/// has an effect.
+pub fn remap(map: ExitCodeMap) {
+    let _ = MAP.set(map);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_map() {
+        let map = ExitCodeMap::parse("fail-on-level=10, strict=11,strict=12").unwrap();
+        assert_eq!(map.code(Failure::FailOnLevel), 10);
+        assert_eq!(map.code(Failure::Strict), 12);
+        assert_eq!(map.code(Failure::Io), 2);
+
+        assert!(ExitCodeMap::parse("strict").is_err());
+        assert!(ExitCodeMap::parse("strict=256").is_err());
+        assert!(
+            ExitCodeMap::parse("parse=3")
+                .unwrap_err()
+                .contains("config, io, fail-on-level, strict")
+        );
+    }
+}
@@ -0,0 +1,200 @@
+//! How the first entries were read and why they were shown (`--explain`).
+//!
+//! [`explain`] describes one entry: which reader parsed the line, which
+//! keys the timestamp, level, message, logger, caller, and error came
+//! from, which filters and `[[rules]]` fired, and whether the record is
+//! shown or dropped. It is written to stderr for the first few entries, for
+//! when auto-detection picks the wrong field.
+
+use std::fmt::Write;
+
+use serde_json::{Map, Value};
+
+use crate::config::Config;
+use crate::fields;
+use crate::parser::{self, LineKind, LogRecord};
+
+/// Describe the entry parsed from `line`, numbered `line_no`.
+pub fn explain(line_no: usize, line: &str, parsed: &LineKind, config: &Config) -> String {
+    let mut out = format!(
+        "cor: explain line {line_no}: {}\n",
+        parser::reader_name(line, config)
+    );
+    let verdict = match parsed {
+        LineKind::Json(record) | LineKind::EmbeddedJson { record, .. } => {
+            let _ = writeln!(out, "cor:   keys: {}", chosen_keys(record, config));
+            record_verdict(record, config, &mut out)
+        }
+        LineKind::Raw(_) => raw_verdict(line, config),
+    };
+    let _ = writeln!(out, "cor:   {verdict}");
+    out
+}
+
+/// The keys each detected field was taken from, e.g. `timestamp=ts
+/// level=severity message=msg`.
+fn chosen_keys(record: &LogRecord, config: &Config) -> String {
+    let Ok(mut map) = serde_json::from_str::<Map<String, Value>>(&record.raw_json) else {
+        return "(not from a JSON object)".to_string();
+    };
+    let one = |key: &Option<String>| key.clone().map(|key| vec![key]);
+    let mut found = Vec::new();
+    for (name, keys, aliases) in [
+        (
+            "timestamp",
+            config.timestamp_key.clone(),
+            fields::TIMESTAMP_ALIASES,
+        ),
+        ("level", config.level_key.clone(), fields::LEVEL_ALIASES),
+        (
+            "message",
+            config.message_key.clone(),
+            fields::MESSAGE_ALIASES,
+        ),
+        ("logger", one(&config.logger_key), fields::LOGGER_ALIASES),
+        ("caller", one(&config.caller_key), fields::CALLER_ALIASES),
+        ("error", one(&config.error_key), fields::ERROR_ALIASES),
+    ] {
+        // Taken in the parser's order, each key at most once.
+        let key = if name == "message"
+            && let Some(ref composed) = config.message_fields
+        {
+            for key in composed {
+                map.remove(key);
+            }
+            Some(composed.join("+"))
+        } else {
+            match keys {
+                Some(keys) => keys.into_iter().find(|key| map.remove(key).is_some()),
+                None => fields::find_and_remove(&mut map, aliases).map(|(key, _)| key),
+            }
+        };
+        found.push(format!("{name}={}", key.as_deref().unwrap_or("-")));
+    }
+    found.join(" ")
+}
+
+/// Why a record is shown or dropped, after listing the rules it matched.
+fn record_verdict(record: &LogRecord, config: &Config, out: &mut String) -> String {
+    let mut hidden_by = None;
+    for (index, rule) in config.rules.iter().enumerate() {
+        if rule.expr().matches(record) {
+            let _ = writeln!(out, "cor:   rule {}: '{}' matched", index + 1, rule.expr());
+            if rule.hides() {
+                hidden_by = Some(index + 1);
+            }
+        }
+    }
+    // Filtered-out records stay visible under `--dim-unmatched`.
+    let dropped = if config.dim_unmatched && !config.json_output {
+        "dimmed"
+    } else {
+        "dropped"
+    };
+    // Checked in the formatter's order, so the first failing filter is named.
+    if let (Some(min), Some(level)) = (config.min_level, record.level)
+        && level < min
+    {
+        return format!("{dropped}: level {} is below {}", level.name(), min.name());
+    }
+    if let Some(expr) = config.where_filter.iter().find(|e| !e.matches(record)) {
+        return format!("{dropped}: filter '{expr}' did not match");
+    }
+    if let Some(ref re) = config.grep_pattern
+        && !record_text(record).any(|text| re.is_match(&text))
+    {
+        return format!("{dropped}: --grep '{re}' did not match");
+    }
+    if let Some(ref re) = config.grep_exclude
+        && record_text(record).any(|text| re.is_match(&text))
+    {
+        return format!("{dropped}: --grep-v '{re}' matched");
+    }
+    if let Some(rule) = hidden_by {
+        return format!("dropped: hidden by rule {rule}");
+    }
+    "shown".to_string()
+}
+
+/// Why a line that is not a record is shown or dropped.
+fn raw_verdict(line: &str, config: &Config) -> String {
+    if config.json_output {
+        return "dropped: --json shows records only".to_string();
+    }
+    if let Some(ref re) = config.grep_pattern
+        && !re.is_match(line)
+    {
+        return format!("dropped: --grep '{re}' did not match");
+    }
+    if let Some(ref re) = config.grep_exclude
+        && re.is_match(line)
+    {
+        return format!("dropped: --grep-v '{re}' matched");
+    }
+    "shown as is".to_string()
+}
+
+/// The texts `--grep` searches in a record.
+fn record_text(record: &LogRecord) -> impl Iterator<Item = String> + '_ {
+    [
+        &record.message,
+        &record.logger,
+        &record.caller,
+        &record.error,
+    ]
+    .into_iter()
+    .flatten()
+    .cloned()
+    .chain(record.extra.values().map(|value| match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::Level;
+    use crate::parser::parse_line;
+
+    #[test]
+    fn test_explains_keys_and_drop_reason() {
+        let config = Config {
+            min_level: Some(Level::Warn),
+            ..Config::default()
+        };
+        let line = r#"{"ts":"2026-01-15T10:30:00Z","severity":"info","event":"started"}"#;
+        let text = explain(7, line, &parse_line(line, &config), &config);
+        assert_eq!(
+            text,
+            "cor: explain line 7: JSON object\n\
+             cor:   keys: timestamp=ts level=severity message=event logger=- caller=- error=-\n\
+             cor:   dropped: level info is below warn\n"
+        );
+
+        let text = explain(8, "plain", &parse_line("plain", &config), &config);
+        assert!(text.ends_with("cor:   shown as is\n"));
+    }
+
+    #[test]
+    fn test_blames_the_first_filter_evaluated() {
+        let config = Config {
+            min_level: Some(Level::Info),
+            where_filter: vec![crate::expr::Expr::parse("status>=500").unwrap()],
+            ..Config::default()
+        };
+        let line = r#"{"level":"debug","msg":"x","status":200}"#;
+        let text = explain(1, line, &parse_line(line, &config), &config);
+        assert!(
+            text.ends_with("cor:   dropped: level debug is below info\n"),
+            "{text}"
+        );
+
+        let line = r#"{"level":"info","msg":"x","status":200}"#;
+        let text = explain(2, line, &parse_line(line, &config), &config);
+        assert!(
+            text.ends_with("cor:   dropped: filter 'status >= 500' did not match\n"),
+            "{text}"
+        );
+    }
+}
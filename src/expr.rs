@@ -0,0 +1,581 @@
+//! Record match expressions.
+//!
+//! A small condition language evaluated against parsed [`LogRecord`]s, e.g.
+//! `level>=error` or `status >= 500 && method == "POST"`. `&&` binds
+//! tighter than `||`, so `a && b || c` means `(a && b) || c`.
+//!
+//! Field names resolve to the extracted record fields first (`level`,
+//! `msg`/`message`, `logger`, `caller`, `error`) and fall back to the
+//! flattened extra fields (`http.status`). `level` compares by severity;
+//! other fields compare numerically when both sides are numbers (including
+//! string-encoded numbers like `"500"`) and as strings otherwise.
+
+use std::cmp::Ordering;
+use std::fmt;
+
+use crate::level::Level;
+use crate::parser::LogRecord;
+
+/// A parsed match expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    /// A single `field op value` comparison.
+    Compare(Comparison),
+    /// Both sides must match.
+    And(Box<Self>, Box<Self>),
+    /// Either side must match.
+    Or(Box<Self>, Box<Self>),
+    /// A `--min`/`--max` bound: the field must be a number (or a
+    /// string-encoded one) within it.
+    Threshold(Comparison),
+}
+
+/// A `field op value` comparison.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Comparison {
+    /// Field name (extracted field or flattened extra key).
+    pub field: String,
+    /// Comparison operator.
+    pub op: CmpOp,
+    /// Right-hand side literal, unquoted.
+    pub value: String,
+}
+
+/// Comparison operators.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CmpOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl CmpOp {
+    /// Operator tokens, longest first so `>=` wins over `>`.
+    const TOKENS: &[(&str, Self)] = &[
+        ("==", Self::Eq),
+        ("!=", Self::Ne),
+        (">=", Self::Ge),
+        ("<=", Self::Le),
+        (">", Self::Gt),
+        ("<", Self::Lt),
+        ("=", Self::Eq),
+    ];
+
+    const fn accepts(self, ordering: Ordering) -> bool {
+        match self {
+            Self::Eq => ordering.is_eq(),
+            Self::Ne => ordering.is_ne(),
+            Self::Lt => ordering.is_lt(),
+            Self::Le => ordering.is_le(),
+            Self::Gt => ordering.is_gt(),
+            Self::Ge => ordering.is_ge(),
+        }
+    }
+
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::Eq => "==",
+            Self::Ne => "!=",
+            Self::Lt => "<",
+            Self::Le => "<=",
+            Self::Gt => ">",
+            Self::Ge => ">=",
+        }
+    }
+}
+
+impl Expr {
+    /// Parse an expression such as `level>=error && service == "payments"`.
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let mut alternatives = split_unquoted(input, "||").into_iter().map(Self::parse_all);
+        let first = alternatives
+            .next()
+            .ok_or_else(|| "empty expression".to_string())??;
+        alternatives.try_fold(first, |acc, next| {
+            Ok(Self::Or(Box::new(acc), Box::new(next?)))
+        })
+    }
+
+    /// Parse a `--filter` condition: `key=value` or `key!=value`.
+    ///
+    /// Unlike [`Expr::parse`] the value is taken literally, so it may contain
+    /// `=`, `&&`, or quotes.
+    pub fn parse_filter(input: &str) -> Result<Self, String> {
+        let (field, value) = input
+            .split_once('=')
+            .ok_or_else(|| format!("expected key=value or key!=value, got '{input}'"))?;
+        let (field, op) = match field.strip_suffix('!') {
+            Some(field) => (field, CmpOp::Ne),
+            None => (field, CmpOp::Eq),
+        };
+        let field = field.trim();
+        if field.is_empty() {
+            return Err(format!("missing field name in '{input}'"));
+        }
+        if field.contains(['<', '>', '!']) {
+            return Err(format!(
+                "only = and != are supported in '{input}'; use --where for other comparisons"
+            ));
+        }
+        if is_level_field(field) && level_operand(value).is_none() {
+            return Err(format!(
+                "invalid level '{value}': expected one of trace, debug, info, warn, error, fatal"
+            ));
+        }
+        Ok(Self::Compare(Comparison {
+            field: field.to_string(),
+            op,
+            value: value.to_string(),
+        }))
+    }
+
+    /// Parse an `--in` set, `field=a,b,c`: the field must equal one of the
+    /// values.
+    pub fn parse_in(input: &str) -> Result<Self, String> {
+        Self::parse_set(input, CmpOp::Eq)
+    }
+
+    /// Parse a `--not-in` set, `field=a,b,c`: the field must equal none of
+    /// the values.
+    pub fn parse_not_in(input: &str) -> Result<Self, String> {
+        Self::parse_set(input, CmpOp::Ne)
+    }
+
+    /// One comparison per value, joined by `||` for `=` and `&&` for `!=`.
+    fn parse_set(input: &str, op: CmpOp) -> Result<Self, String> {
+        let (field, values) = input
+            .split_once('=')
+            .ok_or_else(|| format!("expected field=value,value,..., got '{input}'"))?;
+        let field = field.trim();
+        if field.is_empty() {
+            return Err(format!("missing field name in '{input}'"));
+        }
+        let mut comparisons = values.split(',').map(|value| {
+            let value = value.trim();
+            if value.is_empty() {
+                return Err(format!("empty value in '{input}'"));
+            }
+            if is_level_field(field) && level_operand(value).is_none() {
+                return Err(format!(
+                    "invalid level '{value}': expected one of trace, debug, info, warn, error, fatal"
+                ));
+            }
+            Ok(Self::Compare(Comparison {
+                field: field.to_string(),
+                op,
+                value: value.to_string(),
+            }))
+        });
+        let first = comparisons
+            .next()
+            .expect("split yields at least one item")?;
+        comparisons.try_fold(first, |acc, next| {
+            let (lhs, rhs) = (Box::new(acc), Box::new(next?));
+            Ok(if op == CmpOp::Eq {
+                Self::Or(lhs, rhs)
+            } else {
+                Self::And(lhs, rhs)
+            })
+        })
+    }
+
+    /// Parse a `--min` bound, `field=number`.
+    pub fn parse_min(input: &str) -> Result<Self, String> {
+        Self::parse_threshold(input, CmpOp::Ge)
+    }
+
+    /// Parse a `--max` bound, `field=number`.
+    pub fn parse_max(input: &str) -> Result<Self, String> {
+        Self::parse_threshold(input, CmpOp::Le)
+    }
+
+    fn parse_threshold(input: &str, op: CmpOp) -> Result<Self, String> {
+        let (field, value) = input
+            .split_once('=')
+            .ok_or_else(|| format!("expected field=number, got '{input}'"))?;
+        let (field, value) = (field.trim(), value.trim());
+        if field.is_empty() {
+            return Err(format!("missing field name in '{input}'"));
+        }
+        if is_level_field(field) {
+            return Err("use --level for level thresholds".to_string());
+        }
+        if !value.parse::<f64>().is_ok_and(f64::is_finite) {
+            return Err(format!("'{value}' is not a number"));
+        }
+        Ok(Self::Threshold(Comparison {
+            field: field.to_string(),
+            op,
+            value: value.to_string(),
+        }))
+    }
+
+    /// Parse comparisons joined by `&&`.
+    fn parse_all(input: &str) -> Result<Self, String> {
+        let mut parts = split_unquoted(input, "&&")
+            .into_iter()
+            .map(Comparison::parse);
+        let first = parts
+            .next()
+            .ok_or_else(|| "empty expression".to_string())??;
+        parts.try_fold(Self::Compare(first), |acc, next| {
+            Ok(Self::And(Box::new(acc), Box::new(Self::Compare(next?))))
+        })
+    }
+
+    /// Returns `true` if the record satisfies the expression.
+    pub fn matches(&self, record: &LogRecord) -> bool {
+        match self {
+            Self::Compare(cmp) => cmp.matches(record),
+            Self::And(lhs, rhs) => lhs.matches(record) && rhs.matches(record),
+            Self::Or(lhs, rhs) => lhs.matches(record) || rhs.matches(record),
+            Self::Threshold(cmp) => cmp.matches_number(record),
+        }
+    }
+
+    /// Whether a record could match, knowing only which fields it has:
+    /// `false` when a field the expression needs is absent per `has_field`.
+    /// `level` comparisons and `!=` (which absent fields satisfy) are
+    /// assumed to match.
+    pub fn may_match(&self, has_field: &impl Fn(&str) -> bool) -> bool {
+        match self {
+            Self::Compare(cmp) => {
+                is_level_field(&cmp.field) || cmp.op == CmpOp::Ne || has_field(&cmp.field)
+            }
+            Self::Threshold(cmp) => has_field(&cmp.field),
+            Self::And(lhs, rhs) => lhs.may_match(has_field) && rhs.may_match(has_field),
+            Self::Or(lhs, rhs) => lhs.may_match(has_field) || rhs.may_match(has_field),
+        }
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            // Numbers read back the same without quotes.
+            Self::Compare(cmp) if cmp.value.parse::<f64>().is_ok_and(f64::is_finite) => {
+                write!(f, "{} {} {}", cmp.field, cmp.op.as_str(), cmp.value)
+            }
+            Self::Compare(cmp) => write!(f, "{} {} {:?}", cmp.field, cmp.op.as_str(), cmp.value),
+            Self::And(lhs, rhs) => write!(f, "{lhs} && {rhs}"),
+            Self::Or(lhs, rhs) => write!(f, "{lhs} || {rhs}"),
+            Self::Threshold(cmp) => write!(f, "{} {} {}", cmp.field, cmp.op.as_str(), cmp.value),
+        }
+    }
+}
+
+impl Comparison {
+    fn parse(input: &str) -> Result<Self, String> {
+        let input = input.trim();
+        let (pos, token, op) = CmpOp::TOKENS
+            .iter()
+            .filter_map(|&(token, op)| input.find(token).map(|pos| (pos, token, op)))
+            // Leftmost operator wins; at equal positions the longer token
+            // (listed first) is kept by `min_by_key`'s first-minimum rule.
+            .min_by_key(|&(pos, _, _)| pos)
+            .ok_or_else(|| format!("missing comparison operator in '{input}'"))?;
+
+        let field = input[..pos].trim();
+        let value = unquote(input[pos + token.len()..].trim());
+        if field.is_empty() {
+            return Err(format!("missing field name in '{input}'"));
+        }
+        if is_level_field(field) && level_operand(value).is_none() {
+            return Err(format!(
+                "invalid level '{value}': expected one of trace, debug, info, warn, error, fatal"
+            ));
+        }
+
+        Ok(Self {
+            field: field.to_string(),
+            op,
+            value: value.to_string(),
+        })
+    }
+
+    fn matches(&self, record: &LogRecord) -> bool {
+        if is_level_field(&self.field) {
+            let (Some(actual), Some(expected)) = (record.level, level_operand(&self.value)) else {
+                return false;
+            };
+            return self.op.accepts(actual.cmp(&expected));
+        }
+
+        let Some(actual) = field_text(record, &self.field) else {
+            // Absent fields only satisfy `!=`.
+            return self.op == CmpOp::Ne;
+        };
+        let ordering = match (actual.parse::<f64>(), self.value.parse::<f64>()) {
+            (Ok(a), Ok(b)) => a.partial_cmp(&b),
+            _ => Some(actual.as_str().cmp(self.value.as_str())),
+        };
+        ordering.is_some_and(|o| self.op.accepts(o))
+    }
+
+    /// Like [`Comparison::matches`], but absent and non-numeric values
+    /// never match.
+    fn matches_number(&self, record: &LogRecord) -> bool {
+        let actual = field_text(record, &self.field).and_then(|v| v.parse::<f64>().ok());
+        let (Some(actual), Ok(bound)) = (actual, self.value.parse::<f64>()) else {
+            return false;
+        };
+        actual
+            .partial_cmp(&bound)
+            .is_some_and(|o| self.op.accepts(o))
+    }
+}
+
+const fn is_level_field(field: &str) -> bool {
+    field.eq_ignore_ascii_case("level")
+}
+
+/// Interpret the right-hand side of a `level` comparison by name or number.
+fn level_operand(value: &str) -> Option<Level> {
+    Level::from_str_loose(value).or_else(|| value.parse::<i64>().ok().map(Level::from_numeric))
+}
+
+/// Split `input` at each `sep` outside single- or double-quoted strings, so
+/// `cmd == "make && test"` stays one comparison.
+fn split_unquoted<'s>(input: &'s str, sep: &str) -> Vec<&'s str> {
+    let mut parts = Vec::new();
+    let mut quote = None;
+    let mut start = 0;
+    let mut chars = input.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if input[i..].starts_with(sep) => {
+                parts.push(&input[start..i]);
+                start = i + sep.len();
+                // Skip the rest of the separator.
+                chars.nth(sep.len() - 2);
+            }
+            Some(_) | None => {}
+        }
+    }
+    parts.push(&input[start..]);
+    parts
+}
+
+/// Strip one layer of matching single or double quotes.
+fn unquote(s: &str) -> &str {
+    for quote in ['"', '\''] {
+        if let Some(inner) = s.strip_prefix(quote).and_then(|r| r.strip_suffix(quote)) {
+            return inner;
+        }
+    }
+    s
+}
+
+/// Resolve a field name to its textual value on the record.
+pub fn field_text(record: &LogRecord, field: &str) -> Option<String> {
+    match field {
+        "msg" | "message" => record.message.clone(),
+        "logger" => record.logger.clone(),
+        "caller" => record.caller.clone(),
+        "error" => record.error.clone(),
+        _ => record.extra.get(field).map(|v| match v {
+            serde_json::Value::String(s) => s.clone(),
+            other => other.to_string(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::parser::{LineKind, parse_line};
+
+    fn record(line: &str) -> LogRecord {
+        match parse_line(line, &Config::default()) {
+            LineKind::Json(record) => record,
+            other => panic!("expected JSON record, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_level_comparison() {
+        let expr = Expr::parse("level>=error").unwrap();
+        assert!(expr.matches(&record(r#"{"level":"error","msg":"x"}"#)));
+        assert!(expr.matches(&record(r#"{"level":"fatal","msg":"x"}"#)));
+        assert!(!expr.matches(&record(r#"{"level":"warn","msg":"x"}"#)));
+        assert!(!expr.matches(&record(r#"{"msg":"no level"}"#)));
+    }
+
+    #[test]
+    fn test_numeric_comparison_coerces_strings() {
+        let expr = Expr::parse("status >= 500").unwrap();
+        assert!(expr.matches(&record(r#"{"msg":"x","status":503}"#)));
+        assert!(expr.matches(&record(r#"{"msg":"x","status":"500"}"#)));
+        assert!(!expr.matches(&record(r#"{"msg":"x","status":404}"#)));
+    }
+
+    #[test]
+    fn test_string_equality_and_quotes() {
+        let expr = Expr::parse(r#"method == "POST""#).unwrap();
+        assert!(expr.matches(&record(r#"{"msg":"x","method":"POST"}"#)));
+        assert!(!expr.matches(&record(r#"{"msg":"x","method":"GET"}"#)));
+
+        let expr = Expr::parse("msg='hello world'").unwrap();
+        assert!(expr.matches(&record(r#"{"msg":"hello world"}"#)));
+    }
+
+    #[test]
+    fn test_operators_inside_quotes() {
+        let expr = Expr::parse(r#"cmd == "make && test" || note == 'a || b'"#).unwrap();
+        assert!(expr.matches(&record(r#"{"msg":"x","cmd":"make && test"}"#)));
+        assert!(expr.matches(&record(r#"{"msg":"x","note":"a || b"}"#)));
+        assert!(!expr.matches(&record(r#"{"msg":"x","cmd":"make"}"#)));
+
+        let expr = Expr::parse(r#"cmd == "a&&b" && status >= 500"#).unwrap();
+        assert!(expr.matches(&record(r#"{"msg":"x","cmd":"a&&b","status":503}"#)));
+        assert!(!expr.matches(&record(r#"{"msg":"x","cmd":"a&&b","status":200}"#)));
+    }
+
+    #[test]
+    fn test_and_combination() {
+        let expr = Expr::parse(r#"status >= 500 && method == "POST""#).unwrap();
+        assert!(expr.matches(&record(r#"{"msg":"x","status":500,"method":"POST"}"#)));
+        assert!(!expr.matches(&record(r#"{"msg":"x","status":500,"method":"GET"}"#)));
+    }
+
+    #[test]
+    fn test_or_binds_looser_than_and() {
+        let expr = Expr::parse("status >= 500 && method == POST || level >= error").unwrap();
+        assert!(expr.matches(&record(r#"{"msg":"x","status":500,"method":"POST"}"#)));
+        assert!(expr.matches(&record(r#"{"level":"error","msg":"x","status":200}"#)));
+        assert!(!expr.matches(&record(r#"{"msg":"x","status":500,"method":"GET"}"#)));
+        assert_eq!(
+            expr.to_string(),
+            r#"status >= 500 && method == "POST" || level >= "error""#
+        );
+        assert!(Expr::parse("status >= 500 ||").is_err());
+    }
+
+    #[test]
+    fn test_missing_field_only_matches_not_equal() {
+        assert!(
+            !Expr::parse("service == api")
+                .unwrap()
+                .matches(&record(r#"{"msg":"x"}"#))
+        );
+        assert!(
+            Expr::parse("service != api")
+                .unwrap()
+                .matches(&record(r#"{"msg":"x"}"#))
+        );
+    }
+
+    #[test]
+    fn test_flattened_keys() {
+        let expr = Expr::parse("http.status == 200").unwrap();
+        assert!(expr.matches(&record(r#"{"msg":"x","http":{"status":200}}"#)));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Expr::parse("").is_err());
+        assert!(Expr::parse("level").is_err());
+        assert!(Expr::parse(">= 5").is_err());
+        assert!(Expr::parse("level >= loud").is_err());
+    }
+
+    #[test]
+    fn test_parse_filter() {
+        let expr = Expr::parse_filter("service=payments").unwrap();
+        assert!(expr.matches(&record(r#"{"msg":"x","service":"payments"}"#)));
+        assert!(!expr.matches(&record(r#"{"msg":"x","service":"auth"}"#)));
+        assert!(!expr.matches(&record(r#"{"msg":"x"}"#)));
+
+        let expr = Expr::parse_filter("service!=payments").unwrap();
+        assert!(!expr.matches(&record(r#"{"msg":"x","service":"payments"}"#)));
+        assert!(expr.matches(&record(r#"{"msg":"x"}"#)));
+
+        // The value is literal: no quote stripping or operator splitting.
+        let expr = Expr::parse_filter("query=a=1&&b='2'").unwrap();
+        assert!(expr.matches(&record(r#"{"msg":"x","query":"a=1&&b='2'"}"#)));
+
+        assert!(Expr::parse_filter("service").is_err());
+        assert!(Expr::parse_filter("=payments").is_err());
+        assert!(Expr::parse_filter("status>=500").is_err());
+        assert!(Expr::parse_filter("level=loud").is_err());
+    }
+
+    #[test]
+    fn test_thresholds_are_numeric() {
+        let min = Expr::parse_min("status=500").unwrap();
+        assert!(min.matches(&record(r#"{"msg":"x","status":503}"#)));
+        assert!(min.matches(&record(r#"{"msg":"x","status":"500"}"#)));
+        assert!(!min.matches(&record(r#"{"msg":"x","status":404}"#)));
+        // "abc" sorts after "500" as a string, but is no number.
+        assert!(!min.matches(&record(r#"{"msg":"x","status":"abc"}"#)));
+        assert!(!min.matches(&record(r#"{"msg":"x"}"#)));
+
+        let max = Expr::parse_max("latency_ms = 100").unwrap();
+        assert!(max.matches(&record(r#"{"msg":"x","latency_ms":99.5}"#)));
+        assert!(!max.matches(&record(r#"{"msg":"x","latency_ms":"250"}"#)));
+        assert_eq!(max.to_string(), "latency_ms <= 100");
+
+        assert!(Expr::parse_min("status").is_err());
+        assert!(Expr::parse_min("status=high").is_err());
+        assert!(Expr::parse_max("level=3").is_err());
+    }
+
+    #[test]
+    fn test_value_sets() {
+        let expr = Expr::parse_in("status=404, 500,503").unwrap();
+        assert!(expr.matches(&record(r#"{"msg":"x","status":500}"#)));
+        assert!(expr.matches(&record(r#"{"msg":"x","status":"404"}"#)));
+        assert!(!expr.matches(&record(r#"{"msg":"x","status":200}"#)));
+        assert!(!expr.matches(&record(r#"{"msg":"x"}"#)));
+
+        let expr = Expr::parse_not_in("path=/health,/metrics").unwrap();
+        assert!(!expr.matches(&record(r#"{"msg":"x","path":"/metrics"}"#)));
+        assert!(expr.matches(&record(r#"{"msg":"x","path":"/api"}"#)));
+        assert!(expr.matches(&record(r#"{"msg":"x"}"#)));
+        assert_eq!(
+            expr.to_string(),
+            r#"path != "/health" && path != "/metrics""#
+        );
+
+        assert!(Expr::parse_in("status").is_err());
+        assert!(Expr::parse_in("status=404,,500").is_err());
+        assert!(Expr::parse_in("level=warn,loud").is_err());
+    }
+
+    #[test]
+    fn test_may_match_by_fields() {
+        let has = |field: &str| field == "status";
+        assert!(Expr::parse("status >= 500").unwrap().may_match(&has));
+        assert!(!Expr::parse("path == /api").unwrap().may_match(&has));
+        assert!(Expr::parse("path != /api").unwrap().may_match(&has));
+        assert!(Expr::parse("level >= warn").unwrap().may_match(&has));
+        assert!(
+            Expr::parse("path == /api || status == 500")
+                .unwrap()
+                .may_match(&has)
+        );
+        assert!(
+            !Expr::parse("path == /api && status == 500")
+                .unwrap()
+                .may_match(&has)
+        );
+        assert!(!Expr::parse_min("latency=100").unwrap().may_match(&has));
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        let expr = Expr::parse("level>=error && service=api").unwrap();
+        assert_eq!(expr.to_string(), r#"level >= "error" && service == "api""#);
+        assert_eq!(Expr::parse(&expr.to_string()).unwrap(), expr);
+
+        let expr = Expr::parse("status>=500 && ratio<0.5").unwrap();
+        assert_eq!(expr.to_string(), "status >= 500 && ratio < 0.5");
+        assert_eq!(Expr::parse(&expr.to_string()).unwrap(), expr);
+    }
+}
@@ -0,0 +1,317 @@
+//! Follow mode for file inputs (`-f/--follow`).
+//!
+//! Like `tail -F`, each file is read to its end and then watched for new
+//! data. A file that is truncated is read again from the start; a file that
+//! is replaced (rotated: its path now names a different inode) is reopened
+//! once the old one has been read to the end. Several files are read on
+//! their own threads and merged, each line labelled with its file. Both
+//! are reported to the consumer as notices ([`LineSource::take_notice`])
+//! ahead of the lines that follow them. Invalid UTF-8 is replaced rather
+//! than ending the input.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs::{self, File, Metadata};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, SyncSender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::input::{LineSource, Next};
+
+/// Number of lines buffered between the reader threads and the consumer.
+const CHANNEL_CAPACITY: usize = 1024;
+
+/// How often a file at its end is checked for new data or rotation.
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// A change to a followed file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileEvent {
+    /// The path names a new file, which is read from the start.
+    Replaced,
+    /// The file shrank and is read again from the start.
+    Truncated,
+}
+
+impl fmt::Display for FileEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Replaced => "file replaced, reopening",
+            Self::Truncated => "file truncated",
+        })
+    }
+}
+
+/// What a reader thread sends the consumer.
+#[derive(Debug)]
+enum Message {
+    Line(io::Result<String>),
+    Event(FileEvent),
+}
+
+type Sender = SyncSender<(usize, Message)>;
+
+/// Reader over a file path that waits for more data instead of ending, and
+/// follows truncation and rotation.
+#[derive(Debug)]
+pub struct FollowReader {
+    path: PathBuf,
+    file: File,
+    /// Bytes read from the current file.
+    pos: u64,
+    poll: Duration,
+    /// Where [`FileEvent`]s go, with the input's index, once spawned.
+    events: Option<(usize, Sender)>,
+}
+
+impl FollowReader {
+    /// Open `path` for following.
+    pub fn open(path: &Path) -> io::Result<Self> {
+        Ok(Self {
+            path: path.to_path_buf(),
+            file: File::open(path)?,
+            pos: 0,
+            poll: POLL_INTERVAL,
+            events: None,
+        })
+    }
+
+    fn report(&self, event: FileEvent) {
+        if let Some((index, ref tx)) = self.events {
+            let _ = tx.send((index, Message::Event(event)));
+        }
+    }
+
+    /// Check the path after reaching the end of the current file; returns
+    /// whether reading should resume right away.
+    fn check_path(&mut self) -> io::Result<bool> {
+        // A missing path is a rotation in progress: keep the old file.
+        let Ok(on_disk) = fs::metadata(&self.path) else {
+            return Ok(false);
+        };
+        if !same_file(&self.file.metadata()?, &on_disk) {
+            self.report(FileEvent::Replaced);
+            self.file = File::open(&self.path)?;
+            self.pos = 0;
+            return Ok(true);
+        }
+        if on_disk.len() < self.pos {
+            self.report(FileEvent::Truncated);
+            self.file.seek(SeekFrom::Start(0))?;
+            self.pos = 0;
+            return Ok(true);
+        }
+        Ok(false)
+    }
+}
+
+impl Read for FollowReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            let n = self.file.read(buf)?;
+            if n > 0 {
+                self.pos += n as u64;
+                return Ok(n);
+            }
+            if !self.check_path()? {
+                thread::sleep(self.poll);
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+fn same_file(a: &Metadata, b: &Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    a.dev() == b.dev() && a.ino() == b.ino()
+}
+
+#[cfg(not(unix))]
+fn same_file(_a: &Metadata, _b: &Metadata) -> bool {
+    true
+}
+
+/// An input of [`FollowLines`].
+pub enum Followed {
+    /// A file, followed across truncation and rotation.
+    File(FollowReader),
+    /// Any other reader (usually stdin), read until it ends.
+    Reader(Box<dyn Read + Send>),
+}
+
+/// Merged [`LineSource`] following one or more files.
+#[derive(Debug)]
+pub struct FollowLines {
+    rx: Receiver<(usize, Message)>,
+    labels: Vec<String>,
+    current: usize,
+    /// File events received ahead of the next line.
+    notices: VecDeque<String>,
+}
+
+impl FollowLines {
+    /// Spawn one reader thread per `(label, input)`.
+    pub fn spawn(inputs: Vec<(String, Followed)>) -> Self {
+        let (tx, rx) = mpsc::sync_channel(CHANNEL_CAPACITY);
+        let mut labels = Vec::with_capacity(inputs.len());
+        for (index, (label, input)) in inputs.into_iter().enumerate() {
+            labels.push(label);
+            let tx = tx.clone();
+            let reader: Box<dyn Read + Send> = match input {
+                Followed::File(mut reader) => {
+                    reader.events = Some((index, tx.clone()));
+                    Box::new(reader)
+                }
+                Followed::Reader(reader) => reader,
+            };
+            thread::spawn(move || forward(index, reader, &tx));
+        }
+        Self {
+            rx,
+            labels,
+            current: 0,
+            notices: VecDeque::new(),
+        }
+    }
+}
+
+impl LineSource for FollowLines {
+    fn next_line(&mut self, deadline: Option<Instant>) -> Next {
+        loop {
+            let received = match deadline {
+                None => self.rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+                Some(deadline) => self
+                    .rx
+                    .recv_timeout(deadline.saturating_duration_since(Instant::now())),
+            };
+            match received {
+                Ok((index, Message::Line(line))) => {
+                    self.current = index;
+                    return Next::Line(line);
+                }
+                Ok((index, Message::Event(event))) => {
+                    let label = self.labels.get(index).map_or("-", String::as_str);
+                    self.notices.push_back(format!("{label}: {event}"));
+                }
+                Err(RecvTimeoutError::Timeout) => return Next::TimedOut,
+                Err(RecvTimeoutError::Disconnected) => return Next::Eof,
+            }
+        }
+    }
+
+    fn label(&self) -> Option<&str> {
+        self.labels.get(self.current).map(String::as_str)
+    }
+
+    fn take_notice(&mut self) -> Option<String> {
+        self.notices.pop_front()
+    }
+}
+
+/// Send the lines of `reader` until it ends or the consumer goes away.
+/// Invalid UTF-8 is replaced; other read errors end the input.
+fn forward(index: usize, reader: Box<dyn Read + Send>, tx: &Sender) {
+    let mut reader = BufReader::new(reader);
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        let line = match reader.read_until(b'\n', &mut buf) {
+            Ok(0) => return,
+            Ok(_) => {
+                let line = buf.strip_suffix(b"\n").unwrap_or(&buf);
+                Ok(String::from_utf8_lossy(line).into_owned())
+            }
+            Err(e) => Err(e),
+        };
+        let failed = line.is_err();
+        if tx.send((index, Message::Line(line))).is_err() || failed {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn follow(path: &Path) -> FollowLines {
+        let mut reader = FollowReader::open(path).unwrap();
+        reader.poll = Duration::from_millis(10);
+        FollowLines::spawn(vec![("app.log".to_string(), Followed::File(reader))])
+    }
+
+    fn next(source: &mut FollowLines) -> String {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        match source.next_line(Some(deadline)) {
+            Next::Line(Ok(line)) => line,
+            other => panic!("expected a line, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_follows_appends_and_truncation() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.log");
+        fs::write(&path, "one\n").unwrap();
+        let mut source = follow(&path);
+        assert_eq!(next(&mut source), "one");
+        assert_eq!(source.label(), Some("app.log"));
+
+        let mut file = fs::OpenOptions::new().append(true).open(&path).unwrap();
+        writeln!(file, "two").unwrap();
+        assert_eq!(next(&mut source), "two");
+
+        fs::write(&path, "new\n").unwrap();
+        assert_eq!(next(&mut source), "new");
+        assert_eq!(
+            source.take_notice().as_deref(),
+            Some("app.log: file truncated")
+        );
+        assert_eq!(source.take_notice(), None);
+    }
+
+    #[test]
+    fn test_replaces_invalid_utf8() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.log");
+        fs::write(&path, b"bad \xff\nnext\n").unwrap();
+        let mut source = follow(&path);
+        assert_eq!(next(&mut source), "bad \u{fffd}");
+        assert_eq!(next(&mut source), "next");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_reopens_rotated_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.log");
+        fs::write(&path, "before\n").unwrap();
+        let mut source = follow(&path);
+        assert_eq!(next(&mut source), "before");
+
+        fs::rename(&path, dir.path().join("app.log.1")).unwrap();
+        fs::write(&path, "after rotation with more bytes\n").unwrap();
+        assert_eq!(next(&mut source), "after rotation with more bytes");
+        assert_eq!(
+            source.take_notice().as_deref(),
+            Some("app.log: file replaced, reopening")
+        );
+    }
+
+    #[test]
+    fn test_times_out_while_idle() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.log");
+        fs::write(&path, "").unwrap();
+        let mut source = follow(&path);
+        let deadline = Instant::now() + Duration::from_millis(30);
+        assert!(matches!(source.next_line(Some(deadline)), Next::TimedOut));
+    }
+}
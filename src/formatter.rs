@@ -10,14 +10,32 @@
 //! - Include/exclude field filtering
 //! - JSON passthrough mode
 
+use std::borrow::Cow;
 use std::fmt::Write;
 
-use owo_colors::OwoColorize;
-use owo_colors::Stream::Stdout;
+use owo_colors::Style;
 
+use crate::cli::{QuoteMode, TruncateMode};
 use crate::config::Config;
+use crate::elapsed::{self, Elapsed, ElapsedMode, Origin};
+use crate::expr;
+use crate::fields;
+use crate::highlight;
+use crate::icons;
+use crate::layout::{Layout, Overlay, Role};
 use crate::level::Level;
-use crate::parser::{self, LineKind, LogRecord};
+use crate::level::color_name_to_style;
+use crate::parser::{self, LineKind, LogRecord, ParseError};
+use crate::pretty;
+use crate::rules::{self, Outcome};
+use crate::seen::{self, SeenCounts};
+use crate::sparkline::{self, Sparkline};
+
+/// Color of records matching `--focus`.
+const FOCUS_COLOR: &str = "bright_cyan";
+
+/// Dark grey of records shown by `--dim-unmatched`.
+const UNMATCHED: Overlay = Overlay::Tint(Style::new().bright_black());
 
 /// Format a single line for output.
 ///
@@ -28,7 +46,7 @@ use crate::parser::{self, LineKind, LogRecord};
 /// The result is written into `out`.
 pub fn format_line(line: &str, config: &Config, out: &mut String) {
     let parsed = parser::parse_line(line, config);
-    format_line_parsed(parsed, line, config, out);
+    format_line_parsed(&parsed, line, config, out);
 }
 
 /// Format a pre-parsed [`LineKind`] for output.
@@ -36,68 +54,264 @@ pub fn format_line(line: &str, config: &Config, out: &mut String) {
 /// Like [`format_line`], but accepts an already-parsed [`LineKind`] instead of
 /// a raw line string. The `raw_line` parameter is used for `LineKind::Raw`
 /// passthrough.
-pub fn format_line_parsed(parsed: LineKind, raw_line: &str, config: &Config, out: &mut String) {
-    match parsed {
-        LineKind::Json(record) => {
-            if should_filter(&record, config) || !grep_matches_record(&record, config) {
-                out.clear();
-                return;
-            }
-            if config.json_output {
-                out.push_str(&record.raw_json);
-            } else {
-                format_record(&record, None, config, out);
+pub fn format_line_parsed(parsed: &LineKind, raw_line: &str, config: &Config, out: &mut String) {
+    format_line_with_outcome(parsed, raw_line, config, &mut FormatState::default(), out);
+}
+
+/// Formatting state carried from one record to the next.
+#[derive(Debug, Clone, Default)]
+pub struct FormatState {
+    /// Origin and previous record of the `--elapsed` column.
+    pub elapsed: Elapsed,
+    /// Recent values of the `--sparkline` field.
+    pub sparkline: Sparkline,
+    /// Last value of the `--watch-field` field.
+    pub watched: Option<String>,
+    /// Occurrences of the `--seen-counts` field values.
+    pub seen: SeenCounts,
+}
+
+impl FormatState {
+    /// Whether nothing has been carried over yet. A record formatted from a
+    /// fresh state that leaves it fresh did not depend on earlier records.
+    pub fn is_fresh(&self) -> bool {
+        self.elapsed.is_empty()
+            && self.sparkline.is_empty()
+            && self.watched.is_none()
+            && self.seen.is_empty()
+    }
+
+    /// The value of `field` on `record` if it differs from the last one
+    /// remembered. Records without the field never count as a change.
+    fn changed_value(&self, record: &LogRecord, field: &str) -> Option<String> {
+        expr::field_text(record, field).filter(|value| self.watched.as_ref() != Some(value))
+    }
+}
+
+/// Like [`format_line_parsed`], but also returns the combined [`Outcome`]
+/// of the `[[rules]]` matching the record, so callers can act on the
+/// non-visual actions (bell, stderr routing).
+///
+/// `state` carries what spans records, such as the `--elapsed` origin.
+/// Hidden and filtered-out records leave `out` empty.
+pub fn format_line_with_outcome(
+    parsed: &LineKind,
+    raw_line: &str,
+    config: &Config,
+    state: &mut FormatState,
+    out: &mut String,
+) -> Outcome {
+    let mut layout = Layout::default();
+    let outcome = format_layout(parsed, raw_line, config, state, &mut layout);
+    if layout.is_empty() {
+        out.clear();
+    } else {
+        layout.write_ansi(out);
+    }
+    outcome
+}
+
+/// Like [`format_line_with_outcome`], but lays the entry out as a
+/// [`Layout`] for any renderer instead of ANSI text.
+///
+/// `layout` is cleared first and left empty for hidden and filtered-out
+/// records.
+pub fn format_layout(
+    parsed: &LineKind,
+    raw_line: &str,
+    config: &Config,
+    state: &mut FormatState,
+    layout: &mut Layout,
+) -> Outcome {
+    format_admitted(parsed, raw_line, config, state, layout, |_| true)
+}
+
+/// Like [`format_layout`], but asks `admit` whether an entry that passed
+/// the filters is shown before `state` is updated for it.
+///
+/// `admit` gets the record, or `None` for a raw line or a record shown only
+/// as `--dim-unmatched` context. Refused entries leave `layout` empty. This
+/// is where the drops that act on shown entries only (`--sample`,
+/// `--first-only` and `--throttle`) decide, so a dropped record neither
+/// moves the `--elapsed` column nor counts as the last `--watch-field`
+/// value.
+pub fn format_admitted(
+    parsed: &LineKind,
+    raw_line: &str,
+    config: &Config,
+    state: &mut FormatState,
+    layout: &mut Layout,
+    admit: impl FnOnce(Option<&LogRecord>) -> bool,
+) -> Outcome {
+    layout.clear();
+    let (prefix, record) = match parsed {
+        LineKind::Json(record) => (None, record),
+        LineKind::EmbeddedJson { prefix, record } => (Some(prefix.as_str()), record),
+        LineKind::Raw(parse_error) => {
+            // Raw lines carry no state, so they are laid out first.
+            let unmatched = format_raw(raw_line, parse_error.as_ref(), config, layout);
+            if !layout.is_empty() && !admit(None) {
+                layout.clear();
             }
+            return Outcome {
+                unmatched,
+                ..Outcome::default()
+            };
         }
-        LineKind::EmbeddedJson { prefix, record } => {
-            if should_filter(&record, config) || !grep_matches_record(&record, config) {
-                out.clear();
-                return;
-            }
-            if config.json_output {
-                out.push_str(&record.raw_json);
-            } else {
-                format_record(&record, Some(&prefix), config, out);
-            }
+    };
+
+    // The log starts at its first record, whether or not that one is shown.
+    if config.elapsed.is_some()
+        && let Some(ref ts) = record.timestamp
+    {
+        state.elapsed.start(ts.value);
+    }
+    let matched = !should_filter(record, config) && grep_matches_record(record, config);
+    if !matched {
+        // Shown in dark grey, without rule actions, under `--dim-unmatched`.
+        if config.dim_unmatched && !config.json_output && !config.flat_json && admit(None) {
+            format_record(record, prefix, &Outcome::default(), config, state, layout);
+            layout.set_overlay(UNMATCHED);
+            return Outcome {
+                unmatched: true,
+                ..Outcome::default()
+            };
         }
-        LineKind::Raw(parse_error) => {
-            if config.json_output {
-                out.clear();
-                return;
-            }
-            // Grep filter for raw lines
-            if let Some(ref re) = config.grep_pattern
-                && !re.is_match(raw_line)
-            {
-                out.clear();
-                return;
-            }
-            out.push_str(raw_line);
-
-            // In verbose mode, show parse error if present
-            if config.verbose
-                && let Some(err) = parse_error
-            {
-                let _ = write!(
-                    out,
-                    "\n  {} [{}:{}] {}",
-                    "parse error:".if_supports_color(Stdout, |t| t.red().bold().to_string()),
-                    err.line,
-                    err.column,
-                    err.message
-                        .if_supports_color(Stdout, |t| t.dimmed().to_string()),
-                );
-            }
+        return Outcome::default();
+    }
+    let watched = match config.watch_field {
+        Some(ref field) => match state.changed_value(record, field) {
+            Some(value) => Some(value),
+            None => return Outcome::default(),
+        },
+        None => None,
+    };
+    let outcome = rules::evaluate(&config.rules, record);
+    if outcome.hide {
+        // Left empty.
+        return outcome;
+    }
+    if !admit(Some(record)) {
+        return Outcome::default();
+    }
+    if watched.is_some() {
+        state.watched = watched;
+    }
+    if let Some(ref table) = config.table {
+        layout.push(Role::Raw, Style::new(), &table.row(record));
+    } else if config.flat_json {
+        push_json(&record.to_flat_json(), config, layout);
+    } else if config.json_output {
+        push_json(&record.raw_json, config, layout);
+    } else {
+        format_record(record, prefix, &outcome, config, state, layout);
+        if let Some(overlay) = record_overlay(record, &outcome, config) {
+            layout.set_overlay(overlay);
         }
     }
+    outcome
+}
+
+/// Output a record's JSON text, indented under `--json-pretty`. Its tokens
+/// are colored either way, so a terminal gets a `jq`-like view; without
+/// colors the plain text is written as it is.
+fn push_json(json: &str, config: &Config, layout: &mut Layout) {
+    if config.json_pretty {
+        pretty::push_json(json, &config.theme, layout);
+    } else {
+        pretty::push_colored(json, &config.theme, layout);
+    }
+}
+
+/// Restyling of a whole record: `--focus` tints matches and dims the rest;
+/// otherwise the tint of the matching rules applies.
+fn record_overlay(record: &LogRecord, outcome: &Outcome, config: &Config) -> Option<Overlay> {
+    let tint = match config.focus {
+        Some(ref focus) if !focus.matches(record) => return Some(Overlay::Dim),
+        Some(_) => FOCUS_COLOR,
+        None => outcome.tint.as_deref()?,
+    };
+    color_name_to_style(tint).map(Overlay::Tint)
 }
 
-/// Check if a record matches the grep pattern (returns true if no pattern or match found).
+/// Pass a non-JSON line through, subject to `--json` and `--grep`.
+/// Returns whether it is shown only as `--dim-unmatched` context.
+fn format_raw(
+    raw_line: &str,
+    parse_error: Option<&ParseError>,
+    config: &Config,
+    out: &mut Layout,
+) -> bool {
+    if config.json_output {
+        return false;
+    }
+    // Grep filters for raw lines
+    let grep_rejects = config
+        .grep_pattern
+        .as_ref()
+        .is_some_and(|re| !re.is_match(raw_line));
+    let grep_v_rejects = config
+        .grep_exclude
+        .as_ref()
+        .is_some_and(|re| re.is_match(raw_line));
+    let rejected = grep_rejects || grep_v_rejects;
+    if rejected && !config.dim_unmatched {
+        return false;
+    }
+    let line = strip_unsafe_escapes(raw_line, config);
+    // Lines carrying their own colors are left alone rather than having
+    // highlight codes spliced into them.
+    if line.contains('\x1b') {
+        out.push(Role::Raw, Style::new(), &line);
+    } else {
+        push_highlighted(
+            out,
+            Role::Raw,
+            &line,
+            config.grep_pattern.as_ref(),
+            config.theme.raw.style(),
+            config,
+        );
+    }
+    if rejected {
+        out.set_overlay(UNMATCHED);
+    } else if config.focus.is_some() {
+        // Raw lines never match `--focus`.
+        out.set_overlay(Overlay::Dim);
+    }
+
+    // In verbose mode, show parse error if present
+    if config.verbose
+        && let Some(err) = parse_error
+    {
+        out.push_str("\n  ");
+        out.push(
+            Role::Diagnostic,
+            config.theme.diagnostic.style(),
+            "parse error:",
+        );
+        let _ = write!(out, " [{}:{}] ", err.line, err.column);
+        out.push(Role::Diagnostic, Style::new().dimmed(), &err.message);
+    }
+    rejected
+}
+
+/// Check if a record passes `--grep` and `--grep-v`: some field value must
+/// match the former and none the latter.
 #[inline]
 fn grep_matches_record(record: &LogRecord, config: &Config) -> bool {
-    let Some(ref re) = config.grep_pattern else {
-        return true;
-    };
+    config
+        .grep_pattern
+        .as_ref()
+        .is_none_or(|re| record_matches(record, re))
+        && !config
+            .grep_exclude
+            .as_ref()
+            .is_some_and(|re| record_matches(record, re))
+}
+
+/// Whether any text field or extra field value of `record` matches `re`.
+fn record_matches(record: &LogRecord, re: &regex::Regex) -> bool {
     if let Some(ref msg) = record.message
         && re.is_match(msg)
     {
@@ -127,18 +341,17 @@ fn grep_matches_record(record: &LogRecord, config: &Config) -> bool {
     false
 }
 
-/// Check if a record should be filtered out by level.
+/// Check if a record should be filtered out by level or `--where`.
 #[inline]
 fn should_filter(record: &LogRecord, config: &Config) -> bool {
-    if let Some(ref min_level) = config.min_level {
-        match &record.level {
-            Some(level) => level < min_level,
-            // No level field → show the line (can't evaluate)
-            None => false,
-        }
-    } else {
-        false
+    // The cheap level check goes first; `--explain` reports in this order.
+    // Records without a level pass it (can't evaluate).
+    if let (Some(min_level), Some(level)) = (config.min_level, record.level)
+        && level < min_level
+    {
+        return true;
     }
+    !config.where_filter.iter().all(|expr| expr.matches(record))
 }
 
 /// Format a [`LogRecord`] into colorized human-readable output.
@@ -149,166 +362,434 @@ fn should_filter(record: &LogRecord, config: &Config) -> bool {
 ///                           key: value
 ///                     other_key: other_value
 /// ```
-fn format_record(record: &LogRecord, prefix: Option<&str>, config: &Config, out: &mut String) {
-    // Timestamp (bold when colored)
+fn format_record(
+    record: &LogRecord,
+    prefix: Option<&str>,
+    outcome: &Outcome,
+    config: &Config,
+    state: &mut FormatState,
+    out: &mut Layout,
+) {
+    let theme = &config.theme;
+
+    // Timestamp and/or elapsed offset (bold when colored)
     if let Some(ref ts) = record.timestamp {
-        let ts_str = ts.format_with_tz(&config.timestamp_format, &config.timezone);
-        let _ = write!(
-            out,
-            "{}  ",
-            ts_str.if_supports_color(Stdout, |t| t.bold().to_string())
-        );
+        if config.elapsed != Some(ElapsedMode::Only) {
+            let ts_str =
+                ts.format_localized(&config.timestamp_format, &config.timezone, config.locale);
+            out.push(Role::Timestamp, theme.timestamp.style(), &ts_str);
+            out.push_str("  ");
+        }
+        if config.elapsed.is_some() {
+            // Colored by the gap since the previous record.
+            let (offset, delta) = state.elapsed.track(ts.value);
+            let paint = theme.elapsed(config.elapsed_thresholds.pace(delta));
+            let shown = match config.elapsed_origin {
+                Origin::First => offset,
+                Origin::Previous => delta,
+            };
+            out.push(Role::Elapsed, paint.style(), &elapsed::format_offset(shown));
+            out.push_str("  ");
+        }
+    }
+
+    // Level icon (opt-in)
+    if let Some(icons) = config.icons() {
+        match record.level {
+            Some(level) => out.push(Role::Icon, Style::new(), icons.level(level)),
+            None => out.push_str(icons.blank()),
+        }
+        out.push_str(" ");
     }
 
     // Level badge + colon
     if let Some(ref level) = record.level {
-        let badge = level.badge();
-        let custom_color = config
-            .level_colors
-            .as_ref()
-            .and_then(|colors| colors.get(level))
-            .map(String::as_str);
-        let style = level.style_with_color(custom_color);
-        let _ = write!(
-            out,
-            "{}:",
-            badge.if_supports_color(Stdout, |t| t.style(style).to_string())
-        );
+        out.push(Role::Badge, theme.level(*level).style(), level.badge());
     } else {
         out.push_str(Level::blank_badge());
-        out.push(':');
     }
+    out.push_str(":");
 
-    // Logger name (dimmed, after level badge)
-    if let Some(ref logger) = record.logger {
-        let _ = write!(
-            out,
-            " {}",
-            logger.if_supports_color(Stdout, |t| t.dimmed().to_string())
+    // Logger name (dimmed, after level badge) unless `--hide-logger`
+    if let Some(ref logger) = record.logger
+        && !config.hide_logger
+    {
+        out.push_str(" ");
+        out.push(
+            Role::Logger,
+            theme.logger.style(),
+            &escape_control(logger, config),
         );
     }
 
-    // Prefix (bold cyan when colored)
-    if let Some(pfx) = prefix {
-        let _ = write!(
-            out,
-            " {}",
-            pfx.if_supports_color(Stdout, |t| t.bold().cyan().to_string())
+    // Prefix (bold cyan when colored); may be emptied by `--prefix-pattern`
+    if let Some(pfx) = prefix.filter(|p| !p.trim().is_empty()) {
+        out.push_str(" ");
+        out.push(
+            Role::Prefix,
+            theme.prefix.style(),
+            &escape_control(pfx, config),
         );
     }
 
-    // Message (plain text, no bold)
+    // Rule tags (bold, in brackets)
+    for tag in &outcome.tags {
+        let tag = format!("[{}]", escape_control(tag, config));
+        out.push_str(" ");
+        out.push(Role::Tag, theme.tag.style(), &tag);
+    }
+
+    // Message (plain text by default, unless a rule colors it)
     if let Some(ref msg) = record.message {
-        out.push(' ');
-        out.push_str(msg);
+        out.push_str(" ");
+        let msg = escape_control(msg, config);
+        let style = outcome
+            .color
+            .as_deref()
+            .and_then(color_name_to_style)
+            .unwrap_or_else(|| theme.message.style());
+        push_highlighted(
+            out,
+            Role::Message,
+            &msg,
+            config.grep_pattern.as_ref(),
+            style,
+            config,
+        );
+        push_seen("msg", &msg, config, state, out);
     }
 
     // Caller (dimmed, in parentheses after message)
     if let Some(ref caller) = record.caller {
-        let _ = write!(
-            out,
-            " ({})",
-            caller.if_supports_color(Stdout, |t| t.dimmed().to_string())
+        out.push_str(" (");
+        out.push(
+            Role::Caller,
+            theme.caller.style(),
+            &escape_control(caller, config),
+        );
+        out.push_str(")");
+    }
+
+    // Sparkline of the tracked field, on records carrying it
+    if let Some(value) = config
+        .sparkline
+        .as_deref()
+        .and_then(|field| sparkline::field_value(record, field))
+    {
+        state.sparkline.push(value);
+        out.push_str(" ");
+        out.push(
+            Role::Sparkline,
+            theme.sparkline.style(),
+            &state.sparkline.render(config.glyphs().spark),
         );
     }
 
     // Extra fields + error
-    format_extra_fields(record, config, out);
+    format_extra_fields(record, config, state, out);
+}
+
+/// Append the dim `(seen N×)` counter of `--seen-counts` to `value` of
+/// `field` once it repeats.
+fn push_seen(field: &str, value: &str, config: &Config, state: &mut FormatState, out: &mut Layout) {
+    if !config.counts_seen(field) {
+        return;
+    }
+    let count = state.seen.observe(field, value);
+    if count > 1 {
+        out.push_str(" ");
+        out.push(
+            Role::Diagnostic,
+            Style::new().dimmed(),
+            &seen::marker(count, config.glyphs().times),
+        );
+    }
 }
 
 /// Render extra fields and the error field according to config settings.
-fn format_extra_fields(record: &LogRecord, config: &Config, out: &mut String) {
+fn format_extra_fields(
+    record: &LogRecord,
+    config: &Config,
+    state: &mut FormatState,
+    out: &mut Layout,
+) {
     let max_len = config.max_field_length;
     let key_width = config.key_min_width;
+    let theme = &config.theme;
 
     if !config.no_extra {
-        for (key, value) in &record.extra {
-            if let Some(ref include) = config.include_fields
-                && !include.iter().any(|f| f == key)
-            {
-                continue;
-            }
-            if let Some(ref exclude) = config.exclude_fields
-                && exclude.iter().any(|f| f == key)
-            {
+        let mut fields = record
+            .extra
+            .iter()
+            .filter(|(key, _)| config.allows_field(record.level, key));
+        let shown = match config.max_fields {
+            0 => usize::MAX,
+            max => max,
+        };
+        for (key, value) in fields.by_ref().take(shown) {
+            // Stack traces left among the extras (the error field takes
+            // only the first alias) keep their lines.
+            if let Some(trace) = value.as_str().filter(|s| is_stack_trace(key, s)) {
+                let key = escape_control(key, config);
+                let trace = escape_control(trace, config);
+                if config.single_line {
+                    out.push_str(" ");
+                    out.push(Role::Key, theme.error.style().bold(), &key);
+                    out.push_str("=");
+                    let first_line = trace.lines().next().unwrap_or_default();
+                    out.push(Role::Error, theme.error.style(), first_line);
+                } else {
+                    format_error_field(&key, &trace, key_width, theme.error.style(), out);
+                }
+                push_seen(&key, &trace, config, state, out);
                 continue;
             }
-            let val_str = format_value(value);
-            let val_display = truncate_value(&val_str, max_len);
+            let icon = config.icons().and_then(|icons| icons.field(key));
+            let key_style = theme.key(key).style();
+            let key = escape_control(key, config);
+            let val_str = truncate_value(
+                &format_value(value),
+                max_len,
+                config.truncate_mode,
+                config.glyphs().ellipsis,
+            );
+            // Quoting escapes control characters itself.
+            let val_display = if value.is_string() && needs_quotes(&val_str, config.quote_values) {
+                quote(&val_str)
+            } else {
+                escape_control(&val_str, config).into_owned()
+            };
 
             if config.single_line {
-                let _ = write!(
-                    out,
-                    " {}={}",
-                    key.if_supports_color(Stdout, |t| t
-                        .truecolor(150, 150, 150)
-                        .bold()
-                        .to_string()),
-                    val_display
-                );
+                if let Some(icon) = icon {
+                    out.push_str(" ");
+                    out.push(Role::Icon, Style::new(), icon);
+                }
+                out.push_str(" ");
+                out.push(Role::Key, key_style, &key);
+                out.push_str("=");
             } else {
-                let _ = write!(
-                    out,
-                    "\n{}: {}",
-                    format!("{key:>key_width$}").if_supports_color(Stdout, |t| t
-                        .truecolor(150, 150, 150)
-                        .bold()
-                        .to_string()),
-                    val_display
-                );
+                out.push_str("\n");
+                // The icon sits in the key's padding so values stay aligned.
+                let width = if let Some(icon) = icon {
+                    let key_len = key.chars().count();
+                    let pad = key_width.saturating_sub(key_len + icons::width(icon) + 1);
+                    let _ = write!(out, "{:pad$}", "");
+                    out.push(Role::Icon, Style::new(), icon);
+                    out.push_str(" ");
+                    key_len
+                } else {
+                    key_width
+                };
+                out.push(Role::Key, key_style, &format!("{key:>width$}"));
+                out.push_str(": ");
             }
+            push_highlighted(
+                out,
+                Role::Value,
+                &val_display,
+                None,
+                theme.value.style(),
+                config,
+            );
+            push_seen(&key, &format_value(value), config, state, out);
+        }
+        let folded = fields.count();
+        if folded > 0 {
+            format_folded(folded, config, out);
         }
     }
 
     // Error field
     if let Some(ref error) = record.error {
+        let error = escape_control(error, config);
+        let error = error.as_ref();
         if config.single_line {
             let first_line = error.lines().next().unwrap_or(error);
-            let _ = write!(
-                out,
-                " {}={}",
-                "error".if_supports_color(Stdout, |t| t.red().bold().to_string()),
-                first_line.if_supports_color(Stdout, |t| t.red().to_string())
-            );
+            out.push_str(" ");
+            out.push(Role::Key, theme.error.style().bold(), "error");
+            out.push_str("=");
+            out.push(Role::Error, theme.error.style(), first_line);
         } else {
-            format_error_field(error, key_width, out);
+            format_error_field("error", error, key_width, theme.error.style(), out);
         }
+        push_seen("error", error, config, state, out);
     }
 }
 
-/// Format the error field with red styling and multiline stacktrace support.
-fn format_error_field(error: &str, key_width: usize, out: &mut String) {
-    let label = format!("{:>key_width$}", "error");
-    let styled_label = label.if_supports_color(Stdout, |t| t.red().bold().to_string());
-
-    if error.contains('\n') {
-        // Multiline error: indent continuation lines to align with value column
-        let indent = " ".repeat(key_width + 2); // key_width + ": "
-        let mut lines = error.lines();
-        if let Some(first) = lines.next() {
-            let _ = write!(
-                out,
-                "\n{}: {}",
-                styled_label,
-                first.if_supports_color(Stdout, |t| t.red().to_string())
-            );
-            for line in lines {
-                let _ = write!(
-                    out,
-                    "\n{}{}",
-                    indent,
-                    line.if_supports_color(Stdout, |t| t.red().to_string())
-                );
+/// Marker for the extra fields left out by `max_fields`.
+fn format_folded(count: usize, config: &Config, out: &mut Layout) {
+    let noun = if count == 1 { "field" } else { "fields" };
+    let marker = format!("{} +{count} more {noun}", config.glyphs().ellipsis);
+    if config.single_line {
+        out.push_str(" ");
+    } else {
+        // In the value column, under the shown fields.
+        let _ = write!(out, "\n{:width$}", "", width = config.key_min_width + 2);
+    }
+    out.push(Role::Diagnostic, Style::new().dimmed(), &marker);
+}
+
+/// Format the error field, or another field named like one, with red
+/// styling and multiline stacktrace support.
+fn format_error_field(key: &str, error: &str, key_width: usize, style: Style, out: &mut Layout) {
+    out.push_str("\n");
+    out.push(Role::Key, style.bold(), &format!("{key:>key_width$}"));
+    out.push_str(": ");
+
+    // Multiline error: indent continuation lines to align with value column
+    let indent = " ".repeat(key_width + 2); // key_width + ": "
+    for (i, line) in error.lines().enumerate() {
+        if i > 0 {
+            out.push_str("\n");
+            out.push_str(&indent);
+        }
+        out.push(Role::Error, style, line);
+    }
+}
+
+/// Append `text` as `role` in `style`, with the spans matched by `grep` as
+/// reversed [`Role::Match`] segments and those matched by `--highlight`
+/// patterns as [`Role::Highlight`] segments in their colors.
+fn push_highlighted(
+    out: &mut Layout,
+    role: Role,
+    text: &str,
+    grep: Option<&regex::Regex>,
+    style: Style,
+    config: &Config,
+) {
+    if grep.is_none() && config.highlights.is_empty() {
+        out.push(role, style, text);
+        return;
+    }
+    let patterns = grep
+        .into_iter()
+        .chain(config.highlights.iter().map(|h| &h.regex));
+    let mut last = 0;
+    for (range, index) in highlight::spans(text, patterns) {
+        out.push(role, style, &text[last..range.start]);
+        match index.checked_sub(usize::from(grep.is_some())) {
+            Some(i) => out.push(
+                Role::Highlight,
+                config.highlights[i].style,
+                &text[range.clone()],
+            ),
+            None => out.push(Role::Match, style.reversed(), &text[range.clone()]),
+        }
+        last = range.end;
+    }
+    out.push(role, style, &text[last..]);
+}
+
+/// Escape control characters that could rewrite the terminal.
+///
+/// Newlines and tabs are kept (messages and stacktraces rely on them); other
+/// C0/C1 control characters — notably `ESC`, which starts ANSI sequences
+/// smuggled in through log injection — are shown as `\xNN` / `\u{NN}`.
+/// Disabled with `--raw-control-chars`.
+fn escape_control<'a>(s: &'a str, config: &Config) -> Cow<'a, str> {
+    let is_unsafe = |c: char| c.is_control() && c != '\n' && c != '\t';
+    if config.raw_control_chars || !s.chars().any(is_unsafe) {
+        return Cow::Borrowed(s);
+    }
+    let mut escaped = String::with_capacity(s.len() + 8);
+    for c in s.chars() {
+        match c {
+            '\r' => escaped.push_str("\\r"),
+            c if is_unsafe(c) && u32::from(c) < 0x80 => {
+                let _ = write!(escaped, "\\x{:02x}", u32::from(c));
+            }
+            c if is_unsafe(c) => {
+                let _ = write!(escaped, "\\u{{{:x}}}", u32::from(c));
             }
+            c => escaped.push(c),
         }
-    } else {
-        let _ = write!(
-            out,
-            "\n{}: {}",
-            styled_label,
-            error.if_supports_color(Stdout, |t| t.red().to_string())
-        );
     }
+    Cow::Owned(escaped)
+}
+
+/// Strip terminal escape sequences other than SGR (colors/styles).
+///
+/// Raw passthrough lines keep their colors, but OSC (window title, OSC 52
+/// clipboard writes, hyperlinks), DCS/SOS/PM/APC strings, non-SGR CSI
+/// sequences (cursor movement, screen clearing), and other `ESC` commands
+/// are removed. 8-bit C1 introducers are removed the same way.
+/// Disabled with `--raw-control-chars`.
+fn strip_unsafe_escapes<'a>(s: &'a str, config: &Config) -> Cow<'a, str> {
+    let is_introducer = |c: char| c == '\x1b' || ('\u{80}'..='\u{9f}').contains(&c);
+    if config.raw_control_chars || !s.contains(is_introducer) {
+        return Cow::Borrowed(s);
+    }
+
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        // Normalize 7-bit `ESC x` and 8-bit C1 forms to the same introducer.
+        let kind = match c {
+            '\x1b' => match chars.next() {
+                Some(next) => next,
+                None => break,
+            },
+            '\u{9b}' => '[',
+            '\u{9d}' => ']',
+            '\u{90}' => 'P',
+            '\u{98}' => 'X',
+            '\u{9e}' => '^',
+            '\u{9f}' => '_',
+            c if is_introducer(c) => continue,
+            c => {
+                out.push(c);
+                continue;
+            }
+        };
+        match kind {
+            '[' => {
+                let mut seq = String::from("\x1b[");
+                let mut final_byte = None;
+                for c in chars.by_ref() {
+                    seq.push(c);
+                    if ('\u{40}'..='\u{7e}').contains(&c) {
+                        final_byte = Some(c);
+                        break;
+                    }
+                }
+                // Only 7-bit SGR survives; 8-bit CSI is rare enough to drop.
+                if final_byte == Some('m') && c == '\x1b' {
+                    out.push_str(&seq);
+                }
+            }
+            ']' | 'P' | 'X' | '^' | '_' => {
+                // String sequence: runs until BEL or ST (`ESC \\` / U+009C).
+                while let Some(c) = chars.next() {
+                    if c == '\x07' || c == '\u{9c}' {
+                        break;
+                    }
+                    if c == '\x1b' && chars.next_if_eq(&'\\').is_some() {
+                        break;
+                    }
+                }
+            }
+            // Two-character `ESC` commands, with optional intermediate bytes.
+            mut c => {
+                while ('\u{20}'..='\u{2f}').contains(&c) {
+                    match chars.next() {
+                        Some(next) => c = next,
+                        None => break,
+                    }
+                }
+            }
+        }
+    }
+    Cow::Owned(out)
+}
+
+/// Whether the extra field `key` holds a multi-line exception or stack
+/// trace: its last key segment is an error alias (`exception`,
+/// `error.stack`) and `value` spans several lines.
+fn is_stack_trace(key: &str, value: &str) -> bool {
+    let name = key.rsplit('.').next().unwrap_or(key);
+    value.contains('\n') && fields::ERROR_ALIASES.contains(&name)
 }
 
 /// Format a JSON value for display.
@@ -330,37 +811,151 @@ fn format_value(value: &serde_json::Value) -> String {
     }
 }
 
-/// Truncate a value string to `max_len` characters, appending `…` if truncated.
+/// Whether a string value should be quoted under the given policy.
+fn needs_quotes(s: &str, mode: QuoteMode) -> bool {
+    match mode {
+        QuoteMode::Always => true,
+        QuoteMode::Never => false,
+        QuoteMode::Auto => {
+            s.is_empty()
+                || s.chars()
+                    .any(|c| c.is_whitespace() || c.is_control() || matches!(c, '"' | '=' | '\\'))
+        }
+    }
+}
+
+/// Wrap a value in double quotes, escaping quotes, backslashes, and control characters.
+fn quote(s: &str) -> String {
+    serde_json::Value::from(s).to_string()
+}
+
+/// Truncate a value string to `max_len` characters, marking the cut with
+/// `ellipsis`: after the kept start, between the kept start and end, or
+/// before the kept end, depending on `mode`.
 ///
 /// If `max_len` is `0`, no truncation is applied.
 #[inline]
-fn truncate_value(s: &str, max_len: usize) -> String {
-    if max_len == 0 || s.chars().count() <= max_len {
+fn truncate_value(s: &str, max_len: usize, mode: TruncateMode, ellipsis: &str) -> String {
+    let len = s.chars().count();
+    if max_len == 0 || len <= max_len {
         return s.to_string();
     }
-    let truncated: String = s.chars().take(max_len).collect();
-    format!("{truncated}…")
+    let head = |n| s.chars().take(n).collect::<String>();
+    let tail = |n| s.chars().skip(len - n).collect::<String>();
+    match mode {
+        TruncateMode::Head => format!("{}{ellipsis}", head(max_len)),
+        TruncateMode::Middle => {
+            // An odd character goes to the start.
+            let end = max_len / 2;
+            format!("{}{ellipsis}{}", head(max_len - end), tail(end))
+        }
+        TruncateMode::Tail => format!("{ellipsis}{}", tail(max_len)),
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_needs_quotes_policies() {
+        assert!(needs_quotes("plain", QuoteMode::Always));
+        assert!(!needs_quotes("has space", QuoteMode::Never));
+        assert!(!needs_quotes("plain", QuoteMode::Auto));
+        assert!(needs_quotes("has space", QuoteMode::Auto));
+        assert!(needs_quotes("a=b", QuoteMode::Auto));
+        assert!(needs_quotes("", QuoteMode::Auto));
+    }
+
+    #[test]
+    fn test_quote_escapes() {
+        assert_eq!(quote("say \"hi\""), r#""say \"hi\"""#);
+        assert_eq!(quote("a\tb"), r#""a\tb""#);
+    }
+
+    #[test]
+    fn test_escape_control_chars() {
+        let config = Config::default();
+        assert_eq!(escape_control("plain", &config), "plain");
+        assert_eq!(
+            escape_control("\x1b[31mred\x1b[0m", &config),
+            "\\x1b[31mred\\x1b[0m"
+        );
+        assert_eq!(escape_control("a\rb\u{9b}c", &config), "a\\rb\\u{9b}c");
+        // Newlines and tabs are kept
+        assert_eq!(escape_control("a\n\tb", &config), "a\n\tb");
+    }
+
+    #[test]
+    fn test_escape_control_disabled() {
+        let config = Config {
+            raw_control_chars: true,
+            ..Config::default()
+        };
+        assert_eq!(escape_control("\x1b[2J", &config), "\x1b[2J");
+    }
+
+    #[test]
+    fn test_strip_unsafe_escapes_keeps_sgr() {
+        let config = Config::default();
+        let colored = "\x1b[1;31merror\x1b[0m plain";
+        assert_eq!(strip_unsafe_escapes(colored, &config), colored);
+    }
+
+    #[test]
+    fn test_strip_unsafe_escapes_removes_osc_and_dcs() {
+        let config = Config::default();
+        assert_eq!(
+            strip_unsafe_escapes("a\x1b]0;pwned\x07b", &config),
+            "ab",
+            "OSC title terminated by BEL"
+        );
+        assert_eq!(
+            strip_unsafe_escapes("a\x1b]52;c;ZXZpbA==\x1b\\b", &config),
+            "ab",
+            "OSC 52 clipboard terminated by ST"
+        );
+        assert_eq!(strip_unsafe_escapes("a\x1bPq#0\x1b\\b", &config), "ab");
+        assert_eq!(strip_unsafe_escapes("a\u{9d}0;t\u{9c}b", &config), "ab");
+        // Unterminated strings swallow the rest of the line
+        assert_eq!(strip_unsafe_escapes("a\x1b]0;title", &config), "a");
+    }
+
+    #[test]
+    fn test_strip_unsafe_escapes_removes_non_sgr() {
+        let config = Config::default();
+        assert_eq!(strip_unsafe_escapes("\x1b[2J\x1b[Hx", &config), "x");
+        assert_eq!(strip_unsafe_escapes("\x1bcx\x1b(By", &config), "xy");
+    }
+
+    #[test]
+    fn test_strip_unsafe_escapes_disabled() {
+        let config = Config {
+            raw_control_chars: true,
+            ..Config::default()
+        };
+        let s = "\x1b]0;title\x07";
+        assert_eq!(strip_unsafe_escapes(s, &config), s);
+    }
+
     #[test]
     fn test_truncate_value_no_truncation() {
-        assert_eq!(truncate_value("hello", 120), "hello");
+        assert_eq!(
+            truncate_value("hello", 120, TruncateMode::Head, "…"),
+            "hello"
+        );
     }
 
     #[test]
     fn test_truncate_value_at_limit() {
         let s = "a".repeat(120);
-        assert_eq!(truncate_value(&s, 120), s);
+        assert_eq!(truncate_value(&s, 120, TruncateMode::Head, "…"), s);
     }
 
     #[test]
     fn test_truncate_value_over_limit() {
         let s = "a".repeat(130);
-        let result = truncate_value(&s, 120);
+        let result = truncate_value(&s, 120, TruncateMode::Head, "…");
         assert_eq!(result.chars().count(), 121); // 120 + '…'
         assert!(result.ends_with('…'));
     }
@@ -368,7 +963,32 @@ mod tests {
     #[test]
     fn test_truncate_value_disabled() {
         let s = "a".repeat(1000);
-        assert_eq!(truncate_value(&s, 0), s);
+        assert_eq!(truncate_value(&s, 0, TruncateMode::Head, "…"), s);
+    }
+
+    #[test]
+    fn test_truncate_value_ascii_ellipsis() {
+        assert_eq!(
+            truncate_value("abcdef", 3, TruncateMode::Head, "..."),
+            "abc..."
+        );
+    }
+
+    #[test]
+    fn test_truncate_value_middle_and_tail() {
+        let path = "/var/lib/app/releases/42/config.yaml";
+        assert_eq!(
+            truncate_value(path, 9, TruncateMode::Middle, "…"),
+            "/var/…yaml"
+        );
+        assert_eq!(
+            truncate_value(path, 11, TruncateMode::Tail, "…"),
+            "…config.yaml"
+        );
+        assert_eq!(
+            truncate_value("abcdef", 6, TruncateMode::Tail, "…"),
+            "abcdef"
+        );
     }
 
     #[test]
@@ -413,7 +1033,7 @@ mod tests {
     fn test_truncate_value_multibyte_characters() {
         // Emoji characters are multi-byte but count as 1 char each
         let s = "Hello \u{1F600}\u{1F600}\u{1F600} world";
-        let result = truncate_value(s, 8);
+        let result = truncate_value(s, 8, TruncateMode::Head, "…");
         // Should truncate after 8 chars: "Hello 😀😀" + "…"
         assert!(result.ends_with('…'));
         assert_eq!(result.chars().count(), 9); // 8 + '…'
@@ -422,7 +1042,7 @@ mod tests {
     #[test]
     fn test_truncate_value_cjk_characters() {
         let s = "\u{4F60}\u{597D}\u{4E16}\u{754C}"; // 你好世界
-        let result = truncate_value(s, 2);
+        let result = truncate_value(s, 2, TruncateMode::Head, "…");
         assert_eq!(result, "\u{4F60}\u{597D}\u{2026}"); // 你好…
     }
 
@@ -476,6 +1096,114 @@ mod tests {
         assert!(out.is_empty());
     }
 
+    #[test]
+    fn test_where_filtering() {
+        disable_color();
+        let config = Config {
+            where_filter: vec![
+                crate::expr::Expr::parse(r#"status >= 500 && method == "POST""#).unwrap(),
+            ],
+            ..Config::default()
+        };
+        let mut out = String::new();
+        format_line(
+            r#"{"msg":"kept","status":502,"method":"POST"}"#,
+            &config,
+            &mut out,
+        );
+        assert!(out.contains("kept"));
+        out.clear();
+        format_line(
+            r#"{"msg":"dropped","status":502,"method":"GET"}"#,
+            &config,
+            &mut out,
+        );
+        assert!(out.is_empty());
+        // Raw lines carry no fields and pass through.
+        out.clear();
+        format_line("plain text", &config, &mut out);
+        assert_eq!(out, "plain text");
+    }
+
+    #[test]
+    fn test_focus_tints_matches_and_dims_the_rest() {
+        let config = Config {
+            focus: Some(crate::expr::Expr::parse("request_id=abc").unwrap()),
+            ..Config::default()
+        };
+        let overlay = |line: &str| {
+            let mut layout = Layout::default();
+            let parsed = parser::parse_line(line, &config);
+            format_layout(
+                &parsed,
+                line,
+                &config,
+                &mut FormatState::default(),
+                &mut layout,
+            );
+            layout.overlay()
+        };
+        assert_eq!(
+            overlay(r#"{"msg":"a","request_id":"abc"}"#),
+            color_name_to_style(FOCUS_COLOR).map(Overlay::Tint)
+        );
+        assert_eq!(
+            overlay(r#"{"msg":"b","request_id":"xyz"}"#),
+            Some(Overlay::Dim)
+        );
+        assert_eq!(overlay("plain text"), Some(Overlay::Dim));
+    }
+
+    #[test]
+    fn test_refused_records_leave_state_alone() {
+        let config = Config {
+            watch_field: Some("host".to_string()),
+            ..Config::default()
+        };
+        let mut state = FormatState::default();
+        let mut format = |line: &str, admitted: bool| {
+            let mut layout = Layout::default();
+            let parsed = parser::parse_line(line, &config);
+            format_admitted(&parsed, line, &config, &mut state, &mut layout, |_| {
+                admitted
+            });
+            !layout.is_empty()
+        };
+        assert!(!format(r#"{"msg":"a","host":"db1"}"#, false));
+        // The refused record was not the last value seen.
+        assert!(format(r#"{"msg":"b","host":"db1"}"#, true));
+        assert!(!format(r#"{"msg":"c","host":"db1"}"#, true));
+    }
+
+    #[test]
+    fn test_dim_unmatched_keeps_filtered_records() {
+        let config = Config {
+            where_filter: vec![crate::expr::Expr::parse("status>=500").unwrap()],
+            grep_pattern: Some(regex::Regex::new("fail(ed)?").unwrap()),
+            dim_unmatched: true,
+            ..Config::default()
+        };
+        let format = |line: &str| {
+            let mut layout = Layout::default();
+            let parsed = parser::parse_line(line, &config);
+            format_layout(
+                &parsed,
+                line,
+                &config,
+                &mut FormatState::default(),
+                &mut layout,
+            );
+            layout
+        };
+        let matched = format(r#"{"msg":"failed","status":502}"#);
+        assert_eq!(matched.overlay(), None);
+        let unmatched = format(r#"{"msg":"ok","status":200}"#);
+        assert!(unmatched.text().contains("ok"));
+        assert_eq!(unmatched.overlay(), Some(UNMATCHED));
+        assert_eq!(format("plain text").overlay(), Some(UNMATCHED));
+        assert_eq!(format("fail text").overlay(), None);
+    }
+
     #[test]
     fn test_level_filtering() {
         disable_color();
@@ -523,6 +1251,33 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_max_fields_folds_the_rest() {
+        disable_color();
+        let config = Config {
+            max_fields: 2,
+            exclude_fields: Some(vec!["d".to_string()]),
+            ..Config::default()
+        };
+        let mut out = String::new();
+        let line = r#"{"msg":"dense","a":1,"b":2,"c":3,"d":4,"e":5}"#;
+        format_line(line, &config, &mut out);
+        assert!(out.contains("a: 1") && out.contains("b: 2"), "{out}");
+        assert!(!out.contains("c: 3") && !out.contains("e: 5"), "{out}");
+        assert!(
+            out.ends_with(&format!("\n{:27}… +2 more fields", "")),
+            "{out}"
+        );
+
+        let config = Config {
+            max_fields: 4,
+            single_line: true,
+            ..Config::default()
+        };
+        format_line(line, &config, &mut out);
+        assert!(out.ends_with("d=4 … +1 more field"), "{out}");
+    }
+
     #[test]
     fn test_include_fields() {
         disable_color();
@@ -783,10 +1538,40 @@ mod tests {
     // ── format_error_field tests ────────────────────────────────────
 
     #[test]
-    fn test_format_error_field_single_line() {
+    fn test_stack_trace_extra_rendered_as_block() {
         disable_color();
+        let config = Config {
+            key_min_width: 10,
+            ..Config::default()
+        };
         let mut out = String::new();
-        format_error_field("connection timeout", 25, &mut out);
+        let line = r#"{"msg":"boom","error":"db down","exception":"Traceback:\n  File \"app.py\"\nValueError"}"#;
+        format_line(line, &config, &mut out);
+        assert!(
+            out.ends_with(
+                " exception: Traceback:\n              File \"app.py\"\n            ValueError\n     error: db down"
+            ),
+            "{out}"
+        );
+
+        // Single-line values and other keys are ordinary extras.
+        assert!(!is_stack_trace("exception", "one line"));
+        assert!(!is_stack_trace("body", "a\nb"));
+        assert!(is_stack_trace("error.stack", "a\nb"));
+    }
+
+    #[test]
+    fn test_format_error_field_single_line() {
+        disable_color();
+        let mut layout = Layout::default();
+        format_error_field(
+            "error",
+            "connection timeout",
+            25,
+            Style::new().red(),
+            &mut layout,
+        );
+        let out = layout.text();
         assert!(
             out.contains("error"),
             "error label should appear.\nGot: {out}"
@@ -812,8 +1597,9 @@ mod tests {
     fn test_format_error_field_multiline() {
         disable_color();
         let error = "Traceback:\n  File \"app.py\", line 72\n    raise Error";
-        let mut out = String::new();
-        format_error_field(error, 25, &mut out);
+        let mut layout = Layout::default();
+        format_error_field("error", error, 25, Style::new().red(), &mut layout);
+        let out = layout.text();
         assert!(
             out.contains("error"),
             "error label should appear.\nGot: {out}"
@@ -838,6 +1624,88 @@ mod tests {
         );
     }
 
+    // ── Elapsed column ──────────────────────────────────────────────
+
+    #[test]
+    fn test_format_elapsed_column() {
+        disable_color();
+        let mut config = Config {
+            elapsed: Some(ElapsedMode::Only),
+            ..Config::default()
+        };
+        let mut state = FormatState::default();
+        let mut out = String::new();
+        let mut render = |line: &str, config: &Config, out: &mut String| {
+            out.clear();
+            let parsed = parser::parse_line(line, config);
+            format_line_with_outcome(&parsed, line, config, &mut state, out);
+        };
+        render(
+            r#"{"ts":"2026-01-15T10:30:00Z","level":"info","msg":"start"}"#,
+            &config,
+            &mut out,
+        );
+        assert_eq!(out, "+00:00.000   INFO: start");
+        render(
+            r#"{"ts":"2026-01-15T10:31:03.412Z","level":"info","msg":"done"}"#,
+            &config,
+            &mut out,
+        );
+        assert_eq!(out, "+01:03.412   INFO: done");
+
+        config.elapsed = Some(ElapsedMode::Beside);
+        config.timestamp_format = "%H:%M:%S".to_string();
+        render(
+            r#"{"ts":"2026-01-15T10:30:01Z","level":"info","msg":"more"}"#,
+            &config,
+            &mut out,
+        );
+        assert_eq!(out, "10:30:01  +00:01.000   INFO: more");
+    }
+
+    #[test]
+    fn test_elapsed_origin_is_first_record_even_if_filtered() {
+        disable_color();
+        let config = Config {
+            elapsed: Some(ElapsedMode::Only),
+            min_level: Some(Level::Info),
+            ..Config::default()
+        };
+        let mut state = FormatState::default();
+        let mut out = String::new();
+        for line in [
+            r#"{"ts":"2026-01-15T10:30:00Z","level":"debug","msg":"hidden"}"#,
+            r#"{"ts":"2026-01-15T10:30:02Z","level":"info","msg":"shown"}"#,
+        ] {
+            let parsed = parser::parse_line(line, &config);
+            format_line_with_outcome(&parsed, line, &config, &mut state, &mut out);
+        }
+        assert_eq!(out, "+00:02.000   INFO: shown");
+    }
+
+    // ── Icons ───────────────────────────────────────────────────────
+
+    #[test]
+    fn test_format_record_with_icons() {
+        disable_color();
+        let mut config = Config::default();
+        config.icons.set = icons::IconSet::Emoji;
+        config.key_min_width = 16;
+        let mut out = String::new();
+        let line = r#"{"level":"error","msg":"boom","http.status":500,"user":"bob"}"#;
+        format_line(line, &config, &mut out);
+        let lines: Vec<&str> = out.lines().collect();
+        assert!(lines[0].starts_with("❌ ERROR: boom"), "Got: {out}");
+        // 2 pad + 2-column icon + space + 11-char key fill the 16 columns.
+        assert_eq!(lines[1], "  🌐 http.status: 500", "Got: {out}");
+        assert_eq!(lines[2], "            user: bob", "Got: {out}");
+
+        config.ascii = true;
+        out.clear();
+        format_line(line, &config, &mut out);
+        assert!(out.starts_with("ERROR: boom"), "Got: {out}");
+    }
+
     // ── Logger and caller rendering ─────────────────────────────────
 
     #[test]
@@ -858,6 +1726,19 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_hide_logger() {
+        disable_color();
+        let config = Config {
+            hide_logger: true,
+            ..Config::default()
+        };
+        let mut out = String::new();
+        let line = r#"{"level":"info","msg":"hello","logger":"payments.processor"}"#;
+        format_line(line, &config, &mut out);
+        assert_eq!(out, " INFO: hello");
+    }
+
     #[test]
     fn test_format_record_with_caller() {
         disable_color();
@@ -0,0 +1,84 @@
+//! Output glyphs with ASCII fallbacks (`--ascii`).
+//!
+//! Every non-ASCII marker cor draws — truncation ellipses, banner rules,
+//! chart bars, repeat counts — comes from a [`Glyphs`] set so terminals
+//! without Unicode support get plain `...`, `-`, and `|` instead of
+//! garbled output. The
+//! ASCII set is picked with `--ascii`, `ascii = true` in the config file,
+//! or automatically when the locale does not use UTF-8.
+
+/// Markers used when rendering output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Glyphs {
+    /// Appended to truncated values.
+    pub ellipsis: &'static str,
+    /// Horizontal rule segment, e.g. around annotation banners.
+    pub rule: &'static str,
+    /// Vertical separator.
+    pub bar: &'static str,
+    /// `--sparkline` ticks, lowest to highest.
+    pub spark: &'static [char],
+    /// Multiplication sign of `--seen-counts` markers.
+    pub times: &'static str,
+}
+
+/// Default Unicode glyphs.
+pub const UNICODE: Glyphs = Glyphs {
+    ellipsis: "…",
+    rule: "────",
+    bar: "│",
+    spark: &['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'],
+    times: "×",
+};
+
+/// ASCII-only glyphs.
+pub const ASCII: Glyphs = Glyphs {
+    ellipsis: "...",
+    rule: "----",
+    bar: "|",
+    spark: &['_', '.', '-', '~', '=', '+', '*', '#'],
+    times: "x",
+};
+
+/// Whether the locale in the environment (`LC_ALL`, then `LC_CTYPE`, then
+/// `LANG`) rules out UTF-8 output. An unset locale is assumed to be UTF-8.
+pub fn locale_wants_ascii(var: impl Fn(&str) -> Option<String>) -> bool {
+    ["LC_ALL", "LC_CTYPE", "LANG"]
+        .into_iter()
+        .find_map(|name| var(name).filter(|value| !value.is_empty()))
+        .is_some_and(|locale| {
+            let locale = locale.to_ascii_lowercase();
+            !(locale.contains("utf-8") || locale.contains("utf8"))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env<'a>(vars: &'a [(&'a str, &'a str)]) -> impl Fn(&str) -> Option<String> + 'a {
+        move |name| {
+            vars.iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| (*value).to_string())
+        }
+    }
+
+    #[test]
+    fn test_locale_detection() {
+        assert!(!locale_wants_ascii(env(&[])));
+        assert!(!locale_wants_ascii(env(&[("LANG", "en_US.UTF-8")])));
+        assert!(!locale_wants_ascii(env(&[("LANG", "C.utf8")])));
+        assert!(locale_wants_ascii(env(&[("LANG", "C")])));
+        assert!(locale_wants_ascii(env(&[("LANG", "de_DE.ISO-8859-1")])));
+        // LC_ALL wins over LANG; empty values are skipped.
+        assert!(locale_wants_ascii(env(&[
+            ("LC_ALL", "POSIX"),
+            ("LANG", "en_US.UTF-8")
+        ])));
+        assert!(!locale_wants_ascii(env(&[
+            ("LC_ALL", ""),
+            ("LANG", "en_US.UTF-8")
+        ])));
+    }
+}
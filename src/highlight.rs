@@ -0,0 +1,111 @@
+//! Pattern highlighting (`--highlight REGEX[:color]`).
+//!
+//! Each [`Highlight`] colors the substrings its regex matches in messages,
+//! field values, and raw lines, e.g. request IDs or customer emails,
+//! without filtering anything out. Spans matched by `--grep` keep
+//! their reversed style; where patterns overlap, the earlier one wins.
+
+use std::ops::Range;
+
+use owo_colors::Style;
+use regex::Regex;
+
+use crate::level::color_name_to_style;
+
+/// Color of a pattern given without one.
+const DEFAULT_COLOR: &str = "yellow";
+
+/// A pattern and the style its matches are drawn in.
+#[derive(Debug, Clone)]
+pub struct Highlight {
+    pub regex: Regex,
+    pub style: Style,
+}
+
+impl Highlight {
+    /// Parse `REGEX[:color]`. The text after the last `:` is taken as the
+    /// color only if it names one, so patterns like `\d+:\d+` need no
+    /// escaping.
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let (pattern, style) = match input.rsplit_once(':') {
+            Some((pattern, color)) if !pattern.is_empty() => match color_name_to_style(color) {
+                Some(style) => (pattern, style),
+                None => (input, default_style()),
+            },
+            _ => (input, default_style()),
+        };
+        if pattern.is_empty() {
+            return Err("empty highlight pattern".to_string());
+        }
+        let regex = Regex::new(pattern)
+            .map_err(|e| format!("invalid highlight pattern '{pattern}': {e}"))?;
+        Ok(Self { regex, style })
+    }
+}
+
+fn default_style() -> Style {
+    color_name_to_style(DEFAULT_COLOR).unwrap_or_default()
+}
+
+/// Non-empty, non-overlapping spans of `text` matched by `patterns`, in
+/// order of position, each with the index of its pattern. A match
+/// overlapping an earlier pattern's match is dropped.
+pub fn spans<'a>(
+    text: &str,
+    patterns: impl IntoIterator<Item = &'a Regex>,
+) -> Vec<(Range<usize>, usize)> {
+    let mut spans: Vec<(Range<usize>, usize)> = Vec::new();
+    for (index, regex) in patterns.into_iter().enumerate() {
+        for found in regex.find_iter(text) {
+            let range = found.range();
+            if range.is_empty()
+                || spans
+                    .iter()
+                    .any(|(taken, _)| taken.start < range.end && range.start < taken.end)
+            {
+                continue;
+            }
+            spans.push((range, index));
+        }
+    }
+    spans.sort_by_key(|(range, _)| range.start);
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_color_suffix() {
+        let highlight = Highlight::parse("req-[0-9]+:cyan").unwrap();
+        assert_eq!(highlight.regex.as_str(), "req-[0-9]+");
+        assert_eq!(highlight.style, Style::new().cyan().bold());
+
+        // A suffix that is not a color belongs to the pattern.
+        let highlight = Highlight::parse(r"\d+:\d+").unwrap();
+        assert_eq!(highlight.regex.as_str(), r"\d+:\d+");
+        assert_eq!(highlight.style, Style::new().yellow().bold());
+
+        // Hex and 256-color suffixes are colors too.
+        let highlight = Highlight::parse("timeout:#ff8800").unwrap();
+        assert_eq!(highlight.regex.as_str(), "timeout");
+        assert_eq!(highlight.style, Style::new().truecolor(255, 136, 0).bold());
+        let highlight = Highlight::parse("timeout:208").unwrap();
+        assert_eq!(highlight.regex.as_str(), "timeout");
+
+        assert!(Highlight::parse("").is_err());
+        assert!(Highlight::parse(":red").is_ok_and(|h| h.regex.as_str() == ":red"));
+        assert!(Highlight::parse("(:red").is_err());
+    }
+
+    #[test]
+    fn test_spans_skip_overlaps() {
+        let first = Regex::new("a[bc]+").unwrap();
+        let second = Regex::new("c d|e|x*").unwrap();
+        assert_eq!(
+            spans("abc de abc", [&first, &second]),
+            [(0..3, 0), (5..6, 1), (7..10, 0)]
+        );
+    }
+}
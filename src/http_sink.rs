@@ -0,0 +1,523 @@
+//! Network sinks for `--output` (`net` feature).
+//!
+//! [`WebhookSink`] posts NDJSON batches to an HTTP endpoint and
+//! [`LokiSink`] pushes to a Grafana Loki server. Both deliver in batches
+//! from a background thread, or a task on the shared runtime with the
+//! `async` feature, so a slow endpoint never stalls log processing; when
+//! the queue is full, records are dropped and the count is reported when
+//! the sink finishes. Only plain `http://` endpoints are supported.
+
+use std::collections::BTreeMap;
+use std::io;
+#[cfg(not(feature = "async"))]
+use std::io::{BufRead, BufReader, Read, Write};
+use std::mem;
+#[cfg(not(feature = "async"))]
+use std::net::TcpStream;
+#[cfg(not(feature = "async"))]
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, SyncSender, TrySendError};
+#[cfg(not(feature = "async"))]
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+#[cfg(feature = "async")]
+use tokio::sync::mpsc::{self, Receiver, Sender as SyncSender, error::TrySendError};
+#[cfg(feature = "async")]
+use tokio::task::JoinHandle;
+
+use crate::ingest::ListenAddr;
+use crate::sink::{FormattedRecord, OutputSink, strip_sgr};
+
+/// Records queued for a network sink before new ones are dropped.
+const QUEUE_CAPACITY: usize = 4096;
+
+/// Most records delivered in one request.
+const BATCH_SIZE: usize = 500;
+
+/// Longest a record waits in a partial batch before it is delivered.
+const BATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long connecting to, writing to, or waiting on an endpoint may take.
+const HTTP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Loki's push API path, used when a `loki:` URL has none.
+const LOKI_PUSH_PATH: &str = "/loki/api/v1/push";
+
+/// Queue feeding a background delivery thread or task.
+#[derive(Debug)]
+struct Batcher<T> {
+    /// Endpoint description for messages.
+    name: String,
+    tx: Option<SyncSender<T>>,
+    thread: Option<JoinHandle<()>>,
+    dropped: usize,
+}
+
+impl<T: Send + 'static> Batcher<T> {
+    /// Start a thread `POST`ing batches of queued items to `endpoint`, each
+    /// encoded by `encode`.
+    #[cfg(not(feature = "async"))]
+    #[allow(clippy::unnecessary_wraps)]
+    fn spawn(
+        name: String,
+        endpoint: ListenAddr,
+        encode: impl Fn(Vec<T>) -> Request + Send + 'static,
+    ) -> io::Result<Self> {
+        let (tx, rx) = mpsc::sync_channel(QUEUE_CAPACITY);
+        let label = name.clone();
+        let thread = thread::spawn(move || {
+            run_batches(&rx, |batch| post(&endpoint, &encode(batch)), &label);
+        });
+        Ok(Self {
+            name,
+            tx: Some(tx),
+            thread: Some(thread),
+            dropped: 0,
+        })
+    }
+
+    /// Start a task on the shared runtime `POST`ing batches of queued items
+    /// to `endpoint`, each encoded by `encode`.
+    #[cfg(feature = "async")]
+    fn spawn(
+        name: String,
+        endpoint: ListenAddr,
+        encode: impl Fn(Vec<T>) -> Request + Send + 'static,
+    ) -> io::Result<Self> {
+        let runtime = crate::runtime::handle().map_err(io::Error::other)?;
+        let (tx, rx) = mpsc::channel(QUEUE_CAPACITY);
+        let label = name.clone();
+        let thread = runtime.spawn(async move {
+            run_batches_async(rx, &endpoint, encode, &label).await;
+        });
+        Ok(Self {
+            name,
+            tx: Some(tx),
+            thread: Some(thread),
+            dropped: 0,
+        })
+    }
+
+    /// Queue `item`, dropping it if the delivery thread has fallen behind.
+    fn send(&mut self, item: T) {
+        if let Some(ref tx) = self.tx
+            && let Err(TrySendError::Full(_)) = tx.try_send(item)
+        {
+            self.dropped += 1;
+        }
+    }
+
+    /// Deliver the last batch and stop the thread.
+    fn finish(&mut self) {
+        drop(self.tx.take());
+        if let Some(thread) = self.thread.take() {
+            #[cfg(not(feature = "async"))]
+            let _ = thread.join();
+            #[cfg(feature = "async")]
+            if let Ok(runtime) = crate::runtime::handle() {
+                let _ = runtime.block_on(thread);
+            }
+        }
+        if self.dropped > 0 {
+            eprintln!(
+                "cor: {}: dropped {} records (endpoint too slow)",
+                self.name, self.dropped
+            );
+        }
+    }
+}
+
+/// Items waiting to be delivered together.
+#[derive(Debug)]
+struct Batch<T> {
+    items: Vec<T>,
+    /// When the batch is due, set by its first item.
+    deadline: Option<Instant>,
+}
+
+impl<T> Batch<T> {
+    const fn new() -> Self {
+        Self {
+            items: Vec::new(),
+            deadline: None,
+        }
+    }
+
+    /// Add `item`, returning whether the batch is full.
+    fn push(&mut self, item: T) -> bool {
+        self.items.push(item);
+        self.deadline
+            .get_or_insert_with(|| Instant::now() + BATCH_INTERVAL);
+        self.items.len() >= BATCH_SIZE
+    }
+
+    /// The items gathered so far, starting a new batch.
+    fn take(&mut self) -> Vec<T> {
+        self.deadline = None;
+        mem::take(&mut self.items)
+    }
+}
+
+/// Report a failed delivery; it is best effort, so the batch is lost.
+fn report(name: &str, delivered: io::Result<()>) {
+    if let Err(e) = delivered {
+        eprintln!("cor: {name}: {e}");
+    }
+}
+
+/// Collect items into batches of up to [`BATCH_SIZE`], delivering each when
+/// full or [`BATCH_INTERVAL`] after its first item, until the queue closes.
+#[cfg(not(feature = "async"))]
+fn run_batches<T>(rx: &Receiver<T>, mut deliver: impl FnMut(Vec<T>) -> io::Result<()>, name: &str) {
+    let mut batch = Batch::new();
+    loop {
+        let received = match batch.deadline {
+            None => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+            Some(deadline) => rx.recv_timeout(deadline.saturating_duration_since(Instant::now())),
+        };
+        let closed = match received {
+            Ok(item) => {
+                if !batch.push(item) {
+                    continue;
+                }
+                false
+            }
+            Err(RecvTimeoutError::Timeout) => false,
+            Err(RecvTimeoutError::Disconnected) => true,
+        };
+        let items = batch.take();
+        if !items.is_empty() {
+            report(name, deliver(items));
+        }
+        if closed {
+            return;
+        }
+    }
+}
+
+/// Batch items like [`run_batches`], `POST`ing each batch to `endpoint`.
+#[cfg(feature = "async")]
+async fn run_batches_async<T>(
+    mut rx: Receiver<T>,
+    endpoint: &ListenAddr,
+    encode: impl Fn(Vec<T>) -> Request,
+    name: &str,
+) {
+    let mut batch = Batch::new();
+    loop {
+        let received = match batch.deadline {
+            None => Ok(rx.recv().await),
+            Some(deadline) => tokio::time::timeout_at(deadline.into(), rx.recv()).await,
+        };
+        let closed = match received {
+            Ok(Some(item)) => {
+                if !batch.push(item) {
+                    continue;
+                }
+                false
+            }
+            Err(_) => false,
+            Ok(None) => true,
+        };
+        let items = batch.take();
+        if !items.is_empty() {
+            report(name, post_async(endpoint, &encode(items)).await);
+        }
+        if closed {
+            return;
+        }
+    }
+}
+
+/// Sink `POST`ing records as NDJSON batches to an HTTP endpoint.
+///
+/// Records are sent as their normalized JSON; raw lines as
+/// `{"message": ...}`.
+#[derive(Debug)]
+pub struct WebhookSink {
+    batcher: Batcher<String>,
+}
+
+impl WebhookSink {
+    /// Start delivering to `endpoint`.
+    pub fn new(endpoint: ListenAddr) -> io::Result<Self> {
+        let name = format!("webhook http://{}{}", endpoint.addr, endpoint.path);
+        let batcher = Batcher::spawn(name, endpoint, |lines: Vec<String>| {
+            let mut body = lines.join("\n");
+            body.push('\n');
+            Request {
+                content_type: "application/x-ndjson",
+                body: body.into_bytes(),
+            }
+        })?;
+        Ok(Self { batcher })
+    }
+}
+
+impl OutputSink for WebhookSink {
+    fn emit(&mut self, record: &FormattedRecord<'_>) -> io::Result<()> {
+        let line = match record.json {
+            Some(json) => json.to_string(),
+            None => serde_json::json!({ "message": strip_sgr(record.layout.text()) }).to_string(),
+        };
+        self.batcher.send(line);
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.batcher.finish();
+        Ok(())
+    }
+}
+
+/// One record queued for Loki.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct LokiEntry {
+    source: String,
+    level: Option<String>,
+    /// Arrival time in nanoseconds since the Unix epoch.
+    nanos: u128,
+    line: String,
+}
+
+/// Sink pushing records to Grafana Loki.
+///
+/// Each record becomes a log line in the stream labelled
+/// `{job="cor", source=..., level=...}`; records keep their normalized JSON
+/// so Loki's `json` parser can extract the fields.
+#[derive(Debug)]
+pub struct LokiSink {
+    batcher: Batcher<LokiEntry>,
+}
+
+impl LokiSink {
+    /// Start pushing to the Loki server at `endpoint`; a bare `http://HOST:PORT`
+    /// uses the standard push path.
+    pub fn new(mut endpoint: ListenAddr) -> io::Result<Self> {
+        if endpoint.path == "/" {
+            endpoint.path = LOKI_PUSH_PATH.to_string();
+        }
+        let name = format!("loki http://{}{}", endpoint.addr, endpoint.path);
+        let batcher = Batcher::spawn(name, endpoint, |entries: Vec<LokiEntry>| Request {
+            content_type: "application/json",
+            body: loki_body(&entries).to_string().into_bytes(),
+        })?;
+        Ok(Self { batcher })
+    }
+}
+
+impl OutputSink for LokiSink {
+    fn emit(&mut self, record: &FormattedRecord<'_>) -> io::Result<()> {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let level = record
+            .json
+            .and_then(|json| json.get("level"))
+            .and_then(serde_json::Value::as_str)
+            .map(str::to_string);
+        let line = match record.json {
+            Some(json) => json.to_string(),
+            None => strip_sgr(record.layout.text()).into_owned(),
+        };
+        self.batcher.send(LokiEntry {
+            source: record.source.to_string(),
+            level,
+            nanos,
+            line,
+        });
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.batcher.finish();
+        Ok(())
+    }
+}
+
+/// Push request body with one stream per label set.
+fn loki_body(entries: &[LokiEntry]) -> serde_json::Value {
+    let mut streams: BTreeMap<(&str, Option<&str>), Vec<serde_json::Value>> = BTreeMap::new();
+    for entry in entries {
+        streams
+            .entry((&entry.source, entry.level.as_deref()))
+            .or_default()
+            .push(serde_json::json!([entry.nanos.to_string(), entry.line]));
+    }
+    let streams: Vec<serde_json::Value> = streams
+        .into_iter()
+        .map(|((source, level), values)| {
+            let mut labels = serde_json::json!({ "job": "cor", "source": source });
+            if let Some(level) = level {
+                labels["level"] = level.into();
+            }
+            serde_json::json!({ "stream": labels, "values": values })
+        })
+        .collect();
+    serde_json::json!({ "streams": streams })
+}
+
+/// A batch encoded for delivery.
+#[derive(Debug)]
+struct Request {
+    content_type: &'static str,
+    body: Vec<u8>,
+}
+
+impl Request {
+    /// The request line and headers `POST`ing this batch to `endpoint`.
+    fn head(&self, endpoint: &ListenAddr) -> String {
+        format!(
+            "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: {}\r\n\
+             Content-Length: {}\r\nConnection: close\r\n\r\n",
+            endpoint.path,
+            endpoint.addr,
+            self.content_type,
+            self.body.len()
+        )
+    }
+}
+
+/// `POST` `request` to `endpoint` and check for a `2xx` response.
+#[cfg(not(feature = "async"))]
+fn post(endpoint: &ListenAddr, request: &Request) -> io::Result<()> {
+    let addr = std::net::ToSocketAddrs::to_socket_addrs(&endpoint.addr)?
+        .next()
+        .ok_or_else(|| io::Error::other("address did not resolve"))?;
+    let mut stream = TcpStream::connect_timeout(&addr, HTTP_TIMEOUT)?;
+    stream.set_read_timeout(Some(HTTP_TIMEOUT))?;
+    stream.set_write_timeout(Some(HTTP_TIMEOUT))?;
+    stream.write_all(request.head(endpoint).as_bytes())?;
+    stream.write_all(&request.body)?;
+    let mut status = String::new();
+    BufReader::new(stream.take(1024)).read_line(&mut status)?;
+    check_status(&status)
+}
+
+/// `POST` `request` to `endpoint` on the shared runtime, like [`post`].
+#[cfg(feature = "async")]
+async fn post_async(endpoint: &ListenAddr, request: &Request) -> io::Result<()> {
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt};
+
+    let exchange = async {
+        let mut stream = tokio::net::TcpStream::connect(&endpoint.addr).await?;
+        stream.write_all(request.head(endpoint).as_bytes()).await?;
+        stream.write_all(&request.body).await?;
+        let mut status = String::new();
+        tokio::io::BufReader::new(stream.take(1024))
+            .read_line(&mut status)
+            .await?;
+        check_status(&status)
+    };
+    tokio::time::timeout(HTTP_TIMEOUT, exchange)
+        .await
+        .unwrap_or_else(|_| Err(io::Error::new(io::ErrorKind::TimedOut, "timed out")))
+}
+
+/// Whether the response `status` line reports success.
+fn check_status(status: &str) -> io::Result<()> {
+    match status.split_whitespace().nth(1) {
+        Some(code) if code.starts_with('2') => Ok(()),
+        Some(code) => Err(io::Error::other(format!("server answered {code}"))),
+        None => Err(io::Error::other("no HTTP response")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::{Layout, Role};
+    use owo_colors::Style;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    fn raw(text: &str) -> Layout {
+        let mut layout = Layout::default();
+        layout.push(Role::Raw, Style::new(), text);
+        layout
+    }
+
+    fn record(layout: &Layout) -> FormattedRecord<'_> {
+        FormattedRecord {
+            layout,
+            text: layout.text(),
+            json: None,
+            source: "-",
+        }
+    }
+
+    #[test]
+    fn test_webhook_posts_ndjson_batch() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = String::new();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if let Some(value) = line.strip_prefix("Content-Length: ") {
+                    length = value.trim().parse().unwrap();
+                }
+                request.push_str(&line);
+                if line == "\r\n" {
+                    break;
+                }
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            stream
+                .write_all(b"HTTP/1.1 202 Accepted\r\nContent-Length: 0\r\n\r\n")
+                .unwrap();
+            (request, String::from_utf8(body).unwrap())
+        });
+
+        let mut sink = WebhookSink::new(ListenAddr::parse(&url).unwrap()).unwrap();
+        let json = serde_json::json!({ "level": "info", "message": "hi" });
+        let layout = raw("INFO hi");
+        sink.emit(&FormattedRecord {
+            json: Some(&json),
+            ..record(&layout)
+        })
+        .unwrap();
+        sink.emit(&record(&raw("\x1b[2mraw\x1b[0m"))).unwrap();
+        sink.finish().unwrap();
+
+        let (request, body) = server.join().unwrap();
+        assert!(request.starts_with("POST /hook HTTP/1.1\r\n"));
+        assert!(request.contains("Content-Type: application/x-ndjson\r\n"));
+        assert_eq!(
+            body,
+            "{\"level\":\"info\",\"message\":\"hi\"}\n{\"message\":\"raw\"}\n"
+        );
+    }
+
+    #[test]
+    fn test_loki_streams_grouped_by_labels() {
+        let entry = |source: &str, level: Option<&str>, line: &str| LokiEntry {
+            source: source.to_string(),
+            level: level.map(str::to_string),
+            nanos: 1,
+            line: line.to_string(),
+        };
+        let body = loki_body(&[
+            entry("app.log", Some("info"), "a"),
+            entry("app.log", None, "b"),
+            entry("app.log", Some("info"), "c"),
+        ]);
+        assert_eq!(
+            body,
+            serde_json::json!({ "streams": [
+                { "stream": { "job": "cor", "source": "app.log" }, "values": [["1", "b"]] },
+                {
+                    "stream": { "job": "cor", "source": "app.log", "level": "info" },
+                    "values": [["1", "a"], ["1", "c"]]
+                },
+            ]})
+        );
+    }
+}
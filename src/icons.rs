@@ -0,0 +1,191 @@
+//! Level and field icons (`--icons emoji|nerd`).
+//!
+//! An opt-in visual aid: level badges get an icon in front of them, and
+//! extra fields belonging to a known cluster — HTTP, database, Kubernetes —
+//! get one in front of their key. Two built-in sets exist, plain emoji and
+//! Nerd Font glyphs; individual icons can be replaced in the `[icons]`
+//! config table. Icons are never drawn with `--ascii`.
+
+use std::collections::HashMap;
+
+use clap::ValueEnum;
+
+use crate::level::Level;
+
+/// Built-in icon set.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum IconSet {
+    /// No icons.
+    #[default]
+    None,
+    /// Emoji, rendered by most terminal fonts.
+    Emoji,
+    /// Nerd Font glyphs (requires a patched font).
+    Nerd,
+}
+
+/// Group of related fields sharing an icon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cluster {
+    Http,
+    Db,
+    K8s,
+}
+
+impl Cluster {
+    /// Cluster of the field `key`, judged by its first segment
+    /// (`http.status`, `db_query`, `k8s-pod`).
+    pub fn of(key: &str) -> Option<Self> {
+        let head = key
+            .split(['.', '_', '-'])
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        match head.as_str() {
+            "http" | "https" | "request" | "req" | "response" | "resp" | "url" => Some(Self::Http),
+            "db" | "database" | "sql" | "query" | "postgres" | "mysql" | "redis" | "mongo" => {
+                Some(Self::Db)
+            }
+            "k8s" | "kubernetes" | "kube" | "pod" | "namespace" | "container" => Some(Self::K8s),
+            _ => None,
+        }
+    }
+
+    /// Name used for overrides in the `[icons]` config table.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Http => "http",
+            Self::Db => "db",
+            Self::K8s => "k8s",
+        }
+    }
+}
+
+/// Active icon set plus per-icon overrides.
+#[derive(Debug, Clone, Default)]
+pub struct Icons {
+    pub set: IconSet,
+    /// Replacement icons keyed by level name (`error`) or cluster name (`http`).
+    pub overrides: HashMap<String, String>,
+}
+
+impl Icons {
+    /// Whether any icons are drawn.
+    pub fn enabled(&self) -> bool {
+        self.set != IconSet::None
+    }
+
+    /// Icon shown before the badge of `level`.
+    pub fn level(&self, level: Level) -> &str {
+        self.lookup(level.name(), || match self.set {
+            IconSet::None => "",
+            IconSet::Emoji => match level {
+                Level::Trace => "🔍",
+                Level::Debug => "🐛",
+                Level::Info => "💬",
+                Level::Warn => "🔶",
+                Level::Error => "❌",
+                Level::Fatal => "💀",
+            },
+            IconSet::Nerd => match level {
+                Level::Trace => "\u{f002}",
+                Level::Debug => "\u{f188}",
+                Level::Info => "\u{f05a}",
+                Level::Warn => "\u{f071}",
+                Level::Error => "\u{f057}",
+                Level::Fatal => "\u{f0e7}",
+            },
+        })
+    }
+
+    /// Icon shown before the field `key`, if it belongs to a known cluster.
+    pub fn field(&self, key: &str) -> Option<&str> {
+        let cluster = Cluster::of(key)?;
+        let icon = self.lookup(cluster.name(), || match self.set {
+            IconSet::None => "",
+            IconSet::Emoji => match cluster {
+                Cluster::Http => "🌐",
+                Cluster::Db => "💾",
+                Cluster::K8s => "📦",
+            },
+            IconSet::Nerd => match cluster {
+                Cluster::Http => "\u{f0ac}",
+                Cluster::Db => "\u{f1c0}",
+                Cluster::K8s => "\u{f1b3}",
+            },
+        });
+        (!icon.is_empty()).then_some(icon)
+    }
+
+    /// Blank the width of a level icon, for records without a level.
+    pub const fn blank(&self) -> &'static str {
+        match self.set {
+            IconSet::None => "",
+            IconSet::Emoji => "  ",
+            IconSet::Nerd => " ",
+        }
+    }
+
+    fn lookup(&self, name: &str, builtin: impl FnOnce() -> &'static str) -> &str {
+        if !self.enabled() {
+            return "";
+        }
+        match self.overrides.get(name) {
+            Some(icon) => icon,
+            None => builtin(),
+        }
+    }
+}
+
+/// Terminal columns taken by `icon`: emoji are double width, Nerd Font
+/// glyphs single, and variation selectors take none.
+pub fn width(icon: &str) -> usize {
+    icon.chars()
+        .map(|c| match c {
+            '\u{fe00}'..='\u{fe0f}' | '\u{200d}' => 0,
+            '\u{1f000}'..='\u{1faff}' | '\u{2600}'..='\u{27bf}' => 2,
+            _ => 1,
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clusters() {
+        assert_eq!(Cluster::of("http.status"), Some(Cluster::Http));
+        assert_eq!(Cluster::of("DB_Query"), Some(Cluster::Db));
+        assert_eq!(Cluster::of("k8s-pod"), Some(Cluster::K8s));
+        assert_eq!(Cluster::of("pod"), Some(Cluster::K8s));
+        assert_eq!(Cluster::of("user_id"), None);
+    }
+
+    #[test]
+    fn test_icons_and_overrides() {
+        let mut icons = Icons::default();
+        assert_eq!(icons.level(Level::Error), "");
+        assert_eq!(icons.field("http.status"), None);
+
+        icons.set = IconSet::Emoji;
+        assert_eq!(icons.level(Level::Error), "❌");
+        assert_eq!(icons.field("http.status"), Some("🌐"));
+        assert_eq!(icons.field("user"), None);
+
+        icons
+            .overrides
+            .insert("error".to_string(), "🚨".to_string());
+        icons.overrides.insert("db".to_string(), "DB".to_string());
+        assert_eq!(icons.level(Level::Error), "🚨");
+        assert_eq!(icons.field("db.rows"), Some("DB"));
+    }
+
+    #[test]
+    fn test_width() {
+        assert_eq!(width("🌐"), 2);
+        assert_eq!(width("\u{f0ac}"), 1);
+        assert_eq!(width("⚠\u{fe0f}"), 2);
+        assert_eq!(width("DB"), 2);
+    }
+}
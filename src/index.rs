@@ -0,0 +1,416 @@
+//! Sidecar indexes for large log files (`cor index`).
+//!
+//! `cor index big.log` splits the file into blocks of [`BLOCK_LINES`] lines
+//! and writes `big.log.coridx` recording, per block, its byte offset, the
+//! levels present, and a Bloom filter of the field names present. Later
+//! runs over `big.log` with `--level` or `--where`/`--filter`/`--min`…
+//! filters read only the blocks that can hold a match, skipping the rest
+//! without parsing them.
+//!
+//! An index is only used while it is current: the file's size and
+//! modification time and the options that change how lines parse (custom
+//! keys, level aliases, `--prefix-pattern`) must match those at indexing
+//! time. Blocks containing non-JSON lines are always read, since raw lines
+//! are shown regardless of filters and may be parts of multi-line records.
+
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::level::Level;
+use crate::parser::{LineKind, LogRecord, parse_line};
+
+/// Lines per indexed block.
+pub const BLOCK_LINES: usize = 4096;
+
+/// Format version of the index file.
+const VERSION: u32 = 1;
+
+/// Level bit for records without a level.
+const NO_LEVEL: u8 = 1;
+
+/// Level bit for non-JSON lines.
+const RAW: u8 = 1 << 7;
+
+/// Bloom filter bits per block.
+const BLOOM_WORDS: usize = 4;
+
+/// Path of the index for `path`: the file name with `.coridx` appended.
+pub fn sidecar_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".coridx");
+    PathBuf::from(name)
+}
+
+/// Bit of `level` in a block's level set.
+const fn level_bit(level: Level) -> u8 {
+    1 << (level as u8 / 10)
+}
+
+/// Bloom filter over field names, with three probes per name.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Bloom([u64; BLOOM_WORDS]);
+
+impl Bloom {
+    fn probes(name: &str) -> impl Iterator<Item = usize> {
+        // FNV-1a, split into three probes.
+        let hash = name.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |h, b| {
+            (h ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+        });
+        let bits = (BLOOM_WORDS * 64) as u64;
+        (0..3).map(move |i| usize::try_from((hash >> (i * 21)) % bits).unwrap_or(0))
+    }
+
+    pub fn insert(&mut self, name: &str) {
+        for bit in Self::probes(name) {
+            self.0[bit / 64] |= 1 << (bit % 64);
+        }
+    }
+
+    /// Whether `name` may have been inserted; never `false` for one that was.
+    pub fn contains(&self, name: &str) -> bool {
+        Self::probes(name).all(|bit| self.0[bit / 64] & (1 << (bit % 64)) != 0)
+    }
+}
+
+/// One block of lines.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Block {
+    /// Byte offset of the block's first line.
+    pub offset: u64,
+    /// Levels present, one bit per level, plus records without a level
+    /// and raw lines.
+    pub levels: u8,
+    /// Field names present.
+    pub fields: Bloom,
+}
+
+impl Block {
+    const fn new(offset: u64) -> Self {
+        Self {
+            offset,
+            levels: 0,
+            fields: Bloom([0; BLOOM_WORDS]),
+        }
+    }
+
+    fn add(&mut self, line: &LineKind) {
+        let record = match line {
+            LineKind::Json(record) | LineKind::EmbeddedJson { record, .. } => record,
+            LineKind::Raw(_) => {
+                self.levels |= RAW;
+                return;
+            }
+        };
+        self.levels |= record.level.map_or(NO_LEVEL, level_bit);
+        for name in field_names(record) {
+            self.fields.insert(name);
+        }
+    }
+
+    /// Whether a line in the block may pass the filters of `config`.
+    pub fn may_match(&self, config: &Config) -> bool {
+        if self.levels & RAW != 0 {
+            return true;
+        }
+        let level_ok = config.min_level.is_none_or(|min| {
+            let wanted = [
+                Level::Trace,
+                Level::Debug,
+                Level::Info,
+                Level::Warn,
+                Level::Error,
+                Level::Fatal,
+            ]
+            .into_iter()
+            .filter(|&level| level >= min)
+            .fold(NO_LEVEL, |bits, level| bits | level_bit(level));
+            self.levels & wanted != 0
+        });
+        let has_field = |name: &str| self.fields.contains(name);
+        level_ok
+            && config
+                .where_filter
+                .iter()
+                .all(|expr| expr.may_match(&has_field))
+    }
+}
+
+/// Names a filter can refer to the fields of `record` by.
+fn field_names(record: &LogRecord) -> impl Iterator<Item = &str> {
+    let canonical = [
+        (record.message.is_some(), ["msg", "message"].as_slice()),
+        (record.logger.is_some(), &["logger"]),
+        (record.caller.is_some(), &["caller"]),
+        (record.error.is_some(), &["error"]),
+    ];
+    canonical
+        .into_iter()
+        .filter(|(present, _)| *present)
+        .flat_map(|(_, names)| names.iter().copied())
+        .chain(record.extra.keys().map(String::as_str))
+}
+
+/// A file's index.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Index {
+    version: u32,
+    /// Size of the indexed file.
+    len: u64,
+    /// Modification time of the indexed file, in nanoseconds since the epoch.
+    modified: u128,
+    /// Digest of the options that change how lines parse.
+    parsing: String,
+    pub blocks: Vec<Block>,
+}
+
+impl Index {
+    /// Index the file at `path`, parsing lines as `config` does.
+    pub fn build(path: &Path, config: &Config) -> io::Result<Self> {
+        let (len, modified) = stamp(path)?;
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut blocks = Vec::new();
+        let mut block = Block::new(0);
+        let (mut offset, mut lines) = (0, 0);
+        let mut buf = Vec::new();
+        loop {
+            buf.clear();
+            let read = reader.read_until(b'\n', &mut buf)?;
+            if read == 0 {
+                break;
+            }
+            if lines == BLOCK_LINES {
+                blocks.push(std::mem::replace(&mut block, Block::new(offset)));
+                lines = 0;
+            }
+            let line = String::from_utf8_lossy(&buf);
+            block.add(&parse_line(line.trim_end_matches(['\n', '\r']), config));
+            offset += read as u64;
+            lines += 1;
+        }
+        if lines > 0 {
+            blocks.push(block);
+        }
+        Ok(Self {
+            version: VERSION,
+            len,
+            modified,
+            parsing: parsing_digest(config),
+            blocks,
+        })
+    }
+
+    /// Write the index next to `path`, returning the index file's path.
+    pub fn write(&self, path: &Path) -> io::Result<PathBuf> {
+        let sidecar = sidecar_path(path);
+        let json = serde_json::to_vec(self).map_err(io::Error::other)?;
+        fs::write(&sidecar, json)?;
+        Ok(sidecar)
+    }
+
+    /// The index of `path`, if there is one and it is current.
+    pub fn load(path: &Path, config: &Config) -> Option<Self> {
+        let json = fs::read(sidecar_path(path)).ok()?;
+        let index: Self = serde_json::from_slice(&json).ok()?;
+        let current = index.version == VERSION
+            && stamp(path).ok()? == (index.len, index.modified)
+            && index.parsing == parsing_digest(config);
+        current.then_some(index)
+    }
+
+    /// Byte ranges of the blocks that may hold lines passing the filters
+    /// of `config`, adjacent blocks merged.
+    pub fn ranges(&self, config: &Config) -> Vec<Range<u64>> {
+        let mut ranges: Vec<Range<u64>> = Vec::new();
+        let ends = self
+            .blocks
+            .iter()
+            .skip(1)
+            .map(|b| b.offset)
+            .chain([self.len]);
+        for (block, end) in self.blocks.iter().zip(ends) {
+            if !block.may_match(config) {
+                continue;
+            }
+            match ranges.last_mut() {
+                Some(last) if last.end == block.offset => last.end = end,
+                _ => ranges.push(block.offset..end),
+            }
+        }
+        ranges
+    }
+}
+
+/// Size and modification time of the file at `path`.
+fn stamp(path: &Path) -> io::Result<(u64, u128)> {
+    let metadata = fs::metadata(path)?;
+    let modified = metadata
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos());
+    Ok((metadata.len(), modified))
+}
+
+/// Digest of the options that change what a line parses into.
+fn parsing_digest(config: &Config) -> String {
+    let mut aliases: Vec<_> = config
+        .level_aliases
+        .as_deref()
+        .into_iter()
+        .flatten()
+        .collect();
+    aliases.sort();
+    let options = format!(
+        "{:?}",
+        (
+            &config.message_key,
+            &config.message_fields,
+            config.keep_extracted,
+            &config.level_key,
+            &config.timestamp_key,
+            &config.logger_key,
+            &config.caller_key,
+            &config.error_key,
+            aliases,
+            config.prefix_pattern.as_ref().map(regex::Regex::as_str),
+        )
+    );
+    sha1_smol::Sha1::from(options).digest().to_string()
+}
+
+/// Reader over the given byte ranges of a file, in order.
+#[derive(Debug)]
+pub struct RangesReader {
+    file: File,
+    ranges: std::vec::IntoIter<Range<u64>>,
+    /// Bytes left in the current range.
+    left: u64,
+}
+
+impl RangesReader {
+    pub fn new(file: File, ranges: Vec<Range<u64>>) -> Self {
+        Self {
+            file,
+            ranges: ranges.into_iter(),
+            left: 0,
+        }
+    }
+}
+
+impl Read for RangesReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.left == 0 {
+            let Some(range) = self.ranges.next() else {
+                return Ok(0);
+            };
+            self.file.seek(SeekFrom::Start(range.start))?;
+            self.left = range.end - range.start;
+        }
+        let max = usize::try_from(self.left)
+            .unwrap_or(usize::MAX)
+            .min(buf.len());
+        let n = self.file.read(&mut buf[..max])?;
+        if n == 0 {
+            // The file shrank; the index is stale.
+            self.left = 0;
+            self.ranges = Vec::new().into_iter();
+        }
+        self.left -= n as u64;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fmt::Write as _;
+    use std::io::Write as _;
+
+    /// A file with one block of info records with `path`, one of error
+    /// records with `status`, and one with a raw line.
+    fn sample() -> tempfile::NamedTempFile {
+        let mut text = String::new();
+        for i in 0..BLOCK_LINES {
+            let _ = writeln!(text, r#"{{"level":"info","msg":"get {i}","path":"/a"}}"#);
+        }
+        for i in 0..BLOCK_LINES {
+            let _ = writeln!(text, r#"{{"level":"error","msg":"fail {i}","status":500}}"#);
+        }
+        text.push_str("plain text\n");
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(text.as_bytes()).unwrap();
+        file
+    }
+
+    /// Ranges read with `config`, as `(start, end)` pairs.
+    fn ranges(index: &Index, config: &Config) -> Vec<(u64, u64)> {
+        index
+            .ranges(config)
+            .into_iter()
+            .map(|range| (range.start, range.end))
+            .collect()
+    }
+
+    #[test]
+    fn test_bloom() {
+        let mut bloom = Bloom::default();
+        bloom.insert("status");
+        assert!(bloom.contains("status"));
+        assert!(!bloom.contains("path"));
+    }
+
+    #[test]
+    fn test_skips_blocks_that_cannot_match() {
+        let file = sample();
+        let index = Index::build(file.path(), &Config::default()).unwrap();
+        assert_eq!(index.blocks.len(), 3);
+        let (second, third) = (index.blocks[1].offset, index.blocks[2].offset);
+        let len = index.len;
+
+        assert_eq!(ranges(&index, &Config::default()), [(0, len)]);
+        let errors = Config {
+            min_level: Some(Level::Warn),
+            ..Config::default()
+        };
+        assert_eq!(ranges(&index, &errors), [(second, len)]);
+        let paths = Config {
+            where_filter: vec![crate::expr::Expr::parse("path == /a").unwrap()],
+            ..Config::default()
+        };
+        assert_eq!(ranges(&index, &paths), [(0, second), (third, len)]);
+    }
+
+    #[test]
+    fn test_load_requires_current_index() {
+        let file = sample();
+        let config = Config::default();
+        let index = Index::build(file.path(), &config).unwrap();
+        let sidecar = index.write(file.path()).unwrap();
+        assert_eq!(Index::load(file.path(), &config), Some(index));
+
+        let other_keys = Config {
+            level_key: Some(vec!["severity".into()]),
+            ..Config::default()
+        };
+        assert_eq!(Index::load(file.path(), &other_keys), None);
+
+        writeln!(file.as_file(), "more").unwrap();
+        assert_eq!(Index::load(file.path(), &config), None);
+        fs::remove_file(sidecar).unwrap();
+    }
+
+    #[test]
+    fn test_ranges_reader() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(b"one\ntwo\nthree\nfour\n").unwrap();
+        let mut reader = RangesReader::new(File::open(file.path()).unwrap(), vec![0..4, 8..14]);
+        let mut out = String::new();
+        reader.read_to_string(&mut out).unwrap();
+        assert_eq!(out, "one\nthree\n");
+    }
+}
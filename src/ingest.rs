@@ -0,0 +1,898 @@
+//! HTTP log ingestion (`--listen http://HOST:PORT/PATH`).
+//!
+//! A minimal HTTP/1.1 endpoint that accepts `POST`ed NDJSON bodies, either
+//! with a `Content-Length` or as a chunked stream, and feeds every line into
+//! the normal processing pipeline. Each request is answered with
+//! `202 Accepted` once its body has been read; connections are closed after
+//! one request. `https://` addresses are served over TLS when cor is built
+//! with the `tls` feature (see `crate::tls`).
+//!
+//! A body is read whole before its lines are handed on, so concurrent
+//! uploads never interleave. At most [`MAX_CONNECTIONS`] requests are
+//! served at once, each within [`IO_TIMEOUT`] per read or write, and bodies
+//! past [`MAX_BODY`] bytes or with lines past [`MAX_LINE`] bytes are
+//! refused with `413 Payload Too Large`. The blocking and async servers
+//! share the request parsing and the [`Body`] decoder.
+
+use std::io::{self, Write};
+use std::mem;
+use std::net::{SocketAddr, TcpListener};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+#[cfg(not(feature = "async"))]
+use std::{
+    io::{BufRead, BufReader, Read},
+    net::TcpStream,
+    sync::mpsc::SyncSender,
+    sync::{Mutex, PoisonError},
+    thread,
+};
+
+use crate::input::{self, ThreadedLines};
+
+/// Longest request line or header accepted, in bytes.
+const MAX_HEADER_LINE: u64 = 8 * 1024;
+
+/// Most requests served at once; further connections get `503`.
+const MAX_CONNECTIONS: usize = 32;
+
+/// Longest a client may stall a single read or write.
+pub(crate) const IO_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Largest request body accepted, in bytes.
+const MAX_BODY: u64 = 8 * 1024 * 1024;
+
+/// Longest body line accepted, in bytes.
+const MAX_LINE: u64 = 1024 * 1024;
+
+/// Pause after a failed `accept`, e.g. when out of file descriptors, so the
+/// accept loop does not spin.
+pub(crate) const ACCEPT_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Interim response to `Expect: 100-continue`.
+const CONTINUE: &[u8] = b"HTTP/1.1 100 Continue\r\n\r\n";
+
+/// Where to accept log uploads.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListenAddr {
+    /// `host:port` to bind.
+    pub addr: String,
+    /// Request path accepting uploads, e.g. `/ingest`.
+    pub path: String,
+    /// Serve over TLS (`https://`).
+    pub tls: bool,
+}
+
+impl ListenAddr {
+    /// Parse `http://HOST:PORT[/PATH]` or `https://HOST:PORT[/PATH]`. The
+    /// path defaults to `/`.
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let (rest, tls) = if let Some(rest) = s.strip_prefix("http://") {
+            (rest, false)
+        } else if let Some(rest) = s.strip_prefix("https://") {
+            if !cfg!(feature = "tls") {
+                return Err(format!(
+                    "invalid listen address '{s}': https:// requires cor built with the `tls` feature"
+                ));
+            }
+            (rest, true)
+        } else {
+            return Err(format!(
+                "invalid listen address '{s}': expected http://HOST:PORT/PATH"
+            ));
+        };
+        let (addr, path) = rest
+            .find('/')
+            .map_or((rest, "/"), |i| (&rest[..i], &rest[i..]));
+        match addr.rsplit_once(':') {
+            Some((host, port)) if !host.is_empty() && port.parse::<u16>().is_ok() => {}
+            _ => return Err(format!("invalid listen address '{s}': expected HOST:PORT")),
+        }
+        Ok(Self {
+            addr: addr.to_string(),
+            path: path.to_string(),
+            tls,
+        })
+    }
+}
+
+/// Sets up accepted connections, with TLS for `https://` addresses.
+#[derive(Debug, Clone, Default)]
+pub struct Acceptor {
+    #[cfg(feature = "tls")]
+    tls: Option<Arc<rustls::ServerConfig>>,
+}
+
+impl Acceptor {
+    /// Acceptor using `config` for `https://` addresses.
+    #[cfg(feature = "tls")]
+    pub const fn with_tls(config: Arc<rustls::ServerConfig>) -> Self {
+        Self { tls: Some(config) }
+    }
+
+    /// Whether connections are wrapped in TLS.
+    #[cfg(feature = "tls")]
+    const fn is_tls(&self) -> bool {
+        self.tls.is_some()
+    }
+
+    #[cfg(not(feature = "tls"))]
+    #[allow(clippy::unused_self)]
+    const fn is_tls(&self) -> bool {
+        false
+    }
+
+    /// Serve one request on `stream`, like [`handle`].
+    #[cfg(not(feature = "async"))]
+    #[cfg_attr(not(feature = "tls"), allow(clippy::unused_self))]
+    fn serve(
+        &self,
+        stream: TcpStream,
+        path: &str,
+        tx: &Mutex<SyncSender<io::Result<String>>>,
+    ) -> io::Result<()> {
+        #[cfg(feature = "tls")]
+        if let Some(ref config) = self.tls {
+            let mut stream = crate::tls::accept(stream, config)?;
+            handle(&mut stream, path, tx)?;
+            stream.conn.send_close_notify();
+            return stream.flush();
+        }
+        handle(stream, path, tx)
+    }
+
+    /// Serve one request on `stream` on the shared runtime.
+    #[cfg(feature = "async")]
+    #[cfg_attr(not(feature = "tls"), allow(clippy::unused_self))]
+    async fn serve_async(
+        &self,
+        stream: tokio::net::TcpStream,
+        path: &str,
+        tx: &tokio::sync::Mutex<tokio::sync::mpsc::Sender<io::Result<String>>>,
+    ) -> io::Result<()> {
+        #[cfg(feature = "tls")]
+        if let Some(ref config) = self.tls {
+            let stream = timed(crate::tls::accept_async(stream, config)).await?;
+            return handle_async(stream, path, tx).await;
+        }
+        handle_async(stream, path, tx).await
+    }
+}
+
+/// Bind `listen` and serve uploads on background threads, or as tasks on
+/// the shared runtime with the `async` feature.
+///
+/// Returns the bound address (useful with port 0) and a line source that
+/// never reaches end of input.
+pub fn serve(listen: &ListenAddr, acceptor: &Acceptor) -> io::Result<(SocketAddr, ThreadedLines)> {
+    if listen.tls != acceptor.is_tls() {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "TLS is not configured for this address",
+        ));
+    }
+    let acceptor = acceptor.clone();
+    let listener = TcpListener::bind(&listen.addr)?;
+    let local = listener.local_addr()?;
+    let (tx, source) = ThreadedLines::channel();
+    let path = Arc::<str>::from(listen.path.as_str());
+    #[cfg(feature = "async")]
+    {
+        let runtime = crate::runtime::handle().map_err(io::Error::other)?;
+        listener.set_nonblocking(true)?;
+        let listener = {
+            let _guard = runtime.enter();
+            tokio::net::TcpListener::from_std(listener)?
+        };
+        let tx = crate::runtime::bridge(tx);
+        runtime.spawn(async move { serve_async(listener, acceptor, path, tx).await });
+    }
+    #[cfg(not(feature = "async"))]
+    thread::spawn(move || serve_blocking(&listener, &acceptor, &path, tx));
+    Ok((local, source))
+}
+
+/// Accept uploads on `listener`, one thread per connection.
+#[cfg(not(feature = "async"))]
+fn serve_blocking(
+    listener: &TcpListener,
+    acceptor: &Acceptor,
+    path: &Arc<str>,
+    tx: SyncSender<io::Result<String>>,
+) {
+    let tx = Arc::new(Mutex::new(tx));
+    let connections = Connections::default();
+    for stream in listener.incoming() {
+        let Ok(mut stream) = stream else {
+            thread::sleep(ACCEPT_BACKOFF);
+            continue;
+        };
+        if stream.set_read_timeout(Some(IO_TIMEOUT)).is_err()
+            || stream.set_write_timeout(Some(IO_TIMEOUT)).is_err()
+        {
+            continue;
+        }
+        let Some(slot) = connections.claim() else {
+            let _ = respond(&mut stream, "503 Service Unavailable");
+            continue;
+        };
+        let (acceptor, path, tx) = (acceptor.clone(), Arc::clone(path), Arc::clone(&tx));
+        thread::spawn(move || {
+            // A broken client only affects its own request.
+            let _ = acceptor.serve(stream, &path, &tx);
+            drop(slot);
+        });
+    }
+}
+
+/// Number of requests being served, up to [`MAX_CONNECTIONS`].
+#[derive(Debug, Default)]
+struct Connections(Arc<AtomicUsize>);
+
+impl Connections {
+    /// Count one more request until the returned slot is dropped, or `None`
+    /// if [`MAX_CONNECTIONS`] are already being served.
+    fn claim(&self) -> Option<Slot> {
+        self.0
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |n| {
+                (n < MAX_CONNECTIONS).then_some(n + 1)
+            })
+            .ok()
+            .map(|_| Slot(Arc::clone(&self.0)))
+    }
+}
+
+/// A request counted by [`Connections`].
+#[derive(Debug)]
+struct Slot(Arc<AtomicUsize>);
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Request line and headers of an HTTP/1.1 request.
+#[derive(Debug)]
+pub(crate) struct Request {
+    pub(crate) method: String,
+    /// Request path without the query string.
+    pub(crate) path: String,
+    /// Headers with lowercase names, in order.
+    pub(crate) headers: Vec<(String, String)>,
+}
+
+impl Request {
+    /// Read the request line and headers, up to the blank line.
+    #[cfg(not(feature = "async"))]
+    pub(crate) fn read(reader: &mut impl BufRead) -> io::Result<Self> {
+        let mut request = Self::new(&read_line(reader)?);
+        while request.push_header(&read_line(reader)?)? {}
+        Ok(request)
+    }
+
+    /// Read the request line and headers from an async reader.
+    #[cfg(feature = "async")]
+    pub(crate) async fn read_async(
+        reader: &mut (impl tokio::io::AsyncBufRead + Unpin),
+    ) -> io::Result<Self> {
+        let mut request = Self::new(&read_line_async(reader).await?);
+        while request.push_header(&read_line_async(reader).await?)? {}
+        Ok(request)
+    }
+
+    /// A request with the method and path of `request_line`.
+    fn new(request_line: &str) -> Self {
+        let mut parts = request_line.split_whitespace();
+        let method = parts.next().unwrap_or_default().to_string();
+        let target = parts.next().unwrap_or_default();
+        let path = target.split('?').next().unwrap_or_default().to_string();
+        Self {
+            method,
+            path,
+            headers: Vec::new(),
+        }
+    }
+
+    /// Add a header line, returning `false` at the blank line ending the
+    /// headers.
+    fn push_header(&mut self, header: &str) -> io::Result<bool> {
+        if header.is_empty() {
+            return Ok(false);
+        }
+        let Some((name, value)) = header.split_once(':') else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "malformed header",
+            ));
+        };
+        self.headers
+            .push((name.trim().to_ascii_lowercase(), value.trim().to_string()));
+        Ok(true)
+    }
+
+    /// Value of the first header named `name` (lowercase).
+    pub(crate) fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Whether the client waits for `100 Continue` before sending the body.
+    fn expects_continue(&self) -> bool {
+        self.header("expect")
+            .is_some_and(|v| v.eq_ignore_ascii_case("100-continue"))
+    }
+
+    /// How the body of an upload to `path` is framed, or the status
+    /// refusing the request.
+    fn framing(&self, path: &str) -> Result<Framing, &'static str> {
+        if self.path != path {
+            return Err("404 Not Found");
+        }
+        if self.method != "POST" {
+            return Err("405 Method Not Allowed");
+        }
+        if self
+            .header("transfer-encoding")
+            .is_some_and(|v| v.eq_ignore_ascii_case("chunked"))
+        {
+            return Ok(Framing::Chunked(Chunk::Header));
+        }
+        let length = self
+            .header("content-length")
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0);
+        if length > MAX_BODY {
+            return Err("413 Payload Too Large");
+        }
+        Ok(Framing::Length(length))
+    }
+}
+
+/// Serve one request on `stream`, sending its lines while holding `tx`.
+#[cfg(not(feature = "async"))]
+fn handle(
+    stream: impl Read + Write,
+    path: &str,
+    tx: &Mutex<SyncSender<io::Result<String>>>,
+) -> io::Result<()> {
+    let mut reader = BufReader::new(stream);
+    let status = match receive(&mut reader, path) {
+        Ok(lines) => {
+            // Invalid UTF-8 is skipped downstream.
+            let tx = tx.lock().unwrap_or_else(PoisonError::into_inner);
+            for line in lines {
+                if tx.send(line).is_err() {
+                    break;
+                }
+            }
+            "202 Accepted"
+        }
+        Err(status) => status,
+    };
+    respond(reader.get_mut(), status)
+}
+
+/// Read an upload to `path` from `reader`, or the status refusing it.
+#[cfg(not(feature = "async"))]
+fn receive<S: Read + Write>(
+    reader: &mut BufReader<S>,
+    path: &str,
+) -> Result<Vec<io::Result<String>>, &'static str> {
+    let request = Request::read(reader).map_err(|_| "400 Bad Request")?;
+    let framing = request.framing(path)?;
+    if request.expects_continue() {
+        reader
+            .get_mut()
+            .write_all(CONTINUE)
+            .map_err(|_| "400 Bad Request")?;
+    }
+    let mut body = Body::new(framing);
+    while !body.is_done() {
+        let buf = reader.fill_buf().map_err(|e| refusal(&e))?;
+        let used = body.feed(buf).map_err(|e| refusal(&e))?;
+        reader.consume(used);
+    }
+    Ok(body.finish())
+}
+
+/// Accept uploads on `listener`, one task per connection.
+#[cfg(feature = "async")]
+async fn serve_async(
+    listener: tokio::net::TcpListener,
+    acceptor: Acceptor,
+    path: Arc<str>,
+    tx: tokio::sync::mpsc::Sender<io::Result<String>>,
+) {
+    let tx = Arc::new(tokio::sync::Mutex::new(tx));
+    let connections = Connections::default();
+    loop {
+        let Ok((stream, _)) = listener.accept().await else {
+            tokio::time::sleep(ACCEPT_BACKOFF).await;
+            continue;
+        };
+        let Some(slot) = connections.claim() else {
+            tokio::spawn(respond_async(stream, "503 Service Unavailable"));
+            continue;
+        };
+        let (acceptor, path, tx) = (acceptor.clone(), Arc::clone(&path), Arc::clone(&tx));
+        tokio::spawn(async move {
+            // A broken client only affects its own request.
+            let _ = acceptor.serve_async(stream, &path, &tx).await;
+            drop(slot);
+        });
+    }
+}
+
+/// Serve one request on `stream`, like [`handle`].
+#[cfg(feature = "async")]
+async fn handle_async(
+    stream: impl tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin,
+    path: &str,
+    tx: &tokio::sync::Mutex<tokio::sync::mpsc::Sender<io::Result<String>>>,
+) -> io::Result<()> {
+    let mut reader = tokio::io::BufReader::new(stream);
+    let status = match receive_async(&mut reader, path).await {
+        Ok(lines) => {
+            let tx = tx.lock().await;
+            for line in lines {
+                if tx.send(line).await.is_err() {
+                    break;
+                }
+            }
+            "202 Accepted"
+        }
+        Err(status) => status,
+    };
+    respond_async(reader.into_inner(), status).await
+}
+
+/// Read an upload to `path` from `reader`, like [`receive`].
+#[cfg(feature = "async")]
+async fn receive_async<S>(
+    reader: &mut tokio::io::BufReader<S>,
+    path: &str,
+) -> Result<Vec<io::Result<String>>, &'static str>
+where
+    S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin,
+{
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+
+    let request = timed(Request::read_async(reader))
+        .await
+        .map_err(|_| "400 Bad Request")?;
+    let framing = request.framing(path)?;
+    if request.expects_continue() {
+        timed(reader.get_mut().write_all(CONTINUE))
+            .await
+            .map_err(|_| "400 Bad Request")?;
+    }
+    let mut body = Body::new(framing);
+    while !body.is_done() {
+        let buf = timed(reader.fill_buf()).await.map_err(|e| refusal(&e))?;
+        let used = body.feed(buf).map_err(|e| refusal(&e))?;
+        reader.consume(used);
+    }
+    Ok(body.finish())
+}
+
+/// Write an empty response to `stream`, like [`respond`].
+#[cfg(feature = "async")]
+pub(crate) async fn respond_async(
+    mut stream: impl tokio::io::AsyncWrite + Unpin,
+    status: &str,
+) -> io::Result<()> {
+    use tokio::io::AsyncWriteExt;
+
+    let mut response = Vec::new();
+    respond(&mut response, status)?;
+    timed(stream.write_all(&response)).await?;
+    // Also ends a TLS session cleanly.
+    timed(stream.shutdown()).await
+}
+
+/// `io` cut off with [`io::ErrorKind::TimedOut`] after [`IO_TIMEOUT`].
+#[cfg(feature = "async")]
+pub(crate) async fn timed<T>(io: impl Future<Output = io::Result<T>>) -> io::Result<T> {
+    tokio::time::timeout(IO_TIMEOUT, io)
+        .await
+        .unwrap_or_else(|_| Err(io::ErrorKind::TimedOut.into()))
+}
+
+/// Status answering a body that could not be read.
+fn refusal(e: &io::Error) -> &'static str {
+    if e.kind() == io::ErrorKind::FileTooLarge {
+        "413 Payload Too Large"
+    } else {
+        "400 Bad Request"
+    }
+}
+
+/// Write an empty response and flush it.
+pub(crate) fn respond(writer: &mut impl Write, status: &str) -> io::Result<()> {
+    write!(
+        writer,
+        "HTTP/1.1 {status}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+    )?;
+    writer.flush()
+}
+
+/// How a request body ends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Framing {
+    /// After this many more bytes (`Content-Length`).
+    Length(u64),
+    /// At a zero-sized chunk (`Transfer-Encoding: chunked`).
+    Chunked(Chunk),
+}
+
+/// What comes next in a chunked body.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Chunk {
+    /// A chunk-size line.
+    Header,
+    /// This many more bytes of chunk data.
+    Data(u64),
+    /// The CRLF after a chunk's data.
+    DataEnd,
+    /// Trailer lines up to the blank line ending the body.
+    Trailers,
+    Done,
+}
+
+/// Splits a request body into lines as its bytes arrive, failing with
+/// [`io::ErrorKind::FileTooLarge`] once it passes [`MAX_BODY`] bytes or a
+/// line passes [`MAX_LINE`].
+///
+/// Decoding needs no I/O of its own, so the blocking and async servers feed
+/// it from their buffered readers alike.
+#[derive(Debug)]
+struct Body {
+    framing: Framing,
+    /// Chunk-size or trailer line read so far.
+    control: Vec<u8>,
+    /// Body line read so far.
+    line: Vec<u8>,
+    lines: Vec<io::Result<String>>,
+    /// Body bytes read so far.
+    total: u64,
+}
+
+impl Body {
+    const fn new(framing: Framing) -> Self {
+        Self {
+            framing,
+            control: Vec::new(),
+            line: Vec::new(),
+            lines: Vec::new(),
+            total: 0,
+        }
+    }
+
+    /// Whether the whole body has been read.
+    const fn is_done(&self) -> bool {
+        matches!(
+            self.framing,
+            Framing::Length(0) | Framing::Chunked(Chunk::Done)
+        )
+    }
+
+    /// Decode bytes from the start of `buf`, returning how many were used.
+    /// An empty `buf` means the connection ended before the body did.
+    fn feed(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        match self.framing {
+            Framing::Length(left) => {
+                let used = self.data(buf, left)?;
+                self.framing = Framing::Length(left - used as u64);
+                Ok(used)
+            }
+            Framing::Chunked(Chunk::Data(left)) => {
+                let used = self.data(buf, left)?;
+                self.framing = Framing::Chunked(match left - used as u64 {
+                    0 => Chunk::DataEnd,
+                    left => Chunk::Data(left),
+                });
+                Ok(used)
+            }
+            Framing::Chunked(_) => {
+                let (used, complete) = buf
+                    .iter()
+                    .position(|&b| b == b'\n')
+                    .map_or((buf.len(), false), |end| (end + 1, true));
+                self.control.extend_from_slice(&buf[..used]);
+                if self.control.len() as u64 > MAX_HEADER_LINE {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "chunk header too long",
+                    ));
+                }
+                if complete {
+                    let control = mem::take(&mut self.control);
+                    self.next_chunk(&String::from_utf8_lossy(&control))?;
+                }
+                Ok(used)
+            }
+        }
+    }
+
+    /// Move past the chunk-size, data-end or trailer line `control`.
+    fn next_chunk(&mut self, control: &str) -> io::Result<()> {
+        let control = control.trim_end_matches(['\r', '\n']);
+        let Framing::Chunked(chunk) = self.framing else {
+            return Ok(());
+        };
+        let next = match chunk {
+            Chunk::Header => {
+                let size = control.split(';').next().unwrap_or_default().trim();
+                match u64::from_str_radix(size, 16) {
+                    Ok(0) => Chunk::Trailers,
+                    Ok(size) => Chunk::Data(size),
+                    Err(_) => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            "invalid chunk size",
+                        ));
+                    }
+                }
+            }
+            // Each chunk's data is followed by CRLF.
+            Chunk::DataEnd => Chunk::Header,
+            Chunk::Trailers if control.is_empty() => Chunk::Done,
+            other => other,
+        };
+        self.framing = Framing::Chunked(next);
+        Ok(())
+    }
+
+    /// Take up to `left` bytes of body data from `buf`, returning how many.
+    fn data(&mut self, buf: &[u8], left: u64) -> io::Result<usize> {
+        let used = buf.len().min(usize::try_from(left).unwrap_or(usize::MAX));
+        self.total += used as u64;
+        if self.total > MAX_BODY {
+            return Err(io::ErrorKind::FileTooLarge.into());
+        }
+        let mut data = &buf[..used];
+        while let Some(end) = data.iter().position(|&b| b == b'\n') {
+            self.line.extend_from_slice(&data[..=end]);
+            self.check_line()?;
+            self.lines
+                .push(input::decode_line(mem::take(&mut self.line)));
+            data = &data[end + 1..];
+        }
+        self.line.extend_from_slice(data);
+        self.check_line()?;
+        Ok(used)
+    }
+
+    fn check_line(&self) -> io::Result<()> {
+        if self.line.len() as u64 > MAX_LINE {
+            return Err(io::ErrorKind::FileTooLarge.into());
+        }
+        Ok(())
+    }
+
+    /// The body's lines, including a last one without a newline.
+    fn finish(mut self) -> Vec<io::Result<String>> {
+        if !self.line.is_empty() {
+            self.lines.push(input::decode_line(self.line));
+        }
+        self.lines
+    }
+}
+
+/// Read one CRLF-terminated line, without the terminator.
+#[cfg(not(feature = "async"))]
+fn read_line(reader: &mut impl BufRead) -> io::Result<String> {
+    let mut line = String::new();
+    reader.take(MAX_HEADER_LINE).read_line(&mut line)?;
+    header_line(&line)
+}
+
+/// Read one CRLF-terminated line from an async reader.
+#[cfg(feature = "async")]
+async fn read_line_async(
+    reader: &mut (impl tokio::io::AsyncBufRead + Unpin),
+) -> io::Result<String> {
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt};
+
+    let mut line = String::new();
+    reader.take(MAX_HEADER_LINE).read_line(&mut line).await?;
+    header_line(&line)
+}
+
+/// `line` without its terminator, or an error if it was cut short.
+fn header_line(line: &str) -> io::Result<String> {
+    if !line.ends_with('\n') {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "header line too long or truncated",
+        ));
+    }
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::{LineSource, Next};
+    use std::io::Read;
+    use std::net::TcpStream;
+    use std::time::Instant;
+
+    #[test]
+    fn test_parse_listen_addr() {
+        assert_eq!(
+            ListenAddr::parse("http://0.0.0.0:8080/ingest").unwrap(),
+            ListenAddr {
+                addr: "0.0.0.0:8080".to_string(),
+                path: "/ingest".to_string(),
+                tls: false,
+            }
+        );
+        assert_eq!(ListenAddr::parse("http://localhost:80").unwrap().path, "/");
+        assert_eq!(
+            ListenAddr::parse("https://localhost:443").map(|l| l.tls),
+            if cfg!(feature = "tls") {
+                Ok(true)
+            } else {
+                Err("invalid listen address 'https://localhost:443': https:// requires cor built with the `tls` feature".to_string())
+            }
+        );
+        assert!(ListenAddr::parse("ftp://localhost:80").is_err());
+        assert!(ListenAddr::parse("http://localhost/ingest").is_err());
+    }
+
+    /// Lines of `body`, fed to the decoder `step` bytes at a time.
+    fn decode(framing: Framing, body: &[u8], step: usize) -> io::Result<Vec<String>> {
+        let mut decoder = Body::new(framing);
+        let mut rest = body;
+        while !decoder.is_done() {
+            let used = decoder.feed(&rest[..step.min(rest.len())])?;
+            rest = &rest[used..];
+        }
+        assert!(rest.is_empty(), "left over: {rest:?}");
+        Ok(decoder
+            .finish()
+            .into_iter()
+            .map(|line| line.unwrap_or_else(|_| "<invalid>".to_string()))
+            .collect())
+    }
+
+    #[test]
+    fn test_chunked_decoding() {
+        let body = b"4\r\nab\nc\r\n3;ext=1\r\nd\ne\r\n0\r\nX-Trailer: 1\r\n\r\n";
+        for step in [1, 3, body.len()] {
+            assert_eq!(
+                decode(Framing::Chunked(Chunk::Header), body, step).unwrap(),
+                ["ab", "cd", "e"]
+            );
+        }
+        let err = decode(Framing::Chunked(Chunk::Header), b"zz\r\n", 8).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_body_limits() {
+        let body = b"a\nb\xff\r\nc";
+        assert_eq!(
+            decode(Framing::Length(body.len() as u64), body, 2).unwrap(),
+            ["a", "<invalid>", "c"]
+        );
+        let err = decode(Framing::Length(10), b"short", 8).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+        let line = vec![b'x'; usize::try_from(MAX_LINE).unwrap() + 1];
+        let err = decode(Framing::Length(line.len() as u64), &line, 4096).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::FileTooLarge);
+        let body =
+            format!("{}\n", "x".repeat(1023)).repeat(usize::try_from(MAX_BODY).unwrap() / 1024 + 1);
+        let chunked = format!("{:x}\r\n{body}\r\n0\r\n\r\n", body.len());
+        let err = decode(Framing::Chunked(Chunk::Header), chunked.as_bytes(), 4096).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::FileTooLarge);
+    }
+
+    fn post(addr: SocketAddr, request: &str) -> String {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(request.as_bytes()).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn test_serves_uploads() {
+        let listen = ListenAddr::parse("http://127.0.0.1:0/ingest").unwrap();
+        let (addr, mut source) = serve(&listen, &Acceptor::default()).unwrap();
+
+        let body = "{\"msg\":\"a\"}\n{\"msg\":\"b\"}\n";
+        let response = post(
+            addr,
+            &format!(
+                "POST /ingest HTTP/1.1\r\nContent-Length: {}\r\n\r\n{body}",
+                body.len()
+            ),
+        );
+        assert!(response.starts_with("HTTP/1.1 202"), "got: {response}");
+        let deadline = Some(Instant::now() + Duration::from_secs(5));
+        assert!(
+            matches!(source.next_line(deadline), Next::Line(Ok(ref l)) if l == r#"{"msg":"a"}"#)
+        );
+        assert!(
+            matches!(source.next_line(deadline), Next::Line(Ok(ref l)) if l == r#"{"msg":"b"}"#)
+        );
+
+        let response = post(
+            addr,
+            "POST /ingest HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n\
+             4\r\n{\"ms\r\n8\r\ng\":\"c\"}\n\r\n0\r\n\r\n",
+        );
+        assert!(response.starts_with("HTTP/1.1 202"), "got: {response}");
+        assert!(
+            matches!(source.next_line(deadline), Next::Line(Ok(ref l)) if l == r#"{"msg":"c"}"#)
+        );
+
+        let response = post(
+            addr,
+            &format!(
+                "POST /ingest HTTP/1.1\r\nContent-Length: {}\r\n\r\n",
+                MAX_BODY + 1
+            ),
+        );
+        assert!(response.starts_with("HTTP/1.1 413"), "got: {response}");
+        let response = post(addr, "GET /ingest HTTP/1.1\r\n\r\n");
+        assert!(response.starts_with("HTTP/1.1 405"), "got: {response}");
+        let response = post(addr, "POST /other HTTP/1.1\r\n\r\n");
+        assert!(response.starts_with("HTTP/1.1 404"), "got: {response}");
+    }
+
+    #[cfg(feature = "tls")]
+    #[test]
+    fn test_serves_uploads_over_tls() {
+        use crate::tls::{self, TlsFiles};
+        use std::path::PathBuf;
+
+        let fixture = |name: &str| {
+            Some(
+                PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/tls"))
+                    .join(name),
+            )
+        };
+        let server = tls::server_config(&TlsFiles {
+            ca: None,
+            cert: fixture("server.pem"),
+            key: fixture("server.key"),
+        })
+        .unwrap();
+        let listen = ListenAddr::parse("https://127.0.0.1:0/ingest").unwrap();
+        assert!(serve(&listen, &Acceptor::default()).is_err());
+        let (addr, mut source) = serve(&listen, &Acceptor::with_tls(server)).unwrap();
+
+        let client = tls::client_config(&TlsFiles {
+            ca: fixture("ca.pem"),
+            ..TlsFiles::default()
+        })
+        .unwrap();
+        let stream = TcpStream::connect(addr).unwrap();
+        let mut stream = tls::wrap(stream, "localhost", &client).unwrap();
+        let body = "{\"msg\":\"secret\"}\n";
+        write!(
+            stream,
+            "POST /ingest HTTP/1.1\r\nContent-Length: {}\r\n\r\n{body}",
+            body.len()
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 202"), "got: {response}");
+        let deadline = Some(Instant::now() + Duration::from_secs(5));
+        assert!(
+            matches!(source.next_line(deadline), Next::Line(Ok(ref l)) if l == r#"{"msg":"secret"}"#)
+        );
+    }
+}
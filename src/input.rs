@@ -0,0 +1,509 @@
+//! Line sources feeding the processing loop.
+//!
+//! The plain path wraps any `Iterator<Item = io::Result<String>>` (e.g.
+//! [`lines`]) and blocks until the next line arrives. When a
+//! deadline matters — e.g. giving up on multi-line reassembly so live
+//! output isn't held back — [`ThreadedLines`] reads on a background thread
+//! and hands lines over a channel so the caller can wait with a timeout.
+//! [`KeepReading`] turns end of input into a wait for more data
+//! (`--exit-on-eof false`). With the `mmap` feature, [`MmapLines`] reads a
+//! regular file straight from a memory mapping (`--mmap`), and
+//! [`ParallelLines`](crate::parallel::ParallelLines) parses ahead on worker
+//! threads (`--jobs`).
+//!
+//! Lines keep the `\r` of a CRLF ending so [`Position`]s count every byte
+//! of the input; [`strip_cr`] drops it before a line is parsed.
+
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead, Read};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, SyncSender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::parallel::Prepared;
+use crate::shutdown;
+
+/// Number of lines buffered between the reader thread and the consumer.
+const CHANNEL_CAPACITY: usize = 1024;
+
+/// How often [`KeepReading`] retries after hitting end of input.
+const EOF_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Position of a line within its input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    /// 1-indexed line number.
+    pub line: usize,
+    /// Byte offset of the start of the line.
+    pub offset: u64,
+}
+
+impl Position {
+    pub const fn start() -> Self {
+        Self { line: 1, offset: 0 }
+    }
+
+    /// Move past `line`, as read with any `\r` before its newline, and the
+    /// newline.
+    pub const fn advance(&mut self, line: &str) {
+        self.line += 1;
+        self.offset += line.len() as u64 + 1;
+    }
+
+    /// Move past the line that failed to read with `error`: by its length
+    /// when it is an [`InvalidLine`], else by the line number only.
+    pub fn skip(&mut self, error: &io::Error) {
+        self.line += 1;
+        self.offset += InvalidLine::of(error).map_or(0, |invalid| invalid.len);
+    }
+}
+
+/// Error payload of a line that is not valid UTF-8, so positions can move
+/// past it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidLine {
+    /// Length of the line in bytes, including its newline.
+    pub len: u64,
+}
+
+impl InvalidLine {
+    /// An `InvalidData` error for a line of `len` bytes.
+    pub fn error(len: usize) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidData, Self { len: len as u64 })
+    }
+
+    /// The payload of `error`, if it reports an invalid line.
+    pub fn of(error: &io::Error) -> Option<&Self> {
+        error.get_ref()?.downcast_ref()
+    }
+}
+
+impl fmt::Display for InvalidLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("stream did not contain valid UTF-8")
+    }
+}
+
+impl Error for InvalidLine {}
+
+/// The lines of `reader`, like [`BufRead::lines`] except that a `\r`
+/// before the newline is kept and a line that is not valid UTF-8 yields an
+/// [`InvalidLine`] error.
+pub const fn lines<R: BufRead>(reader: R) -> Lines<R> {
+    Lines {
+        reader,
+        buf: Vec::new(),
+    }
+}
+
+/// Iterator returned by [`lines`].
+#[derive(Debug)]
+pub struct Lines<R> {
+    reader: R,
+    /// Bytes of the last invalid line, reused for the next one.
+    buf: Vec<u8>,
+}
+
+impl<R: BufRead> Iterator for Lines<R> {
+    type Item = io::Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut buf = std::mem::take(&mut self.buf);
+        buf.clear();
+        let len = match self.reader.read_until(b'\n', &mut buf) {
+            Ok(0) => return None,
+            Ok(len) => len,
+            Err(e) => return Some(Err(e)),
+        };
+        trim_newline(&mut buf);
+        Some(String::from_utf8(buf).map_err(|e| {
+            self.buf = e.into_bytes();
+            InvalidLine::error(len)
+        }))
+    }
+}
+
+/// One line read with its terminator, as [`lines`] yields it.
+pub fn decode_line(mut buf: Vec<u8>) -> io::Result<String> {
+    let len = buf.len();
+    trim_newline(&mut buf);
+    String::from_utf8(buf).map_err(|_| InvalidLine::error(len))
+}
+
+/// Drop a trailing `\n`.
+fn trim_newline(buf: &mut Vec<u8>) {
+    if buf.last() == Some(&b'\n') {
+        buf.pop();
+    }
+}
+
+/// Drop the `\r` a CRLF line ending leaves at the end of a line.
+pub fn strip_cr(line: &mut String) {
+    if line.ends_with('\r') {
+        line.pop();
+    }
+}
+
+/// Outcome of asking a [`LineSource`] for the next line.
+#[derive(Debug)]
+pub enum Next {
+    /// A line (or a read error) was available.
+    Line(io::Result<String>),
+    /// The deadline passed before a line arrived. More input may follow.
+    TimedOut,
+    /// The input is exhausted.
+    Eof,
+}
+
+/// A source of input lines that can optionally wait with a deadline.
+pub trait LineSource {
+    /// Return the next line, waiting at most until `deadline` when given.
+    ///
+    /// Sources that cannot wait with a timeout block and ignore the deadline.
+    fn next_line(&mut self, deadline: Option<Instant>) -> Next;
+
+    /// Label of the input the last line came from, for sources that merge
+    /// several inputs.
+    fn label(&self) -> Option<&str> {
+        None
+    }
+
+    /// The last line parsed ahead of time, for sources that parse on
+    /// worker threads ([`ParallelLines`]).
+    fn take_prepared(&mut self) -> Option<Prepared> {
+        None
+    }
+
+    /// Where the last line started in its input, for sources that reorder
+    /// lines ([`SortedLines`](crate::sort::SortedLines)) or skip them
+    /// ([`MmapLines`]); `None` when every line comes in input order.
+    fn position(&self) -> Option<Position> {
+        None
+    }
+
+    /// Whether the source may leave out records the filters drop anyway,
+    /// for sources that can check them without copying ([`MmapLines`]).
+    /// The processing loop allows it only while nothing depends on seeing
+    /// every line.
+    fn skip_filtered(&mut self, _allowed: bool) {}
+
+    /// A notice about the input to show the user, e.g. that a followed file
+    /// was rotated. Notices come before the lines read after them.
+    fn take_notice(&mut self) -> Option<String> {
+        None
+    }
+}
+
+/// Blocking [`LineSource`] over a line iterator. Deadlines are ignored.
+#[derive(Debug)]
+pub struct IterLines<I>(pub I);
+
+impl<I: Iterator<Item = io::Result<String>>> LineSource for IterLines<I> {
+    fn next_line(&mut self, _deadline: Option<Instant>) -> Next {
+        self.0.next().map_or(Next::Eof, Next::Line)
+    }
+}
+
+/// Blocking [`LineSource`] over a memory-mapped regular file, splitting
+/// lines like [`lines`] without going through a read buffer.
+///
+/// With [`with_level_filter`](Self::with_level_filter), lines are checked
+/// in place with [`parse_record_ref`](crate::record_ref::parse_record_ref)
+/// and records below `--level` are skipped without being copied, while the
+/// processing loop allows it ([`LineSource::skip_filtered`]). Lines handed
+/// out are copied into a `String` once.
+#[cfg(feature = "mmap")]
+#[derive(Debug)]
+pub struct MmapLines<'a> {
+    map: memmap2::Mmap,
+    /// Position of the next line.
+    next: Position,
+    /// Position of the last line handed out.
+    last: Option<Position>,
+    /// Config whose `min_level` lines are checked against.
+    filter: Option<&'a crate::config::Config>,
+    skip_filtered: bool,
+}
+
+#[cfg(feature = "mmap")]
+impl<'a> MmapLines<'a> {
+    /// Map `file`, which must be a regular file.
+    pub fn open(file: &std::fs::File) -> io::Result<Self> {
+        if !file.metadata()?.is_file() {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "not a regular file",
+            ));
+        }
+        // SAFETY: the mapping is read-only; the file being truncated while
+        // mapped is documented as unsupported for `--mmap`.
+        let map = unsafe { memmap2::Mmap::map(file)? };
+        #[cfg(unix)]
+        let _ = map.advise(memmap2::Advice::Sequential);
+        Ok(Self {
+            map,
+            next: Position::start(),
+            last: None,
+            filter: None,
+            skip_filtered: false,
+        })
+    }
+
+    /// Skip records below the `min_level` of `config` when allowed.
+    #[must_use]
+    pub const fn with_level_filter(mut self, config: &'a crate::config::Config) -> Self {
+        self.filter = Some(config);
+        self
+    }
+
+    /// Whether `line` is a record the level filter drops.
+    fn is_filtered(&self, line: &str) -> bool {
+        let Some(config) = self.filter.filter(|_| self.skip_filtered) else {
+            return false;
+        };
+        config.min_level.is_some_and(|min| {
+            crate::record_ref::parse_record_ref(line, config)
+                .and_then(|record| record.level)
+                .is_some_and(|level| level < min)
+        })
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl LineSource for MmapLines<'_> {
+    fn next_line(&mut self, _deadline: Option<Instant>) -> Next {
+        loop {
+            let offset = usize::try_from(self.next.offset).unwrap_or(usize::MAX);
+            let rest = self.map.get(offset..).unwrap_or_default();
+            if rest.is_empty() {
+                return Next::Eof;
+            }
+            let (line, len) = match memchr::memchr(b'\n', rest) {
+                Some(end) => (&rest[..end], end + 1),
+                None => (rest, rest.len()),
+            };
+            let pos = self.next;
+            self.next.line += 1;
+            self.next.offset += len as u64;
+            let line = std::str::from_utf8(line);
+            if line.is_ok_and(|line| self.is_filtered(line)) {
+                continue;
+            }
+            self.last = Some(pos);
+            return Next::Line(line.map(str::to_owned).map_err(|_| InvalidLine::error(len)));
+        }
+    }
+
+    fn position(&self) -> Option<Position> {
+        self.last
+    }
+
+    fn skip_filtered(&mut self, allowed: bool) {
+        self.skip_filtered = allowed;
+    }
+}
+
+/// [`LineSource`] that reads on a background thread so callers can wait
+/// for the next line with a deadline.
+#[derive(Debug)]
+pub struct ThreadedLines {
+    rx: Receiver<io::Result<String>>,
+}
+
+impl ThreadedLines {
+    /// A source fed by the returned sender; input ends once every clone of
+    /// the sender has been dropped.
+    pub fn channel() -> (SyncSender<io::Result<String>>, Self) {
+        let (tx, rx) = mpsc::sync_channel(CHANNEL_CAPACITY);
+        (tx, Self { rx })
+    }
+
+    /// Spawn a reader thread pulling lines from `reader`.
+    pub fn spawn<R: BufRead + Send + 'static>(reader: R) -> Self {
+        let (tx, this) = Self::channel();
+        thread::spawn(move || {
+            for line in lines(reader) {
+                if tx.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        this
+    }
+}
+
+impl LineSource for ThreadedLines {
+    fn next_line(&mut self, deadline: Option<Instant>) -> Next {
+        match deadline {
+            None => self.rx.recv().map_or(Next::Eof, Next::Line),
+            Some(deadline) => {
+                let timeout = deadline.saturating_duration_since(Instant::now());
+                match self.rx.recv_timeout(timeout) {
+                    Ok(line) => Next::Line(line),
+                    Err(RecvTimeoutError::Timeout) => Next::TimedOut,
+                    Err(RecvTimeoutError::Disconnected) => Next::Eof,
+                }
+            }
+        }
+    }
+}
+
+/// Reader that never reports end of input: on EOF it sleeps and retries,
+/// picking up data appended to a file or written by a new FIFO writer.
+/// Only a pending [`shutdown`] ends it.
+#[derive(Debug)]
+pub struct KeepReading<R>(pub R);
+
+impl<R: Read> Read for KeepReading<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            match self.0.read(buf)? {
+                0 if shutdown::signal().is_some() => return Ok(0),
+                0 => thread::sleep(EOF_POLL_INTERVAL),
+                n => return Ok(n),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lines_count_every_byte() {
+        let input: &[u8] = b"one\r\n\xff\xfe\ntwo\n\xff";
+        let mut pos = Position::start();
+        let mut texts = Vec::new();
+        for line in lines(input) {
+            match line {
+                Ok(line) => {
+                    pos.advance(&line);
+                    texts.push(line);
+                }
+                Err(e) => pos.skip(&e),
+            }
+            texts.push(format!("@{}:{}", pos.line, pos.offset));
+        }
+        assert_eq!(texts, ["one\r", "@2:5", "@3:8", "two", "@4:12", "@5:13"]);
+    }
+
+    #[test]
+    fn test_iter_lines_yields_then_eof() {
+        let mut source = IterLines(vec![Ok("a".to_string())].into_iter());
+        assert!(matches!(source.next_line(None), Next::Line(Ok(ref l)) if l == "a"));
+        assert!(matches!(source.next_line(None), Next::Eof));
+    }
+
+    #[test]
+    fn test_threaded_lines_reads_all() {
+        let mut source = ThreadedLines::spawn(io::Cursor::new("one\ntwo\n"));
+        assert!(matches!(source.next_line(None), Next::Line(Ok(ref l)) if l == "one"));
+        assert!(matches!(source.next_line(None), Next::Line(Ok(ref l)) if l == "two"));
+        assert!(matches!(source.next_line(None), Next::Eof));
+    }
+
+    #[test]
+    fn test_threaded_lines_times_out() {
+        // A reader that never produces a line: the write end stays open.
+        let (reader, _writer) = pipe_reader();
+        let mut source = ThreadedLines::spawn(reader);
+        let deadline = Instant::now() + Duration::from_millis(20);
+        assert!(matches!(source.next_line(Some(deadline)), Next::TimedOut));
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn test_mmap_lines_split_like_bufread() {
+        use std::io::Write;
+
+        let mut file = tempfile::tempfile().unwrap();
+        file.write_all(b"one\r\ntwo\n\xff\nlast").unwrap();
+        let mut source = MmapLines::open(&file).unwrap();
+        assert!(matches!(source.next_line(None), Next::Line(Ok(ref l)) if l == "one\r"));
+        assert!(matches!(source.next_line(None), Next::Line(Ok(ref l)) if l == "two"));
+        assert!(matches!(
+            source.next_line(None),
+            Next::Line(Err(ref e)) if InvalidLine::of(e) == Some(&InvalidLine { len: 2 })
+        ));
+        assert!(matches!(source.next_line(None), Next::Line(Ok(ref l)) if l == "last"));
+        assert!(matches!(source.next_line(None), Next::Eof));
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn test_mmap_lines_skip_filtered_records_in_place() {
+        use std::io::Write;
+
+        let info = r#"{"level":"info","msg":"ok"}"#;
+        let error = r#"{"level":"error","msg":"boom"}"#;
+        let mut file = tempfile::tempfile().unwrap();
+        write!(file, "{info}\n{error}\nplain\n{info}\n").unwrap();
+        let config = crate::config::Config {
+            min_level: Some(crate::level::Level::Error),
+            ..crate::config::Config::default()
+        };
+        let mut source = MmapLines::open(&file).unwrap().with_level_filter(&config);
+        assert!(matches!(source.next_line(None), Next::Line(Ok(ref l)) if l == info));
+        source.skip_filtered(true);
+        assert!(matches!(source.next_line(None), Next::Line(Ok(ref l)) if l == error));
+        assert_eq!(
+            source.position(),
+            Some(Position {
+                line: 2,
+                offset: info.len() as u64 + 1
+            })
+        );
+        assert!(matches!(source.next_line(None), Next::Line(Ok(ref l)) if l == "plain"));
+        assert!(matches!(source.next_line(None), Next::Eof));
+        assert_eq!(source.position().map(|pos| pos.line), Some(3));
+    }
+
+    #[test]
+    fn test_keep_reading_waits_past_eof() {
+        // Each chunk is followed by an EOF from the inner reader.
+        let chunks = vec![b"one\n".to_vec(), Vec::new(), b"two\n".to_vec()];
+        let reader = io::BufReader::new(KeepReading(Chunks(chunks.into_iter())));
+        let mut source = ThreadedLines::spawn(reader);
+        assert!(matches!(source.next_line(None), Next::Line(Ok(ref l)) if l == "one"));
+        assert!(matches!(source.next_line(None), Next::Line(Ok(ref l)) if l == "two"));
+        let deadline = Instant::now() + Duration::from_millis(20);
+        assert!(matches!(source.next_line(Some(deadline)), Next::TimedOut));
+    }
+
+    /// A reader returning one chunk per call, then EOF forever.
+    struct Chunks(std::vec::IntoIter<Vec<u8>>);
+
+    impl io::Read for Chunks {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let chunk = self.0.next().unwrap_or_default();
+            buf[..chunk.len()].copy_from_slice(&chunk);
+            Ok(chunk.len())
+        }
+    }
+
+    /// A `BufRead` that blocks until its paired sender is dropped.
+    fn pipe_reader() -> (io::BufReader<ChannelReader>, mpsc::Sender<Vec<u8>>) {
+        let (tx, rx) = mpsc::channel();
+        (io::BufReader::new(ChannelReader(rx)), tx)
+    }
+
+    struct ChannelReader(Receiver<Vec<u8>>);
+
+    impl io::Read for ChannelReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match self.0.recv() {
+                Ok(chunk) => {
+                    let n = chunk.len().min(buf.len());
+                    buf[..n].copy_from_slice(&chunk[..n]);
+                    Ok(n)
+                }
+                Err(_) => Ok(0),
+            }
+        }
+    }
+}
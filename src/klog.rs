@@ -0,0 +1,189 @@
+//! klog/glog lines, as written by Kubernetes components.
+//!
+//! `I0115 10:30:00.123456    1234 server.go:42] message` is read into a
+//! [`LogRecord`]: the leading `I`/`W`/`E`/`F` becomes the level, the date
+//! and time the timestamp, `file:line` the caller, and the thread ID a
+//! `thread` field. The header carries no year or zone; the current year is
+//! assumed (the previous one for dates ahead of today) and times are read
+//! as UTC. The clock is read once, for the first klog line; under
+//! `--stable` the reference is [`STABLE_NOW`] instead, so every date falls
+//! in 2000 whenever the input is read.
+//!
+//! Structured klog messages (`"message" key="value" n=3`) have their quoted
+//! message unquoted and the `key=value` pairs turned into fields.
+
+use std::sync::LazyLock;
+
+use serde_json::Value;
+
+use crate::extras::Extras;
+use crate::level::Level;
+use crate::parser::LogRecord;
+use crate::timestamp::Timestamp;
+
+/// Reference time under `--stable`: the last day of 2000, a leap year, so
+/// every `MMDD` is read as a date in 2000.
+pub const STABLE_NOW: jiff::Timestamp = jiff::Timestamp::constant(978_220_800, 0);
+
+/// Parse a klog/glog line, or `None` if the line isn't one. The year comes
+/// from `now`, or from the clock when `None`.
+pub fn parse(line: &str, now: Option<jiff::Timestamp>) -> Option<LogRecord> {
+    let level = match line.as_bytes().first()? {
+        b'I' => Level::Info,
+        b'W' => Level::Warn,
+        b'E' => Level::Error,
+        b'F' => Level::Fatal,
+        _ => return None,
+    };
+    let (header, message) = line.split_once("] ")?;
+    let mut parts = header[1..].split_ascii_whitespace();
+    let (date, time, thread, caller) = (parts.next()?, parts.next()?, parts.next()?, parts.next()?);
+    if parts.next().is_some()
+        || date.len() != 4
+        || !date.bytes().all(|b| b.is_ascii_digit())
+        || !thread.bytes().all(|b| b.is_ascii_digit())
+        || !caller.contains(':')
+    {
+        return None;
+    }
+    let timestamp = timestamp(date, time, now)?;
+
+    let mut extra = Extras::new();
+    extra.insert(
+        "thread".to_string(),
+        Value::from(thread.parse::<u64>().ok()?),
+    );
+    let message = structured(message, &mut extra).unwrap_or_else(|| message.to_string());
+    let mut record = LogRecord {
+        timestamp: Some(timestamp),
+        level: Some(level),
+        message: Some(message),
+        logger: None,
+        caller: Some(caller.to_string()),
+        error: None,
+        extra,
+        raw_json: String::new(),
+    };
+    record.raw_json = record.to_normalized_json().to_string();
+    Some(record)
+}
+
+/// The time the first klog line was read.
+static NOW: LazyLock<jiff::Timestamp> = LazyLock::new(jiff::Timestamp::now);
+
+/// The timestamp of `MMDD` and `hh:mm:ss.uuuuuu`, in the year of `now`
+/// unless that puts it more than a day ahead.
+fn timestamp(date: &str, time: &str, now: Option<jiff::Timestamp>) -> Option<Timestamp> {
+    let now = now.unwrap_or(*NOW);
+    let year = now.to_zoned(jiff::tz::TimeZone::UTC).year();
+    let at = |year: i16| {
+        let text = format!("{year}-{}-{}T{time}Z", &date[..2], &date[2..]);
+        Timestamp::from_json_value(&Value::from(text))
+    };
+    let ts = at(year)?;
+    if ts.value > now + jiff::SignedDuration::from_hours(24) {
+        return at(year - 1);
+    }
+    Some(ts)
+}
+
+/// The message of a structured klog line, with its `key=value` pairs
+/// added to `extra`; `None` for a plain message.
+fn structured(message: &str, extra: &mut Extras) -> Option<String> {
+    let (text, mut rest) = quoted(message.strip_prefix('"')?)?;
+    let mut pairs = Vec::new();
+    while !rest.is_empty() {
+        let (key, after) = rest.trim_start().split_once('=')?;
+        let (value, after) = if let Some(quoted_value) = after.strip_prefix('"') {
+            let (value, after) = quoted(quoted_value)?;
+            (Value::from(value), after)
+        } else {
+            let end = after.find(' ').unwrap_or(after.len());
+            let bare = &after[..end];
+            let value = serde_json::from_str::<Value>(bare)
+                .ok()
+                .filter(|v| v.is_number() || v.is_boolean())
+                .unwrap_or_else(|| Value::from(bare));
+            (value, &after[end..])
+        };
+        if key.is_empty() || key.contains(' ') {
+            return None;
+        }
+        pairs.push((key.to_string(), value));
+        rest = after.trim_start();
+    }
+    extra.extend(pairs);
+    Some(text)
+}
+
+/// Read a Go-quoted string up to its closing quote, returning it unescaped
+/// and the text after the quote.
+fn quoted(s: &str) -> Option<(String, &str)> {
+    let mut chars = s.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => {
+                let text = serde_json::from_str(&format!("\"{}\"", &s[..i])).ok()?;
+                return Some((text, &s[i + 1..]));
+            }
+            '\\' => {
+                chars.next()?;
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_plain_line() {
+        let record = parse(
+            "W0115 10:30:00.123456    1234 server.go:42] slow request {id=7}",
+            None,
+        )
+        .unwrap();
+        assert_eq!(record.level, Some(Level::Warn));
+        assert_eq!(record.message.as_deref(), Some("slow request {id=7}"));
+        assert_eq!(record.caller.as_deref(), Some("server.go:42"));
+        assert_eq!(record.extra["thread"], 1234);
+        let ts = record.timestamp.unwrap();
+        assert_eq!(
+            ts.format_with("%m-%d %H:%M:%S%.6f"),
+            "01-15 10:30:00.123456"
+        );
+    }
+
+    #[test]
+    fn test_parse_structured_line() {
+        let line = r#"I0115 10:30:00.000000       1 controller.go:118] "Pod \"web\" synced" pod="default/web" attempt=3 ready=true"#;
+        let record = parse(line, None).unwrap();
+        assert_eq!(record.level, Some(Level::Info));
+        assert_eq!(record.message.as_deref(), Some(r#"Pod "web" synced"#));
+        assert_eq!(record.extra["pod"], "default/web");
+        assert_eq!(record.extra["attempt"], 3);
+        assert_eq!(record.extra["ready"], true);
+    }
+
+    #[test]
+    fn test_rejects_other_lines() {
+        assert!(parse("Info: service started] ok", None).is_none());
+        assert!(parse("I0115 10:30:00.000000 main] no caller", None).is_none());
+        assert!(parse("INFO 2026-01-15 started", None).is_none());
+    }
+
+    #[test]
+    fn test_stable_reference_pins_the_year() {
+        for line in [
+            "I0101 00:00:00.000000 1 a.go:1] first",
+            "I0229 12:00:00.000000 1 a.go:1] leap day",
+            "I1231 23:59:59.999999 1 a.go:1] last",
+        ] {
+            let ts = parse(line, Some(STABLE_NOW)).unwrap().timestamp.unwrap();
+            assert_eq!(ts.format_with("%Y"), "2000", "{line}");
+        }
+    }
+}
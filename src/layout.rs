@@ -0,0 +1,301 @@
+//! Structured formatted output.
+//!
+//! The formatter lays an entry out as a [`Layout`]: its text, plus
+//! [`Segment`]s marking the spans that are a timestamp, a level badge, the
+//! message, a field key, and so on, each with the style it is drawn in.
+//! Spaces, padding, and punctuation between segments carry no role.
+//!
+//! Renderers consume the layout: [`Layout::write_ansi`] produces the
+//! terminal output, [`Layout::text`] is the plain text, and
+//! [`Layout::write_html`] wraps segments in `<span class="cor-ROLE">`. New
+//! output targets only need to map roles (or styles) to their own markup.
+
+use std::fmt::{self, Write};
+use std::ops::Range;
+
+use owo_colors::Stream::Stdout;
+use owo_colors::{OwoColorize, Style};
+
+/// What a span of the output shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Role {
+    /// Record timestamp.
+    Timestamp,
+    /// `--elapsed` offset column.
+    Elapsed,
+    /// Level or field icon.
+    Icon,
+    /// Level badge.
+    Badge,
+    /// Logger name.
+    Logger,
+    /// Text before embedded JSON.
+    Prefix,
+    /// Tag added by a rule.
+    Tag,
+    /// Message text.
+    Message,
+    /// Part of the message matched by `--grep`.
+    Match,
+    /// Text matched by a `--highlight` pattern.
+    Highlight,
+    /// Source location.
+    Caller,
+    /// `--sparkline` of a numeric field.
+    Sparkline,
+    /// Field key, including the `error` label.
+    Key,
+    /// Field value.
+    Value,
+    /// Error text.
+    Error,
+    /// Line passed through as is; may carry its own escape sequences.
+    Raw,
+    /// cor's own note about the line, such as a parse error.
+    Diagnostic,
+}
+
+impl Role {
+    /// Lowercase name, used for HTML classes.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Timestamp => "timestamp",
+            Self::Elapsed => "elapsed",
+            Self::Icon => "icon",
+            Self::Badge => "badge",
+            Self::Logger => "logger",
+            Self::Prefix => "prefix",
+            Self::Tag => "tag",
+            Self::Message => "message",
+            Self::Match => "match",
+            Self::Highlight => "highlight",
+            Self::Caller => "caller",
+            Self::Sparkline => "sparkline",
+            Self::Key => "key",
+            Self::Value => "value",
+            Self::Error => "error",
+            Self::Raw => "raw",
+            Self::Diagnostic => "diagnostic",
+        }
+    }
+}
+
+/// A span of a [`Layout`]'s text with a role.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Segment {
+    pub role: Role,
+    /// Style the span is drawn in on a terminal.
+    pub style: Style,
+    /// Byte range in [`Layout::text`].
+    pub range: Range<usize>,
+}
+
+/// Restyling of a whole entry, drawn over its segment styles.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Overlay {
+    /// Draw every piece in one style (`tint` rules, `--focus` matches).
+    Tint(Style),
+    /// Dim every piece, keeping its colors (records outside `--focus`).
+    Dim,
+}
+
+/// One formatted entry: text with role-tagged segments.
+///
+/// Plain text is appended with [`fmt::Write`] (`write!`) or
+/// [`Layout::push_str`]; segments with [`Layout::push`].
+#[derive(Debug, Clone, Default)]
+pub struct Layout {
+    text: String,
+    segments: Vec<Segment>,
+    overlay: Option<Overlay>,
+}
+
+impl Layout {
+    /// Empty the layout, keeping its allocations.
+    pub fn clear(&mut self) {
+        self.text.clear();
+        self.segments.clear();
+        self.overlay = None;
+    }
+
+    /// Whether nothing was laid out (e.g. the record was filtered out).
+    pub const fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// The entry as plain text. Only [`Role::Raw`] segments can contain
+    /// escape sequences.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Segments in order of position.
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    /// Append text without a role.
+    pub fn push_str(&mut self, s: &str) {
+        self.text.push_str(s);
+    }
+
+    /// Append `s` as a segment with `role`, drawn in `style`.
+    pub fn push(&mut self, role: Role, style: Style, s: &str) {
+        if s.is_empty() {
+            return;
+        }
+        let start = self.text.len();
+        self.text.push_str(s);
+        self.segments.push(Segment {
+            role,
+            style,
+            range: start..self.text.len(),
+        });
+    }
+
+    /// Restyle the whole entry when rendered with [`Layout::write_ansi`].
+    pub const fn set_overlay(&mut self, overlay: Overlay) {
+        self.overlay = Some(overlay);
+    }
+
+    /// The overlay set with [`Layout::set_overlay`], if any.
+    pub const fn overlay(&self) -> Option<Overlay> {
+        self.overlay
+    }
+
+    /// Render with ANSI styles when stdout supports color, as printed by cor.
+    pub fn write_ansi(&self, out: &mut String) {
+        self.for_each_piece(|piece, segment| match self.piece_style(piece, segment) {
+            Some(style) if !style.is_plain() => {
+                let _ = write!(
+                    out,
+                    "{}",
+                    piece.if_supports_color(Stdout, |t| t.style(style).to_string())
+                );
+            }
+            _ => out.push_str(piece),
+        });
+    }
+
+    /// Render as HTML, each segment in a `<span class="cor-ROLE">`.
+    pub fn write_html(&self, out: &mut String) {
+        self.for_each_piece(|piece, segment| match segment {
+            Some(segment) => {
+                let _ = write!(out, "<span class=\"cor-{}\">", segment.role.name());
+                push_html_escaped(out, piece);
+                out.push_str("</span>");
+            }
+            None => push_html_escaped(out, piece),
+        });
+    }
+
+    /// Style of one piece, after the overlay. Pieces carrying their own
+    /// escape sequences are left alone.
+    fn piece_style(&self, piece: &str, segment: Option<&Segment>) -> Option<Style> {
+        let style = segment.map(|s| s.style);
+        match self.overlay {
+            Some(_) if piece.contains('\x1b') => style,
+            Some(Overlay::Tint(tint)) => Some(tint),
+            Some(Overlay::Dim) => Some(style.unwrap_or_default().dimmed()),
+            None => style,
+        }
+    }
+
+    /// Call `f` with every run of text in order, with its segment if any.
+    fn for_each_piece<'a>(&'a self, mut f: impl FnMut(&'a str, Option<&'a Segment>)) {
+        let mut last = 0;
+        for segment in &self.segments {
+            if segment.range.start > last {
+                f(&self.text[last..segment.range.start], None);
+            }
+            f(&self.text[segment.range.clone()], Some(segment));
+            last = segment.range.end;
+        }
+        if last < self.text.len() {
+            f(&self.text[last..], None);
+        }
+    }
+}
+
+impl fmt::Write for Layout {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.push_str(s);
+        Ok(())
+    }
+}
+
+fn push_html_escaped(out: &mut String, s: &str) {
+    for c in s.chars() {
+        match c {
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '&' => out.push_str("&amp;"),
+            '"' => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Layout {
+        let mut layout = Layout::default();
+        layout.push(Role::Badge, Style::new().red().bold(), "ERROR");
+        layout.push_str(": ");
+        layout.push(Role::Message, Style::new(), "a < b");
+        let _ = write!(layout, "\n{:>5}: ", "");
+        layout.push(Role::Value, Style::new(), "");
+        layout
+    }
+
+    #[test]
+    fn test_segments_index_the_text() {
+        let layout = sample();
+        assert_eq!(layout.text(), "ERROR: a < b\n     : ");
+        let roles: Vec<(Role, &str)> = layout
+            .segments()
+            .iter()
+            .map(|s| (s.role, &layout.text()[s.range.clone()]))
+            .collect();
+        // Empty segments are not recorded.
+        assert_eq!(roles, [(Role::Badge, "ERROR"), (Role::Message, "a < b")]);
+    }
+
+    #[test]
+    fn test_render_html() {
+        let mut html = String::new();
+        sample().write_html(&mut html);
+        assert_eq!(
+            html,
+            "<span class=\"cor-badge\">ERROR</span>: \
+             <span class=\"cor-message\">a &lt; b</span>\n     : "
+        );
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut layout = sample();
+        layout.clear();
+        assert!(layout.is_empty());
+        assert!(layout.segments().is_empty());
+        assert_eq!(layout.overlay(), None);
+    }
+
+    #[test]
+    fn test_overlay_styles_every_piece() {
+        let mut layout = sample();
+        layout.set_overlay(Overlay::Dim);
+        assert_eq!(layout.piece_style(": ", None), Some(Style::new().dimmed()));
+        assert_eq!(
+            layout.piece_style("ERROR", layout.segments().first()),
+            Some(Style::new().red().bold().dimmed())
+        );
+
+        let tint = Style::new().cyan();
+        layout.set_overlay(Overlay::Tint(tint));
+        assert_eq!(layout.piece_style(": ", None), Some(tint));
+        assert_eq!(layout.piece_style("\x1b[1mraw\x1b[0m", None), None);
+    }
+}
@@ -8,6 +8,8 @@ use std::fmt;
 
 use owo_colors::Style;
 
+use crate::theme::Paint;
+
 /// Canonical log level enumeration.
 ///
 /// Ordered by severity (ascending) for `>=` filtering via [`Ord`].
@@ -42,6 +44,18 @@ impl Level {
         }
     }
 
+    /// Lowercase level name (e.g., `"info"`), as used in normalized JSON.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Trace => "trace",
+            Self::Debug => "debug",
+            Self::Info => "info",
+            Self::Warn => "warn",
+            Self::Error => "error",
+            Self::Fatal => "fatal",
+        }
+    }
+
     /// The blank badge (5 spaces) used when no level is recognized.
     pub const fn blank_badge() -> &'static str {
         "     "
@@ -111,6 +125,17 @@ impl Level {
         }
     }
 
+    /// Parse a syslog severity (0 = emergency … 7 = debug), as used by GELF.
+    pub const fn from_syslog(n: i64) -> Self {
+        match n {
+            ..=2 => Self::Fatal,
+            3 => Self::Error,
+            4 => Self::Warn,
+            5 | 6 => Self::Info,
+            7.. => Self::Debug,
+        }
+    }
+
     /// Parse a level from a [`serde_json::Value`].
     ///
     /// Handles both string and numeric representations.
@@ -141,31 +166,20 @@ impl Level {
     }
 }
 
-/// Convert a color name string to an [`owo_colors::Style`].
+/// Convert a color to a bold [`owo_colors::Style`].
 ///
-/// Supports standard ANSI colors and bright variants. All styles are bold.
-/// Returns `None` for unrecognized color names.
+/// Colors are read by [`Paint::parse`]: an ANSI name (`red`, `bright_blue`,
+/// ...), `#rrggbb` hex, or a 256-color index. Returns `None` for anything
+/// that names no color.
 pub fn color_name_to_style(color: &str) -> Option<Style> {
-    let style = match color.to_lowercase().as_str() {
-        "black" => Style::new().black().bold(),
-        "red" => Style::new().red().bold(),
-        "green" => Style::new().green().bold(),
-        "yellow" => Style::new().yellow().bold(),
-        "blue" => Style::new().blue().bold(),
-        "magenta" | "purple" => Style::new().magenta().bold(),
-        "cyan" => Style::new().cyan().bold(),
-        "white" => Style::new().white().bold(),
-        "bright_black" => Style::new().bright_black().bold(),
-        "bright_red" => Style::new().bright_red().bold(),
-        "bright_green" => Style::new().bright_green().bold(),
-        "bright_yellow" => Style::new().bright_yellow().bold(),
-        "bright_blue" => Style::new().bright_blue().bold(),
-        "bright_magenta" => Style::new().bright_magenta().bold(),
-        "bright_cyan" => Style::new().bright_cyan().bold(),
-        "bright_white" => Style::new().bright_white().bold(),
-        _ => return None,
-    };
-    Some(style)
+    let paint = Paint::parse(color).ok().filter(|p| p.fg.is_some())?;
+    Some(
+        Paint {
+            bold: true,
+            ..paint
+        }
+        .style(),
+    )
 }
 
 impl fmt::Display for Level {
@@ -178,6 +192,18 @@ impl fmt::Display for Level {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_color_name_to_style_accepts_every_color_form() {
+        assert_eq!(color_name_to_style("Red"), Some(Style::new().red().bold()));
+        assert_eq!(
+            color_name_to_style("#ff8800"),
+            Some(Style::new().truecolor(255, 136, 0).bold())
+        );
+        assert!(color_name_to_style("208").is_some());
+        assert!(color_name_to_style("mauve").is_none());
+        assert!(color_name_to_style("bold").is_none());
+    }
+
     #[test]
     fn test_from_str_loose_basic() {
         assert_eq!(Level::from_str_loose("info"), Some(Level::Info));
@@ -240,6 +266,17 @@ mod tests {
         assert_eq!(Level::from_numeric(100), Level::Fatal);
     }
 
+    #[test]
+    fn test_from_syslog() {
+        assert_eq!(Level::from_syslog(0), Level::Fatal);
+        assert_eq!(Level::from_syslog(2), Level::Fatal);
+        assert_eq!(Level::from_syslog(3), Level::Error);
+        assert_eq!(Level::from_syslog(4), Level::Warn);
+        assert_eq!(Level::from_syslog(5), Level::Info);
+        assert_eq!(Level::from_syslog(6), Level::Info);
+        assert_eq!(Level::from_syslog(7), Level::Debug);
+    }
+
     #[test]
     fn test_level_ordering() {
         assert!(Level::Trace < Level::Debug);
@@ -18,20 +18,124 @@
 //! assert!(out.contains("INFO"));
 //! assert!(out.contains("hello"));
 //! ```
+//!
+//! # Thread safety
+//!
+//! [`Config`] is `Send + Sync` and read-only while formatting, so one
+//! `Config` (or an `Arc<Config>`) can serve many threads, e.g. the request
+//! handlers of a server. Its compiled state — level aliases, per-level field
+//! filters, regexes — is reference-counted, so cloning a `Config` is cheap
+//! and shares it. State that changes from one record to the next lives in a
+//! [`FormatState`], which each thread keeps for itself.
+//!
+//! ```
+//! use cor::{Config, format_line};
+//!
+//! let config = Config::default();
+//! std::thread::scope(|scope| {
+//!     for n in 0..4 {
+//!         let config = &config;
+//!         scope.spawn(move || {
+//!             let mut out = String::new();
+//!             format_line(&format!(r#"{{"msg":"request {n}"}}"#), config, &mut out);
+//!             assert!(out.contains(&format!("request {n}")));
+//!         });
+//!     }
+//! });
+//! ```
 
+pub mod annotate;
+pub mod background;
+pub mod bench;
+pub mod bookmark;
+#[cfg(feature = "net")]
+pub mod broadcast;
+pub mod capabilities;
 pub mod cli;
 pub mod config;
+pub mod counters;
+pub mod dedup;
+pub mod diag;
+pub mod discover;
+pub mod elapsed;
 pub mod error;
+pub mod exit;
+pub mod explain;
+pub mod expr;
+pub mod extras;
 pub mod fields;
+pub mod follow;
 pub mod formatter;
+pub mod glyphs;
+pub mod highlight;
+#[cfg(feature = "net")]
+pub mod http_sink;
+pub mod icons;
+pub mod index;
+#[cfg(feature = "net")]
+pub mod ingest;
+pub mod input;
+pub mod klog;
+pub mod layout;
 pub mod level;
+pub mod locale;
+#[cfg(feature = "net")]
+pub mod net;
+pub mod notify;
+pub mod parallel;
+#[cfg(feature = "parquet")]
+pub mod parquet_export;
 pub mod parser;
+pub mod pipeline;
+pub mod pretty;
+pub mod provenance;
+pub mod reader;
+pub mod reassembly;
+pub mod record;
+pub mod record_ref;
+pub mod rules;
+#[cfg(feature = "async")]
+pub mod runtime;
+pub mod sample;
+pub mod seen;
+pub mod shutdown;
+pub mod sink;
+pub mod sort;
+pub mod sparkline;
+pub mod split;
+pub mod stats;
+pub mod syslog;
+pub mod table;
+pub mod theme;
+pub mod throttle;
 pub mod timestamp;
+#[cfg(feature = "tls")]
+pub mod tls;
 
 // Re-export primary API types for convenience.
-pub use config::Config;
+pub use config::{Config, ConfigBuilder};
 pub use error::CorError;
-pub use formatter::{format_line, format_line_parsed};
+pub use extras::Extras;
+pub use formatter::{
+    FormatState, format_admitted, format_layout, format_line, format_line_parsed,
+    format_line_with_outcome,
+};
+pub use layout::Layout;
 pub use level::Level;
 pub use parser::{LineKind, LogRecord, parse_line, sanitize_json_newlines, un_double_escape_json};
+pub use reader::LogReader;
+pub use record_ref::{LogRecordRef, parse_record_ref};
 pub use timestamp::Timestamp;
+
+// The library types documented as shareable across threads.
+const _: () = {
+    const fn send_sync<T: Send + Sync>() {}
+    send_sync::<Config>();
+    send_sync::<ConfigBuilder>();
+    send_sync::<FormatState>();
+    send_sync::<Layout>();
+    send_sync::<LineKind>();
+    send_sync::<LogRecord>();
+    send_sync::<LogRecordRef<'static>>();
+    send_sync::<LogReader<'static, &'static [u8]>>();
+};
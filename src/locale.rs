@@ -0,0 +1,291 @@
+//! Localized day and month names for timestamp display.
+//!
+//! jiff's strftime only renders English names. When a non-English
+//! [`Locale`] is selected, the name directives (`%a`, `%A`, `%b`, `%h`,
+//! `%B`) are substituted with names from the tables below before the
+//! pattern is handed to jiff. The AM/PM marker is not localized.
+
+use clap::ValueEnum;
+
+/// Language used for day and month names in timestamps.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Locale {
+    /// English (jiff's built-in names).
+    #[default]
+    En,
+    /// German.
+    De,
+    /// French.
+    Fr,
+    /// Spanish.
+    Es,
+    /// Italian.
+    It,
+    /// Portuguese.
+    Pt,
+    /// Dutch.
+    Nl,
+}
+
+impl Locale {
+    /// Parse a locale name such as `de` or `de_DE.UTF-8` (language part only).
+    pub fn from_str_loose(s: &str) -> Option<Self> {
+        let lang = s
+            .split(['_', '-', '.'])
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+        Self::from_str(&lang, true).ok()
+    }
+
+    /// Full weekday name, Monday = 0.
+    pub const fn weekday(self, index: usize) -> &'static str {
+        self.tables().0[index]
+    }
+
+    /// Abbreviated weekday name, Monday = 0.
+    pub const fn weekday_abbrev(self, index: usize) -> &'static str {
+        self.tables().1[index]
+    }
+
+    /// Full month name, January = 0.
+    pub const fn month(self, index: usize) -> &'static str {
+        self.tables().2[index]
+    }
+
+    /// Abbreviated month name, January = 0.
+    pub const fn month_abbrev(self, index: usize) -> &'static str {
+        self.tables().3[index]
+    }
+
+    #[allow(clippy::type_complexity)] // four parallel name tables
+    const fn tables(
+        self,
+    ) -> (
+        &'static [&'static str; 7],
+        &'static [&'static str; 7],
+        &'static [&'static str; 12],
+        &'static [&'static str; 12],
+    ) {
+        match self {
+            Self::En => (&EN_DAYS, &EN_DAYS_ABBR, &EN_MONTHS, &EN_MONTHS_ABBR),
+            Self::De => (&DE_DAYS, &DE_DAYS_ABBR, &DE_MONTHS, &DE_MONTHS_ABBR),
+            Self::Fr => (&FR_DAYS, &FR_DAYS_ABBR, &FR_MONTHS, &FR_MONTHS_ABBR),
+            Self::Es => (&ES_DAYS, &ES_DAYS_ABBR, &ES_MONTHS, &ES_MONTHS_ABBR),
+            Self::It => (&IT_DAYS, &IT_DAYS_ABBR, &IT_MONTHS, &IT_MONTHS_ABBR),
+            Self::Pt => (&PT_DAYS, &PT_DAYS_ABBR, &PT_MONTHS, &PT_MONTHS_ABBR),
+            Self::Nl => (&NL_DAYS, &NL_DAYS_ABBR, &NL_MONTHS, &NL_MONTHS_ABBR),
+        }
+    }
+}
+
+const EN_DAYS: [&str; 7] = [
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+];
+const EN_DAYS_ABBR: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+const EN_MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+const EN_MONTHS_ABBR: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+const DE_DAYS: [&str; 7] = [
+    "Montag",
+    "Dienstag",
+    "Mittwoch",
+    "Donnerstag",
+    "Freitag",
+    "Samstag",
+    "Sonntag",
+];
+const DE_DAYS_ABBR: [&str; 7] = ["Mo", "Di", "Mi", "Do", "Fr", "Sa", "So"];
+const DE_MONTHS: [&str; 12] = [
+    "Januar",
+    "Februar",
+    "März",
+    "April",
+    "Mai",
+    "Juni",
+    "Juli",
+    "August",
+    "September",
+    "Oktober",
+    "November",
+    "Dezember",
+];
+const DE_MONTHS_ABBR: [&str; 12] = [
+    "Jan", "Feb", "Mär", "Apr", "Mai", "Jun", "Jul", "Aug", "Sep", "Okt", "Nov", "Dez",
+];
+
+const FR_DAYS: [&str; 7] = [
+    "lundi", "mardi", "mercredi", "jeudi", "vendredi", "samedi", "dimanche",
+];
+const FR_DAYS_ABBR: [&str; 7] = ["lun.", "mar.", "mer.", "jeu.", "ven.", "sam.", "dim."];
+const FR_MONTHS: [&str; 12] = [
+    "janvier",
+    "février",
+    "mars",
+    "avril",
+    "mai",
+    "juin",
+    "juillet",
+    "août",
+    "septembre",
+    "octobre",
+    "novembre",
+    "décembre",
+];
+const FR_MONTHS_ABBR: [&str; 12] = [
+    "janv.", "févr.", "mars", "avr.", "mai", "juin", "juil.", "août", "sept.", "oct.", "nov.",
+    "déc.",
+];
+
+const ES_DAYS: [&str; 7] = [
+    "lunes",
+    "martes",
+    "miércoles",
+    "jueves",
+    "viernes",
+    "sábado",
+    "domingo",
+];
+const ES_DAYS_ABBR: [&str; 7] = ["lun", "mar", "mié", "jue", "vie", "sáb", "dom"];
+const ES_MONTHS: [&str; 12] = [
+    "enero",
+    "febrero",
+    "marzo",
+    "abril",
+    "mayo",
+    "junio",
+    "julio",
+    "agosto",
+    "septiembre",
+    "octubre",
+    "noviembre",
+    "diciembre",
+];
+const ES_MONTHS_ABBR: [&str; 12] = [
+    "ene", "feb", "mar", "abr", "may", "jun", "jul", "ago", "sept", "oct", "nov", "dic",
+];
+
+const IT_DAYS: [&str; 7] = [
+    "lunedì",
+    "martedì",
+    "mercoledì",
+    "giovedì",
+    "venerdì",
+    "sabato",
+    "domenica",
+];
+const IT_DAYS_ABBR: [&str; 7] = ["lun", "mar", "mer", "gio", "ven", "sab", "dom"];
+const IT_MONTHS: [&str; 12] = [
+    "gennaio",
+    "febbraio",
+    "marzo",
+    "aprile",
+    "maggio",
+    "giugno",
+    "luglio",
+    "agosto",
+    "settembre",
+    "ottobre",
+    "novembre",
+    "dicembre",
+];
+const IT_MONTHS_ABBR: [&str; 12] = [
+    "gen", "feb", "mar", "apr", "mag", "giu", "lug", "ago", "set", "ott", "nov", "dic",
+];
+
+const PT_DAYS: [&str; 7] = [
+    "segunda-feira",
+    "terça-feira",
+    "quarta-feira",
+    "quinta-feira",
+    "sexta-feira",
+    "sábado",
+    "domingo",
+];
+const PT_DAYS_ABBR: [&str; 7] = ["seg", "ter", "qua", "qui", "sex", "sáb", "dom"];
+const PT_MONTHS: [&str; 12] = [
+    "janeiro",
+    "fevereiro",
+    "março",
+    "abril",
+    "maio",
+    "junho",
+    "julho",
+    "agosto",
+    "setembro",
+    "outubro",
+    "novembro",
+    "dezembro",
+];
+const PT_MONTHS_ABBR: [&str; 12] = [
+    "jan", "fev", "mar", "abr", "mai", "jun", "jul", "ago", "set", "out", "nov", "dez",
+];
+
+const NL_DAYS: [&str; 7] = [
+    "maandag",
+    "dinsdag",
+    "woensdag",
+    "donderdag",
+    "vrijdag",
+    "zaterdag",
+    "zondag",
+];
+const NL_DAYS_ABBR: [&str; 7] = ["ma", "di", "wo", "do", "vr", "za", "zo"];
+const NL_MONTHS: [&str; 12] = [
+    "januari",
+    "februari",
+    "maart",
+    "april",
+    "mei",
+    "juni",
+    "juli",
+    "augustus",
+    "september",
+    "oktober",
+    "november",
+    "december",
+];
+const NL_MONTHS_ABBR: [&str; 12] = [
+    "jan", "feb", "mrt", "apr", "mei", "jun", "jul", "aug", "sep", "okt", "nov", "dec",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_str_loose() {
+        assert_eq!(Locale::from_str_loose("de"), Some(Locale::De));
+        assert_eq!(Locale::from_str_loose("fr_FR.UTF-8"), Some(Locale::Fr));
+        assert_eq!(Locale::from_str_loose("pt-BR"), Some(Locale::Pt));
+        assert_eq!(Locale::from_str_loose("xx"), None);
+    }
+
+    #[test]
+    fn test_names() {
+        assert_eq!(Locale::De.month(2), "März");
+        assert_eq!(Locale::Fr.weekday_abbrev(0), "lun.");
+        assert_eq!(Locale::En.weekday(6), "Sunday");
+    }
+}
@@ -1,22 +1,77 @@
 use std::fs::File;
-use std::io::{self, BufRead, BufReader, LineWriter, Write};
-use std::path::Path;
+use std::io::{self, BufRead, BufReader, BufWriter, IsTerminal, LineWriter, Read, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 use std::process::ExitCode;
+use std::thread;
+use std::time::{Duration, Instant};
 
 use clap::Parser;
+use owo_colors::OwoColorize;
+use owo_colors::Stream::Stdout;
 
-use cor::cli::{Cli, ColorMode};
+use cor::annotate::{self, MarkFifo};
+use cor::background;
+use cor::bench::{BenchResult, generate_log_batch};
+use cor::bookmark::Bookmarks;
+#[cfg(feature = "net")]
+use cor::broadcast::Broadcaster;
+use cor::cli::{BufferMode, Cli, ColorMode, Command};
 use cor::config::Config;
-use cor::formatter::{format_line, format_line_parsed};
-use cor::parser::{self, LineKind};
+use cor::counters::{self, LiveCounts};
+use cor::dedup::Dedup;
+use cor::diag::{Diagnostics, Event, GaveUp};
+use cor::discover::FieldReport;
+use cor::exit::{self, Failure};
+use cor::explain;
+use cor::follow::{FollowLines, FollowReader, Followed};
+use cor::formatter::{FormatState, format_admitted};
+use cor::index::{Index, RangesReader};
+#[cfg(feature = "net")]
+use cor::ingest::{self, Acceptor, ListenAddr};
+#[cfg(feature = "mmap")]
+use cor::input::MmapLines;
+use cor::input::{self, IterLines, KeepReading, LineSource, Next, Position, ThreadedLines};
+use cor::layout::Layout;
+use cor::level::Level;
+#[cfg(feature = "net")]
+use cor::net::{Connector, NetLines};
+use cor::notify::{self, Notifier};
+use cor::parallel::ParallelLines;
+#[cfg(feature = "parquet")]
+use cor::parquet_export::ParquetSink;
+use cor::parser::{self, LineKind, LogRecord};
+use cor::provenance::{self, Provenance};
+use cor::reassembly::{Assembly, FailedShapeCache, might_start_json};
+use cor::record::{Recorder, ReplayLines};
+use cor::rules::Outcome;
+use cor::sample::Sampler;
+use cor::shutdown;
+use cor::sink::{FormattedRecord, OutputSink, Tee};
+use cor::sort;
+use cor::split::Splitter;
+use cor::stats::Stats;
+use cor::theme::{ColorDepth, Rgb};
+use cor::throttle::{self, Throttle};
 
-/// Maximum number of continuation lines to buffer when reassembling
-/// multi-line JSON (e.g., exception tracebacks with raw newlines).
-///
-/// This limit prevents unbounded memory growth when a line starts with `{"`
-/// but never forms valid JSON. 200 lines accommodates most real-world
-/// tracebacks while bounding worst-case memory to ~200KB (assuming 1KB/line).
-const MAX_JSON_CONTINUATION_LINES: usize = 200;
+/// Time an expression — or the rest of the enclosing block — as a
+/// `--debug-cor` span (`trace` feature). Compiles away without the feature.
+macro_rules! span {
+    ($name:literal, $body:expr) => {{
+        span!($name);
+        $body
+    }};
+    ($name:literal) => {
+        #[cfg(feature = "trace")]
+        let _span = tracing::debug_span!($name).entered();
+    };
+}
+
+/// How often idle inputs check for `--mark-fifo` annotations.
+const ANNOTATION_POLL: Duration = Duration::from_millis(200);
+
+/// Stdout buffer size for `--buffer-mode block`.
+const BLOCK_BUFFER: usize = 64 * 1024;
 
 /// Convert an I/O result to an optional exit code.
 ///
@@ -30,28 +85,11 @@ fn check_write_result(result: io::Result<()>, context: &str) -> Option<ExitCode>
         Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Some(ExitCode::SUCCESS),
         Err(e) => {
             eprintln!("cor: {context}: {e}");
-            Some(ExitCode::from(2))
+            Some(Failure::Io.exit_code())
         }
     }
 }
 
-/// Write a formatted line with line gap, returning early exit code on error.
-///
-/// Batches the entry and its trailing blank lines into a single `write!`
-/// call so `LineWriter` only flushes once per entry (on the final newline)
-/// rather than `1 + line_gap` times. Keeps streaming responsive without
-/// paying per-gap syscalls in batch mode.
-#[inline]
-fn write_entry(
-    writer: &mut LineWriter<io::StdoutLock<'_>>,
-    line_buf: &str,
-    line_gap: usize,
-) -> Option<ExitCode> {
-    // One '\n' to terminate the entry + `line_gap` blank-line newlines.
-    let trailing = "\n".repeat(1 + line_gap);
-    check_write_result(write!(writer, "{line_buf}{trailing}"), "write error")
-}
-
 fn main() -> ExitCode {
     // Reset SIGPIPE to default behavior so upstream writers get a clean
     // SIGPIPE signal instead of a BrokenPipeError when cor exits early.
@@ -60,182 +98,1690 @@ fn main() -> ExitCode {
     let cli = Cli::parse();
 
     // Handle --completions: generate and exit
+    #[cfg(feature = "completions")]
     if let Some(shell) = cli.completions {
         let mut cmd = <Cli as clap::CommandFactory>::command();
         clap_complete::generate(shell, &mut cmd, "cor", &mut io::stdout());
         return ExitCode::SUCCESS;
     }
 
-    let config = match Config::from_cli(&cli) {
+    if cli.capabilities {
+        println!("{:#}", cor::capabilities::capabilities());
+        return ExitCode::SUCCESS;
+    }
+
+    #[cfg(feature = "trace")]
+    if cli.debug_cor {
+        init_tracing();
+    }
+
+    if let Some(ref map) = cli.exit_code_map {
+        exit::remap(map.clone());
+    }
+
+    let mut config = match Config::from_cli(&cli) {
         Ok(config) => config,
         Err(e) => {
             eprintln!("cor: {e}");
-            return ExitCode::from(1);
+            return Failure::Config.exit_code();
         }
     };
 
     match config.color_mode {
         ColorMode::Always => owo_colors::set_override(true),
         ColorMode::Never => owo_colors::set_override(false),
+        // Split files are never terminals, so don't color them just because stdout is one.
+        ColorMode::Auto if config.split_by.is_some() => owo_colors::set_override(false),
         ColorMode::Auto => {} // owo-colors auto-detects via supports-color
     }
+    if let Some(background) = detect_background(&cli, &config) {
+        config.theme = config.theme.with_background(background);
+    }
+    // Last, as contrast fixes may add truecolor colors.
+    if !cli.stable {
+        let depth = ColorDepth::detect(|name| std::env::var(name).ok());
+        config.theme = config.theme.with_depth(depth);
+    }
+
+    if let Some(Command::Bench(ref args)) = cli.command {
+        return run_bench(&cli, &config, args.lines);
+    }
+    if let Some(Command::Index(ref args)) = cli.command {
+        return run_index(&cli, &config, &args.file);
+    }
+
+    let replay = match open_replay(&cli) {
+        Ok(replay) => replay,
+        Err(code) => return code,
+    };
+    let show_labels = cli.with_filename
+        || cli.has_connections()
+        || replay.as_ref().is_some_and(ReplayLines::has_labels);
 
     let stdout = io::stdout();
-    // LineWriter flushes on every newline so streaming inputs (e.g.
-    // `kubectl logs -f`) print immediately instead of waiting for EOF
-    // or for a block buffer to fill. See issue #3.
-    //
-    // Use an 8 KiB capacity to match the previous `BufWriter::new` default
-    // so long formatted lines (many fields, large values) still get
-    // coalesced into a single write before the trailing newline triggers
-    // the flush. `LineWriter::new` would default to 1 KiB.
-    let mut writer = LineWriter::with_capacity(8 * 1024, stdout.lock());
+    let writer = stdout_writer(config.buffer_mode, &stdout);
+    let mut session = match Session::new(&config, writer, &cli, show_labels) {
+        Ok(session) => session,
+        Err(code) => return code,
+    };
     let mut had_error = false;
 
-    if cli.files.is_empty() {
-        // No files: read from stdin (original behavior)
-        let stdin = io::stdin();
-        let exit = process_lines(stdin.lock().lines(), &config, &mut writer);
-        if let Some(code) = exit {
-            return code;
+    if let Some(code) = session.write_header() {
+        return code;
+    }
+    if let Some(code) = session.process_inputs(&cli, replay, &mut had_error) {
+        return code;
+    }
+
+    if let Some(code) = session.finish() {
+        return code;
+    }
+
+    if let Some(ref at) = session.failed_at {
+        eprintln!("cor: fail-on: matched at {at}");
+        return Failure::FailOnLevel.exit_code();
+    }
+
+    if let Some(signal) = shutdown::signal() {
+        return ExitCode::from(u8::try_from(128 + signal).unwrap_or(1));
+    }
+
+    if session.timed_out {
+        if let Some(timeout) = config.input_timeout {
+            eprintln!("cor: no input received for {timeout:?}");
         }
+        return ExitCode::from(config.timeout_exit_code);
+    }
+
+    let malformed = &session.malformed;
+    if malformed.count > 0 {
+        let more = if malformed.count > malformed.first.len() {
+            ", ..."
+        } else {
+            ""
+        };
+        eprintln!(
+            "cor: strict: {} malformed JSON line(s) at {}{more}",
+            malformed.count,
+            malformed.first.join(", ")
+        );
+        return Failure::Strict.exit_code();
+    }
+
+    if had_error {
+        Failure::Io.exit_code()
     } else {
-        for path in &cli.files {
+        ExitCode::SUCCESS
+    }
+}
+
+/// Print `--debug-cor` spans to stderr with their timings as they close.
+#[cfg(feature = "trace")]
+fn init_tracing() {
+    use tracing_subscriber::fmt::format::FmtSpan;
+
+    tracing_subscriber::fmt()
+        .with_writer(io::stderr)
+        .with_max_level(tracing::Level::DEBUG)
+        .with_span_events(FmtSpan::CLOSE)
+        .with_target(false)
+        .init();
+}
+
+/// Report a file that could not be opened; setup failures exit with the
+/// config code.
+fn open_error(path: &Path, e: &io::Error) -> ExitCode {
+    eprintln!("cor: {}: {e}", path.display());
+    Failure::Config.exit_code()
+}
+
+/// Detect the terminal background when colored output goes to it, so the
+/// theme's light or dark variant is used and colors too close to it are
+/// adjusted. Stable output, an explicit `--theme`, and `NO_COLOR` skip the
+/// query; `--no-detect-background` leaves only `COLORFGBG`.
+fn detect_background(cli: &Cli, config: &Config) -> Option<Rgb> {
+    let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+    let colored = match config.color_mode {
+        ColorMode::Never => false,
+        ColorMode::Auto => config.split_by.is_none() && !no_color,
+        ColorMode::Always => true,
+    };
+    if !colored || cli.stable || !io::stdout().is_terminal() {
+        return None;
+    }
+    // An explicitly chosen palette is used as is.
+    if cli.theme.is_some() {
+        return None;
+    }
+    if !config.detect_background {
+        return background::from_colorfgbg();
+    }
+    background::detect(background::QUERY_TIMEOUT)
+}
+
+/// Bind the `--serve-ws` broadcaster and announce its address.
+#[cfg(feature = "net")]
+fn bind_broadcaster(cli: &Cli) -> Result<Option<Broadcaster>, ExitCode> {
+    let Some(ref addr) = cli.serve_ws else {
+        return Ok(None);
+    };
+    let broadcaster = Broadcaster::bind(addr).map_err(|e| {
+        eprintln!("cor: {addr}: {e}");
+        Failure::Config.exit_code()
+    })?;
+    let addr = broadcaster.local_addr();
+    eprintln!("cor: broadcasting records on ws://{addr}/ and http://{addr}/ (SSE)");
+    Ok(Some(broadcaster))
+}
+
+/// Open the `--output` sinks.
+fn open_sinks(cli: &Cli, config: &Config) -> Result<Tee, ExitCode> {
+    let mut tee = Tee::default();
+    for spec in &cli.output {
+        let sink = spec.open(config.line_gap).map_err(|e| {
+            eprintln!("cor: {spec}: {e}");
+            Failure::Config.exit_code()
+        })?;
+        tee.push(sink);
+    }
+    #[cfg(feature = "parquet")]
+    if let Some(ref path) = cli.export_parquet {
+        let sink = ParquetSink::create(path, cli.parquet_schema.clone()).map_err(|e| {
+            eprintln!("cor: {}: {e}", path.display());
+            Failure::Config.exit_code()
+        })?;
+        tee.push(Box::new(sink));
+    }
+    Ok(tee)
+}
+
+/// Open the `--diag` stream: stderr, or the inherited `--diag-fd`.
+fn open_diagnostics(cli: &Cli) -> Result<Option<Diagnostics>, ExitCode> {
+    if cli.diag.is_none() {
+        return Ok(None);
+    }
+    #[cfg(unix)]
+    if let Some(fd) = cli.diag_fd {
+        return Diagnostics::from_fd(fd).map(Some).map_err(|e| {
+            eprintln!("cor: --diag-fd: {e}");
+            Failure::Config.exit_code()
+        });
+    }
+    Ok(Some(Diagnostics::stderr()))
+}
+
+/// Exit with a usage error if `subcommand` is combined with other inputs.
+fn reject_inputs(cli: &Cli, subcommand: &str) {
+    if !cli.files.is_empty() || cli.network_input() {
+        <Cli as clap::CommandFactory>::command()
+            .error(
+                clap::error::ErrorKind::ArgumentConflict,
+                format!("{subcommand} cannot be combined with input files, --listen or --connect"),
+            )
+            .exit();
+    }
+}
+
+/// Run `cor bench`: process `count` synthetic lines through the full
+/// pipeline, discarding the output, and report throughput.
+fn run_bench(cli: &Cli, config: &Config, count: usize) -> ExitCode {
+    reject_inputs(cli, "bench");
+    let lines = generate_log_batch(count);
+    let bytes = lines.iter().map(|line| line.len() + 1).sum();
+    let mut session = match Session::new(config, io::sink(), cli, false) {
+        Ok(session) => session,
+        Err(code) => return code,
+    };
+    let start = Instant::now();
+    let source = IterLines(lines.into_iter().map(Ok));
+    if let Some(code) = session
+        .process_lines(source, "bench")
+        .or_else(|| session.finish())
+    {
+        return code;
+    }
+    let result = BenchResult {
+        lines: count,
+        bytes,
+        elapsed: start.elapsed(),
+    };
+    println!("cor bench: {result}");
+    ExitCode::SUCCESS
+}
+
+/// Run `cor index`: write the sidecar index of `path`.
+fn run_index(cli: &Cli, config: &Config, path: &Path) -> ExitCode {
+    reject_inputs(cli, "index");
+    let written = Index::build(path, config).and_then(|index| {
+        let sidecar = index.write(path)?;
+        Ok((index.blocks.len(), sidecar))
+    });
+    match written {
+        Ok((blocks, sidecar)) => {
+            println!("cor index: {blocks} blocks → {}", sidecar.display());
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("cor: {}: {e}", path.display());
+            Failure::Config.exit_code()
+        }
+    }
+}
+
+/// Stdout writer buffered according to `--buffer-mode`.
+fn stdout_writer(mode: BufferMode, stdout: &io::Stdout) -> Box<dyn Write + '_> {
+    match mode {
+        // LineWriter flushes on every newline so streaming inputs (e.g.
+        // `kubectl logs -f`) print immediately instead of waiting for EOF
+        // or for a block buffer to fill. See issue #3.
+        //
+        // Use an 8 KiB capacity to match the previous `BufWriter::new` default
+        // so long formatted lines (many fields, large values) still get
+        // coalesced into a single write before the trailing newline triggers
+        // the flush. `LineWriter::new` would default to 1 KiB.
+        BufferMode::Line => Box::new(LineWriter::with_capacity(8 * 1024, stdout.lock())),
+        BufferMode::Block => Box::new(BufWriter::with_capacity(BLOCK_BUFFER, stdout.lock())),
+        BufferMode::None => Box::new(Unbuffered(stdout.lock())),
+    }
+}
+
+/// Writer flushing after every write (`--buffer-mode none`).
+struct Unbuffered<W>(W);
+
+impl<W: Write> Write for Unbuffered<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.0.write(buf)?;
+        self.0.flush()?;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+/// Open the recording for `cor replay`, rejecting other inputs.
+fn open_replay(cli: &Cli) -> Result<Option<ReplayLines<BufReader<File>>>, ExitCode> {
+    let Some(Command::Replay(ref args)) = cli.command else {
+        return Ok(None);
+    };
+    reject_inputs(cli, "replay");
+    ReplayLines::open(&args.file, args.speed)
+        .map(Some)
+        .map_err(|e| {
+            eprintln!("cor: {}: {e}", args.file.display());
+            Failure::Config.exit_code()
+        })
+}
+
+/// Build the connector for `--connect`, loading TLS certificates when any
+/// endpoint uses `tls://`.
+#[cfg(feature = "net")]
+#[cfg_attr(not(feature = "tls"), allow(clippy::unnecessary_wraps))]
+fn connector(cli: &Cli) -> Result<Connector, String> {
+    #[cfg(feature = "tls")]
+    if cli.connect.iter().any(|endpoint| endpoint.tls) {
+        return cor::tls::client_config(&tls_files(cli)).map(Connector::with_tls);
+    }
+    #[cfg(not(feature = "tls"))]
+    let _ = cli;
+    Ok(Connector::default())
+}
+
+/// Build the acceptor for `--listen`, loading the server certificate when
+/// it uses `https://`.
+#[cfg(feature = "net")]
+#[cfg_attr(not(feature = "tls"), allow(clippy::unnecessary_wraps))]
+fn acceptor(cli: &Cli, listen: &ListenAddr) -> Result<Acceptor, String> {
+    #[cfg(feature = "tls")]
+    if listen.tls {
+        return cor::tls::server_config(&tls_files(cli)).map(Acceptor::with_tls);
+    }
+    #[cfg(not(feature = "tls"))]
+    let _ = (cli, listen);
+    Ok(Acceptor::default())
+}
+
+/// The `--tls-*` certificate files.
+#[cfg(feature = "tls")]
+fn tls_files(cli: &Cli) -> cor::tls::TlsFiles {
+    cor::tls::TlsFiles {
+        ca: cli.tls_ca.clone(),
+        cert: cli.tls_cert.clone(),
+        key: cli.tls_key.clone(),
+    }
+}
+
+/// Number of malformed line locations listed by `--strict`.
+const STRICT_REPORTED_LINES: usize = 5;
+
+/// Lines that looked like JSON but failed to parse, for `--strict`.
+#[derive(Debug, Default)]
+struct Malformed {
+    count: usize,
+    /// `source:line` of the first few offenders.
+    first: Vec<String>,
+}
+
+impl Malformed {
+    fn add(&mut self, source: &str, line: usize) {
+        self.count += 1;
+        if self.first.len() < STRICT_REPORTED_LINES {
+            self.first.push(format!("{source}:{line}"));
+        }
+    }
+}
+
+/// A record being reassembled from the continuation lines of one input.
+struct Pending {
+    assembly: Assembly,
+    /// Position of each buffered line.
+    positions: Vec<Position>,
+    /// Continuation lines read so far.
+    lines: usize,
+    /// When reassembly gives up however lines arrive (`continuation_timeout`).
+    cutoff: Option<Instant>,
+    /// When it gives up unless another line arrives (`continuation_idle`).
+    idle: Option<Instant>,
+}
+
+impl Pending {
+    fn new(line: String, pos: Position, config: &Config) -> Self {
+        let now = Instant::now();
+        Self {
+            assembly: Assembly::new(line),
+            positions: vec![pos],
+            lines: 0,
+            cutoff: config.continuation_timeout.map(|t| now + t),
+            idle: config.continuation_idle.map(|t| now + t),
+        }
+    }
+
+    /// When reassembly gives up if no line completes it first.
+    fn deadline(&self) -> Option<Instant> {
+        self.cutoff.into_iter().chain(self.idle).min()
+    }
+}
+
+/// Reading state of one input of a line source. Merged sources (e.g.
+/// `--connect` with several endpoints) have one per label, so their lines
+/// are numbered and reassembled separately.
+struct Input {
+    /// The source's label for the input, or the input name.
+    name: String,
+    /// Position of the next line to be read.
+    next_pos: Position,
+    /// Record being reassembled from this input's lines.
+    pending: Option<Pending>,
+}
+
+/// Reading state of a line source.
+struct InputState {
+    /// Inputs seen so far, in order of their first line.
+    inputs: Vec<Input>,
+    /// When the last line arrived. `--timeout` counts from here, including
+    /// continuation lines.
+    last_input: Instant,
+    /// Line shapes that recently failed to reassemble.
+    failed_shapes: FailedShapeCache,
+}
+
+impl InputState {
+    fn new() -> Self {
+        Self {
+            inputs: Vec::new(),
+            last_input: Instant::now(),
+            failed_shapes: FailedShapeCache::new(),
+        }
+    }
+
+    /// Index of the input named `name`, added on its first line.
+    fn input(&mut self, name: &str) -> usize {
+        self.inputs
+            .iter()
+            .position(|input| input.name == name)
+            .unwrap_or_else(|| {
+                self.inputs.push(Input {
+                    name: name.to_string(),
+                    next_pos: Position::start(),
+                    pending: None,
+                });
+                self.inputs.len() - 1
+            })
+    }
+
+    /// When the first pending reassembly gives up.
+    fn reassembly_deadline(&self) -> Option<Instant> {
+        self.inputs
+            .iter()
+            .filter_map(|input| input.pending.as_ref()?.deadline())
+            .min()
+    }
+}
+
+/// Per-run processing state shared by every input.
+#[allow(clippy::struct_excessive_bools)]
+struct Session<'a, W: Write> {
+    config: &'a Config,
+    writer: W,
+    /// Reusable layout of the formatted entry.
+    layout: Layout,
+    /// Layout and outcome of the next entry, formatted ahead by `--jobs`.
+    formatted: Option<(Layout, Outcome)>,
+    /// Reusable buffer for the formatted entry.
+    line_buf: String,
+    /// Bookmarks for `--mark-on`.
+    bookmarks: Option<Bookmarks>,
+    /// Per-value output files for `--split-by`.
+    splitter: Option<Splitter>,
+    /// Live JSON feed for `--serve-ws`.
+    #[cfg(feature = "net")]
+    broadcaster: Option<Broadcaster>,
+    /// Additional outputs from `--output`.
+    sinks: Tee,
+    /// Raw input capture for `--record`.
+    recorder: Option<Recorder>,
+    /// Annotations arriving on `--mark-fifo`.
+    annotations: Option<MarkFifo>,
+    /// Whether `##cor:mark` control lines in the input are honored; off
+    /// for network inputs.
+    input_marks: bool,
+    /// Fields collected for `--discover-fields`, replacing normal output.
+    field_report: Option<FieldReport>,
+    /// Machine-readable event stream for `--diag`.
+    diag: Option<Diagnostics>,
+    /// Malformed JSON lines seen, for `--strict`.
+    malformed: Malformed,
+    /// Set when `--timeout` expired; stops processing further inputs.
+    timed_out: bool,
+    /// Where a `--fail-on` match was seen; stops processing further input.
+    failed_at: Option<String>,
+    /// Prefix entries with the name of their input (files, connections).
+    show_labels: bool,
+    /// Formatter state spanning records (e.g. the `--elapsed` origin).
+    format_state: FormatState,
+    /// Keys seen by `--first-only`.
+    dedup: Option<Dedup>,
+    /// Random record selection for `--sample`.
+    sampler: Option<Sampler>,
+    /// Cap on entries written per window (`--throttle`).
+    throttle: Option<Throttle>,
+    /// Read regular files through a memory mapping (`--mmap`).
+    #[cfg(feature = "mmap")]
+    mmap: bool,
+    /// Worker threads parsing offline input (`--jobs`).
+    jobs: usize,
+    /// Whether an input that `--jobs` does not apply to was reported.
+    jobs_ignored: bool,
+    /// Process each input in timestamp order (`--sort`).
+    sort: bool,
+    /// Running level counts for `--live-counts`.
+    live_counts: Option<LiveCounts>,
+    /// Whether the current input is waited on past end of input: the last
+    /// one with `--exit-on-eof false`.
+    waits_at_eof: bool,
+    /// Lowest level ringing the bell (`--bell-on`).
+    bell_on: Option<Level>,
+    /// Whether stderr is a terminal, the only place a bell is rung.
+    bell_tty: bool,
+    /// Desktop notifications for `--notify-on`.
+    notifier: Option<Notifier>,
+    /// Input counts for `--stats`.
+    stats: Option<Stats>,
+    /// Entries still to be described by `--explain`.
+    explain: usize,
+    /// Stop reading and finish cleanly on SIGINT/SIGTERM.
+    watch_shutdown: bool,
+}
+
+impl<'a, W: Write> Session<'a, W> {
+    /// Set up the outputs and side channels selected by `config` and `cli`.
+    fn new(config: &'a Config, writer: W, cli: &Cli, show_labels: bool) -> Result<Self, ExitCode> {
+        let bookmarks = match config.mark_on.clone() {
+            Some(expr) => {
+                let side_file = config.mark_file.as_deref();
+                let path = side_file.unwrap_or_else(|| Path::new("-"));
+                Some(Bookmarks::new(expr, side_file).map_err(|e| open_error(path, &e))?)
+            }
+            None => None,
+        };
+        let splitter = match config.split_by {
+            Some(ref field) => Some(
+                Splitter::new(field, &config.split_dir)
+                    .map_err(|e| open_error(&config.split_dir, &e))?,
+            ),
+            None => None,
+        };
+        let annotations = match cli.mark_fifo {
+            Some(ref path) => Some(MarkFifo::spawn(path).map_err(|e| open_error(path, &e))?),
+            None => None,
+        };
+        let recorder = match cli.record {
+            Some(ref path) => {
+                Some(Recorder::create(path, show_labels).map_err(|e| open_error(path, &e))?)
+            }
+            None => None,
+        };
+        let mut session = Self {
+            config,
+            writer,
+            layout: Layout::default(),
+            formatted: None,
+            line_buf: String::new(),
+            bookmarks,
+            splitter,
+            #[cfg(feature = "net")]
+            broadcaster: bind_broadcaster(cli)?,
+            sinks: open_sinks(cli, config)?,
+            recorder,
+            annotations,
+            input_marks: true,
+            field_report: cli
+                .discover_fields
+                .then(|| FieldReport::new(cli.examples.unwrap_or(0), config.glyphs())),
+            diag: open_diagnostics(cli)?,
+            malformed: Malformed::default(),
+            timed_out: false,
+            failed_at: None,
+            show_labels,
+            format_state: FormatState::default(),
+            dedup: config.first_only.clone().map(Dedup::new),
+            sampler: config.sample.map(|rate| {
+                // `--stable` output is reproducible, so is its sample.
+                if config.stable {
+                    Sampler::new(rate, config.sample_level, 0)
+                } else {
+                    Sampler::random(rate, config.sample_level)
+                }
+            }),
+            throttle: config.throttle.map(Throttle::new),
+            #[cfg(feature = "mmap")]
+            mmap: cli.mmap,
+            jobs: match cli.jobs {
+                0 => thread::available_parallelism().map_or(1, usize::from),
+                jobs => jobs,
+            },
+            jobs_ignored: false,
+            sort: cli.sort,
+            live_counts: cli
+                .live_counts
+                .filter(|_| io::stderr().is_terminal())
+                .map(LiveCounts::new),
+            waits_at_eof: !config.exit_on_eof,
+            bell_on: cli.bell_on.as_deref().and_then(Level::from_str_loose),
+            bell_tty: io::stderr().is_terminal(),
+            notifier: cli
+                .notify_on
+                .as_deref()
+                .and_then(Level::from_str_loose)
+                .map(Notifier::new),
+            stats: cli.stats.then(Stats::default),
+            explain: cli.explain.unwrap_or(0),
+            watch_shutdown: false,
+        };
+        // Long-running inputs and buffered outputs shut down cleanly on
+        // SIGINT/SIGTERM; otherwise the default handlers are kept.
+        session.watch_shutdown =
+            session.holds_output() || cli.follow || cli.network_input() || !config.exit_on_eof;
+        if session.watch_shutdown
+            && let Err(e) = shutdown::install()
+        {
+            eprintln!("cor: cannot install signal handlers: {e}");
+            session.watch_shutdown = false;
+        }
+        Ok(session)
+    }
+
+    /// Whether anything besides stdout holds output or summaries that a
+    /// signal would lose.
+    fn holds_output(&self) -> bool {
+        !self.sinks.is_empty()
+            || self.splitter.is_some()
+            || self.broadcasting()
+            || self.recorder.is_some()
+            || self.bookmarks.is_some()
+            || self.field_report.is_some()
+            || self.diag.is_some()
+            || self.dedup.is_some()
+            || self.throttle.is_some()
+            || self.stats.is_some()
+    }
+
+    /// Whether records go to `--serve-ws` clients.
+    #[cfg_attr(not(feature = "net"), allow(clippy::unused_self))]
+    const fn broadcasting(&self) -> bool {
+        #[cfg(feature = "net")]
+        return self.broadcaster.is_some();
+        #[cfg(not(feature = "net"))]
+        false
+    }
+
+    /// Process the input selected on the command line: a replay, network
+    /// inputs, files, or stdin.
+    fn process_inputs(
+        &mut self,
+        cli: &Cli,
+        replay: Option<ReplayLines<BufReader<File>>>,
+        had_error: &mut bool,
+    ) -> Option<ExitCode> {
+        if let (Some(replay), Some(Command::Replay(args))) = (replay, &cli.command) {
+            self.ignore_jobs("replay");
+            return self.process_lines(replay, &args.file.display().to_string());
+        }
+        #[cfg(feature = "net")]
+        if cli.network_input() {
+            return self.process_network(cli);
+        }
+        if cli.files.is_empty() {
+            // No files: read from stdin (original behavior)
+            self.process_stdin()
+        } else if cli.follow {
+            self.process_follow(&cli.files, had_error)
+        } else {
+            self.process_files(&cli.files, had_error)
+        }
+    }
+
+    /// Process `--listen` or `--connect` input. Setup failures exit with 1.
+    #[cfg(feature = "net")]
+    fn process_network(&mut self, cli: &Cli) -> Option<ExitCode> {
+        // Remote clients must not inject banners.
+        self.input_marks = false;
+        self.ignore_jobs("network input");
+        if let Some(ref listen) = cli.listen {
+            let served = acceptor(cli, listen).and_then(|acceptor| {
+                ingest::serve(listen, &acceptor).map_err(|e| format!("{}: {e}", listen.addr))
+            });
+            return match served {
+                Ok((addr, source)) => {
+                    let scheme = if listen.tls { "https" } else { "http" };
+                    eprintln!("cor: listening on {scheme}://{addr}{}", listen.path);
+                    self.process_lines(source, "-")
+                }
+                Err(e) => {
+                    eprintln!("cor: {e}");
+                    Some(Failure::Config.exit_code())
+                }
+            };
+        }
+        let source = connector(cli).and_then(|connector| {
+            NetLines::spawn(&cli.connect, &connector).map_err(|e| e.to_string())
+        });
+        match source {
+            Ok(source) => self.process_lines(source, "-"),
+            Err(e) => {
+                eprintln!("cor: {e}");
+                Some(Failure::Config.exit_code())
+            }
+        }
+    }
+
+    /// Process file arguments in order (`-` for stdin), stopping early on
+    /// `--timeout`. Files that cannot be opened are reported and set
+    /// `had_error`.
+    fn process_files(&mut self, paths: &[PathBuf], had_error: &mut bool) -> Option<ExitCode> {
+        for (index, path) in paths.iter().enumerate() {
+            // `--exit-on-eof false` waits on the last input only; earlier
+            // ones would otherwise keep the rest from ever being read.
+            self.waits_at_eof = !self.config.exit_on_eof && index + 1 == paths.len();
             let exit = if path == Path::new("-") {
-                let stdin = io::stdin();
-                process_lines(stdin.lock().lines(), &config, &mut writer)
+                self.process_stdin()
             } else {
                 match File::open(path) {
-                    Ok(file) => {
-                        let reader = BufReader::new(file);
-                        process_lines(reader.lines(), &config, &mut writer)
-                    }
+                    Ok(file) => self.process_file(file, path),
                     Err(e) => {
                         eprintln!("cor: {}: {e}", path.display());
-                        had_error = true;
+                        *had_error = true;
                         continue;
                     }
                 }
             };
-            if let Some(code) = exit {
-                return code;
+            if exit.is_some()
+                || self.timed_out
+                || self.failed_at.is_some()
+                || shutdown::signal().is_some()
+            {
+                return exit;
             }
         }
+        None
     }
 
-    if let Some(code) = check_write_result(writer.flush(), "flush error") {
-        return code;
+    /// Process an opened file argument: only the blocks a `cor index`
+    /// allows, from a memory mapping with `--mmap`, or read in full.
+    fn process_file(&mut self, file: File, path: &Path) -> Option<ExitCode> {
+        let name = path.display().to_string();
+        if self.sort {
+            return self.process_sorted(file, &name);
+        }
+        if let Some(ranges) = self.indexed_ranges(path) {
+            return self.process_reader(RangesReader::new(file, ranges), &name, false);
+        }
+        // Non-regular files (FIFOs, /proc) cannot be mapped and are read.
+        #[cfg(feature = "mmap")]
+        if self.mmap
+            && !self.waits_at_eof
+            && !self.needs_deadline()
+            && let Ok(lines) = MmapLines::open(&file)
+        {
+            return self.process_lines(lines.with_level_filter(self.config), &name);
+        }
+        let idles = file.metadata().is_ok_and(|m| !m.is_file());
+        self.process_reader(file, &name, idles)
     }
 
-    if had_error {
-        ExitCode::from(1)
-    } else {
-        ExitCode::SUCCESS
+    /// Byte ranges of `path` that may pass the filters, from a current
+    /// `cor index`. `None` (read everything) without filters, without an
+    /// index, or when [`sees_every_line`](Self::sees_every_line).
+    fn indexed_ranges(&self, path: &Path) -> Option<Vec<Range<u64>>> {
+        let filtered = self.config.min_level.is_some() || !self.config.where_filter.is_empty();
+        if !filtered || self.sees_every_line() {
+            return None;
+        }
+        Index::load(path, self.config).map(|index| index.ranges(self.config))
     }
-}
 
-/// Process all input lines, handling single-line and multi-line JSON reassembly.
-///
-/// Returns `Some(ExitCode)` for early termination (errors / broken pipe),
-/// or `None` when all input has been processed normally.
-fn process_lines(
-    mut lines_iter: impl Iterator<Item = io::Result<String>>,
-    config: &Config,
-    writer: &mut LineWriter<io::StdoutLock<'_>>,
-) -> Option<ExitCode> {
-    let mut line_buf = String::new();
-
-    while let Some(line_result) = lines_iter.next() {
-        let line = match line_result {
-            Ok(l) => l,
-            Err(e) if e.kind() == io::ErrorKind::InvalidData => continue,
-            Err(e) => {
-                eprintln!("cor: read error: {e}");
-                return Some(ExitCode::from(2));
+    /// Whether lines failing the filters must still be read, so neither
+    /// index blocks nor `--mmap` lines may be skipped: for
+    /// `--dim-unmatched`, bookmarks, recordings, `--discover-fields`,
+    /// `--diag`, `--stats`, live counts and `--elapsed` (timed from the
+    /// first record, shown or not), or waiting past end of file. Line
+    /// numbers do not count skipped index blocks, so `--explain`,
+    /// `--provenance` and `--strict`, which report them, read everything
+    /// too.
+    const fn sees_every_line(&self) -> bool {
+        self.config.dim_unmatched
+            || self.config.elapsed.is_some()
+            || self.bookmarks.is_some()
+            || self.recorder.is_some()
+            || self.field_report.is_some()
+            || self.diag.is_some()
+            || self.stats.is_some()
+            || self.live_counts.is_some()
+            || self.explain > 0
+            || self.config.provenance
+            || self.config.strict
+            || !self.config.fail_on.is_empty()
+            || self.config.fail_on_grep.is_some()
+            || self.waits_at_eof
+    }
+
+    /// Follow file arguments as they grow (`--follow`), merging their lines.
+    /// Files that cannot be opened are reported and set `had_error`.
+    fn process_follow(&mut self, paths: &[PathBuf], had_error: &mut bool) -> Option<ExitCode> {
+        let mut inputs = Vec::new();
+        for path in paths {
+            let reader = if path == Path::new("-") {
+                Followed::Reader(Box::new(io::stdin()))
+            } else {
+                match FollowReader::open(path) {
+                    Ok(reader) => Followed::File(reader),
+                    Err(e) => {
+                        eprintln!("cor: {}: {e}", path.display());
+                        *had_error = true;
+                        continue;
+                    }
+                }
+            };
+            inputs.push((path.display().to_string(), reader));
+        }
+        if inputs.is_empty() {
+            return None;
+        }
+        self.ignore_jobs("--follow");
+        self.process_lines(FollowLines::spawn(inputs), "-")
+    }
+
+    /// Process stdin, reading on a background thread when a deadline
+    /// needs to be honored.
+    fn process_stdin(&mut self) -> Option<ExitCode> {
+        if self.sort {
+            return self.process_sorted(io::stdin().lock(), "-");
+        }
+        if !self.waits_at_eof && !self.needs_deadline() && !self.watch_shutdown && self.jobs == 1 {
+            self.process_lines(IterLines(input::lines(io::stdin().lock())), "-")
+        } else {
+            self.process_reader(io::stdin(), "-", true)
+        }
+    }
+
+    /// Read all of an input, then process its lines in timestamp order
+    /// (`--sort`). Reading stops at the first read error, which is
+    /// processed last.
+    fn process_sorted(&mut self, reader: impl Read, name: &str) -> Option<ExitCode> {
+        let mut lines = Vec::new();
+        for line in input::lines(BufReader::new(reader)) {
+            let failed = line
+                .as_ref()
+                .is_err_and(|e| e.kind() != io::ErrorKind::InvalidData);
+            lines.push(line);
+            if failed {
+                break;
             }
-        };
+        }
+        self.ignore_jobs("--sort");
+        let sorted = span!("sort", sort::sort_lines(lines, self.config));
+        self.process_lines(sorted, name)
+    }
 
-        // Try normal single-line parsing first.
-        let parsed = parser::parse_line(&line, config);
-
-        match parsed {
-            LineKind::Raw(_) if might_start_json(&line) => {
-                // The line contains '{' but failed to parse — may be split
-                // across multiple lines due to raw newlines in JSON strings.
-                let mut buffer = line;
-                let mut assembled = false;
-
-                for _ in 0..MAX_JSON_CONTINUATION_LINES {
-                    let next = match lines_iter.next() {
-                        Some(Ok(l)) => l,
-                        Some(Err(e)) if e.kind() == io::ErrorKind::InvalidData => continue,
-                        _ => break,
-                    };
-
-                    buffer.push('\n');
-                    buffer.push_str(&next);
-
-                    // Sanitize raw newlines inside JSON strings, then re-parse.
-                    let sanitized = parser::sanitize_json_newlines(&buffer);
-                    let re_parsed = parser::parse_line(&sanitized, config);
-
-                    if !matches!(re_parsed, LineKind::Raw(_)) {
-                        // Successfully assembled — format the sanitized version.
-                        line_buf.clear();
-                        format_line_parsed(re_parsed, &sanitized, config, &mut line_buf);
-                        assembled = true;
-                        break;
+    /// Whether inputs must be read with deadlines: for the configured
+    /// timeouts, or to show `--mark-fifo` annotations or `--live-counts`
+    /// while input is idle.
+    const fn needs_deadline(&self) -> bool {
+        self.config.needs_deadline() || self.annotations.is_some() || self.live_counts.is_some()
+    }
+
+    /// Process a reader, waiting past end of input for `--exit-on-eof false`.
+    /// A reader that `idles` (a pipe) may block without a line arriving, as
+    /// does any reader waiting at its end.
+    fn process_reader<R: Read + Send + 'static>(
+        &mut self,
+        reader: R,
+        name: &str,
+        idles: bool,
+    ) -> Option<ExitCode> {
+        if self.waits_at_eof {
+            self.process_buffered(BufReader::new(KeepReading(reader)), name, true)
+        } else {
+            self.process_buffered(BufReader::new(reader), name, idles)
+        }
+    }
+
+    /// Process a buffered reader, reading on a background thread when a
+    /// deadline needs to be honored, or parsing on `--jobs` workers.
+    ///
+    /// A pending shutdown is noticed between lines, which is enough for
+    /// files; a reader that `idles` is read on a thread so the loop can poll
+    /// for it while no line arrives.
+    fn process_buffered<R: BufRead + Send + 'static>(
+        &mut self,
+        reader: R,
+        name: &str,
+        idles: bool,
+    ) -> Option<ExitCode> {
+        if self.needs_deadline() || (idles && self.watch_shutdown) {
+            self.ignore_jobs("input read as it arrives");
+            self.process_lines(ThreadedLines::spawn(reader), name)
+        } else if self.jobs > 1 {
+            // Workers only format when the layout is used at all.
+            let format = self.field_report.is_none();
+            self.process_lines(
+                ParallelLines::spawn(input::lines(reader), self.config, self.jobs, format),
+                name,
+            )
+        } else {
+            self.process_lines(IterLines(input::lines(reader)), name)
+        }
+    }
+
+    /// Process all input lines, handling single-line and multi-line JSON reassembly.
+    ///
+    /// Returns `Some(ExitCode)` for early termination (errors / broken pipe),
+    /// or `None` when all input has been processed normally.
+    fn process_lines(&mut self, mut source: impl LineSource, name: &str) -> Option<ExitCode> {
+        let mut state = InputState::new();
+        let skip_filtered = !self.sees_every_line();
+
+        loop {
+            // A line dropped by the filters may still continue a record.
+            source.skip_filtered(skip_filtered && state.inputs.iter().all(|i| i.pending.is_none()));
+            let until = state.reassembly_deadline();
+            let next = self.next_input(&mut source, &mut state, name, until);
+            // Reordering sources (`--sort`) place each line themselves.
+            if let (Ok(Next::Line(_)), Some(pos)) = (&next, source.position()) {
+                let index = state.input(source.label().unwrap_or(name));
+                state.inputs[index].next_pos = pos;
+            }
+            let mut line = match next {
+                Err(exit) => return Some(exit),
+                Ok(Next::Line(Ok(l))) => l,
+                Ok(Next::Line(Err(e))) if e.kind() == io::ErrorKind::InvalidData => {
+                    let index = state.input(source.label().unwrap_or(name));
+                    let input = &mut state.inputs[index];
+                    self.dropped(&input.name, &mut input.next_pos, &e);
+                    continue;
+                }
+                Ok(Next::Line(Err(e))) => {
+                    if let exit @ Some(_) = self.give_up_reassembly(&mut state, GaveUp::Eof, None) {
+                        return exit;
+                    }
+                    eprintln!("cor: read error: {e}");
+                    return Some(Failure::Io.exit_code());
+                }
+                Ok(Next::TimedOut) => {
+                    let now = Instant::now();
+                    let idle = self
+                        .config
+                        .input_timeout
+                        .is_some_and(|t| now >= state.last_input + t);
+                    // Past `--timeout` every pending record gives up.
+                    let due = (!idle).then_some(now);
+                    let exit = self.give_up_reassembly(&mut state, GaveUp::Timeout, due);
+                    if exit.is_some() || self.failed_at.is_some() {
+                        return exit;
                     }
+                    if idle {
+                        self.timed_out = true;
+                        return None;
+                    }
+                    continue;
                 }
+                Ok(Next::Eof) => return self.give_up_reassembly(&mut state, GaveUp::Eof, None),
+            };
+            // Merged sources (e.g. `--connect`) name the input per line.
+            let index = state.input(source.label().unwrap_or(name));
+            let input = &mut state.inputs[index];
+            let pos = input.next_pos;
+            input.next_pos.advance(&line);
+            input::strip_cr(&mut line);
+            let prepared = source.take_prepared();
 
-                if !assembled {
-                    // Could not reassemble — output each buffered line as raw.
-                    for raw_line in buffer.split('\n') {
-                        line_buf.clear();
-                        format_line(raw_line, config, &mut line_buf);
-                        if !line_buf.is_empty()
-                            && let exit @ Some(_) = write_entry(writer, &line_buf, config.line_gap)
-                        {
-                            return exit;
+            let exit = if input.pending.is_some() {
+                self.continue_reassembly(&mut state, index, &line, pos)
+            } else {
+                // Try normal single-line parsing first, unless a `--jobs`
+                // worker already did.
+                let parsed = if let Some(prepared) = prepared {
+                    self.formatted = prepared.formatted;
+                    prepared.kind
+                } else {
+                    span!("parse", parser::parse_line(&line, self.config))
+                };
+                match parsed {
+                    LineKind::Raw(_)
+                        if might_start_json(&line)
+                            && !(self.config.reassembly_cache
+                                && state.failed_shapes.should_skip(&line)) =>
+                    {
+                        // The line contains '{' but failed to parse — may be
+                        // split across multiple lines due to raw newlines in
+                        // JSON strings.
+                        self.formatted = None;
+                        state.inputs[index].pending = Some(Pending::new(line, pos, self.config));
+                        if self.config.max_continuation_lines == 0 {
+                            self.end_reassembly(&mut state, index, None, GaveUp::Limit)
+                        } else {
+                            None
                         }
                     }
+                    _ => self.emit(&parsed, &line, &line, &state.inputs[index].name, pos),
+                }
+            };
+
+            if exit.is_some() || self.failed_at.is_some() {
+                return exit;
+            }
+        }
+    }
+
+    /// Add `line`, read at `pos`, to the record being reassembled for input
+    /// `index`, and emit it once it parses or a limit is hit.
+    fn continue_reassembly(
+        &mut self,
+        state: &mut InputState,
+        index: usize,
+        line: &str,
+        pos: Position,
+    ) -> Option<ExitCode> {
+        let config = self.config;
+        let pending = state.inputs[index].pending.as_mut()?;
+        pending.lines += 1;
+        pending.positions.push(pos);
+        // The idle window restarts with every continuation line.
+        pending.idle = config.continuation_idle.map(|t| Instant::now() + t);
+        let assembled = span!("parse", pending.assembly.push(line, config));
+        if assembled.is_none()
+            && !pending.assembly.is_full(config)
+            && pending.lines < config.max_continuation_lines
+        {
+            return None;
+        }
+        self.end_reassembly(state, index, assembled, GaveUp::Limit)
+    }
+
+    /// Give up the pending reassemblies due by `due`, or all of them
+    /// without, emitting their lines as raw.
+    fn give_up_reassembly(
+        &mut self,
+        state: &mut InputState,
+        reason: GaveUp,
+        due: Option<Instant>,
+    ) -> Option<ExitCode> {
+        for index in 0..state.inputs.len() {
+            let is_due = state.inputs[index]
+                .pending
+                .as_ref()
+                .is_some_and(|p| due.is_none_or(|due| p.deadline().is_some_and(|d| d <= due)));
+            if is_due && let exit @ Some(_) = self.end_reassembly(state, index, None, reason) {
+                return exit;
+            }
+        }
+        None
+    }
+
+    /// Finish the reassembly pending for input `index`: emit the record
+    /// when `assembled`, or else every buffered line as raw.
+    fn end_reassembly(
+        &mut self,
+        state: &mut InputState,
+        index: usize,
+        assembled: Option<(LineKind, String)>,
+        gave_up: GaveUp,
+    ) -> Option<ExitCode> {
+        let input = &mut state.inputs[index];
+        let Pending {
+            assembly,
+            positions,
+            ..
+        } = input.pending.take()?;
+        let pos = positions[0];
+        let name = input.name.as_str();
+
+        // Hitting a limit or a timeout says nothing about the line's shape,
+        // so only remember shapes whose buffer is malformed JSON.
+        if self.config.reassembly_cache {
+            if assembled.is_some() {
+                state.failed_shapes.remove(assembly.first_line());
+            } else if assembly.is_broken() {
+                state.failed_shapes.insert(assembly.first_line());
+            }
+        }
+
+        if let Some(ref mut diag) = self.diag {
+            let (source, line, lines) = (name, pos.line, assembly.text().split('\n').count());
+            if assembled.is_some() {
+                diag.counts.reassembled += 1;
+            }
+            diag.emit(&if assembled.is_some() {
+                Event::Reassembled {
+                    source,
+                    line,
+                    lines,
+                }
+            } else {
+                Event::ReassemblyFailed {
+                    source,
+                    line,
+                    lines,
+                    reason: gave_up,
+                }
+            });
+        }
+
+        if let Some((re_parsed, sanitized)) = assembled {
+            // Formatted from the sanitized text.
+            self.emit(&re_parsed, &sanitized, assembly.text(), name, pos)
+        } else {
+            // Could not reassemble — output each buffered line as raw.
+            self.emit_each_line(assembly.text(), name, &positions)
+        }
+    }
+
+    /// Wait for the next top-level input line until `--timeout` expires.
+    ///
+    /// Received lines are recorded for `--record`, and the source's notices
+    /// (a followed file rotated) are shown as warnings. Annotations — from
+    /// `--mark-fifo` or `##cor:mark` control lines — are printed here and
+    /// never returned.
+    ///
+    /// Returns [`Next::TimedOut`] once `--timeout` or `until` (the first
+    /// pending reassembly deadline) passes.
+    fn next_input(
+        &mut self,
+        source: &mut impl LineSource,
+        state: &mut InputState,
+        name: &str,
+        until: Option<Instant>,
+    ) -> Result<Next, ExitCode> {
+        loop {
+            if shutdown::signal().is_some() {
+                return Ok(Next::Eof);
+            }
+            if let Some(exit) = self.write_annotations() {
+                return Err(exit);
+            }
+            // A storm that stopped is reported once its window runs out.
+            if self
+                .throttle
+                .as_ref()
+                .and_then(Throttle::marker_due)
+                .is_some_and(|due| Instant::now() >= due)
+                && let Some(exit) = self
+                    .write_suppressed()
+                    .or_else(|| check_write_result(self.writer.flush(), "flush error"))
+            {
+                return Err(exit);
+            }
+            if let Some(text) = self
+                .live_counts
+                .as_mut()
+                .and_then(|c| c.redraw(Instant::now()))
+                && let Some(exit) =
+                    check_write_result(self.writer.flush(), "flush error").or_else(|| {
+                        check_write_result(io::stderr().write_all(text.as_bytes()), "write error")
+                    })
+            {
+                return Err(exit);
+            }
+            let input_deadline = self.config.input_timeout.map(|t| state.last_input + t);
+            let poll = self
+                .annotations
+                .is_some()
+                .then(|| Instant::now() + ANNOTATION_POLL);
+            let shutdown_poll = self.watch_shutdown.then(|| Instant::now() + shutdown::POLL);
+            let counts_poll = self
+                .live_counts
+                .is_some()
+                .then(|| Instant::now() + counters::INTERVAL);
+            let marker_due = self.throttle.as_ref().and_then(Throttle::marker_due);
+            let deadline = [
+                input_deadline,
+                until,
+                poll,
+                shutdown_poll,
+                counts_poll,
+                marker_due,
+            ]
+            .into_iter()
+            .flatten()
+            .min();
+            let next = span!("read", source.next_line(deadline));
+            while let Some(notice) = source.take_notice() {
+                self.warn(&notice);
+            }
+            let line = match next {
+                Next::TimedOut
+                    if [input_deadline, until]
+                        .into_iter()
+                        .flatten()
+                        .all(|d| Instant::now() < d) =>
+                {
                     continue;
                 }
+                Next::Line(Ok(line)) => line,
+                next => return Ok(next),
+            };
+            state.last_input = Instant::now();
+            if let Some(exit) = self.record(&line, source) {
+                return Err(exit);
+            }
+            let Some(text) = annotate::parse_mark(&line).filter(|_| self.input_marks) else {
+                return Ok(Next::Line(Ok(line)));
+            };
+            let index = state.input(source.label().unwrap_or(name));
+            state.inputs[index].next_pos.advance(&line);
+            if let Some(exit) = self.write_banner(text) {
+                return Err(exit);
+            }
+        }
+    }
+
+    /// Write banners for annotations received on `--mark-fifo` so far.
+    fn write_annotations(&mut self) -> Option<ExitCode> {
+        let pending: Vec<String> = self.annotations.as_ref()?.try_iter().collect();
+        for text in pending {
+            if let exit @ Some(_) = self.write_banner(&text) {
+                return exit;
+            }
+        }
+        None
+    }
+
+    /// Write an annotation banner stamped with the current time.
+    fn write_banner(&mut self, text: &str) -> Option<ExitCode> {
+        self.line_buf.clear();
+        annotate::format_banner(
+            text,
+            jiff::Timestamp::now(),
+            self.config,
+            &mut self.line_buf,
+        );
+        self.write_entry()
+    }
+
+    /// Parse and emit each line of `buffer` on its own, read at `positions`.
+    fn emit_each_line(
+        &mut self,
+        buffer: &str,
+        name: &str,
+        positions: &[Position],
+    ) -> Option<ExitCode> {
+        for (raw_line, &pos) in buffer.split('\n').zip(positions) {
+            let parsed = span!("parse", parser::parse_line(raw_line, self.config));
+            let exit = self.emit(&parsed, raw_line, raw_line, name, pos);
+            if exit.is_some() || self.failed_at.is_some() {
+                return exit;
+            }
+        }
+        None
+    }
+
+    /// Say once that `--jobs` does not apply to `what`, which is processed
+    /// on one thread.
+    fn ignore_jobs(&mut self, what: &str) {
+        if self.jobs > 1 && !self.jobs_ignored {
+            self.jobs_ignored = true;
+            self.warn(&format!(
+                "--jobs ignored for {what}, processed on one thread"
+            ));
+        }
+    }
+
+    /// Skip a line that could not be decoded (invalid UTF-8).
+    fn dropped(&mut self, source: &str, next_pos: &mut Position, error: &io::Error) {
+        if let Some(ref mut diag) = self.diag {
+            diag.counts.dropped += 1;
+            diag.emit(&Event::Dropped {
+                source,
+                line: next_pos.line,
+                reason: "invalid_utf8",
+            });
+        }
+        next_pos.skip(error);
+    }
+
+    /// Append a raw input line to the `--record` file.
+    fn record(&mut self, line: &str, source: &impl LineSource) -> Option<ExitCode> {
+        let recorder = self.recorder.as_mut()?;
+        check_write_result(recorder.record(line, source.label()), "record error")
+    }
+
+    /// Whether an entry matches `--fail-on` or `--fail-on-grep`.
+    fn fails_on(&self, parsed: &LineKind, original: &str) -> bool {
+        let record_fails = match parsed {
+            LineKind::Json(record) | LineKind::EmbeddedJson { record, .. } => {
+                self.config.fail_on.iter().any(|expr| expr.matches(record))
+            }
+            LineKind::Raw(_) => false,
+        };
+        record_fails
+            || self
+                .config
+                .fail_on_grep
+                .as_ref()
+                .is_some_and(|re| re.is_match(original))
+    }
+
+    /// Ring the bell and send a desktop notification for a shown entry, as
+    /// asked by a `bell` rule, `--bell-on`, or `--notify-on`.
+    fn alert(&mut self, parsed: &LineKind, bell: bool) -> Option<ExitCode> {
+        let record = match parsed {
+            LineKind::Json(record) | LineKind::EmbeddedJson { record, .. } => Some(record),
+            LineKind::Raw(_) => None,
+        };
+        let level = record.and_then(|r| r.level);
+        if let (Some(record), Some(level)) = (record, level)
+            && self
+                .notifier
+                .as_mut()
+                .is_some_and(|n| n.due(Some(level), Instant::now()))
+            && let Err(e) = notify::send(level, record.message.as_deref().unwrap_or_default())
+            && self.notifier.as_mut().is_some_and(Notifier::report_failure)
+        {
+            self.warn(&format!("desktop notification failed: {e}"));
+        }
+        // The bell goes to stderr so it never ends up in piped output, and
+        // only when that is a terminal that can ring it.
+        if (bell
+            || level
+                .zip(self.bell_on)
+                .is_some_and(|(level, min)| level >= min))
+            && self.bell_tty
+        {
+            return check_write_result(io::stderr().write_all(b"\x07"), "write error");
+        }
+        None
+    }
+
+    /// Report a problem that does not stop processing: as a `warning`
+    /// event with `--diag`, otherwise on stderr.
+    fn warn(&mut self, message: &str) {
+        match self.diag {
+            Some(ref mut diag) => diag.emit(&Event::Warning { message }),
+            None => eprintln!("cor: {message}"),
+        }
+    }
+
+    /// Bookmark, format, and write one parsed entry, applying rule actions.
+    ///
+    /// `raw_line` is what gets formatted (and passed through for raw lines);
+    /// `original` is the input text as read, which differs for reassembled
+    /// multi-line records.
+    fn emit(
+        &mut self,
+        parsed: &LineKind,
+        raw_line: &str,
+        original: &str,
+        source: &str,
+        pos: Position,
+    ) -> Option<ExitCode> {
+        let formatted = self.formatted.take();
+        self.count(parsed, raw_line, original, source, pos);
+        if self.failed_at.is_none() && self.fails_on(parsed, original) {
+            self.failed_at = Some(format!("{source}:{}", pos.line));
+        }
+        if let exit @ Some(_) = self.explain(parsed, raw_line, pos) {
+            return exit;
+        }
+        if let Some(ref mut report) = self.field_report {
+            if let LineKind::Json(record) | LineKind::EmbeddedJson { record, .. } = parsed {
+                report.observe(record);
+            }
+            return None;
+        }
+        let mut split_key = None;
+        let mut json = None;
+        let mut provenance = None;
+        if let LineKind::Json(record) | LineKind::EmbeddedJson { record, .. } = parsed {
+            provenance = self.provenance(raw_line, original, source, pos);
+            if let Some(ref mut bookmarks) = self.bookmarks {
+                let result = bookmarks.observe(record, source, pos.line, pos.offset, original);
+                if let exit @ Some(_) = check_write_result(result, "mark file error") {
+                    return exit;
+                }
+            }
+            split_key = self.splitter.as_ref().and_then(|s| s.key_for(record));
+            if self.broadcasting() || !self.sinks.is_empty() {
+                let mut normalized = record.to_normalized_json();
+                provenance::insert(&mut normalized, provenance.as_ref());
+                json = Some(normalized);
+            }
+        }
+
+        let outcome = self.lay_out(parsed, raw_line, formatted);
+        // Filtered-out lines produce an empty layout — skip them.
+        if self.layout.is_empty() {
+            if let Some(ref mut diag) = self.diag {
+                diag.counts.filtered += 1;
+            }
+            return None;
+        }
+        // Throttled entries are reported before the next one, wherever it goes.
+        if let exit @ Some(_) = self.write_suppressed() {
+            return exit;
+        }
+        span!("write");
+        self.line_buf.clear();
+        self.layout.write_ansi(&mut self.line_buf);
+        if self.config.flat_json {
+            provenance::append(&mut self.line_buf, provenance.as_ref());
+        }
+        // `--dim-unmatched` context is for the terminal only.
+        let json = json.filter(|_| !outcome.unmatched);
+        let split_key = split_key.filter(|_| !outcome.unmatched);
+        #[cfg(feature = "net")]
+        if let (Some(json), Some(broadcaster)) = (json.as_ref(), self.broadcaster.as_ref()) {
+            broadcaster.send(&json.to_string());
+        }
+        if !self.sinks.is_empty() && !outcome.unmatched {
+            let record = FormattedRecord {
+                layout: &self.layout,
+                text: &self.line_buf,
+                json: json.as_ref(),
+                source,
+            };
+            if let exit @ Some(_) = check_write_result(self.sinks.emit(&record), "output error") {
+                return exit;
+            }
+        }
+        if self.show_labels {
+            let label = format!("[{source}]");
+            let label = label.if_supports_color(Stdout, |t| t.dimmed().to_string());
+            self.line_buf.insert_str(0, &format!("{label} "));
+        }
+        if !outcome.unmatched
+            && let exit @ Some(_) = self.alert(parsed, outcome.bell)
+        {
+            return exit;
+        }
+        if outcome.stderr {
+            return self.write_stderr_entry();
+        }
+        if let (Some(key), Some(splitter)) = (split_key, self.splitter.as_mut()) {
+            let result = splitter.write_entry(&key, &self.line_buf, self.config.line_gap);
+            return check_write_result(result, "split file error");
+        }
+        self.write_entry()
+    }
+
+    /// Lay the entry out into `self.layout`, unless a `--jobs` worker did
+    /// already (`formatted`). Entries dropped by `--sample`, `--first-only`
+    /// or `--throttle` are left empty; that is decided, in this order, for
+    /// entries passing the filters and before the format state is touched.
+    fn lay_out(
+        &mut self,
+        parsed: &LineKind,
+        raw_line: &str,
+        formatted: Option<(Layout, Outcome)>,
+    ) -> Outcome {
+        let (sampler, dedup, throttle) = (&mut self.sampler, &mut self.dedup, &mut self.throttle);
+        let mut admit = |record: Option<&LogRecord>| {
+            if let Some(record) = record {
+                if sampler.as_mut().is_some_and(|s| !s.keep(record.level)) {
+                    return false;
+                }
+                if let Some(dedup) = dedup.as_mut()
+                    && let Some(key) = dedup.key(record)
+                    && !dedup.first(key)
+                {
+                    return false;
+                }
             }
-            _ => {
-                line_buf.clear();
-                format_line_parsed(parsed, &line, config, &mut line_buf);
+            throttle.as_mut().is_none_or(|t| t.admit(Instant::now()))
+        };
+        if let Some((layout, outcome)) = formatted {
+            // Laid out ahead only when the format state was not involved.
+            self.layout = layout;
+            let record = parsed.record().filter(|_| !outcome.unmatched);
+            if !self.layout.is_empty() && !admit(record) {
+                self.layout.clear();
             }
+            return outcome;
         }
+        span!(
+            "format",
+            format_admitted(
+                parsed,
+                raw_line,
+                self.config,
+                &mut self.format_state,
+                &mut self.layout,
+                admit
+            )
+        )
+    }
 
-        // Filtered-out lines produce an empty buffer — skip them.
-        if line_buf.is_empty() {
-            continue;
+    /// Describe the entry on stderr while `--explain` entries are left.
+    fn explain(&mut self, parsed: &LineKind, raw_line: &str, pos: Position) -> Option<ExitCode> {
+        if self.explain == 0 {
+            return None;
         }
+        self.explain -= 1;
+        let text = explain::explain(pos.line, raw_line, parsed, self.config);
+        check_write_result(io::stderr().write_all(text.as_bytes()), "write error")
+    }
 
-        if let exit @ Some(_) = write_entry(writer, &line_buf, config.line_gap) {
+    /// The `--provenance` object of a record, if enabled.
+    fn provenance(
+        &self,
+        raw_line: &str,
+        original: &str,
+        source: &str,
+        pos: Position,
+    ) -> Option<serde_json::Value> {
+        if !self.config.provenance {
+            return None;
+        }
+        let meta = Provenance {
+            source,
+            line: pos.line,
+            parser: parser::reader_name(raw_line, self.config),
+            preset: self.config.preset,
+            reassembled: original.contains('\n'),
+        };
+        Some(meta.to_json())
+    }
+
+    /// Count the entry for `--diag`, `--strict`, `--stats`, and
+    /// `--live-counts`, reporting lines that look like broken JSON.
+    fn count(
+        &mut self,
+        parsed: &LineKind,
+        raw_line: &str,
+        original: &str,
+        source: &str,
+        pos: Position,
+    ) {
+        let malformed = matches!(parsed, LineKind::Raw(Some(_))) && might_start_json(raw_line);
+        if let (Some(counts), LineKind::Json(record) | LineKind::EmbeddedJson { record, .. }) =
+            (self.live_counts.as_mut(), parsed)
+        {
+            counts.observe(record.level);
+        }
+        if let Some(ref mut stats) = self.stats {
+            stats.observe(parsed, original.split('\n').count(), malformed);
+        }
+        if let LineKind::Raw(Some(error)) = parsed
+            && malformed
+        {
+            if self.config.strict {
+                self.malformed.add(source, pos.line);
+            }
+            if let Some(ref mut diag) = self.diag {
+                diag.counts.malformed += 1;
+                diag.emit(&Event::ParseError {
+                    source,
+                    line: pos.line,
+                    error: &error.message,
+                });
+            }
+        }
+        if let Some(ref mut diag) = self.diag {
+            diag.counts.entries += 1;
+            if matches!(parsed, LineKind::Raw(_)) {
+                diag.counts.raw += 1;
+            } else {
+                diag.counts.records += 1;
+            }
+        }
+    }
+
+    /// Write the header row of `--output-format csv|tsv`.
+    fn write_header(&mut self) -> Option<ExitCode> {
+        let table = self.config.table.as_ref()?;
+        check_write_result(writeln!(self.writer, "{}", table.header()), "write error")
+    }
+
+    /// Write the formatted entry with line gap, returning early exit code on error.
+    ///
+    /// Batches the entry and its trailing blank lines into a single `write!`
+    /// call so `LineWriter` only flushes once per entry (on the final newline)
+    /// rather than `1 + line_gap` times. Keeps streaming responsive without
+    /// paying per-gap syscalls in batch mode.
+    #[inline]
+    fn write_entry(&mut self) -> Option<ExitCode> {
+        if let exit @ Some(_) = self.clear_counts() {
             return exit;
         }
+        // One '\n' to terminate the entry + `line_gap` blank-line newlines.
+        let trailing = "\n".repeat(1 + self.config.line_gap);
+        check_write_result(
+            write!(self.writer, "{}{trailing}", self.line_buf),
+            "write error",
+        )
     }
 
-    None
-}
+    /// Write the entry to stderr instead of stdout (the `stderr` rule action).
+    fn write_stderr_entry(&mut self) -> Option<ExitCode> {
+        if let exit @ Some(_) = self.clear_counts() {
+            return exit;
+        }
+        let trailing = "\n".repeat(1 + self.config.line_gap);
+        let result = write!(io::stderr(), "{}{trailing}", self.line_buf);
+        check_write_result(result, "write error")
+    }
 
-/// Check if a line might be the start of an incomplete JSON object.
-///
-/// Returns `true` if the line contains `{"` which is a strong indicator
-/// of a JSON object start. This avoids false positives from lines that
-/// contain stray `{` characters (e.g., code snippets).
-fn might_start_json(line: &str) -> bool {
-    let trimmed = line.trim();
-    if let Some(brace_pos) = trimmed.find('{') {
-        let after_brace = &trimmed[brace_pos + 1..];
-        after_brace.trim_start().starts_with('"')
-    } else {
-        false
+    /// Clear the `--live-counts` status line before an entry is written
+    /// to the terminal.
+    fn clear_counts(&mut self) -> Option<ExitCode> {
+        let clear = self.live_counts.as_mut()?.clear()?;
+        check_write_result(io::stderr().write_all(clear.as_bytes()), "write error")
     }
-}
 
+    /// Write the `--throttle` marker for entries suppressed since the last
+    /// one, if any.
+    fn write_suppressed(&mut self) -> Option<ExitCode> {
+        let suppressed = self.throttle.as_mut().map_or(0, Throttle::take_suppressed);
+        if suppressed == 0 {
+            return None;
+        }
+        if let exit @ Some(_) = self.clear_counts() {
+            return exit;
+        }
+        let marker = throttle::marker(suppressed, self.config.glyphs().ellipsis);
+        let marker = marker.if_supports_color(Stdout, |t| t.dimmed().to_string());
+        let trailing = "\n".repeat(1 + self.config.line_gap);
+        check_write_result(write!(self.writer, "{marker}{trailing}"), "write error")
+    }
+
+    /// Flush output (including split files) and print the bookmark index
+    /// collected without a side file.
+    fn finish(&mut self) -> Option<ExitCode> {
+        if let exit @ Some(_) = self.write_suppressed() {
+            return exit;
+        }
+        if let Some(ref mut counts) = self.live_counts {
+            let result = io::stderr().write_all(counts.finish().as_bytes());
+            if let exit @ Some(_) = check_write_result(result, "write error") {
+                return exit;
+            }
+        }
+        if let Some(ref mut diag) = self.diag {
+            diag.finish();
+        }
+        if let Some(ref report) = self.field_report
+            && let exit @ Some(_) =
+                check_write_result(report.write(&mut self.writer), "write error")
+        {
+            return exit;
+        }
+        if let exit @ Some(_) = check_write_result(self.writer.flush(), "flush error") {
+            return exit;
+        }
+        if let Some(ref mut splitter) = self.splitter
+            && let exit @ Some(_) = check_write_result(splitter.flush(), "split file error")
+        {
+            return exit;
+        }
+        if let exit @ Some(_) = check_write_result(self.sinks.finish(), "output error") {
+            return exit;
+        }
+        if let Some(ref mut recorder) = self.recorder
+            && let exit @ Some(_) = check_write_result(recorder.flush(), "record error")
+        {
+            return exit;
+        }
+        if let Some(ref bookmarks) = self.bookmarks {
+            let result = bookmarks.write_index(&mut io::stderr().lock());
+            if let exit @ Some(_) = check_write_result(result, "write error") {
+                return exit;
+            }
+        }
+        if let Some(ref dedup) = self.dedup {
+            let result = dedup.write_summary(&mut io::stderr().lock());
+            if let exit @ Some(_) = check_write_result(result, "write error") {
+                return exit;
+            }
+        }
+        if let Some(ref stats) = self.stats {
+            let result = stats.write_summary(&mut io::stderr().lock());
+            if let exit @ Some(_) = check_write_result(result, "write error") {
+                return exit;
+            }
+        }
+        None
+    }
+}
 /// Reset SIGPIPE to the default (terminate) behavior.
 ///
 /// By default, Rust ignores SIGPIPE to surface `BrokenPipe` I/O errors.
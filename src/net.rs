@@ -0,0 +1,391 @@
+//! Network inputs (`--connect`).
+//!
+//! Each endpoint is read on its own thread. When a connection fails or the
+//! remote end closes it, the thread waits and reconnects with exponential
+//! backoff, so a flaky producer never ends the viewing session. Lines from
+//! every connection are merged into one [`LineSource`], tagged with the
+//! label of the connection they came from, so the consumer numbers and
+//! reassembles them per connection. A line that is not valid UTF-8 is
+//! passed on as an error and reading goes on.
+//!
+//! `tls://` endpoints are wrapped in TLS when cor is built with the `tls`
+//! feature (see `crate::tls`). With the `async` feature, every endpoint is
+//! read as a task on the shared runtime (see `crate::runtime`) instead of a
+//! thread each.
+
+use std::fmt;
+use std::io;
+#[cfg(not(feature = "async"))]
+use std::io::{BufReader, Read};
+#[cfg(feature = "async")]
+use std::mem;
+#[cfg(not(feature = "async"))]
+use std::net::{TcpStream, ToSocketAddrs};
+#[cfg(not(feature = "async"))]
+use std::sync::mpsc::SyncSender;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+#[cfg(not(feature = "async"))]
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::input::{self, LineSource, Next};
+
+/// Number of lines buffered between the connection threads and the consumer.
+const CHANNEL_CAPACITY: usize = 1024;
+
+/// Delay before the first reconnect attempt.
+const INITIAL_BACKOFF: Duration = Duration::from_millis(250);
+
+/// Upper bound for the reconnect delay.
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// How long a single connection attempt may take.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A remote log producer to connect to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Endpoint {
+    /// Label shown before lines from this connection.
+    pub label: String,
+    /// `host:port` address.
+    pub addr: String,
+    /// Wrap the connection in TLS (`tls://`).
+    pub tls: bool,
+}
+
+impl Endpoint {
+    /// Parse `[LABEL=]tcp://HOST:PORT` or `[LABEL=]tls://HOST:PORT`. The
+    /// label defaults to `HOST:PORT`.
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let (label, url) = match s.split_once('=') {
+            Some((label, url)) if !label.contains("://") => (Some(label), url),
+            _ => (None, s),
+        };
+        let (addr, tls) = if let Some(addr) = url.strip_prefix("tcp://") {
+            (addr, false)
+        } else if let Some(addr) = url.strip_prefix("tls://") {
+            if !cfg!(feature = "tls") {
+                return Err(format!(
+                    "invalid endpoint '{s}': tls:// requires cor built with the `tls` feature"
+                ));
+            }
+            (addr, true)
+        } else {
+            return Err(format!(
+                "invalid endpoint '{s}': expected tcp://HOST:PORT or LABEL=tcp://HOST:PORT"
+            ));
+        };
+        match addr.rsplit_once(':') {
+            Some((host, port)) if !host.is_empty() && port.parse::<u16>().is_ok() => {}
+            _ => return Err(format!("invalid endpoint '{s}': expected HOST:PORT")),
+        }
+        Ok(Self {
+            label: label.unwrap_or(addr).to_string(),
+            addr: addr.to_string(),
+            tls,
+        })
+    }
+
+    /// Host part of the address, used as the TLS server name.
+    pub fn host(&self) -> &str {
+        let host = self
+            .addr
+            .rsplit_once(':')
+            .map_or(&*self.addr, |(host, _)| host);
+        host.trim_start_matches('[').trim_end_matches(']')
+    }
+}
+
+impl fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let scheme = if self.tls { "tls" } else { "tcp" };
+        write!(f, "{} ({scheme}://{})", self.label, self.addr)
+    }
+}
+
+/// Opens connections to endpoints, applying TLS where requested.
+#[derive(Debug, Clone, Default)]
+pub struct Connector {
+    #[cfg(feature = "tls")]
+    tls: Option<std::sync::Arc<rustls::ClientConfig>>,
+}
+
+impl Connector {
+    /// Connector using `config` for `tls://` endpoints.
+    #[cfg(feature = "tls")]
+    pub const fn with_tls(config: std::sync::Arc<rustls::ClientConfig>) -> Self {
+        Self { tls: Some(config) }
+    }
+
+    /// Connect to `endpoint`, returning a byte stream of its log output.
+    #[cfg(not(feature = "async"))]
+    #[cfg_attr(not(feature = "tls"), allow(clippy::unused_self))]
+    fn open(&self, endpoint: &Endpoint) -> io::Result<Box<dyn Read + Send>> {
+        let stream = connect(&endpoint.addr)?;
+        if !endpoint.tls {
+            return Ok(Box::new(stream));
+        }
+        #[cfg(feature = "tls")]
+        if let Some(ref config) = self.tls {
+            return Ok(Box::new(crate::tls::wrap(stream, endpoint.host(), config)?));
+        }
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "TLS is not configured for this endpoint",
+        ))
+    }
+
+    /// Connect to `endpoint` on the shared runtime, like [`Self::open`].
+    #[cfg(feature = "async")]
+    #[cfg_attr(not(feature = "tls"), allow(clippy::unused_self))]
+    async fn open_async(
+        &self,
+        endpoint: &Endpoint,
+    ) -> io::Result<Box<dyn tokio::io::AsyncRead + Send + Unpin>> {
+        let connect = tokio::net::TcpStream::connect(&endpoint.addr);
+        let stream = tokio::time::timeout(CONNECT_TIMEOUT, connect)
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "connection timed out"))??;
+        if !endpoint.tls {
+            return Ok(Box::new(stream));
+        }
+        #[cfg(feature = "tls")]
+        if let Some(ref config) = self.tls {
+            let stream = crate::tls::wrap_async(stream, endpoint.host(), config).await?;
+            return Ok(Box::new(stream));
+        }
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "TLS is not configured for this endpoint",
+        ))
+    }
+}
+
+/// Merged [`LineSource`] over one or more reconnecting TCP connections.
+#[derive(Debug)]
+pub struct NetLines {
+    rx: Receiver<(usize, io::Result<String>)>,
+    labels: Vec<String>,
+    current: usize,
+}
+
+impl NetLines {
+    /// Spawn one reconnecting reader thread per endpoint, or a task with the
+    /// `async` feature.
+    #[cfg_attr(not(feature = "async"), allow(clippy::unnecessary_wraps))]
+    pub fn spawn(endpoints: &[Endpoint], connector: &Connector) -> io::Result<Self> {
+        let (tx, rx) = mpsc::sync_channel(CHANNEL_CAPACITY);
+        #[cfg(feature = "async")]
+        {
+            let runtime = crate::runtime::handle().map_err(io::Error::other)?;
+            let tx = crate::runtime::bridge(tx);
+            for (index, endpoint) in endpoints.iter().enumerate() {
+                let (endpoint, connector, tx) = (endpoint.clone(), connector.clone(), tx.clone());
+                runtime.spawn(async move {
+                    read_with_reconnect_async(index, &endpoint, &connector, &tx).await;
+                });
+            }
+        }
+        #[cfg(not(feature = "async"))]
+        for (index, endpoint) in endpoints.iter().enumerate() {
+            let tx = tx.clone();
+            let endpoint = endpoint.clone();
+            let connector = connector.clone();
+            thread::spawn(move || read_with_reconnect(index, &endpoint, &connector, &tx));
+        }
+        Ok(Self {
+            rx,
+            labels: endpoints.iter().map(|e| e.label.clone()).collect(),
+            current: 0,
+        })
+    }
+}
+
+impl LineSource for NetLines {
+    fn next_line(&mut self, deadline: Option<Instant>) -> Next {
+        let received = match deadline {
+            None => self.rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+            Some(deadline) => self
+                .rx
+                .recv_timeout(deadline.saturating_duration_since(Instant::now())),
+        };
+        match received {
+            Ok((index, line)) => {
+                self.current = index;
+                Next::Line(line)
+            }
+            Err(RecvTimeoutError::Timeout) => Next::TimedOut,
+            Err(RecvTimeoutError::Disconnected) => Next::Eof,
+        }
+    }
+
+    fn label(&self) -> Option<&str> {
+        self.labels.get(self.current).map(String::as_str)
+    }
+}
+
+/// Read lines from `endpoint` forever, reconnecting with backoff.
+///
+/// Returns only once the consumer has gone away.
+#[cfg(not(feature = "async"))]
+fn read_with_reconnect(
+    index: usize,
+    endpoint: &Endpoint,
+    connector: &Connector,
+    tx: &SyncSender<(usize, io::Result<String>)>,
+) {
+    let mut backoff = INITIAL_BACKOFF;
+    loop {
+        match connector.open(endpoint) {
+            Ok(stream) => {
+                backoff = INITIAL_BACKOFF;
+                for line in input::lines(BufReader::new(stream)) {
+                    // Invalid UTF-8 drops the line; other read errors end
+                    // this connection, not the session.
+                    if line
+                        .as_ref()
+                        .is_err_and(|e| e.kind() != io::ErrorKind::InvalidData)
+                    {
+                        break;
+                    }
+                    if tx.send((index, line)).is_err() {
+                        return;
+                    }
+                }
+                eprintln!("cor: {endpoint}: connection closed, reconnecting in {backoff:?}");
+            }
+            Err(e) => {
+                eprintln!("cor: {endpoint}: {e}, retrying in {backoff:?}");
+            }
+        }
+        thread::sleep(backoff);
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+}
+
+/// Read lines from `endpoint` forever, like [`read_with_reconnect`] but as
+/// a task.
+#[cfg(feature = "async")]
+async fn read_with_reconnect_async(
+    index: usize,
+    endpoint: &Endpoint,
+    connector: &Connector,
+    tx: &tokio::sync::mpsc::Sender<(usize, io::Result<String>)>,
+) {
+    use tokio::io::AsyncBufReadExt;
+
+    let mut backoff = INITIAL_BACKOFF;
+    loop {
+        match connector.open_async(endpoint).await {
+            Ok(stream) => {
+                backoff = INITIAL_BACKOFF;
+                let mut reader = tokio::io::BufReader::new(stream);
+                let mut line = Vec::new();
+                // Invalid UTF-8 is passed on as an error; read errors end
+                // this connection, not the session.
+                while let Ok(1..) = reader.read_until(b'\n', &mut line).await {
+                    let line = input::decode_line(mem::take(&mut line));
+                    if tx.send((index, line)).await.is_err() {
+                        return;
+                    }
+                }
+                eprintln!("cor: {endpoint}: connection closed, reconnecting in {backoff:?}");
+            }
+            Err(e) => {
+                eprintln!("cor: {endpoint}: {e}, retrying in {backoff:?}");
+            }
+        }
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+}
+
+/// Connect to the first reachable address `addr` resolves to.
+#[cfg(not(feature = "async"))]
+fn connect(addr: &str) -> io::Result<TcpStream> {
+    let mut last_error = None;
+    for socket_addr in addr.to_socket_addrs()? {
+        match TcpStream::connect_timeout(&socket_addr, CONNECT_TIMEOUT) {
+            Ok(stream) => return Ok(stream),
+            Err(e) => last_error = Some(e),
+        }
+    }
+    Err(last_error
+        .unwrap_or_else(|| io::Error::new(io::ErrorKind::NotFound, "address resolved to nothing")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_endpoint() {
+        assert_eq!(
+            Endpoint::parse("tcp://localhost:5170").unwrap(),
+            Endpoint {
+                label: "localhost:5170".to_string(),
+                addr: "localhost:5170".to_string(),
+                tls: false,
+            }
+        );
+        assert_eq!(
+            Endpoint::parse("tls://[::1]:6514").map(|e| e.host().to_string()),
+            if cfg!(feature = "tls") {
+                Ok("::1".to_string())
+            } else {
+                Err("invalid endpoint 'tls://[::1]:6514': tls:// requires cor built with the `tls` feature".to_string())
+            }
+        );
+        assert_eq!(
+            Endpoint::parse("api=tcp://10.0.0.7:9000").unwrap().label,
+            "api"
+        );
+        assert!(Endpoint::parse("localhost:5170").is_err());
+        assert!(Endpoint::parse("tcp://localhost").is_err());
+        assert!(Endpoint::parse("udp://localhost:5170").is_err());
+    }
+
+    #[test]
+    fn test_reconnects_after_close() {
+        use std::io::Write;
+        use std::net::TcpListener;
+        use std::thread;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            for line in ["first", "second"] {
+                let (mut conn, _) = listener.accept().unwrap();
+                writeln!(conn, "{line}").unwrap();
+            }
+        });
+
+        let endpoint = Endpoint::parse(&format!("web=tcp://{addr}")).unwrap();
+        let mut source = NetLines::spawn(&[endpoint], &Connector::default()).unwrap();
+        let deadline = Some(Instant::now() + Duration::from_secs(5));
+        assert!(matches!(source.next_line(deadline), Next::Line(Ok(ref l)) if l == "first"));
+        assert_eq!(source.label(), Some("web"));
+        assert!(matches!(source.next_line(deadline), Next::Line(Ok(ref l)) if l == "second"));
+    }
+
+    #[test]
+    fn test_invalid_utf8_keeps_the_connection() {
+        use std::io::Write;
+        use std::net::TcpListener;
+        use std::thread;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            let (mut conn, _) = listener.accept().unwrap();
+            conn.write_all(b"a\n\xff\nb\n").unwrap();
+        });
+
+        let endpoint = Endpoint::parse(&format!("tcp://{addr}")).unwrap();
+        let mut source = NetLines::spawn(&[endpoint], &Connector::default()).unwrap();
+        let deadline = Some(Instant::now() + Duration::from_secs(5));
+        assert!(matches!(source.next_line(deadline), Next::Line(Ok(ref l)) if l == "a"));
+        assert!(matches!(source.next_line(deadline), Next::Line(Err(_))));
+        assert!(matches!(source.next_line(deadline), Next::Line(Ok(ref l)) if l == "b"));
+    }
+}
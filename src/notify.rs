@@ -0,0 +1,124 @@
+//! Desktop notifications for severe records (`--notify-on`).
+//!
+//! A [`Notifier`] hands a record's level and message to the platform's
+//! notification tool — `notify-send` on Linux and the BSDs, `osascript` on
+//! macOS — without waiting for it, so someone following logs in a
+//! background terminal learns when an error shows up. During an error
+//! storm at most one notification per [`MIN_INTERVAL`] is shown.
+
+use std::io;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::level::Level;
+
+/// Shortest gap between two notifications.
+pub const MIN_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Longest message shown in a notification, in characters.
+const MAX_BODY: usize = 200;
+
+/// Notifications for records at or above a level.
+#[derive(Debug, Clone)]
+pub struct Notifier {
+    level: Level,
+    last: Option<Instant>,
+    /// Whether a failure to notify was already reported.
+    reported: bool,
+}
+
+impl Notifier {
+    pub const fn new(level: Level) -> Self {
+        Self {
+            level,
+            last: None,
+            reported: false,
+        }
+    }
+
+    /// Whether a failed [`send`] should be reported: only the first is, so
+    /// a missing notification tool is mentioned once.
+    pub const fn report_failure(&mut self) -> bool {
+        !std::mem::replace(&mut self.reported, true)
+    }
+
+    /// Whether a record with `level` arriving at `now` gets a
+    /// notification; if so, the next one waits for [`MIN_INTERVAL`].
+    pub fn due(&mut self, level: Option<Level>, now: Instant) -> bool {
+        let due = level.is_some_and(|l| l >= self.level)
+            && self
+                .last
+                .is_none_or(|last| now.duration_since(last) >= MIN_INTERVAL);
+        if due {
+            self.last = Some(now);
+        }
+        due
+    }
+}
+
+/// Show a desktop notification for a record with `level` and `message`.
+pub fn send(level: Level, message: &str) -> io::Result<()> {
+    let title = format!("cor: {}", level.badge().trim_start());
+    let body: String = message.chars().take(MAX_BODY).collect();
+    let mut child = command(&title, &body)?
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    // Reap the notifier in the background instead of waiting for it.
+    thread::spawn(move || child.wait());
+    Ok(())
+}
+
+/// The platform command showing a notification.
+fn command(title: &str, body: &str) -> io::Result<Command> {
+    if cfg!(target_os = "macos") {
+        // Passed as arguments, so the text needs no AppleScript quoting.
+        let mut command = Command::new("osascript");
+        command.args([
+            "-e",
+            "on run argv",
+            "-e",
+            "display notification (item 2 of argv) with title (item 1 of argv)",
+            "-e",
+            "end run",
+            title,
+            body,
+        ]);
+        Ok(command)
+    } else if cfg!(unix) {
+        let mut command = Command::new("notify-send");
+        // `--` keeps a message starting with `-` from reading as an option.
+        command.args(["--app-name=cor", "--", title, body]);
+        Ok(command)
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "not supported on this platform",
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_due_at_level_once_per_interval() {
+        let mut notifier = Notifier::new(Level::Error);
+        let start = Instant::now();
+        assert!(!notifier.due(Some(Level::Warn), start));
+        assert!(!notifier.due(None, start));
+        assert!(notifier.due(Some(Level::Error), start));
+        assert!(!notifier.due(Some(Level::Fatal), start + Duration::from_secs(1)));
+        assert!(notifier.due(Some(Level::Fatal), start + MIN_INTERVAL));
+    }
+
+    #[test]
+    fn test_failure_reported_once() {
+        let mut notifier = Notifier::new(Level::Error);
+        assert!(notifier.report_failure());
+        assert!(!notifier.report_failure());
+    }
+}
@@ -0,0 +1,196 @@
+//! Parallel parse and format pipeline (`--jobs`).
+//!
+//! For offline input, [`ParallelLines`] reads lines on one thread, hands
+//! them in batches to worker threads that parse them — and lay them out
+//! too, while formatting a record leaves [`FormatState`] untouched, i.e.
+//! does not depend on the records before it — and yields them back in
+//! input order. Everything that carries state
+//! across records (reassembly, bookmarks, `--first-only`, split files,
+//! writing) stays on the processing loop.
+
+use std::collections::BTreeMap;
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Instant;
+
+use crate::config::Config;
+use crate::formatter::{FormatState, format_layout};
+use crate::input::{LineSource, Next};
+use crate::layout::Layout;
+use crate::parser::{self, LineKind};
+use crate::rules::Outcome;
+
+/// Lines handed to a worker at a time.
+const BATCH_LINES: usize = 256;
+
+/// A line parsed ahead of the processing loop.
+#[derive(Debug)]
+pub struct Prepared {
+    pub kind: LineKind,
+    /// The laid-out entry and its rule outcome, when formatted ahead.
+    pub formatted: Option<(Layout, Outcome)>,
+}
+
+type Batch = Vec<(io::Result<String>, Option<Prepared>)>;
+
+/// [`LineSource`] whose lines are parsed on worker threads. Deadlines are
+/// ignored.
+#[derive(Debug)]
+pub struct ParallelLines {
+    results: Receiver<(usize, Batch)>,
+    /// Batches that finished ahead of their turn, by sequence number.
+    pending: BTreeMap<usize, Batch>,
+    next: usize,
+    current: std::vec::IntoIter<(io::Result<String>, Option<Prepared>)>,
+    prepared: Option<Prepared>,
+}
+
+impl ParallelLines {
+    /// Read `lines` on a background thread and prepare them on `jobs`
+    /// worker threads. With `format`, workers lay records out as well until
+    /// one needs state carried from earlier records; that record and the
+    /// rest are left to the processing loop to lay out.
+    pub fn spawn<I>(lines: I, config: &Config, jobs: usize, format: bool) -> Self
+    where
+        I: Iterator<Item = io::Result<String>> + Send + 'static,
+    {
+        let jobs = jobs.max(1);
+        let config = Arc::new(config.clone());
+        let format = Arc::new(AtomicBool::new(format));
+        let (work_tx, work_rx) = mpsc::sync_channel::<(usize, Vec<io::Result<String>>)>(jobs * 2);
+        let (result_tx, results) = mpsc::sync_channel(jobs * 2);
+        thread::spawn(move || {
+            let mut lines = lines;
+            for seq in 0.. {
+                let batch: Vec<_> = lines.by_ref().take(BATCH_LINES).collect();
+                if batch.is_empty() || work_tx.send((seq, batch)).is_err() {
+                    break;
+                }
+            }
+        });
+        let work_rx = Arc::new(Mutex::new(work_rx));
+        for _ in 0..jobs {
+            let work_rx = Arc::clone(&work_rx);
+            let result_tx = result_tx.clone();
+            let config = Arc::clone(&config);
+            let format = Arc::clone(&format);
+            thread::spawn(move || {
+                loop {
+                    // The lock is released before the batch is worked on.
+                    let work = work_rx.lock().ok().and_then(|rx| rx.recv().ok());
+                    let Some((seq, lines)) = work else {
+                        break;
+                    };
+                    let batch = lines
+                        .into_iter()
+                        .map(|line| {
+                            let prepared = line.as_ref().ok().map(|l| prepare(l, &config, &format));
+                            (line, prepared)
+                        })
+                        .collect();
+                    if result_tx.send((seq, batch)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        Self {
+            results,
+            pending: BTreeMap::new(),
+            next: 0,
+            current: Vec::new().into_iter(),
+            prepared: None,
+        }
+    }
+}
+
+/// Parse `line`, laying it out too while `format` is set. A layout that
+/// used [`FormatState`] is dropped and clears `format`.
+fn prepare(line: &str, config: &Config, format: &AtomicBool) -> Prepared {
+    let line = line.strip_suffix('\r').unwrap_or(line);
+    let kind = parser::parse_line(line, config);
+    let mut formatted = None;
+    if format.load(Ordering::Relaxed) {
+        let mut state = FormatState::default();
+        let mut layout = Layout::default();
+        let outcome = format_layout(&kind, line, config, &mut state, &mut layout);
+        if state.is_fresh() {
+            formatted = Some((layout, outcome));
+        } else {
+            format.store(false, Ordering::Relaxed);
+        }
+    }
+    Prepared { kind, formatted }
+}
+
+impl LineSource for ParallelLines {
+    fn next_line(&mut self, _deadline: Option<Instant>) -> Next {
+        loop {
+            if let Some((line, prepared)) = self.current.next() {
+                self.prepared = prepared;
+                return Next::Line(line);
+            }
+            let batch = match self.pending.remove(&self.next) {
+                Some(batch) => batch,
+                None => match self.results.recv() {
+                    Ok((seq, batch)) if seq == self.next => batch,
+                    Ok((seq, batch)) => {
+                        self.pending.insert(seq, batch);
+                        continue;
+                    }
+                    Err(_) => return Next::Eof,
+                },
+            };
+            self.next += 1;
+            self.current = batch.into_iter();
+        }
+    }
+
+    fn take_prepared(&mut self) -> Option<Prepared> {
+        self.prepared.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lines_keep_input_order() {
+        let lines = (0..BATCH_LINES * 10 + 7)
+            .map(|n| Ok(format!(r#"{{"level":"info","msg":"line {n}"}}"#)));
+        let mut source = ParallelLines::spawn(lines, &Config::default(), 4, true);
+        let mut n = 0;
+        while let Next::Line(line) = source.next_line(None) {
+            assert_eq!(
+                line.unwrap(),
+                format!(r#"{{"level":"info","msg":"line {n}"}}"#)
+            );
+            let prepared = source.take_prepared().unwrap();
+            assert!(
+                matches!(prepared.kind, LineKind::Json(ref r) if r.message == Some(format!("line {n}")))
+            );
+            let (layout, _) = prepared.formatted.unwrap();
+            assert!(layout.text().contains(&format!("line {n}")));
+            n += 1;
+        }
+        assert_eq!(n, BATCH_LINES * 10 + 7);
+    }
+
+    #[test]
+    fn test_records_needing_state_are_left_to_the_loop() {
+        let config = Config {
+            elapsed: Some(crate::elapsed::ElapsedMode::Only),
+            ..Config::default()
+        };
+        let lines = std::iter::once(Ok(
+            r#"{"ts":"2024-01-01T00:00:00Z","msg":"timed"}"#.to_string()
+        ));
+        let mut source = ParallelLines::spawn(lines, &config, 2, true);
+        assert!(matches!(source.next_line(None), Next::Line(Ok(_))));
+        assert!(source.take_prepared().unwrap().formatted.is_none());
+    }
+}
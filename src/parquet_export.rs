@@ -0,0 +1,446 @@
+//! Parquet export of normalized records (`--export-parquet`, `parquet`
+//! feature).
+//!
+//! [`ParquetSink`] is an [`OutputSink`] collecting the normalized JSON of
+//! every record cor shows (see
+//! [`LogRecord::to_normalized_json`](crate::parser::LogRecord::to_normalized_json))
+//! into row groups of [`ROW_GROUP_SIZE`] records, so a captured stream can
+//! be loaded into `DuckDB`, pandas, or Spark for heavy analysis.
+//!
+//! The schema is given with `--parquet-schema` or inferred from the first
+//! row group: canonical fields first, then the extra fields in alphabetical
+//! order, each typed by the values seen (integers, floats, booleans, or
+//! strings; `timestamp` as a UTC timestamp). Fields first seen after the
+//! first row group are not exported, and values that don't fit their
+//! column's type are written as strings in string columns and as nulls
+//! otherwise. Raw lines are skipped.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+use parquet::basic::{Compression, LogicalType, Repetition, TimeUnit, Type as PhysicalType};
+use parquet::data_type::{BoolType, ByteArray, ByteArrayType, DoubleType, Int64Type};
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::{SerializedColumnWriter, SerializedFileWriter};
+use parquet::schema::types::Type;
+use serde_json::Value;
+
+use crate::sink::{FormattedRecord, OutputSink};
+
+/// Records per row group; the schema is inferred from the first one.
+pub const ROW_GROUP_SIZE: usize = 10_000;
+
+/// Canonical fields of the normalized JSON, in column order.
+const CANONICAL: [&str; 6] = ["timestamp", "level", "message", "logger", "caller", "error"];
+
+/// Type of a Parquet column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnType {
+    /// UTF-8 text; other JSON values are written as compact JSON.
+    String,
+    /// 64-bit signed integer.
+    Int,
+    /// 64-bit float.
+    Float,
+    /// Boolean.
+    Bool,
+    /// UTC timestamp in microseconds, from RFC 3339 text.
+    Timestamp,
+}
+
+impl ColumnType {
+    fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "string" | "str" | "text" => Some(Self::String),
+            "int" | "integer" | "int64" => Some(Self::Int),
+            "float" | "double" | "float64" => Some(Self::Float),
+            "bool" | "boolean" => Some(Self::Bool),
+            "timestamp" | "ts" => Some(Self::Timestamp),
+            _ => None,
+        }
+    }
+
+    /// Type of a single JSON value, `None` for `null`.
+    fn of(value: &Value) -> Option<Self> {
+        match value {
+            Value::Null => None,
+            Value::Bool(_) => Some(Self::Bool),
+            Value::Number(n) if n.is_i64() => Some(Self::Int),
+            Value::Number(_) => Some(Self::Float),
+            _ => Some(Self::String),
+        }
+    }
+
+    /// Narrowest type holding both `self` and `other`.
+    fn widen(self, other: Self) -> Self {
+        match (self, other) {
+            _ if self == other => self,
+            (Self::Int, Self::Float) | (Self::Float, Self::Int) => Self::Float,
+            _ => Self::String,
+        }
+    }
+}
+
+impl fmt::Display for ColumnType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::String => "string",
+            Self::Int => "int",
+            Self::Float => "float",
+            Self::Bool => "bool",
+            Self::Timestamp => "timestamp",
+        })
+    }
+}
+
+/// A named, typed column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Column {
+    pub name: String,
+    pub kind: ColumnType,
+}
+
+/// Columns of a Parquet file, as given with `--parquet-schema`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schema(pub Vec<Column>);
+
+impl Schema {
+    /// Parse `FIELD:TYPE,...`, e.g. `timestamp:timestamp,level:string,status:int`.
+    pub fn parse(s: &str) -> Result<Self, String> {
+        s.split(',')
+            .map(|spec| {
+                let (name, kind) = spec
+                    .split_once(':')
+                    .filter(|(name, _)| !name.trim().is_empty())
+                    .ok_or_else(|| format!("invalid column '{spec}': expected FIELD:TYPE"))?;
+                let kind = ColumnType::parse(kind).ok_or_else(|| {
+                    format!(
+                        "invalid column type '{kind}': expected string, int, float, bool, or timestamp"
+                    )
+                })?;
+                Ok(Column {
+                    name: name.trim().to_string(),
+                    kind,
+                })
+            })
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+}
+
+/// Columns for `rows`: canonical fields first, then the others in
+/// alphabetical order, each typed by its non-null values.
+pub fn infer_schema(rows: &[serde_json::Map<String, Value>]) -> Vec<Column> {
+    let mut kinds: BTreeMap<&str, Option<ColumnType>> = BTreeMap::new();
+    for (key, value) in rows.iter().flatten() {
+        let kind = kinds.entry(key).or_default();
+        if let Some(seen) = ColumnType::of(value) {
+            *kind = Some(kind.map_or(seen, |kind| kind.widen(seen)));
+        }
+    }
+    let column = |name: &str, kind: Option<ColumnType>| Column {
+        name: name.to_string(),
+        kind: if name == "timestamp" {
+            ColumnType::Timestamp
+        } else {
+            kind.unwrap_or(ColumnType::String)
+        },
+    };
+    let mut columns: Vec<Column> = CANONICAL
+        .iter()
+        .filter_map(|name| kinds.remove(name).map(|kind| column(name, kind)))
+        .collect();
+    columns.extend(kinds.into_iter().map(|(name, kind)| column(name, kind)));
+    columns
+}
+
+/// Sink writing normalized records to a Parquet file.
+pub struct ParquetSink {
+    file: Option<File>,
+    writer: Option<SerializedFileWriter<File>>,
+    columns: Option<Vec<Column>>,
+    rows: Vec<serde_json::Map<String, Value>>,
+}
+
+impl fmt::Debug for ParquetSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ParquetSink")
+            .field("columns", &self.columns)
+            .field("rows", &self.rows.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl ParquetSink {
+    /// Sink writing to a new file at `path`, with the given columns or
+    /// ones inferred from the first row group.
+    pub fn create(path: &Path, schema: Option<Schema>) -> io::Result<Self> {
+        Ok(Self {
+            file: Some(File::create(path)?),
+            writer: None,
+            columns: schema.map(|schema| schema.0),
+            rows: Vec::new(),
+        })
+    }
+
+    /// Write the buffered rows as one row group, opening the file writer
+    /// on the first call.
+    fn write_row_group(&mut self) -> io::Result<()> {
+        let columns = self.columns.get_or_insert_with(|| infer_schema(&self.rows));
+        if self.writer.is_none() {
+            let file = self
+                .file
+                .take()
+                .ok_or_else(|| io::Error::other("sink closed"))?;
+            let properties = WriterProperties::builder()
+                .set_compression(Compression::SNAPPY)
+                .build();
+            let writer =
+                SerializedFileWriter::new(file, message_type(columns)?, Arc::new(properties))
+                    .map_err(io::Error::other)?;
+            self.writer = Some(writer);
+        }
+        if self.rows.is_empty() {
+            return Ok(());
+        }
+        let writer = self.writer.as_mut().expect("opened above");
+        let mut group = writer.next_row_group().map_err(io::Error::other)?;
+        for column in columns.iter() {
+            let mut chunk = group
+                .next_column()
+                .map_err(io::Error::other)?
+                .ok_or_else(|| io::Error::other("schema has fewer columns than expected"))?;
+            let values = self.rows.iter().map(|row| row.get(&column.name));
+            write_column(&mut chunk, column.kind, values).map_err(io::Error::other)?;
+            chunk.close().map_err(io::Error::other)?;
+        }
+        group.close().map_err(io::Error::other)?;
+        self.rows.clear();
+        Ok(())
+    }
+}
+
+impl OutputSink for ParquetSink {
+    fn emit(&mut self, record: &FormattedRecord<'_>) -> io::Result<()> {
+        let Some(Value::Object(row)) = record.json else {
+            return Ok(());
+        };
+        self.rows.push(row.clone());
+        if self.rows.len() >= ROW_GROUP_SIZE {
+            self.write_row_group()?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        if self.file.is_none() && self.writer.is_none() {
+            return Ok(());
+        }
+        self.write_row_group()?;
+        if let Some(writer) = self.writer.take() {
+            writer.close().map_err(io::Error::other)?;
+        }
+        Ok(())
+    }
+}
+
+/// The Parquet schema for `columns`, all optional.
+fn message_type(columns: &[Column]) -> io::Result<Arc<Type>> {
+    let fields = columns
+        .iter()
+        .map(|column| {
+            let (physical, logical) = match column.kind {
+                ColumnType::String => (PhysicalType::BYTE_ARRAY, Some(LogicalType::String)),
+                ColumnType::Int => (PhysicalType::INT64, None),
+                ColumnType::Float => (PhysicalType::DOUBLE, None),
+                ColumnType::Bool => (PhysicalType::BOOLEAN, None),
+                ColumnType::Timestamp => (
+                    PhysicalType::INT64,
+                    Some(LogicalType::Timestamp {
+                        is_adjusted_to_u_t_c: true,
+                        unit: TimeUnit::MICROS(parquet::format::MicroSeconds {}),
+                    }),
+                ),
+            };
+            Type::primitive_type_builder(&column.name, physical)
+                .with_repetition(Repetition::OPTIONAL)
+                .with_logical_type(logical)
+                .build()
+                .map(Arc::new)
+        })
+        .collect::<Result<_, _>>()
+        .map_err(io::Error::other)?;
+    Type::group_type_builder("cor")
+        .with_fields(fields)
+        .build()
+        .map(Arc::new)
+        .map_err(io::Error::other)
+}
+
+/// Write one column chunk: the values that fit `kind`, with definition
+/// level 1 for present values and 0 for nulls.
+fn write_column<'a>(
+    chunk: &mut SerializedColumnWriter<'_>,
+    kind: ColumnType,
+    values: impl Iterator<Item = Option<&'a Value>>,
+) -> parquet::errors::Result<()> {
+    match kind {
+        ColumnType::String => write_typed::<ByteArrayType, _>(chunk, values, |value| {
+            Some(match value {
+                Value::String(s) => ByteArray::from(s.as_str()),
+                other => ByteArray::from(other.to_string().into_bytes()),
+            })
+        }),
+        ColumnType::Int => write_typed::<Int64Type, _>(chunk, values, Value::as_i64),
+        ColumnType::Float => write_typed::<DoubleType, _>(chunk, values, Value::as_f64),
+        ColumnType::Bool => write_typed::<BoolType, _>(chunk, values, Value::as_bool),
+        ColumnType::Timestamp => write_typed::<Int64Type, _>(chunk, values, |value| {
+            let ts: jiff::Timestamp = value.as_str()?.parse().ok()?;
+            Some(ts.as_microsecond())
+        }),
+    }
+}
+
+fn write_typed<'a, T, F>(
+    chunk: &mut SerializedColumnWriter<'_>,
+    values: impl Iterator<Item = Option<&'a Value>>,
+    convert: F,
+) -> parquet::errors::Result<()>
+where
+    T: parquet::data_type::DataType,
+    F: Fn(&Value) -> Option<T::T>,
+{
+    let mut present = Vec::new();
+    let mut levels = Vec::new();
+    for value in values {
+        match value.filter(|v| !v.is_null()).and_then(&convert) {
+            Some(value) => {
+                present.push(value);
+                levels.push(1);
+            }
+            None => levels.push(0),
+        }
+    }
+    chunk
+        .typed::<T>()
+        .write_batch(&present, Some(&levels), None)
+        .map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::Layout;
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+    use parquet::record::Field;
+    use serde_json::json;
+
+    fn rows(values: &[Value]) -> Vec<serde_json::Map<String, Value>> {
+        values
+            .iter()
+            .map(|v| v.as_object().unwrap().clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_schema() {
+        assert_eq!(
+            Schema::parse("timestamp:timestamp, status:INT").unwrap().0,
+            [
+                Column {
+                    name: "timestamp".into(),
+                    kind: ColumnType::Timestamp
+                },
+                Column {
+                    name: "status".into(),
+                    kind: ColumnType::Int
+                },
+            ]
+        );
+        assert!(Schema::parse("status").unwrap_err().contains("FIELD:TYPE"));
+        assert!(Schema::parse(":int").unwrap_err().contains("FIELD:TYPE"));
+        assert!(
+            Schema::parse("status:decimal")
+                .unwrap_err()
+                .contains("decimal")
+        );
+    }
+
+    #[test]
+    fn test_infer_schema() {
+        let rows = rows(&[
+            json!({"message": "a", "timestamp": "2026-01-15T10:30:00Z", "status": 200, "ok": true}),
+            json!({"message": "b", "status": 1.5, "ok": null, "tags": ["x"], "level": "info"}),
+        ]);
+        let columns: Vec<(String, ColumnType)> = infer_schema(&rows)
+            .into_iter()
+            .map(|c| (c.name, c.kind))
+            .collect();
+        assert_eq!(
+            columns,
+            [
+                ("timestamp".into(), ColumnType::Timestamp),
+                ("level".into(), ColumnType::String),
+                ("message".into(), ColumnType::String),
+                ("ok".into(), ColumnType::Bool),
+                ("status".into(), ColumnType::Float),
+                ("tags".into(), ColumnType::String),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_writes_readable_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.parquet");
+        let mut sink = ParquetSink::create(&path, None).unwrap();
+        let layout = Layout::default();
+        let records = [
+            Some(
+                json!({"timestamp": "2026-01-15T10:30:00Z", "level": "info", "message": "up", "status": 200}),
+            ),
+            None,
+            Some(json!({"level": "error", "message": "down", "status": "n/a"})),
+        ];
+        for json in &records {
+            sink.emit(&FormattedRecord {
+                layout: &layout,
+                text: "",
+                json: json.as_ref(),
+                source: "-",
+            })
+            .unwrap();
+        }
+        sink.finish().unwrap();
+
+        let reader = SerializedFileReader::new(File::open(&path).unwrap()).unwrap();
+        let rows: Vec<Vec<(String, Field)>> = reader
+            .get_row_iter(None)
+            .unwrap()
+            .map(|row| {
+                row.unwrap()
+                    .get_column_iter()
+                    .map(|(name, field)| (name.clone(), field.clone()))
+                    .collect()
+            })
+            .collect();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(
+            rows[0],
+            [
+                (
+                    "timestamp".into(),
+                    Field::TimestampMicros(1_768_473_000_000_000)
+                ),
+                ("level".into(), Field::Str("info".into())),
+                ("message".into(), Field::Str("up".into())),
+                ("status".into(), Field::Str("200".into())),
+            ]
+        );
+        assert_eq!(rows[1][0], ("timestamp".into(), Field::Null));
+    }
+}
@@ -6,11 +6,14 @@
 //! (embedded JSON), and plain text passthrough.
 
 use std::borrow::Cow;
-use std::collections::BTreeMap;
 
 use crate::config::Config;
+use crate::extras::Extras;
 use crate::fields;
+use crate::klog;
 use crate::level::Level;
+use crate::pipeline::{self, Stage};
+use crate::syslog;
 use crate::timestamp::Timestamp;
 
 /// The parsed classification of a stdin line.
@@ -26,6 +29,16 @@ pub enum LineKind {
     Raw(Option<ParseError>),
 }
 
+impl LineKind {
+    /// The record of a JSON line, with or without a prefix.
+    pub const fn record(&self) -> Option<&LogRecord> {
+        match self {
+            Self::Json(record) | Self::EmbeddedJson { record, .. } => Some(record),
+            Self::Raw(_) => None,
+        }
+    }
+}
+
 /// JSON parse error with context for verbose output.
 #[derive(Debug)]
 pub struct ParseError {
@@ -53,17 +66,95 @@ pub struct LogRecord {
     /// Error message or stacktrace.
     pub error: Option<String>,
     /// Remaining fields, ordered alphabetically.
-    pub extra: BTreeMap<String, serde_json::Value>,
+    pub extra: Extras,
     /// The original raw JSON string (for `--json` mode passthrough).
     pub raw_json: String,
 }
 
+impl LogRecord {
+    /// The record as a JSON object with detected fields under canonical
+    /// names (`timestamp`, `level`, `message`, `logger`, `caller`, `error`)
+    /// alongside the (flattened) extra fields.
+    ///
+    /// Canonical fields win over extra fields of the same name. Timestamps
+    /// are RFC 3339 in UTC; levels are lowercase names.
+    pub fn to_normalized_json(&self) -> serde_json::Value {
+        let mut map: serde_json::Map<String, serde_json::Value> = self
+            .extra
+            .iter()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        if let Some(ref ts) = self.timestamp {
+            map.insert("timestamp".into(), ts.value.to_string().into());
+        }
+        if let Some(level) = self.level {
+            map.insert("level".into(), level.name().into());
+        }
+        let text_fields = [
+            ("message", &self.message),
+            ("logger", &self.logger),
+            ("caller", &self.caller),
+            ("error", &self.error),
+        ];
+        for (key, value) in text_fields {
+            if let Some(value) = value {
+                map.insert(key.into(), value.clone().into());
+            }
+        }
+        serde_json::Value::Object(map)
+    }
+
+    /// The record as one line of flat JSON for `--output-format=flat-json`:
+    /// `ts`, `level`, `msg`, `logger`, `caller`, and `error` first, then the
+    /// extra fields under their dot-notation keys.
+    ///
+    /// Like [`to_normalized_json`](Self::to_normalized_json), canonical
+    /// fields win over extra fields of the same name.
+    pub fn to_flat_json(&self) -> String {
+        let canonical = [
+            ("ts", self.timestamp.as_ref().map(|ts| ts.value.to_string())),
+            ("level", self.level.map(|level| level.name().to_string())),
+            ("msg", self.message.clone()),
+            ("logger", self.logger.clone()),
+            ("caller", self.caller.clone()),
+            ("error", self.error.clone()),
+        ];
+        let mut out = String::from("{");
+        let mut push = |key: &str, value: &serde_json::Value| {
+            if out.len() > 1 {
+                out.push(',');
+            }
+            out.push_str(&serde_json::Value::from(key).to_string());
+            out.push(':');
+            out.push_str(&value.to_string());
+        };
+        for (key, value) in &canonical {
+            if let Some(value) = value {
+                push(key, &value.as_str().into());
+            }
+        }
+        for (key, value) in &self.extra {
+            let shadowed = canonical
+                .iter()
+                .any(|(name, canonical)| name == key && canonical.is_some());
+            if !shadowed {
+                push(key, value);
+            }
+        }
+        out.push('}');
+        out
+    }
+}
+
 /// Parse a single line from stdin into a [`LineKind`].
 ///
 /// Detection strategy:
-/// 1. Lines starting with `{` → try parsing as JSON object
-/// 2. Lines containing `{` → try embedded JSON (prefix + JSON)
-/// 3. Everything else → [`LineKind::Raw`] (passthrough)
+/// 1. RFC 5424 syslog lines → record from the header (see [`syslog`])
+/// 2. Kubernetes CRI lines → prefix stripped, payload parsed as below
+/// 3. klog/glog lines → record from the header (see [`klog`])
+/// 4. Lines starting with `{` → try parsing as JSON object
+/// 5. Lines containing `{` → try embedded JSON (prefix + JSON)
+/// 6. Everything else → [`LineKind::Raw`] (passthrough)
 ///
 /// JSON arrays are treated as [`LineKind::Raw`] since they are not log entries.
 pub fn parse_line(line: &str, config: &Config) -> LineKind {
@@ -72,6 +163,42 @@ pub fn parse_line(line: &str, config: &Config) -> LineKind {
         return LineKind::Raw(None);
     }
 
+    // RFC 5424 syslog, possibly carrying a JSON message
+    if trimmed.starts_with('<')
+        && let Some((header, message)) = syslog::parse(trimmed)
+    {
+        if message.starts_with('{')
+            && let Ok(mut record) = try_parse_json(message.trim_end(), config)
+        {
+            header.fill(&mut record);
+            return LineKind::Json(record);
+        }
+        return LineKind::Json(header.into_record(message));
+    }
+
+    // Kubernetes CRI prefix: the payload is parsed on its own, with the
+    // runtime's timestamp as a fallback
+    if trimmed.starts_with(|c: char| c.is_ascii_digit())
+        && let Some((timestamp, payload)) = split_cri(trimmed)
+    {
+        return match parse_line(payload, config) {
+            LineKind::Json(mut record) => {
+                if record.timestamp.is_none() {
+                    record.timestamp = Timestamp::from_json_value(&timestamp.into());
+                }
+                LineKind::Json(record)
+            }
+            other => other,
+        };
+    }
+
+    // klog/glog header from Kubernetes components
+    if trimmed.starts_with(['I', 'W', 'E', 'F'])
+        && let Some(record) = klog::parse(trimmed, config.year_reference)
+    {
+        return LineKind::Json(record);
+    }
+
     // Fast path: line starts with '{'
     if trimmed.starts_with('{') {
         match try_parse_json(trimmed, config) {
@@ -84,8 +211,11 @@ pub fn parse_line(line: &str, config: &Config) -> LineKind {
     if let Some(brace_pos) = trimmed.find('{') {
         let json_part = &trimmed[brace_pos..];
         match try_parse_json(json_part, config) {
-            Ok(record) => {
-                let prefix = trimmed[..brace_pos].to_string();
+            Ok(mut record) => {
+                let mut prefix = trimmed[..brace_pos].to_string();
+                if let Some(ref re) = config.prefix_pattern {
+                    derive_prefix_fields(re, &mut prefix, &mut record);
+                }
                 return LineKind::EmbeddedJson { prefix, record };
             }
             Err(err) => return LineKind::Raw(Some(err)),
@@ -95,6 +225,87 @@ pub fn parse_line(line: &str, config: &Config) -> LineKind {
     LineKind::Raw(None)
 }
 
+/// Which reader [`parse_line`] picks for `line`, in words, for `--explain`.
+pub fn reader_name(line: &str, config: &Config) -> String {
+    let trimmed = line.trim();
+    if trimmed.is_empty() {
+        return "empty line".to_string();
+    }
+    if trimmed.starts_with('<')
+        && let Some((_, message)) = syslog::parse(trimmed)
+    {
+        return if message.starts_with('{') {
+            "RFC 5424 syslog with a JSON message".to_string()
+        } else {
+            "RFC 5424 syslog".to_string()
+        };
+    }
+    if trimmed.starts_with(|c: char| c.is_ascii_digit())
+        && let Some((_, payload)) = split_cri(trimmed)
+    {
+        return format!(
+            "Kubernetes CRI prefix, then {}",
+            reader_name(payload, config)
+        );
+    }
+    if trimmed.starts_with(['I', 'W', 'E', 'F'])
+        && klog::parse(trimmed, config.year_reference).is_some()
+    {
+        return "klog header".to_string();
+    }
+    let Some(brace_pos) = trimmed.find('{') else {
+        return "plain text".to_string();
+    };
+    let object = match serde_json::from_str::<serde_json::Value>(&trimmed[brace_pos..]) {
+        Ok(serde_json::Value::Object(map)) => map,
+        Ok(_) => return "plain text (JSON, but not an object)".to_string(),
+        Err(e) => return format!("plain text (malformed JSON: {e})"),
+    };
+    let kind = if config.pipeline.contains(&Stage::UnwrapDocker) && is_docker(&object) {
+        "Docker json-file envelope"
+    } else if config.pipeline.contains(&Stage::Gelf) && is_gelf(&object) {
+        "GELF payload"
+    } else {
+        "JSON object"
+    };
+    if brace_pos == 0 {
+        kind.to_string()
+    } else {
+        format!("{kind} after a text prefix")
+    }
+}
+
+/// Split a containerd/CRI-O line (`<RFC3339> stdout F payload`) into its
+/// timestamp and payload, or `None` if the line has no CRI prefix.
+fn split_cri(line: &str) -> Option<(&str, &str)> {
+    let mut parts = line.splitn(4, ' ');
+    let (timestamp, stream, tag) = (parts.next()?, parts.next()?, parts.next()?);
+    let payload = parts.next().unwrap_or("");
+    let full_or_partial =
+        tag == "F" || tag == "P" || tag.starts_with("F:") || tag.starts_with("P:");
+    ((stream == "stdout" || stream == "stderr") && full_or_partial && timestamp.contains('T'))
+        .then_some((timestamp, payload))
+}
+
+/// Turn the named groups of `re` matching `prefix` into extra fields of
+/// `record`, and drop the matched text from `prefix`.
+///
+/// Fields already present in the JSON win over captured ones.
+fn derive_prefix_fields(re: &regex::Regex, prefix: &mut String, record: &mut LogRecord) {
+    let Some(captures) = re.captures(prefix) else {
+        return;
+    };
+    for name in re.capture_names().flatten() {
+        if let Some(value) = captures.name(name) {
+            record
+                .extra
+                .insert_missing(name.to_string(), value.as_str().into());
+        }
+    }
+    let matched = captures.get(0).map_or(0..0, |m| m.range());
+    prefix.replace_range(matched, "");
+}
+
 /// Try to parse a string as a JSON object and extract log fields.
 ///
 /// If the initial parse fails, retries after un-double-escaping backslash
@@ -165,6 +376,31 @@ fn try_parse_json_str(s: &str, config: &Config) -> Result<LogRecord, ParseError>
         });
     };
 
+    // Configured transform stages, in order. Rewritten objects are
+    // re-serialized so `--json` output shows the rewrite.
+    let mut rewritten = false;
+    for stage in &config.pipeline {
+        rewritten |= match stage {
+            Stage::UnwrapDocker if is_docker(&map) => return Ok(unwrap_docker(map, config)),
+            Stage::Gelf if is_gelf(&map) => {
+                normalize_gelf(&mut map);
+                false
+            }
+            Stage::UnwrapDocker | Stage::Gelf => false,
+            Stage::Redact => pipeline::redact(&mut map),
+            Stage::InferLevel => pipeline::infer_level(&mut map, config),
+            Stage::HttpSummary => pipeline::http_summary(&mut map, config),
+        };
+    }
+    let raw_json = if rewritten {
+        serde_json::to_string(&map).unwrap_or_else(|_| s.to_string())
+    } else {
+        s.to_string()
+    };
+
+    // With --keep-extracted, detected fields are restored into the extras below.
+    let original = config.keep_extracted.then(|| map.clone());
+
     // Extract timestamp
     let timestamp = extract_timestamp(&mut map, config);
 
@@ -179,6 +415,12 @@ fn try_parse_json_str(s: &str, config: &Config) -> Result<LogRecord, ParseError>
     let caller = extract_caller(&mut map, config);
     let error = extract_error(&mut map, config);
 
+    if let Some(original) = original {
+        for (key, value) in original {
+            map.entry(key).or_insert(value);
+        }
+    }
+
     // Flatten remaining fields (1 level of dot-notation)
     let extra = flatten_extra(map);
 
@@ -190,18 +432,107 @@ fn try_parse_json_str(s: &str, config: &Config) -> Result<LogRecord, ParseError>
         caller,
         error,
         extra,
-        raw_json: s.to_string(),
+        raw_json,
     })
 }
 
+/// Whether the object is a Docker `json-file` log driver envelope:
+/// string `log`, `stream`, and `time`, plus optional `attrs`.
+fn is_docker(map: &serde_json::Map<String, serde_json::Value>) -> bool {
+    ["log", "stream", "time"]
+        .iter()
+        .all(|key| map.get(*key).is_some_and(serde_json::Value::is_string))
+        && map.len() == 3 + usize::from(map.contains_key("attrs"))
+}
+
+/// The record inside a Docker envelope: the `log` payload parsed as a line
+/// of its own (plain text becomes the message), with the envelope's `time`
+/// as a fallback timestamp and its `stream` and `attrs` as fields.
+fn unwrap_docker(
+    mut map: serde_json::Map<String, serde_json::Value>,
+    config: &Config,
+) -> LogRecord {
+    let field = |map: &mut serde_json::Map<_, _>, key| match map.remove(key) {
+        Some(serde_json::Value::String(s)) => s,
+        _ => String::new(),
+    };
+    let log = field(&mut map, "log");
+    let payload = log.trim_end_matches(['\r', '\n']);
+    let mut record = match parse_line(payload, config) {
+        LineKind::Json(record) => record,
+        _ => LogRecord {
+            timestamp: None,
+            level: None,
+            message: (!payload.is_empty()).then(|| payload.to_string()),
+            logger: None,
+            caller: None,
+            error: None,
+            extra: Extras::new(),
+            raw_json: String::new(),
+        },
+    };
+    if record.timestamp.is_none() {
+        record.timestamp = Timestamp::from_json_value(&field(&mut map, "time").into());
+    }
+    let stream = field(&mut map, "stream");
+    record
+        .extra
+        .insert_missing("stream".to_string(), stream.into());
+    if let Some(serde_json::Value::Object(attrs)) = map.remove("attrs") {
+        for (key, value) in attrs {
+            record.extra.insert_missing(key, value);
+        }
+    }
+    if record.raw_json.is_empty() {
+        record.raw_json = record.to_normalized_json().to_string();
+    }
+    record
+}
+
+/// Whether the object is a GELF payload (Graylog Extended Log Format),
+/// which always carries `version`, `host`, and `short_message`.
+fn is_gelf(map: &serde_json::Map<String, serde_json::Value>) -> bool {
+    map.get("version").is_some_and(serde_json::Value::is_string)
+        && map.contains_key("host")
+        && map.contains_key("short_message")
+}
+
+/// Rewrite a GELF payload into the shape the alias tables expect: the
+/// numeric syslog `level` becomes a level name, `_`-prefixed custom fields
+/// lose their prefix (unless that name is taken), and the `version` and a
+/// `full_message` repeating `short_message` are dropped.
+fn normalize_gelf(map: &mut serde_json::Map<String, serde_json::Value>) {
+    map.remove("version");
+    if let Some(level) = map.get_mut("level")
+        && let Some(n) = level.as_i64()
+    {
+        *level = Level::from_syslog(n).name().into();
+    }
+    if map.get("full_message") == map.get("short_message") {
+        map.remove("full_message");
+    }
+    let custom: Vec<String> = map
+        .keys()
+        .filter(|key| key.len() > 1 && key.starts_with('_'))
+        .cloned()
+        .collect();
+    for key in custom {
+        let name = key[1..].to_string();
+        if !map.contains_key(&name)
+            && let Some(value) = map.remove(&key)
+        {
+            map.insert(name, value);
+        }
+    }
+}
+
 /// Extract the timestamp field using config override or alias table.
 fn extract_timestamp(
     map: &mut serde_json::Map<String, serde_json::Value>,
     config: &Config,
 ) -> Option<Timestamp> {
-    if let Some(ref key) = config.timestamp_key {
-        map.remove(key.as_str())
-            .and_then(|v| Timestamp::from_json_value(&v))
+    if let Some(ref keys) = config.timestamp_key {
+        remove_first(map, keys).and_then(|v| Timestamp::from_json_value(&v))
     } else {
         fields::find_and_remove(map, fields::TIMESTAMP_ALIASES)
             .and_then(|(_, v)| Timestamp::from_json_value(&v))
@@ -213,12 +544,12 @@ fn extract_level(
     map: &mut serde_json::Map<String, serde_json::Value>,
     config: &Config,
 ) -> Option<Level> {
-    if let Some(ref key) = config.level_key {
-        map.remove(key.as_str())
-            .and_then(|v| Level::from_json_value(&v, config.level_aliases.as_ref()))
+    if let Some(ref keys) = config.level_key {
+        remove_first(map, keys)
+            .and_then(|v| Level::from_json_value(&v, config.level_aliases.as_deref()))
     } else {
         fields::find_and_remove(map, fields::LEVEL_ALIASES)
-            .and_then(|(_, v)| Level::from_json_value(&v, config.level_aliases.as_ref()))
+            .and_then(|(_, v)| Level::from_json_value(&v, config.level_aliases.as_deref()))
     }
 }
 
@@ -227,13 +558,37 @@ fn extract_message(
     map: &mut serde_json::Map<String, serde_json::Value>,
     config: &Config,
 ) -> Option<String> {
-    if let Some(ref key) = config.message_key {
-        map.remove(key.as_str()).and_then(value_to_string)
+    if let Some(ref fields) = config.message_fields {
+        compose_message(map, fields, &config.message_separator)
+    } else if let Some(ref keys) = config.message_key {
+        remove_first(map, keys).and_then(value_to_string)
     } else {
         fields::find_and_remove(map, fields::MESSAGE_ALIASES).and_then(|(_, v)| value_to_string(v))
     }
 }
 
+/// Join the values of `fields` (in order) into one message, removing them
+/// from the map. Absent and `null` fields are skipped.
+fn compose_message(
+    map: &mut serde_json::Map<String, serde_json::Value>,
+    fields: &[String],
+    separator: &str,
+) -> Option<String> {
+    let parts: Vec<String> = fields
+        .iter()
+        .filter_map(|field| map.remove(field.as_str()).and_then(value_to_string))
+        .collect();
+    (!parts.is_empty()).then(|| parts.join(separator))
+}
+
+/// Remove the first of `keys` present in the map (an ordered fallback list).
+fn remove_first(
+    map: &mut serde_json::Map<String, serde_json::Value>,
+    keys: &[String],
+) -> Option<serde_json::Value> {
+    keys.iter().find_map(|key| map.remove(key.as_str()))
+}
+
 /// Extract the logger field using config override or alias table.
 fn extract_logger(
     map: &mut serde_json::Map<String, serde_json::Value>,
@@ -252,12 +607,38 @@ fn extract_caller(
     config: &Config,
 ) -> Option<String> {
     if let Some(ref key) = config.caller_key {
-        map.remove(key.as_str()).and_then(value_to_string)
+        map.remove(key.as_str()).and_then(caller_to_string)
     } else {
-        fields::find_and_remove(map, fields::CALLER_ALIASES).and_then(|(_, v)| value_to_string(v))
+        fields::find_and_remove(map, fields::CALLER_ALIASES).and_then(|(_, v)| caller_to_string(v))
     }
 }
 
+/// Caller as text. Structured locations such as Bunyan's `src` or slog's
+/// `source` (`{"file":…,"line":…,"function":…}`) become `file:line in
+/// function`; other values are rendered like any field.
+pub(crate) fn caller_to_string(v: serde_json::Value) -> Option<String> {
+    let serde_json::Value::Object(ref location) = v else {
+        return value_to_string(v);
+    };
+    let text = |keys: &[&str]| {
+        keys.iter()
+            .find_map(|key| location.get(*key))
+            .and_then(|value| value_to_string(value.clone()))
+    };
+    let file = text(&["file", "filename", "path"]);
+    let line = text(&["line", "lineno"]);
+    let function = text(&["function", "func", "funcName"]);
+    let place = match (file, line) {
+        (Some(file), Some(line)) => format!("{file}:{line}"),
+        (Some(file), None) => file,
+        (None, _) => return function.or_else(|| value_to_string(v)),
+    };
+    Some(match function {
+        Some(function) => format!("{place} in {function}"),
+        None => place,
+    })
+}
+
 /// Extract the error field using config override or alias table.
 fn extract_error(
     map: &mut serde_json::Map<String, serde_json::Value>,
@@ -430,10 +811,8 @@ fn value_to_string(v: serde_json::Value) -> Option<String> {
 ///
 /// Arrays are NOT flattened — kept as-is.
 /// Objects deeper than 1 level are kept as compact JSON.
-fn flatten_extra(
-    map: serde_json::Map<String, serde_json::Value>,
-) -> BTreeMap<String, serde_json::Value> {
-    let mut result = BTreeMap::new();
+fn flatten_extra(map: serde_json::Map<String, serde_json::Value>) -> Extras {
+    let mut result = Extras::new();
 
     for (key, value) in map {
         match value {
@@ -461,6 +840,31 @@ mod tests {
         Config::default()
     }
 
+    #[test]
+    fn test_to_normalized_json() {
+        let line = r#"{"level":"WARN","msg":"slow","time":"2026-01-15T10:30:00Z","message":"x","port":8080}"#;
+        let LineKind::Json(record) = parse_line(line, &default_config()) else {
+            panic!("expected JSON record");
+        };
+        let json = record.to_normalized_json();
+        assert_eq!(json["level"], "warn");
+        assert_eq!(json["message"], "slow");
+        assert_eq!(json["timestamp"], "2026-01-15T10:30:00Z");
+        assert_eq!(json["port"], 8080);
+    }
+
+    #[test]
+    fn test_to_flat_json() {
+        let line = r#"{"level":"WARN","msg":"slow","time":"2026-01-15T10:30:00Z","ts":"x","http":{"status":503}}"#;
+        let LineKind::Json(record) = parse_line(line, &default_config()) else {
+            panic!("expected JSON record");
+        };
+        assert_eq!(
+            record.to_flat_json(),
+            r#"{"ts":"2026-01-15T10:30:00Z","level":"warn","msg":"slow","http.status":503}"#
+        );
+    }
+
     #[test]
     fn test_parse_pure_json() {
         let line = r#"{"level":"info","msg":"hello","port":8080}"#;
@@ -489,6 +893,35 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_prefix_pattern_derives_fields() {
+        let config = Config {
+            prefix_pattern: Some(
+                regex::Regex::new(r"^(?P<pod>\S+) (?P<stream>stdout|stderr) ").unwrap(),
+            ),
+            ..default_config()
+        };
+        let line = r#"api-7f9c stderr 10:15:00 {"msg":"boom","stream":"json"}"#;
+        match parse_line(line, &config) {
+            LineKind::EmbeddedJson { prefix, record } => {
+                assert_eq!(prefix, "10:15:00 ");
+                assert_eq!(record.extra["pod"], "api-7f9c");
+                // JSON fields win over captures.
+                assert_eq!(record.extra["stream"], "json");
+            }
+            _ => panic!("Expected EmbeddedJson variant"),
+        }
+
+        // Non-matching prefixes are left alone.
+        match parse_line(r#"plain {"msg":"hi"}"#, &config) {
+            LineKind::EmbeddedJson { prefix, record } => {
+                assert_eq!(prefix, "plain ");
+                assert!(!record.extra.contains_key("pod"));
+            }
+            _ => panic!("Expected EmbeddedJson variant"),
+        }
+    }
+
     #[test]
     fn test_parse_raw() {
         let line = "Just a plain text log line";
@@ -532,8 +965,8 @@ mod tests {
     #[test]
     fn test_custom_keys() {
         let config = Config {
-            message_key: Some("event".to_string()),
-            level_key: Some("sev".to_string()),
+            message_key: Some(vec!["event".to_string()]),
+            level_key: Some(vec!["sev".to_string()]),
             ..Config::default()
         };
         let line = r#"{"sev":"warn","event":"disk full"}"#;
@@ -547,6 +980,73 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_custom_key_fallback_list() {
+        let config = Config {
+            message_key: Some(vec!["event".to_string(), "description".to_string()]),
+            timestamp_key: Some(vec!["at".to_string(), "when".to_string()]),
+            ..Config::default()
+        };
+        for line in [
+            r#"{"event":"from event","description":"other","at":"2026-01-15T10:30:00Z"}"#,
+            r#"{"description":"from event","when":"2026-01-15T10:30:00Z"}"#,
+        ] {
+            let LineKind::Json(record) = parse_line(line, &config) else {
+                panic!("Expected Json variant");
+            };
+            assert_eq!(record.message.as_deref(), Some("from event"));
+            assert!(record.timestamp.is_some());
+        }
+        // Only the key that was used is consumed
+        let line = r#"{"event":"a","description":"b"}"#;
+        let LineKind::Json(record) = parse_line(line, &config) else {
+            panic!("Expected Json variant");
+        };
+        assert_eq!(record.extra.get("description"), Some(&json!("b")));
+    }
+
+    #[test]
+    fn test_compose_message_from_fields() {
+        let config = Config {
+            message_fields: Some(vec!["msg".to_string(), "detail".to_string()]),
+            ..Config::default()
+        };
+        let line = r#"{"level":"error","msg":"charge failed","detail":"card declined","id":7}"#;
+        let LineKind::Json(record) = parse_line(line, &config) else {
+            panic!("Expected Json variant");
+        };
+        assert_eq!(
+            record.message.as_deref(),
+            Some("charge failed: card declined")
+        );
+        assert!(!record.extra.contains_key("detail"));
+        assert_eq!(record.extra.get("id"), Some(&json!(7)));
+
+        // Missing fields are skipped
+        let LineKind::Json(record) = parse_line(r#"{"detail":"only detail"}"#, &config) else {
+            panic!("Expected Json variant");
+        };
+        assert_eq!(record.message.as_deref(), Some("only detail"));
+    }
+
+    #[test]
+    fn test_keep_extracted_restores_detected_fields() {
+        let config = Config {
+            keep_extracted: true,
+            ..Config::default()
+        };
+        let line = r#"{"ts":"2026-01-15T10:30:00Z","level":"warn","msg":"hi","port":1}"#;
+        let LineKind::Json(record) = parse_line(line, &config) else {
+            panic!("Expected Json variant");
+        };
+        assert_eq!(record.level, Some(Level::Warn));
+        assert_eq!(record.message.as_deref(), Some("hi"));
+        assert!(record.timestamp.is_some());
+        for key in ["ts", "level", "msg", "port"] {
+            assert!(record.extra.contains_key(key), "missing {key}");
+        }
+    }
+
     #[test]
     fn test_malformed_json_is_raw() {
         let line = r#"{"level":"info", "msg":}"#; // trailing comma, invalid
@@ -932,7 +1432,7 @@ mod tests {
         // When --message-key=event is set, the "msg" alias field should NOT be
         // consumed and should remain in extra.
         let config = Config {
-            message_key: Some("event".to_string()),
+            message_key: Some(vec!["event".to_string()]),
             ..Config::default()
         };
         let line = r#"{"level":"info","msg":"alias msg","event":"custom msg"}"#;
@@ -1119,6 +1619,144 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_syslog_line() {
+        let line = "<165>1 2026-01-15T10:30:00Z web-1 api 42 - - request done";
+        let LineKind::Json(record) = parse_line(line, &default_config()) else {
+            panic!("expected a record");
+        };
+        assert_eq!(record.level, Some(Level::Info));
+        assert_eq!(record.message.as_deref(), Some("request done"));
+        assert_eq!(record.logger.as_deref(), Some("api"));
+
+        // A JSON message wins over the header.
+        let line = r#"<11>1 - web-1 api - - - {"level":"warn","msg":"slow","ms":900}"#;
+        let LineKind::Json(record) = parse_line(line, &default_config()) else {
+            panic!("expected a record");
+        };
+        assert_eq!(record.level, Some(Level::Warn));
+        assert_eq!(record.message.as_deref(), Some("slow"));
+        assert_eq!(record.extra["ms"], 900);
+        assert_eq!(record.extra["hostname"], "web-1");
+    }
+
+    #[test]
+    fn test_cri_line() {
+        let line = r#"2026-01-15T10:30:00.123456789Z stdout F {"level":"info","msg":"ready"}"#;
+        let LineKind::Json(record) = parse_line(line, &default_config()) else {
+            panic!("expected a record");
+        };
+        assert_eq!(record.message.as_deref(), Some("ready"));
+        assert_eq!(record.timestamp.unwrap().value.as_second(), 1_768_473_000);
+
+        // The payload's own timestamp wins.
+        let line = r#"2026-01-15T10:30:00Z stderr F {"ts":"2026-01-15T10:29:59Z","msg":"x"}"#;
+        let LineKind::Json(record) = parse_line(line, &default_config()) else {
+            panic!("expected a record");
+        };
+        assert_eq!(record.timestamp.unwrap().value.as_second(), 1_768_472_999);
+
+        // A klog payload is read as klog.
+        let line = "2026-01-15T10:30:00Z stderr F W0115 10:30:00.000000 7 main.go:9] low disk";
+        let LineKind::Json(record) = parse_line(line, &default_config()) else {
+            panic!("expected a record");
+        };
+        assert_eq!(record.level, Some(Level::Warn));
+
+        assert!(matches!(
+            parse_line(
+                "2026-01-15T10:30:00Z stdout F plain text",
+                &default_config()
+            ),
+            LineKind::Raw(None)
+        ));
+    }
+
+    #[test]
+    fn test_docker_envelope() {
+        let line = r#"{"log":"{\"level\":\"warn\",\"msg\":\"slow\"}\n","stream":"stderr","time":"2026-01-15T10:30:00.5Z"}"#;
+        let LineKind::Json(record) = parse_line(line, &default_config()) else {
+            panic!("expected a record");
+        };
+        assert_eq!(record.level, Some(Level::Warn));
+        assert_eq!(record.message.as_deref(), Some("slow"));
+        assert_eq!(record.extra["stream"], "stderr");
+        assert!(record.timestamp.is_some());
+        assert_eq!(record.raw_json, r#"{"level":"warn","msg":"slow"}"#);
+
+        // Plain text becomes the message; attrs become fields.
+        let line = r#"{"log":"listening on :80\n","stream":"stdout","time":"2026-01-15T10:30:00Z","attrs":{"tag":"web"}}"#;
+        let LineKind::Json(record) = parse_line(line, &default_config()) else {
+            panic!("expected a record");
+        };
+        assert_eq!(record.message.as_deref(), Some("listening on :80"));
+        assert_eq!(record.extra["tag"], "web");
+        assert_eq!(record.extra["stream"], "stdout");
+
+        // Extra keys mean it is not an envelope.
+        let line = r#"{"log":"x","stream":"stdout","time":"2026-01-15T10:30:00Z","level":"error"}"#;
+        let LineKind::Json(record) = parse_line(line, &default_config()) else {
+            panic!("expected a record");
+        };
+        assert_eq!(record.level, Some(Level::Error));
+    }
+
+    #[test]
+    fn test_klog_line() {
+        let line = "E0115 10:30:00.123456    1234 server.go:42] connection refused";
+        let LineKind::Json(record) = parse_line(line, &default_config()) else {
+            panic!("expected a record");
+        };
+        assert_eq!(record.level, Some(Level::Error));
+        assert_eq!(record.message.as_deref(), Some("connection refused"));
+        assert_eq!(record.caller.as_deref(), Some("server.go:42"));
+        assert!(record.timestamp.is_some());
+    }
+
+    #[test]
+    fn test_gelf_normalization() {
+        let line = r#"{"version":"1.1","host":"web-1","short_message":"disk full","full_message":"disk full\n/dev/sda1 100%","timestamp":1768473000.5,"level":3,"_user_id":42,"_host":"shadowed"}"#;
+        let LineKind::Json(record) = parse_line(line, &default_config()) else {
+            panic!("expected JSON record");
+        };
+        assert_eq!(record.level, Some(Level::Error));
+        assert_eq!(record.message.as_deref(), Some("disk full"));
+        assert!(record.timestamp.is_some());
+        assert_eq!(record.extra["user_id"], 42);
+        assert_eq!(record.extra["host"], "web-1");
+        assert_eq!(record.extra["_host"], "shadowed");
+        assert!(record.extra.contains_key("full_message"));
+        assert!(!record.extra.contains_key("version"));
+
+        // Numeric levels outside GELF keep the bunyan/pino scale.
+        let LineKind::Json(record) = parse_line(r#"{"level":3,"msg":"x"}"#, &default_config())
+        else {
+            panic!("expected JSON record");
+        };
+        assert_eq!(record.level, Some(Level::Trace));
+    }
+
+    #[test]
+    fn test_structured_caller() {
+        let config = default_config();
+        let caller = |line: &str| match parse_line(line, &config) {
+            LineKind::Json(record) => record.caller,
+            _ => panic!("Expected Json variant"),
+        };
+        assert_eq!(
+            caller(r#"{"msg":"hi","src":{"file":"app.js","line":12,"func":"handle"}}"#).as_deref(),
+            Some("app.js:12 in handle")
+        );
+        assert_eq!(
+            caller(r#"{"msg":"hi","source":{"file":"/srv/main.go","line":42}}"#).as_deref(),
+            Some("/srv/main.go:42")
+        );
+        assert_eq!(
+            caller(r#"{"msg":"hi","source":{"host":"db1"}}"#).as_deref(),
+            Some(r#"{"host":"db1"}"#)
+        );
+    }
+
     #[test]
     fn test_error_alias_extraction() {
         let config = default_config();
@@ -0,0 +1,220 @@
+//! Transform stages run on each JSON object before field detection.
+//!
+//! The `pipeline` list of the config file picks which [`Stage`]s run and in
+//! what order, e.g. `pipeline = ["unwrap_docker", "redact", "infer_level"]`.
+//! Without it, the envelope stages of [`Stage::DEFAULT`] run, which is how
+//! cor has always read Docker and GELF lines.
+
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use crate::config::Config;
+use crate::fields;
+use crate::level::Level;
+
+type Object = Map<String, Value>;
+
+/// A transform stage, named in `snake_case` in the config file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    /// Read the record inside a Docker `json-file` envelope.
+    UnwrapDocker,
+    /// Map a GELF payload onto the usual field names.
+    Gelf,
+    /// Mask the values of fields named like credentials.
+    Redact,
+    /// Take the level from the first word of the message when the record
+    /// has no level field.
+    InferLevel,
+    /// Give HTTP access records without a message a `METHOD path status`
+    /// message.
+    HttpSummary,
+}
+
+impl Stage {
+    /// Stages run when the config file has no `pipeline`.
+    pub const DEFAULT: [Self; 2] = [Self::UnwrapDocker, Self::Gelf];
+
+    /// Every stage, in declaration order.
+    pub const ALL: [Self; 5] = [
+        Self::UnwrapDocker,
+        Self::Gelf,
+        Self::Redact,
+        Self::InferLevel,
+        Self::HttpSummary,
+    ];
+
+    /// Name of the stage in the config file.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::UnwrapDocker => "unwrap_docker",
+            Self::Gelf => "gelf",
+            Self::Redact => "redact",
+            Self::InferLevel => "infer_level",
+            Self::HttpSummary => "http_summary",
+        }
+    }
+
+    /// Whether the stage can change field values, not just unwrap an
+    /// envelope.
+    pub const fn rewrites_fields(self) -> bool {
+        matches!(self, Self::Redact | Self::InferLevel | Self::HttpSummary)
+    }
+}
+
+/// Replacement of redacted values.
+const REDACTED: &str = "[REDACTED]";
+
+/// Key fragments marking a field as a credential.
+const SECRET_KEYS: &[&str] = &[
+    "password",
+    "passwd",
+    "secret",
+    "token",
+    "api_key",
+    "apikey",
+    "authorization",
+    "cookie",
+];
+
+/// Mask the values of credential fields, in nested objects too. Returns
+/// whether anything was masked.
+pub fn redact(map: &mut Object) -> bool {
+    let mut changed = false;
+    for (key, value) in map.iter_mut() {
+        let key = key.to_ascii_lowercase();
+        if SECRET_KEYS.iter().any(|secret| key.contains(secret)) {
+            *value = REDACTED.into();
+            changed = true;
+        } else if let Value::Object(nested) = value {
+            changed |= redact(nested);
+        }
+    }
+    changed
+}
+
+/// Add a `level` from the first word of the message (`ERROR: ...`,
+/// `[warn] ...`) to a record without a level field. Returns whether one
+/// was added.
+pub fn infer_level(map: &mut Object, config: &Config) -> bool {
+    let level_keys = config.level_key.as_deref();
+    let has_level = match level_keys {
+        Some(keys) => keys.iter().any(|key| map.contains_key(key)),
+        None => fields::find_key(map, fields::LEVEL_ALIASES).is_some(),
+    };
+    if has_level {
+        return false;
+    }
+    let level = message(map, config)
+        .and_then(|msg| msg.split_whitespace().next())
+        .map(|word| word.trim_matches(|c: char| !c.is_ascii_alphabetic()))
+        .filter(|word| word.len() >= 3)
+        .and_then(Level::from_str_loose);
+    let Some(level) = level else {
+        return false;
+    };
+    let key = level_keys
+        .and_then(<[String]>::first)
+        .map_or("level", String::as_str);
+    map.insert(key.to_string(), level.name().into());
+    true
+}
+
+/// Add a `METHOD path status` message to an HTTP access record without
+/// one, from top-level fields or an `http` object. Returns whether one was
+/// added.
+pub fn http_summary(map: &mut Object, config: &Config) -> bool {
+    if message(map, config).is_some() {
+        return false;
+    }
+    let text = |map: &Object, keys: &[&str]| {
+        let http = map.get("http").and_then(Value::as_object);
+        keys.iter().find_map(|key| {
+            let value = map.get(*key).or_else(|| http?.get(*key))?;
+            match value {
+                Value::String(s) => Some(s.clone()),
+                Value::Number(n) => Some(n.to_string()),
+                _ => None,
+            }
+        })
+    };
+    let (Some(method), Some(path)) = (
+        text(map, &["method", "http_method"]),
+        text(map, &["path", "url", "uri", "target"]),
+    ) else {
+        return false;
+    };
+    let mut summary = format!("{method} {path}");
+    if let Some(status) = text(map, &["status", "status_code", "statusCode"]) {
+        summary.push(' ');
+        summary.push_str(&status);
+    }
+    let key = config
+        .message_key
+        .as_deref()
+        .and_then(<[String]>::first)
+        .map_or("msg", String::as_str);
+    map.insert(key.to_string(), summary.into());
+    true
+}
+
+/// The message text of the object, if it has one.
+fn message<'a>(map: &'a Object, config: &Config) -> Option<&'a str> {
+    match config.message_key {
+        Some(ref keys) => keys.iter().find_map(|key| map.get(key)),
+        None => fields::MESSAGE_ALIASES.iter().find_map(|key| map.get(*key)),
+    }
+    .and_then(Value::as_str)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn object(value: Value) -> Object {
+        match value {
+            Value::Object(map) => map,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_redact() {
+        let mut map = object(serde_json::json!({
+            "user": "ann",
+            "Password": "hunter2",
+            "req": {"headers": {"Authorization": "Bearer x"}}
+        }));
+        assert!(redact(&mut map));
+        assert_eq!(map["user"], "ann");
+        assert_eq!(map["Password"], REDACTED);
+        assert_eq!(map["req"]["headers"]["Authorization"], REDACTED);
+    }
+
+    #[test]
+    fn test_infer_level() {
+        let config = Config::default();
+        let mut map = object(serde_json::json!({"msg": "[WARN] disk almost full"}));
+        infer_level(&mut map, &config);
+        assert_eq!(map["level"], "warn");
+
+        let mut map = object(serde_json::json!({"msg": "ERROR x", "severity": "info"}));
+        infer_level(&mut map, &config);
+        assert!(!map.contains_key("level"));
+    }
+
+    #[test]
+    fn test_http_summary() {
+        let config = Config::default();
+        let mut map = object(
+            serde_json::json!({"http": {"method": "GET", "path": "/health"}, "status": 200}),
+        );
+        http_summary(&mut map, &config);
+        assert_eq!(map["msg"], "GET /health 200");
+
+        let mut map = object(serde_json::json!({"msg": "kept", "method": "GET", "path": "/"}));
+        http_summary(&mut map, &config);
+        assert_eq!(map["msg"], "kept");
+    }
+}
@@ -0,0 +1,146 @@
+//! Highlighted JSON for `--json` and `--json-pretty`.
+//!
+//! [`push_json`] re-indents the text of a JSON value two spaces per level,
+//! like `jq`, and colors keys, strings, numbers, and literals;
+//! [`push_colored`] colors it in place for `--json`, values in the theme's
+//! `json_string`, `json_number`, and `json_literal` colors. Both work on
+//! the text rather than a parsed [`serde_json::Value`], so key order and
+//! number spelling stay as in the input, and without colors
+//! `push_colored` gives back the input byte for byte.
+
+use crate::layout::{Layout, Role};
+use crate::theme::Theme;
+
+/// Append `json`, the text of a valid JSON value, indented and colored.
+pub fn push_json(json: &str, theme: &Theme, out: &mut Layout) {
+    push_tokens(json, theme, true, out);
+}
+
+/// Append `json` colored, keeping its whitespace as it is.
+pub fn push_colored(json: &str, theme: &Theme, out: &mut Layout) {
+    push_tokens(json, theme, false, out);
+}
+
+/// Append the tokens of `json` colored, re-indented if `indent` is set and
+/// otherwise separated by the input's own whitespace.
+fn push_tokens(json: &str, theme: &Theme, indent: bool, out: &mut Layout) {
+    let bytes = json.as_bytes();
+    let next_token = |from: usize| (from..bytes.len()).find(|&i| !bytes[i].is_ascii_whitespace());
+    let newline = |depth: usize, out: &mut Layout| {
+        out.push_str("\n");
+        out.push_str(&"  ".repeat(depth));
+    };
+    let mut depth = 0;
+    let mut i = 0;
+    while let Some(start) = next_token(i) {
+        if !indent {
+            out.push_str(&json[i..start]);
+        }
+        i = start + 1;
+        match bytes[start] {
+            b'{' | b'[' | b'}' | b']' | b',' | b':' if !indent => out.push_str(&json[start..i]),
+            open @ (b'{' | b'[') => {
+                let close = if open == b'{' { b'}' } else { b']' };
+                out.push_str(&json[start..=start]);
+                if let Some(next) = next_token(i)
+                    && bytes[next] == close
+                {
+                    out.push_str(&json[next..=next]);
+                    i = next + 1;
+                } else {
+                    depth += 1;
+                    newline(depth, out);
+                }
+            }
+            b'}' | b']' => {
+                depth = depth.saturating_sub(1);
+                newline(depth, out);
+                out.push_str(&json[start..=start]);
+            }
+            b',' => {
+                out.push_str(",");
+                newline(depth, out);
+            }
+            b':' => out.push_str(": "),
+            b'"' => {
+                let mut escaped = false;
+                while i < bytes.len() && (escaped || bytes[i] != b'"') {
+                    escaped = !escaped && bytes[i] == b'\\';
+                    i += 1;
+                }
+                i = (i + 1).min(bytes.len());
+                let text = &json[start..i];
+                if next_token(i).is_some_and(|next| bytes[next] == b':') {
+                    out.push(Role::Key, theme.key.style(), text);
+                } else {
+                    out.push(Role::Value, theme.json_string.style(), text);
+                }
+            }
+            first => {
+                while i < bytes.len()
+                    && !b",:]}\"".contains(&bytes[i])
+                    && !bytes[i].is_ascii_whitespace()
+                {
+                    i += 1;
+                }
+                let paint = if first == b'-' || first.is_ascii_digit() {
+                    theme.json_number
+                } else {
+                    theme.json_literal
+                };
+                out.push(Role::Value, paint.style(), &json[start..i]);
+            }
+        }
+    }
+    if !indent {
+        out.push_str(&json[i..]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_indents_and_keeps_key_order() {
+        let mut out = Layout::default();
+        push_json(
+            r#"{"z":1.50,"a":{"s":"x,\"}","e":[],"l":[true,null]}}"#,
+            &Theme::default(),
+            &mut out,
+        );
+        assert_eq!(
+            out.text(),
+            "{\n  \"z\": 1.50,\n  \"a\": {\n    \"s\": \"x,\\\"}\",\n    \"e\": [],\n    \"l\": [\n      true,\n      null\n    ]\n  }\n}"
+        );
+        let roles: Vec<Role> = out.segments().iter().map(|s| s.role).collect();
+        assert_eq!(roles.iter().filter(|&&r| r == Role::Key).count(), 5);
+    }
+
+    #[test]
+    fn test_colored_keeps_text() {
+        let json = r#"{ "a" :[1, "x\"y"],"b":null } "#;
+        let mut out = Layout::default();
+        push_colored(json, &Theme::default(), &mut out);
+        assert_eq!(out.text(), json);
+        let styled: Vec<&str> = out
+            .segments()
+            .iter()
+            .map(|s| &json[s.range.clone()])
+            .collect();
+        assert_eq!(styled, [r#""a""#, "1", r#""x\"y""#, r#""b""#, "null"]);
+    }
+
+    #[test]
+    fn test_values_take_theme_colors() {
+        let theme = Theme {
+            json_number: crate::theme::Paint::parse("#ff8800").unwrap(),
+            ..Theme::default()
+        };
+        let mut out = Layout::default();
+        push_colored(r#"{"n":1,"s":"x"}"#, &theme, &mut out);
+        let styles: Vec<_> = out.segments().iter().map(|s| s.style).collect();
+        assert_eq!(styles[1], theme.json_number.style());
+        assert_eq!(styles[3], theme.json_string.style());
+    }
+}
@@ -0,0 +1,97 @@
+//! Per-record provenance in normalized JSON output (`--provenance`).
+//!
+//! [`Provenance`] records how cor derived a record: the input it came
+//! from, its line number, the reader that parsed it, the `--preset` in
+//! effect, and whether it was reassembled from several lines. It is added
+//! to the record under [`KEY`], so downstream systems can audit records
+//! that look wrong.
+
+use clap::ValueEnum;
+use serde_json::{Map, Value};
+
+use crate::cli::Preset;
+
+/// Key of the provenance object; the leading underscore keeps it apart
+/// from application fields.
+pub const KEY: &str = "_cor";
+
+/// How one record was derived.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Provenance<'a> {
+    /// Input name: a file path, or `-` for stdin.
+    pub source: &'a str,
+    /// Line number of the record's first line in `source`.
+    pub line: usize,
+    /// The reader that parsed the line, as `--explain` names it.
+    pub parser: String,
+    /// The `--preset` applied.
+    pub preset: Option<Preset>,
+    /// Whether the record was joined from several input lines.
+    pub reassembled: bool,
+}
+
+impl Provenance<'_> {
+    /// The `_cor` object; `profile` is null without a preset.
+    pub fn to_json(&self) -> Value {
+        let profile = self
+            .preset
+            .and_then(|preset| preset.to_possible_value())
+            .map(|value| value.get_name().to_string());
+        let mut map = Map::new();
+        map.insert("source".into(), self.source.into());
+        map.insert("line".into(), self.line.into());
+        map.insert("parser".into(), self.parser.clone().into());
+        map.insert("profile".into(), profile.into());
+        map.insert("reassembled".into(), self.reassembled.into());
+        Value::Object(map)
+    }
+}
+
+/// Add `provenance`, if any, under [`KEY`] to the normalized JSON `record`.
+pub fn insert(record: &mut Value, provenance: Option<&Value>) {
+    if let (Some(provenance), Some(map)) = (provenance, record.as_object_mut()) {
+        map.insert(KEY.into(), provenance.clone());
+    }
+}
+
+/// Add `provenance`, if any, under [`KEY`] as the last member of `object`,
+/// the text of a JSON object.
+pub fn append(object: &mut String, provenance: Option<&Value>) {
+    let (Some(provenance), Some(end)) = (provenance, object.rfind('}')) else {
+        return;
+    };
+    let separator = if object[..end].trim_end().ends_with('{') {
+        ""
+    } else {
+        ","
+    };
+    object.replace_range(end.., &format!("{separator}\"{KEY}\":{provenance}}}"));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_append_to_object_text() {
+        let provenance = Provenance {
+            source: "app.log",
+            line: 12,
+            parser: "JSON object".to_string(),
+            preset: Some(Preset::K8s),
+            reassembled: true,
+        }
+        .to_json();
+        let mut text = r#"{"msg":"hi"}"#.to_string();
+        append(&mut text, Some(&provenance));
+        assert_eq!(
+            text,
+            r#"{"msg":"hi","_cor":{"line":12,"parser":"JSON object","profile":"k8s","reassembled":true,"source":"app.log"}}"#
+        );
+
+        let mut empty = "{}".to_string();
+        append(&mut empty, Some(&Value::Null));
+        append(&mut empty, None);
+        assert_eq!(empty, r#"{"_cor":null}"#);
+    }
+}
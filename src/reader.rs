@@ -0,0 +1,196 @@
+//! Streaming library API.
+//!
+//! [`LogReader`] turns a [`BufRead`] into parsed entries the way the `cor`
+//! CLI reads its input: a line that looks like the start of a JSON object
+//! but fails to parse is joined with the lines after it until the record
+//! is complete, within `max_continuation_lines` and
+//! `max_continuation_bytes`. When that fails, every buffered line comes out
+//! on its own as a raw line. Reads block, so the continuation timeouts of
+//! [`Config`] do not apply.
+
+use std::collections::VecDeque;
+use std::io::{self, BufRead};
+
+use crate::config::Config;
+use crate::parser::{self, LineKind};
+use crate::reassembly::{Assembly, FailedShapeCache, might_start_json};
+
+/// Iterator of the entries read from `R`.
+///
+/// Like [`BufRead::lines`], a line that is not valid UTF-8 yields an error
+/// and reading can go on past it. An error while reading continuation lines
+/// ends the reassembly: the buffered lines come out raw, then the error.
+///
+/// # Example
+///
+/// ```
+/// use cor::{Config, LineKind, LogReader};
+///
+/// let input = "{\"level\":\"info\",\"msg\":\"line one\nline two\"}\nplain text\n";
+/// let config = Config::default();
+/// let mut reader = LogReader::new(input.as_bytes(), &config);
+///
+/// let Some(Ok(LineKind::Json(record))) = reader.next() else { panic!() };
+/// assert_eq!(record.message.as_deref(), Some("line one\nline two"));
+/// assert!(matches!(reader.next(), Some(Ok(LineKind::Raw(None)))));
+/// assert_eq!(reader.text(), "plain text");
+/// assert!(reader.next().is_none());
+/// ```
+#[derive(Debug)]
+pub struct LogReader<'a, R> {
+    lines: io::Lines<R>,
+    config: &'a Config,
+    failed_shapes: FailedShapeCache,
+    /// Lines of a failed reassembly still to be returned.
+    queued: VecDeque<String>,
+    /// Read error that ended a reassembly, returned after `queued`.
+    error: Option<io::Error>,
+    text: String,
+}
+
+impl<'a, R: BufRead> LogReader<'a, R> {
+    /// Read entries from `reader`, parsed with `config`.
+    pub fn new(reader: R, config: &'a Config) -> Self {
+        Self {
+            lines: reader.lines(),
+            config,
+            failed_shapes: FailedShapeCache::new(),
+            queued: VecDeque::new(),
+            error: None,
+            text: String::new(),
+        }
+    }
+
+    /// Input text of the entry last returned: the line itself, or for a
+    /// reassembled record the joined lines with the newlines inside strings
+    /// escaped.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Read continuation lines after `line` until the buffer parses as JSON
+    /// or a limit is hit.
+    fn reassemble(&mut self, line: String) -> LineKind {
+        let config = self.config;
+        let mut assembly = Assembly::new(line);
+        let mut assembled = None;
+        for _ in 0..config.max_continuation_lines {
+            let next = match self.lines.next() {
+                Some(Ok(l)) => l,
+                Some(Err(e)) => {
+                    self.error = Some(e);
+                    break;
+                }
+                None => break,
+            };
+            assembled = assembly.push(&next, config);
+            if assembled.is_some() || assembly.is_full(config) {
+                break;
+            }
+        }
+
+        // A read error says nothing about the line's shape.
+        if config.reassembly_cache && self.error.is_none() {
+            if assembled.is_some() {
+                self.failed_shapes.remove(assembly.first_line());
+            } else if assembly.is_broken() {
+                self.failed_shapes.insert(assembly.first_line());
+            }
+        }
+
+        if let Some((parsed, sanitized)) = assembled {
+            self.text = sanitized;
+            return parsed;
+        }
+        // Could not reassemble — return each buffered line as raw.
+        self.queued = assembly
+            .into_text()
+            .split('\n')
+            .map(str::to_owned)
+            .collect();
+        self.next_queued()
+    }
+
+    fn next_queued(&mut self) -> LineKind {
+        self.text = self.queued.pop_front().unwrap_or_default();
+        parser::parse_line(&self.text, self.config)
+    }
+}
+
+impl<R: BufRead> Iterator for LogReader<'_, R> {
+    type Item = io::Result<LineKind>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.queued.is_empty() {
+            return Some(Ok(self.next_queued()));
+        }
+        if let Some(e) = self.error.take() {
+            return Some(Err(e));
+        }
+        let line = match self.lines.next()? {
+            Ok(line) => line,
+            Err(e) => return Some(Err(e)),
+        };
+        let parsed = parser::parse_line(&line, self.config);
+        if matches!(parsed, LineKind::Raw(_))
+            && might_start_json(&line)
+            && !(self.config.reassembly_cache && self.failed_shapes.should_skip(&line))
+        {
+            return Some(Ok(self.reassemble(line)));
+        }
+        self.text = line;
+        Some(Ok(parsed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_failed_reassembly_returns_each_line() {
+        let config = Config {
+            max_continuation_lines: 2,
+            ..Config::default()
+        };
+        let input = "{\"msg\":\"never closed\none\ntwo\n{\"msg\":\"ok\"}\n";
+        let mut reader = LogReader::new(input.as_bytes(), &config);
+        let mut texts = Vec::new();
+        while let Some(entry) = reader.next() {
+            let json = matches!(entry.unwrap(), LineKind::Json(_));
+            texts.push((reader.text().to_string(), json));
+        }
+        assert_eq!(
+            texts,
+            [
+                ("{\"msg\":\"never closed".to_string(), false),
+                ("one".to_string(), false),
+                ("two".to_string(), false),
+                ("{\"msg\":\"ok\"}".to_string(), true),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_read_error_ends_reassembly_after_buffered_lines() {
+        let config = Config::default();
+        let input: &[u8] = b"{\"msg\":\"open\none\n\xff\n{\"msg\":\"ok\"}\n";
+        let mut reader = LogReader::new(input, &config);
+        let mut texts = Vec::new();
+        while let Some(entry) = reader.next() {
+            texts.push(match entry {
+                Ok(_) => reader.text().to_string(),
+                Err(e) => format!("error: {:?}", e.kind()),
+            });
+        }
+        assert_eq!(
+            texts,
+            [
+                "{\"msg\":\"open",
+                "one",
+                "error: InvalidData",
+                "{\"msg\":\"ok\"}",
+            ]
+        );
+    }
+}
@@ -0,0 +1,306 @@
+//! Helpers for multi-line JSON reassembly.
+//!
+//! When a line looks like the start of a JSON object but fails to parse
+//! ([`might_start_json`]), the reader buffers continuation lines in an
+//! [`Assembly`] and retries. Streams where every
+//! line has the same broken shape (e.g. binary garbage containing `{"`)
+//! would pay that buffering cost over and over; [`FailedShapeCache`]
+//! remembers the shapes of recent lines whose buffer turned out to be
+//! malformed JSON ([`Assembly::is_broken`]) so reassembly can be skipped for
+//! them. A record that only ran past a limit, like a long traceback, says
+//! nothing about the next line of its shape and is not remembered.
+//! Every [`SHAPE_RETRY_AFTER`] skips a shape gets another attempt, so a
+//! stream that starts sending well-formed multi-line records is noticed.
+
+use std::collections::VecDeque;
+
+use crate::config::Config;
+use crate::parser::{self, LineKind};
+
+/// Number of distinct failed shapes remembered before the oldest is evicted.
+const SHAPE_CACHE_CAPACITY: usize = 64;
+
+/// Lines of a failed shape skipped before reassembly is tried again.
+pub const SHAPE_RETRY_AFTER: u32 = 1024;
+
+/// Maximum number of characters of the prefix (before `{`) kept in a shape.
+const SHAPE_PREFIX_CHARS: usize = 32;
+
+/// Maximum number of characters after `{` kept in a shape.
+const SHAPE_BODY_CHARS: usize = 16;
+
+/// Check if a line might be the start of an incomplete JSON object.
+///
+/// Returns `true` if the line contains `{"` which is a strong indicator
+/// of a JSON object start. This avoids false positives from lines that
+/// contain stray `{` characters (e.g., code snippets).
+pub fn might_start_json(line: &str) -> bool {
+    let trimmed = line.trim();
+    if let Some(brace_pos) = trimmed.find('{') {
+        let after_brace = &trimmed[brace_pos + 1..];
+        after_brace.trim_start().starts_with('"')
+    } else {
+        false
+    }
+}
+
+/// A JSON object being reassembled from continuation lines.
+#[derive(Debug, Clone)]
+pub struct Assembly {
+    buffer: String,
+}
+
+impl Assembly {
+    /// Start reassembling from the line that failed to parse.
+    pub const fn new(first_line: String) -> Self {
+        Self { buffer: first_line }
+    }
+
+    /// Append a continuation line and re-parse the buffer with raw newlines
+    /// inside JSON strings escaped. Once it parses, returns the record and
+    /// the sanitized text it was parsed from.
+    pub fn push(&mut self, line: &str, config: &Config) -> Option<(LineKind, String)> {
+        self.buffer.push('\n');
+        self.buffer.push_str(line);
+        let sanitized = parser::sanitize_json_newlines(&self.buffer);
+        let parsed = parser::parse_line(&sanitized, config);
+        (!matches!(parsed, LineKind::Raw(_))).then(|| (parsed, sanitized.into_owned()))
+    }
+
+    /// Whether the buffer reached `max_continuation_bytes`.
+    pub const fn is_full(&self, config: &Config) -> bool {
+        config.max_continuation_bytes > 0 && self.buffer.len() >= config.max_continuation_bytes
+    }
+
+    /// Whether the buffer is malformed JSON rather than just incomplete, so
+    /// no further lines could make it parse.
+    pub fn is_broken(&self) -> bool {
+        let Some(brace) = self.buffer.find('{') else {
+            return true;
+        };
+        let sanitized = parser::sanitize_json_newlines(&self.buffer[brace..]);
+        serde_json::from_str::<serde::de::IgnoredAny>(&sanitized).is_err_and(|e| !e.is_eof())
+    }
+
+    /// The buffered lines, joined with `\n`.
+    pub fn text(&self) -> &str {
+        &self.buffer
+    }
+
+    /// The line reassembly started from.
+    pub fn first_line(&self) -> &str {
+        self.buffer.split('\n').next().unwrap_or_default()
+    }
+
+    /// The buffered lines, joined with `\n`.
+    pub fn into_text(self) -> String {
+        self.buffer
+    }
+}
+
+/// Compute the shape signature of a line that might start a JSON object.
+///
+/// The shape is the text before the first `{` plus a short window after it,
+/// with ASCII digits collapsed to `0` so lines that differ only in
+/// timestamps, counters, or IDs share a shape.
+pub fn line_shape(line: &str) -> String {
+    let trimmed = line.trim();
+    let (prefix, body) = match trimmed.find('{') {
+        Some(pos) => (&trimmed[..pos], &trimmed[pos..]),
+        None => (trimmed, ""),
+    };
+
+    let mut shape = String::with_capacity(SHAPE_PREFIX_CHARS + SHAPE_BODY_CHARS);
+    let normalize = |c: char| if c.is_ascii_digit() { '0' } else { c };
+    shape.extend(prefix.chars().take(SHAPE_PREFIX_CHARS).map(normalize));
+    shape.extend(body.chars().take(SHAPE_BODY_CHARS).map(normalize));
+    shape
+}
+
+/// Bounded LRU cache of line shapes whose reassembly recently failed.
+#[derive(Debug, Default)]
+pub struct FailedShapeCache {
+    /// Shapes, least recently skipped first, with their skip counts.
+    shapes: VecDeque<(String, u32)>,
+}
+
+impl FailedShapeCache {
+    /// Create an empty cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns `true` if reassembly already failed for a line of this shape
+    /// and should be skipped. After [`SHAPE_RETRY_AFTER`] skips the shape is
+    /// forgotten and `false` returned, so reassembly is retried.
+    pub fn should_skip(&mut self, line: &str) -> bool {
+        let shape = line_shape(line);
+        let Some(index) = self.shapes.iter().position(|(s, _)| *s == shape) else {
+            return false;
+        };
+        let Some((shape, skips)) = self.shapes.remove(index) else {
+            return false;
+        };
+        if skips >= SHAPE_RETRY_AFTER {
+            return false;
+        }
+        self.shapes.push_back((shape, skips + 1));
+        true
+    }
+
+    /// Remember that reassembly failed for a line of this shape.
+    pub fn insert(&mut self, line: &str) {
+        let shape = line_shape(line);
+        if self.shapes.iter().any(|(s, _)| *s == shape) {
+            return;
+        }
+        if self.shapes.len() == SHAPE_CACHE_CAPACITY {
+            self.shapes.pop_front();
+        }
+        self.shapes.push_back((shape, 0));
+    }
+
+    /// Forget a shape after a line of that shape was reassembled successfully.
+    pub fn remove(&mut self, line: &str) {
+        let shape = line_shape(line);
+        self.shapes.retain(|(s, _)| *s != shape);
+    }
+
+    /// Number of shapes currently remembered.
+    pub fn len(&self) -> usize {
+        self.shapes.len()
+    }
+
+    /// Returns `true` if no shapes are remembered.
+    pub fn is_empty(&self) -> bool {
+        self.shapes.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_might_start_json() {
+        assert!(might_start_json(r#"{"msg": "unterminated"#));
+        assert!(might_start_json(r#"app: { "level""#));
+        assert!(!might_start_json("fn main() {"));
+        assert!(!might_start_json("plain text"));
+    }
+
+    #[test]
+    fn test_assembly_parses_once_complete() {
+        let config = Config::default();
+        let mut assembly = Assembly::new(r#"{"level":"error","msg":"first"#.to_string());
+        assert!(assembly.push("second", &config).is_none());
+        let (parsed, text) = assembly.push(r#"third"}"#, &config).unwrap();
+        assert!(
+            matches!(parsed, LineKind::Json(ref r) if r.message.as_deref() == Some("first\nsecond\nthird"))
+        );
+        assert_eq!(text, r#"{"level":"error","msg":"first\nsecond\nthird"}"#);
+        assert_eq!(assembly.first_line(), r#"{"level":"error","msg":"first"#);
+        assert_eq!(assembly.text().lines().count(), 3);
+    }
+
+    #[test]
+    fn test_assembly_is_broken_only_on_malformed_json() {
+        let config = Config::default();
+        let mut assembly = Assembly::new(r#"{"level":"error","msg":"first"#.to_string());
+        assert!(assembly.push("  at frame", &config).is_none());
+        assert!(!assembly.is_broken());
+        let mut assembly = Assembly::new(r#"{"level":"error" garbage"#.to_string());
+        assert!(assembly.push("more", &config).is_none());
+        assert!(assembly.is_broken());
+    }
+
+    #[test]
+    fn test_line_shape_normalizes_digits() {
+        assert_eq!(
+            line_shape(r#"2026-01-15 10:30:00 {"level":1"#),
+            line_shape(r#"2027-12-31 23:59:59 {"level":9"#)
+        );
+    }
+
+    #[test]
+    fn test_line_shape_distinguishes_prefixes() {
+        assert_ne!(line_shape(r#"app-a {"msg""#), line_shape(r#"app-b {"msg""#));
+    }
+
+    #[test]
+    fn test_line_shape_bounded_length() {
+        let long = format!("{} {{\"{}", "x".repeat(500), "y".repeat(500));
+        assert_eq!(
+            line_shape(&long).chars().count(),
+            SHAPE_PREFIX_CHARS + SHAPE_BODY_CHARS
+        );
+    }
+
+    #[test]
+    fn test_cache_insert_and_contains() {
+        let mut cache = FailedShapeCache::new();
+        assert!(cache.is_empty());
+        cache.insert(r#"\x00\x01 {"garbage 1"#);
+        assert!(cache.should_skip(r#"\x00\x01 {"garbage 2"#));
+        assert!(!cache.should_skip(r#"{"level":"info""#));
+        // Duplicate shapes are stored once
+        cache.insert(r#"\x00\x01 {"garbage 3"#);
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn test_cache_remove() {
+        let mut cache = FailedShapeCache::new();
+        cache.insert(r#"prefix {"a"#);
+        cache.remove(r#"prefix {"a"#);
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_cache_evicts_oldest() {
+        let mut cache = FailedShapeCache::new();
+        // Digits normalize away, so build distinct letter-only prefixes.
+        let prefix = |i: usize| {
+            let hi = char::from(b'a' + u8::try_from(i / 26).unwrap());
+            let lo = char::from(b'a' + u8::try_from(i % 26).unwrap());
+            format!("{hi}{lo} {{\"x")
+        };
+        for i in 0..=SHAPE_CACHE_CAPACITY {
+            cache.insert(&prefix(i));
+        }
+        assert_eq!(cache.len(), SHAPE_CACHE_CAPACITY);
+        assert!(
+            !cache.should_skip(&prefix(0)),
+            "oldest shape should be evicted"
+        );
+        assert!(cache.should_skip(&prefix(SHAPE_CACHE_CAPACITY)));
+    }
+
+    #[test]
+    fn test_cache_keeps_recently_skipped_shapes() {
+        let mut cache = FailedShapeCache::new();
+        let prefix = |i: usize| {
+            let hi = char::from(b'a' + u8::try_from(i / 26).unwrap());
+            let lo = char::from(b'a' + u8::try_from(i % 26).unwrap());
+            format!("{hi}{lo} {{\"x")
+        };
+        for i in 0..SHAPE_CACHE_CAPACITY {
+            cache.insert(&prefix(i));
+        }
+        assert!(cache.should_skip(&prefix(0)));
+        cache.insert(&prefix(SHAPE_CACHE_CAPACITY));
+        assert!(cache.should_skip(&prefix(0)));
+        assert!(!cache.should_skip(&prefix(1)));
+    }
+
+    #[test]
+    fn test_cache_retries_shape_after_many_skips() {
+        let mut cache = FailedShapeCache::new();
+        cache.insert(r#"garbage {"x"#);
+        for _ in 0..SHAPE_RETRY_AFTER {
+            assert!(cache.should_skip(r#"garbage {"x"#));
+        }
+        assert!(!cache.should_skip(r#"garbage {"x"#));
+        assert!(cache.is_empty());
+    }
+}
@@ -0,0 +1,215 @@
+//! Session recording (`--record`) and replay (`cor replay`).
+//!
+//! A recording is NDJSON: a header object followed by one entry per raw
+//! input line, stamped with the milliseconds elapsed since recording
+//! started. Lines are captured before any parsing or filtering, so a replay
+//! can re-render the same input with different flags. [`ReplayLines`]
+//! reproduces the original pacing, which also replays timing-dependent
+//! behavior such as `--continuation-timeout`.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, LineWriter, Write};
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::input::{LineSource, Next};
+
+/// Recording format version written to and accepted from the header.
+const FORMAT_VERSION: u32 = 1;
+
+/// First line of a recording.
+#[derive(Debug, Serialize, Deserialize)]
+struct Header {
+    cor_session: u32,
+    /// Whether entries carry input labels (e.g. from `--connect`).
+    #[serde(default)]
+    labels: bool,
+}
+
+/// One recorded input line.
+#[derive(Debug, Serialize, Deserialize)]
+struct Entry {
+    /// Milliseconds since the recording started.
+    t: u64,
+    /// Label of the input the line came from, for merged inputs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    src: Option<String>,
+    line: String,
+}
+
+/// Writes raw input lines to a session recording.
+#[derive(Debug)]
+pub struct Recorder {
+    writer: LineWriter<File>,
+    start: Instant,
+}
+
+impl Recorder {
+    /// Create (or truncate) the recording at `path`. `labels` marks whether
+    /// entries are tagged with their input, so a replay shows them too.
+    pub fn create(path: &Path, labels: bool) -> io::Result<Self> {
+        let mut writer = LineWriter::new(File::create(path)?);
+        let header = Header {
+            cor_session: FORMAT_VERSION,
+            labels,
+        };
+        serde_json::to_writer(&mut writer, &header)?;
+        writer.write_all(b"\n")?;
+        Ok(Self {
+            writer,
+            start: Instant::now(),
+        })
+    }
+
+    /// Append `line`, received now from the input labelled `src`.
+    pub fn record(&mut self, line: &str, src: Option<&str>) -> io::Result<()> {
+        let entry = Entry {
+            t: u64::try_from(self.start.elapsed().as_millis()).unwrap_or(u64::MAX),
+            src: src.map(str::to_owned),
+            line: line.to_owned(),
+        };
+        serde_json::to_writer(&mut self.writer, &entry)?;
+        self.writer.write_all(b"\n")
+    }
+
+    /// Flush buffered entries.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// [`LineSource`] replaying a recording with its original timing.
+#[derive(Debug)]
+pub struct ReplayLines<R> {
+    lines: io::Lines<R>,
+    /// Timing is divided by this factor; `0` replays without delays.
+    speed: f64,
+    labels: bool,
+    start: Instant,
+    /// Entry read but not yet due when the last deadline passed.
+    pending: Option<Entry>,
+    current: Option<String>,
+}
+
+impl ReplayLines<BufReader<File>> {
+    /// Open the recording at `path`.
+    pub fn open(path: &Path, speed: f64) -> io::Result<Self> {
+        Self::new(BufReader::new(File::open(path)?), speed)
+    }
+}
+
+impl<R: BufRead> ReplayLines<R> {
+    /// Replay the recording read from `reader`, validating its header.
+    pub fn new(reader: R, speed: f64) -> io::Result<Self> {
+        let mut lines = reader.lines();
+        let header: Header = match lines.next() {
+            Some(line) => serde_json::from_str(&line?).map_err(|_| not_a_recording())?,
+            None => return Err(not_a_recording()),
+        };
+        if header.cor_session != FORMAT_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unsupported recording version {}", header.cor_session),
+            ));
+        }
+        Ok(Self {
+            lines,
+            speed,
+            labels: header.labels,
+            start: Instant::now(),
+            pending: None,
+            current: None,
+        })
+    }
+
+    /// Whether the recorded lines carry input labels.
+    pub const fn has_labels(&self) -> bool {
+        self.labels
+    }
+
+    /// When `entry` is due, relative to the start of the replay.
+    fn due(&self, entry: &Entry) -> Instant {
+        if self.speed <= 0.0 {
+            return self.start;
+        }
+        #[allow(clippy::cast_precision_loss)]
+        let offset = Duration::from_secs_f64(entry.t as f64 / 1000.0 / self.speed);
+        self.start + offset
+    }
+}
+
+impl<R: BufRead> LineSource for ReplayLines<R> {
+    fn next_line(&mut self, deadline: Option<Instant>) -> Next {
+        let entry = match self.pending.take() {
+            Some(entry) => entry,
+            None => match self.lines.next() {
+                None => return Next::Eof,
+                Some(Err(e)) => return Next::Line(Err(e)),
+                Some(Ok(line)) => match serde_json::from_str::<Entry>(&line) {
+                    Ok(entry) => entry,
+                    Err(e) => return Next::Line(Err(io::Error::other(format!("recording: {e}")))),
+                },
+            },
+        };
+        let due = self.due(&entry);
+        if let Some(deadline) = deadline
+            && deadline < due
+        {
+            thread::sleep(deadline.saturating_duration_since(Instant::now()));
+            self.pending = Some(entry);
+            return Next::TimedOut;
+        }
+        thread::sleep(due.saturating_duration_since(Instant::now()));
+        self.current = entry.src;
+        Next::Line(Ok(entry.line))
+    }
+
+    fn label(&self) -> Option<&str> {
+        self.current.as_deref()
+    }
+}
+
+fn not_a_recording() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "not a cor session recording")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_and_replay_round_trip() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let mut recorder = Recorder::create(file.path(), true).unwrap();
+        recorder.record(r#"{"msg":"a"}"#, Some("api")).unwrap();
+        recorder.record("plain\ttext", None).unwrap();
+        recorder.flush().unwrap();
+
+        let mut replay = ReplayLines::open(file.path(), 0.0).unwrap();
+        assert!(replay.has_labels());
+        assert!(matches!(replay.next_line(None), Next::Line(Ok(ref l)) if l == r#"{"msg":"a"}"#));
+        assert_eq!(replay.label(), Some("api"));
+        assert!(matches!(replay.next_line(None), Next::Line(Ok(ref l)) if l == "plain\ttext"));
+        assert_eq!(replay.label(), None);
+        assert!(matches!(replay.next_line(None), Next::Eof));
+    }
+
+    #[test]
+    fn test_replay_honors_deadlines() {
+        let recording = "{\"cor_session\":1}\n{\"t\":200,\"line\":\"late\"}\n";
+        let mut replay = ReplayLines::new(recording.as_bytes(), 1.0).unwrap();
+        let deadline = Some(Instant::now() + Duration::from_millis(20));
+        assert!(matches!(replay.next_line(deadline), Next::TimedOut));
+        assert!(matches!(replay.next_line(None), Next::Line(Ok(ref l)) if l == "late"));
+    }
+
+    #[test]
+    fn test_rejects_other_files() {
+        let err = ReplayLines::new(&b"{\"msg\":\"hi\"}\n"[..], 1.0).unwrap_err();
+        assert_eq!(err.to_string(), "not a cor session recording");
+        assert!(ReplayLines::new(&b""[..], 1.0).is_err());
+    }
+}
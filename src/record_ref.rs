@@ -0,0 +1,345 @@
+//! Borrowed log records.
+//!
+//! [`LogRecordRef`] is the zero-copy counterpart of [`LogRecord`] for
+//! library users scanning many JSON lines: keys and strings without escape
+//! sequences borrow from the input line, and extra fields stay unparsed
+//! [`RawValue`]s until asked for. [`LogRecordRef::into_owned`] converts to
+//! the owned record the formatter works with.
+//!
+//! Only JSON object lines are handled. Syslog, CRI, klog, and embedded JSON
+//! lines, Docker and GELF envelopes, and any line when the
+//! [`pipeline`](crate::pipeline) has stages rewriting fields need
+//! [`parse_line`](crate::parser::parse_line).
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::de::{self, Deserialize, Deserializer, MapAccess, Visitor};
+use serde_json::value::RawValue;
+
+use crate::config::Config;
+use crate::fields;
+use crate::level::Level;
+use crate::parser::{self, LogRecord};
+use crate::pipeline::Stage;
+use crate::timestamp::Timestamp;
+
+/// Fields of a JSON object, keyed by name.
+type Fields<'a> = BTreeMap<Cow<'a, str>, &'a RawValue>;
+
+/// A log record borrowing from its input line.
+#[derive(Debug, Clone)]
+pub struct LogRecordRef<'a> {
+    pub timestamp: Option<Timestamp>,
+    pub level: Option<Level>,
+    pub message: Option<Cow<'a, str>>,
+    /// Logger name (e.g., `payments.processor`).
+    pub logger: Option<Cow<'a, str>>,
+    /// Caller/source location (e.g., `server/handler.go:42`).
+    pub caller: Option<Cow<'a, str>>,
+    /// Error message or stacktrace.
+    pub error: Option<Cow<'a, str>>,
+    /// Remaining fields, ordered alphabetically, as unparsed JSON. Nested
+    /// objects are flattened one level like [`LogRecord::extra`].
+    pub extra: Fields<'a>,
+    /// The input JSON.
+    pub raw_json: &'a str,
+}
+
+impl LogRecordRef<'_> {
+    /// Value of an extra field, parsed.
+    pub fn extra_value(&self, key: &str) -> Option<serde_json::Value> {
+        serde_json::from_str(self.extra.get(key)?.get()).ok()
+    }
+
+    /// The equivalent owned record.
+    pub fn into_owned(self) -> LogRecord {
+        let text = |field: Option<Cow<'_, str>>| field.map(Cow::into_owned);
+        LogRecord {
+            timestamp: self.timestamp,
+            level: self.level,
+            message: text(self.message),
+            logger: text(self.logger),
+            caller: text(self.caller),
+            error: text(self.error),
+            extra: self
+                .extra
+                .into_iter()
+                .filter_map(|(key, raw)| {
+                    let value = serde_json::from_str(raw.get()).ok()?;
+                    Some((key.into_owned(), value))
+                })
+                .collect(),
+            raw_json: self.raw_json.to_string(),
+        }
+    }
+}
+
+/// Parse a JSON object line into a borrowed record with the same field
+/// detection as [`parse_line`](crate::parser::parse_line).
+///
+/// Returns `None` for anything else, including lines this API leaves to
+/// `parse_line` (see the module docs).
+pub fn parse_record_ref<'a>(line: &'a str, config: &Config) -> Option<LogRecordRef<'a>> {
+    let trimmed = line.trim();
+    if !trimmed.starts_with('{') {
+        return None;
+    }
+    // Stages that rewrite fields need an owned object.
+    if config.pipeline.iter().any(|stage| stage.rewrites_fields()) {
+        return None;
+    }
+    let mut map = serde_json::from_str::<Object<'a>>(trimmed).ok()?.0;
+    let is_envelope = |keys: &[&str]| keys.iter().all(|key| map.contains_key(*key));
+    if (config.pipeline.contains(&Stage::UnwrapDocker) && is_envelope(&["log", "stream", "time"]))
+        || (config.pipeline.contains(&Stage::Gelf)
+            && is_envelope(&["version", "host", "short_message"]))
+    {
+        return None;
+    }
+
+    // With --keep-extracted, detected fields are restored into the extras below.
+    let original = config.keep_extracted.then(|| map.clone());
+
+    let timestamp = match config.timestamp_key {
+        Some(ref keys) => take(&mut map, keys),
+        None => take(&mut map, fields::TIMESTAMP_ALIASES),
+    }
+    .and_then(|raw| Timestamp::from_json_value(&value(raw)?));
+    let level = match config.level_key {
+        Some(ref keys) => take(&mut map, keys),
+        None => take(&mut map, fields::LEVEL_ALIASES),
+    }
+    .and_then(|raw| Level::from_json_value(&value(raw)?, config.level_aliases.as_deref()));
+    let message = if let Some(ref keys) = config.message_fields {
+        let parts: Vec<Cow<'a, str>> = keys
+            .iter()
+            .filter_map(|key| map.remove(key.as_str()).and_then(text))
+            .collect();
+        (!parts.is_empty()).then(|| Cow::Owned(parts.join(&config.message_separator)))
+    } else {
+        match config.message_key {
+            Some(ref keys) => take(&mut map, keys),
+            None => take(&mut map, fields::MESSAGE_ALIASES),
+        }
+        .and_then(text)
+    };
+    let logger = match config.logger_key {
+        Some(ref key) => map.remove(key.as_str()),
+        None => take(&mut map, fields::LOGGER_ALIASES),
+    }
+    .and_then(text);
+    let caller = match config.caller_key {
+        Some(ref key) => map.remove(key.as_str()),
+        None => take(&mut map, fields::CALLER_ALIASES),
+    }
+    .and_then(|raw| {
+        // Structured locations are rendered like the owned parser does.
+        if raw.get().starts_with('{') {
+            parser::caller_to_string(value(raw)?).map(Cow::Owned)
+        } else {
+            text(raw)
+        }
+    });
+    let error = match config.error_key {
+        Some(ref key) => map.remove(key.as_str()),
+        None => take(&mut map, fields::ERROR_ALIASES),
+    }
+    .and_then(text);
+
+    if let Some(original) = original {
+        for (key, raw) in original {
+            map.entry(key).or_insert(raw);
+        }
+    }
+
+    Some(LogRecordRef {
+        timestamp,
+        level,
+        message,
+        logger,
+        caller,
+        error,
+        extra: flatten(map),
+        raw_json: trimmed,
+    })
+}
+
+/// Remove the first of `keys` present in the map.
+fn take<'a, K: AsRef<str>>(map: &mut Fields<'a>, keys: &[K]) -> Option<&'a RawValue> {
+    keys.iter().find_map(|key| map.remove(key.as_ref()))
+}
+
+/// Parse a raw value, for the few fields that need inspecting.
+fn value(raw: &RawValue) -> Option<serde_json::Value> {
+    serde_json::from_str(raw.get()).ok()
+}
+
+/// A raw value as text: strings unquoted (borrowed unless they contain
+/// escapes), `null` as `None`, anything else as its JSON.
+fn text(raw: &RawValue) -> Option<Cow<'_, str>> {
+    match raw.get() {
+        "null" => None,
+        json if json.starts_with('"') => serde_json::from_str::<Str<'_>>(json).ok().map(|s| s.0),
+        json => Some(Cow::Borrowed(json)),
+    }
+}
+
+/// Flatten nested objects one level using dot-notation, like the owned
+/// parser.
+fn flatten(map: Fields<'_>) -> Fields<'_> {
+    let mut result = BTreeMap::new();
+    for (key, raw) in map {
+        if raw.get().starts_with('{')
+            && let Ok(nested) = serde_json::from_str::<Object<'_>>(raw.get())
+        {
+            for (nested_key, nested_raw) in nested.0 {
+                result.insert(Cow::Owned(format!("{key}.{nested_key}")), nested_raw);
+            }
+        } else {
+            result.insert(key, raw);
+        }
+    }
+    result
+}
+
+/// A JSON string, borrowed from the input unless it contains escapes.
+struct Str<'a>(Cow<'a, str>);
+
+impl<'de> Deserialize<'de> for Str<'de> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct StrVisitor;
+
+        impl<'de> Visitor<'de> for StrVisitor {
+            type Value = Str<'de>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a string")
+            }
+
+            fn visit_borrowed_str<E: de::Error>(self, s: &'de str) -> Result<Self::Value, E> {
+                Ok(Str(Cow::Borrowed(s)))
+            }
+
+            fn visit_str<E: de::Error>(self, s: &str) -> Result<Self::Value, E> {
+                Ok(Str(Cow::Owned(s.to_string())))
+            }
+        }
+
+        deserializer.deserialize_str(StrVisitor)
+    }
+}
+
+/// A JSON object with borrowed keys and raw values. Later duplicate keys
+/// win, as in `serde_json`.
+struct Object<'a>(Fields<'a>);
+
+impl<'de> Deserialize<'de> for Object<'de> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ObjectVisitor;
+
+        impl<'de> Visitor<'de> for ObjectVisitor {
+            type Value = Object<'de>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a JSON object")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Self::Value, A::Error> {
+                let mut map = BTreeMap::new();
+                while let Some((key, raw)) = access.next_entry::<Str<'de>, &'de RawValue>()? {
+                    map.insert(key.0, raw);
+                }
+                Ok(Object(map))
+            }
+        }
+
+        deserializer.deserialize_map(ObjectVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{LineKind, parse_line};
+
+    #[test]
+    fn test_borrows_and_matches_owned_parser() {
+        let config = Config::default();
+        let line = r#"{"ts":"2026-01-15T10:30:00Z","level":"warn","msg":"slow \"query\"","logger":"db","http":{"status":503},"tags":["a"],"n":null}"#;
+        let record = parse_record_ref(line, &config).unwrap();
+        assert!(matches!(record.logger, Some(Cow::Borrowed("db"))));
+        // Escapes force a copy.
+        assert!(matches!(record.message, Some(Cow::Owned(ref m)) if m == "slow \"query\""));
+        assert_eq!(record.extra["http.status"].get(), "503");
+        assert_eq!(record.extra_value("tags"), Some(serde_json::json!(["a"])));
+
+        let owned = record.into_owned();
+        let LineKind::Json(expected) = parse_line(line, &config) else {
+            panic!("expected JSON record");
+        };
+        assert_eq!(
+            owned.timestamp.map(|t| t.value),
+            expected.timestamp.map(|t| t.value)
+        );
+        assert_eq!(owned.level, expected.level);
+        assert_eq!(owned.message, expected.message);
+        assert_eq!(owned.logger, expected.logger);
+        assert_eq!(owned.extra, expected.extra);
+    }
+
+    /// Every JSON object line of the parser's fixtures parses to the same
+    /// record through both APIs.
+    #[test]
+    fn test_into_owned_matches_parse_line_on_fixtures() {
+        let config = Config::default();
+        let fixtures = [
+            include_str!("../tests/fixtures/bunyan.jsonl"),
+            include_str!("../tests/fixtures/logrus.jsonl"),
+            include_str!("../tests/fixtures/pino.jsonl"),
+            include_str!("../tests/fixtures/slog.jsonl"),
+            include_str!("../tests/fixtures/structlog.jsonl"),
+            include_str!("../tests/fixtures/zap.jsonl"),
+        ];
+        for line in fixtures.iter().flat_map(|f| f.lines()) {
+            let owned = parse_record_ref(line, &config)
+                .unwrap_or_else(|| panic!("not parsed: {line}"))
+                .into_owned();
+            let LineKind::Json(expected) = parse_line(line, &config) else {
+                panic!("expected JSON record: {line}");
+            };
+            assert_eq!(
+                owned.timestamp.map(|t| (t.value, t.original)),
+                expected.timestamp.map(|t| (t.value, t.original)),
+                "{line}"
+            );
+            assert_eq!(owned.level, expected.level, "{line}");
+            assert_eq!(owned.message, expected.message, "{line}");
+            assert_eq!(owned.logger, expected.logger, "{line}");
+            assert_eq!(owned.caller, expected.caller, "{line}");
+            assert_eq!(owned.error, expected.error, "{line}");
+            assert_eq!(owned.extra, expected.extra, "{line}");
+            assert_eq!(owned.raw_json, expected.raw_json, "{line}");
+        }
+        // Embedded JSON is left to `parse_line`.
+        for line in include_str!("../tests/fixtures/embedded.jsonl").lines() {
+            assert!(parse_record_ref(line, &config).is_none(), "{line}");
+        }
+    }
+
+    #[test]
+    fn test_leaves_other_lines_to_parse_line() {
+        let config = Config::default();
+        assert!(parse_record_ref("plain text", &config).is_none());
+        assert!(parse_record_ref(r#"{"broken"#, &config).is_none());
+        assert!(parse_record_ref("[1, 2]", &config).is_none());
+        assert!(
+            parse_record_ref(
+                r#"{"log":"hi\n","stream":"stdout","time":"2026-01-15T10:30:00Z"}"#,
+                &config
+            )
+            .is_none()
+        );
+    }
+}
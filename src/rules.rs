@@ -0,0 +1,211 @@
+//! Conditional formatting rules (`[[rules]]` in the config file).
+//!
+//! Each rule pairs a match [`Expr`] with actions applied to matching
+//! records:
+//!
+//! ```toml
+//! [[rules]]
+//! match = 'status >= 500'
+//! color = "red"      # color the message
+//! tint = "#5fafd7"   # color the whole record, fields included
+//! tag = "5XX"        # show a [5XX] tag before the message
+//! bell = true        # ring the terminal bell (on stderr, if a terminal)
+//! stderr = true      # write the record to stderr instead of stdout
+//!
+//! [[rules]]
+//! match = 'path == "/healthz"'
+//! hide = true
+//! ```
+//!
+//! Every matching rule applies, in file order: tags accumulate, flags are
+//! OR-ed together, and the last matching color and tint win.
+
+use serde::Deserialize;
+
+use crate::expr::Expr;
+use crate::level::color_name_to_style;
+use crate::parser::LogRecord;
+
+/// A rule as written in the config file.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RuleConfig {
+    /// Match expression, e.g. `level>=error && service == "payments"`.
+    #[serde(rename = "match")]
+    pub expr: String,
+    /// Color for the message: a name, `#rrggbb`, or a 256-color index, as
+    /// in `[colors]`.
+    pub color: Option<String>,
+    /// Color for the whole record, message and fields alike.
+    pub tint: Option<String>,
+    /// Suppress matching records.
+    #[serde(default)]
+    pub hide: bool,
+    /// Tag shown in brackets before the message.
+    pub tag: Option<String>,
+    /// Ring the terminal bell.
+    #[serde(default)]
+    pub bell: bool,
+    /// Route matching records to stderr.
+    #[serde(default)]
+    pub stderr: bool,
+}
+
+/// A compiled rule.
+#[derive(Debug, Clone)]
+pub struct Rule {
+    expr: Expr,
+    actions: RuleConfig,
+}
+
+impl Rule {
+    /// Compile a rule, validating its expression and color.
+    pub fn compile(config: RuleConfig) -> Result<Self, String> {
+        let expr = Expr::parse(&config.expr)
+            .map_err(|e| format!("invalid match expression '{}': {e}", config.expr))?;
+        for color in [&config.color, &config.tint].into_iter().flatten() {
+            if color_name_to_style(color).is_none() {
+                return Err(format!("unknown color '{color}'"));
+            }
+        }
+        Ok(Self {
+            expr,
+            actions: config,
+        })
+    }
+
+    /// The match expression.
+    pub const fn expr(&self) -> &Expr {
+        &self.expr
+    }
+
+    /// Whether the rule suppresses matching records.
+    pub const fn hides(&self) -> bool {
+        self.actions.hide
+    }
+}
+
+/// Combined actions of every rule matching a record.
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Outcome {
+    /// Suppress the record.
+    pub hide: bool,
+    /// Color name for the message.
+    pub color: Option<String>,
+    /// Color name for the whole record.
+    pub tint: Option<String>,
+    /// Tags shown before the message.
+    pub tags: Vec<String>,
+    /// Ring the terminal bell.
+    pub bell: bool,
+    /// Write the record to stderr.
+    pub stderr: bool,
+    /// Shown by `--dim-unmatched` though it fails the filters: context on
+    /// the terminal only, never sent to sinks or alerting. Set by the
+    /// formatter, not by rules.
+    pub unmatched: bool,
+}
+
+/// Evaluate `rules` against a record.
+pub fn evaluate(rules: &[Rule], record: &LogRecord) -> Outcome {
+    let mut outcome = Outcome::default();
+    for rule in rules.iter().filter(|rule| rule.expr.matches(record)) {
+        let actions = &rule.actions;
+        outcome.hide |= actions.hide;
+        outcome.bell |= actions.bell;
+        outcome.stderr |= actions.stderr;
+        if actions.color.is_some() {
+            outcome.color.clone_from(&actions.color);
+        }
+        if actions.tint.is_some() {
+            outcome.tint.clone_from(&actions.tint);
+        }
+        if let Some(ref tag) = actions.tag {
+            outcome.tags.push(tag.clone());
+        }
+    }
+    outcome
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::parser::{LineKind, parse_line};
+
+    fn record(line: &str) -> LogRecord {
+        match parse_line(line, &Config::default()) {
+            LineKind::Json(record) => record,
+            other => panic!("expected JSON record, got {other:?}"),
+        }
+    }
+
+    fn rule(expr: &str) -> RuleConfig {
+        RuleConfig {
+            expr: expr.to_string(),
+            ..RuleConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_compile_rejects_bad_expression_and_color() {
+        assert!(Rule::compile(rule("status")).is_err());
+        let bad_color = RuleConfig {
+            color: Some("mauve".to_string()),
+            ..rule("status >= 500")
+        };
+        assert!(Rule::compile(bad_color).unwrap_err().contains("mauve"));
+        let bad_tint = RuleConfig {
+            tint: Some("teal".to_string()),
+            ..rule("status >= 500")
+        };
+        assert!(Rule::compile(bad_tint).unwrap_err().contains("teal"));
+        let hex_and_indexed = RuleConfig {
+            color: Some("#ff8800".to_string()),
+            tint: Some("67".to_string()),
+            ..rule("status >= 500")
+        };
+        assert!(Rule::compile(hex_and_indexed).is_ok());
+    }
+
+    #[test]
+    fn test_matching_rules_combine() {
+        let rules = [
+            Rule::compile(RuleConfig {
+                color: Some("yellow".to_string()),
+                tag: Some("SLOW".to_string()),
+                ..rule("duration_ms > 1000")
+            })
+            .unwrap(),
+            Rule::compile(RuleConfig {
+                color: Some("red".to_string()),
+                tag: Some("5XX".to_string()),
+                bell: true,
+                stderr: true,
+                ..rule("status >= 500")
+            })
+            .unwrap(),
+        ];
+        let outcome = evaluate(
+            &rules,
+            &record(r#"{"msg":"x","status":503,"duration_ms":2000}"#),
+        );
+        assert_eq!(outcome.color.as_deref(), Some("red"));
+        assert_eq!(outcome.tags, ["SLOW", "5XX"]);
+        assert!(outcome.bell && outcome.stderr && !outcome.hide);
+
+        let outcome = evaluate(&rules, &record(r#"{"msg":"x","status":200}"#));
+        assert_eq!(outcome, Outcome::default());
+    }
+
+    #[test]
+    fn test_hide_rule() {
+        let rules = [Rule::compile(RuleConfig {
+            hide: true,
+            ..rule(r#"path == "/healthz""#)
+        })
+        .unwrap()];
+        assert!(evaluate(&rules, &record(r#"{"msg":"x","path":"/healthz"}"#)).hide);
+        assert!(!evaluate(&rules, &record(r#"{"msg":"x","path":"/api"}"#)).hide);
+    }
+}
@@ -0,0 +1,80 @@
+//! Shared async runtime for network inputs (`async` feature).
+//!
+//! Built with the `async` feature, `--listen` uploads, `--connect`
+//! connections (plain and TLS), `--serve-ws` clients, and the `webhook:`
+//! and `loki:` sinks run as tasks on one small tokio runtime instead of a
+//! thread each, so many concurrent connections stay cheap. The processing
+//! loop itself stays synchronous: tasks hand their lines to it through
+//! [`bridge`], which forwards them into the usual blocking channels.
+
+use std::io;
+use std::sync::OnceLock;
+use std::sync::mpsc::SyncSender;
+use std::thread;
+
+use tokio::runtime::{Builder, Handle, Runtime};
+use tokio::sync::mpsc;
+
+use crate::error::CorError;
+
+/// Worker threads driving every network task.
+const WORKER_THREADS: usize = 2;
+
+/// Capacity of the async side of a [`bridge`].
+const BRIDGE_CAPACITY: usize = 1024;
+
+/// Handle of the shared runtime, started on first use.
+pub fn handle() -> Result<&'static Handle, CorError> {
+    static RUNTIME: OnceLock<Result<Runtime, String>> = OnceLock::new();
+    RUNTIME
+        .get_or_init(|| {
+            Builder::new_multi_thread()
+                .worker_threads(WORKER_THREADS)
+                .thread_name("cor-net")
+                .enable_io()
+                .enable_time()
+                .build()
+                .map_err(|e| e.to_string())
+        })
+        .as_ref()
+        .map(Runtime::handle)
+        .map_err(|e| {
+            CorError::Io(io::Error::other(format!(
+                "failed to start the network runtime: {e}"
+            )))
+        })
+}
+
+/// An async sender whose items are forwarded to the blocking `tx`.
+///
+/// A single thread does the forwarding, so tasks wait on a full channel
+/// without blocking a runtime worker. `tx` is dropped, ending the consumer's
+/// input, once every clone of the returned sender is gone.
+pub fn bridge<T: Send + 'static>(tx: SyncSender<T>) -> mpsc::Sender<T> {
+    let (async_tx, mut rx) = mpsc::channel(BRIDGE_CAPACITY);
+    thread::spawn(move || {
+        while let Some(item) = rx.blocking_recv() {
+            if tx.send(item).is_err() {
+                break;
+            }
+        }
+    });
+    async_tx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bridge_forwards_until_senders_drop() {
+        let (tx, rx) = std::sync::mpsc::sync_channel(1);
+        let async_tx = bridge(tx);
+        handle().unwrap().spawn(async move {
+            for n in 0..3 {
+                async_tx.send(n).await.unwrap();
+            }
+        });
+        assert_eq!(rx.iter().collect::<Vec<_>>(), [0, 1, 2]);
+    }
+}
@@ -0,0 +1,103 @@
+//! Probabilistic sampling (`--sample`).
+//!
+//! A chatty debug stream can be thinned to a random fraction of its records
+//! while following it. A [`Sampler`] keeps each record below its level with
+//! probability `rate`; records at or above the level (errors by default)
+//! and raw lines are always shown.
+
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+
+use crate::level::Level;
+
+/// Records at this level or above are never sampled out, unless
+/// `--sample-level` says otherwise.
+pub const DEFAULT_LEVEL: Level = Level::Error;
+
+/// Parse a sampling rate: a fraction like `0.1` or a percentage like `10%`,
+/// above 0 and at most 1.
+pub fn parse_rate(s: &str) -> Result<f64, String> {
+    let invalid = || format!("invalid sample rate '{s}': expected a fraction like 0.1 or 10%");
+    let rate = match s.trim().strip_suffix('%') {
+        Some(percent) => percent.trim().parse::<f64>().map_err(|_| invalid())? / 100.0,
+        None => s.trim().parse::<f64>().map_err(|_| invalid())?,
+    };
+    if rate > 0.0 && rate <= 1.0 {
+        Ok(rate)
+    } else {
+        Err(format!(
+            "invalid sample rate '{s}': must be above 0 and at most 1"
+        ))
+    }
+}
+
+/// Random keep-or-drop decisions for records.
+#[derive(Debug, Clone)]
+pub struct Sampler {
+    rate: f64,
+    level: Level,
+    /// splitmix64 state.
+    state: u64,
+}
+
+impl Sampler {
+    /// Keep records below `level` with probability `rate`, drawing from a
+    /// generator seeded with `seed`.
+    pub const fn new(rate: f64, level: Level, seed: u64) -> Self {
+        Self {
+            rate,
+            level,
+            state: seed,
+        }
+    }
+
+    /// A sampler seeded differently on every run.
+    pub fn random(rate: f64, level: Level) -> Self {
+        Self::new(rate, level, RandomState::new().hash_one(0_u8))
+    }
+
+    /// Whether to show a record with `level`. Records without a level are
+    /// sampled like those below the threshold.
+    pub fn keep(&mut self, level: Option<Level>) -> bool {
+        level.is_some_and(|l| l >= self.level) || self.next_unit() < self.rate
+    }
+
+    /// Uniform draw from `[0, 1)`.
+    fn next_unit(&mut self) -> f64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+        // The top 53 bits fill an f64 mantissa exactly.
+        #[allow(clippy::cast_precision_loss)]
+        let unit = (z >> 11) as f64 / (1_u64 << 53) as f64;
+        unit
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rate() {
+        assert_eq!(parse_rate("0.1"), Ok(0.1));
+        assert_eq!(parse_rate("25%"), Ok(0.25));
+        assert_eq!(parse_rate("1"), Ok(1.0));
+        for bad in ["0", "1.5", "-0.1", "ten", "%"] {
+            assert!(parse_rate(bad).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn test_keeps_about_rate_and_all_errors() {
+        let mut sampler = Sampler::new(0.1, Level::Error, 42);
+        let kept = (0..10_000)
+            .filter(|_| sampler.keep(Some(Level::Debug)))
+            .count();
+        assert!((800..1200).contains(&kept), "kept {kept}");
+        assert!((0..100).all(|_| sampler.keep(Some(Level::Fatal))));
+        assert!((0..100).all(|_| sampler.keep(Some(Level::Error))));
+    }
+}
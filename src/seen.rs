@@ -0,0 +1,68 @@
+//! Repeat counters on field values (`--seen-counts`).
+//!
+//! [`SeenCounts`] counts how often each value of the chosen fields has
+//! appeared so far, so the formatter can append a dim `(seen 412×)` to a
+//! value from its second appearance on — dedup information without
+//! hiding any record.
+
+use std::collections::HashMap;
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+
+/// Distinct values remembered; values first seen after that are not
+/// counted, which keeps memory bounded on high-cardinality fields.
+const MAX_VALUES: usize = 100_000;
+
+/// Occurrences per field value.
+///
+/// Values are keyed by a 64-bit hash of the field name and value rather
+/// than the strings themselves, so memory stays at [`MAX_VALUES`] small
+/// entries however long the values are, and counting a value allocates
+/// nothing.
+#[derive(Debug, Clone, Default)]
+pub struct SeenCounts {
+    counts: HashMap<u64, usize>,
+    hasher: RandomState,
+}
+
+impl SeenCounts {
+    /// Whether no value has been counted yet.
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// Count an occurrence of `value` in `field`, returning how often it has
+    /// been seen including this one. Untracked values count as seen once.
+    pub fn observe(&mut self, field: &str, value: &str) -> usize {
+        let key = self.hasher.hash_one((field, value));
+        if let Some(n) = self.counts.get_mut(&key) {
+            *n += 1;
+            return *n;
+        }
+        if self.counts.len() < MAX_VALUES {
+            self.counts.insert(key, 1);
+        }
+        1
+    }
+}
+
+/// The marker for a value seen `count` times, with `times` as the
+/// multiplication sign.
+pub fn marker(count: usize, times: &str) -> String {
+    format!("(seen {count}{times})")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counts_per_field() {
+        let mut seen = SeenCounts::default();
+        assert_eq!(seen.observe("error", "timeout"), 1);
+        assert_eq!(seen.observe("error", "timeout"), 2);
+        assert_eq!(seen.observe("msg", "timeout"), 1);
+        assert_eq!(seen.observe("error", "timeout"), 3);
+        assert_eq!(marker(3, "×"), "(seen 3×)");
+    }
+}
@@ -0,0 +1,69 @@
+//! Coordinated shutdown on SIGINT and SIGTERM.
+//!
+//! By default a signal ends cor at once, losing whatever output sinks,
+//! split files, recordings, and summaries still hold. When any of those are
+//! in play, [`install`] swaps the default handlers for one that only records
+//! the signal. The processing loop checks [`signal`] between lines (at least
+//! every [`POLL`] while input is idle), stops reading, and leaves through
+//! the normal end-of-input path: flush stdout and split files, finish the
+//! sinks, close the recording, print summaries, and exit with `128 +
+//! signal`. A second signal exits immediately.
+//!
+//! Library users driving their own loop can call [`request`] to start the
+//! same shutdown from code.
+
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::time::Duration;
+
+/// How often an idle input loop checks for a pending shutdown.
+pub const POLL: Duration = Duration::from_millis(100);
+
+/// Signal that requested shutdown, or 0.
+static SIGNAL: AtomicI32 = AtomicI32::new(0);
+
+/// Record SIGINT and SIGTERM instead of dying on them.
+#[cfg(unix)]
+pub fn install() -> std::io::Result<()> {
+    for signal in [libc::SIGINT, libc::SIGTERM] {
+        // SAFETY: the handler only touches an atomic and calls the
+        // async-signal-safe `_exit`; the sigaction struct is zeroed before
+        // the handler and mask are set.
+        let installed = unsafe {
+            let mut action: libc::sigaction = std::mem::zeroed();
+            action.sa_sigaction = on_signal as extern "C" fn(libc::c_int) as libc::sighandler_t;
+            libc::sigemptyset(&raw mut action.sa_mask);
+            libc::sigaction(signal, &raw const action, std::ptr::null_mut())
+        };
+        if installed != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+/// Signals keep their default behavior off Unix.
+#[cfg(not(unix))]
+pub fn install() -> std::io::Result<()> {
+    Ok(())
+}
+
+#[cfg(unix)]
+extern "C" fn on_signal(signal: libc::c_int) {
+    if SIGNAL.swap(signal, Ordering::SeqCst) != 0 {
+        // SAFETY: `_exit` is async-signal-safe.
+        unsafe { libc::_exit(128 + signal) };
+    }
+}
+
+/// Ask the processing loop to shut down as if interrupted by `signal`.
+pub fn request(signal: i32) {
+    let _ = SIGNAL.compare_exchange(0, signal, Ordering::SeqCst, Ordering::SeqCst);
+}
+
+/// The signal that requested shutdown, if any.
+pub fn signal() -> Option<i32> {
+    match SIGNAL.load(Ordering::SeqCst) {
+        0 => None,
+        signal => Some(signal),
+    }
+}
@@ -0,0 +1,336 @@
+//! Additional outputs for rendered records (`--output`).
+//!
+//! Every record cor renders can be handed to any number of [`OutputSink`]s
+//! besides stdout: a plain-text file, a webhook receiving NDJSON batches, or
+//! a Grafana Loki push endpoint (the last two in [`crate::http_sink`], with
+//! the `net` feature). [`Tee`] composes several sinks into one, and library
+//! users can add their own by implementing the trait.
+
+use std::borrow::Cow;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "net")]
+use crate::http_sink::{LokiSink, WebhookSink};
+#[cfg(feature = "net")]
+use crate::ingest::ListenAddr;
+use crate::layout::Layout;
+
+/// One rendered record as handed to a sink.
+#[derive(Debug, Clone, Copy)]
+pub struct FormattedRecord<'a> {
+    /// The entry's text and role-tagged segments, for rendering it anew.
+    pub layout: &'a Layout,
+    /// The entry as printed to stdout, possibly with ANSI colors, without
+    /// the trailing newline.
+    pub text: &'a str,
+    /// Normalized JSON of the record; `None` for raw lines.
+    pub json: Option<&'a serde_json::Value>,
+    /// Name of the input the record came from (`-` for stdin).
+    pub source: &'a str,
+}
+
+/// Destination for rendered records.
+pub trait OutputSink: Send {
+    /// Take one record.
+    fn emit(&mut self, record: &FormattedRecord<'_>) -> io::Result<()>;
+
+    /// Called once after the last record: flush buffers and deliver
+    /// anything still pending.
+    fn finish(&mut self) -> io::Result<()>;
+}
+
+/// Sink writing entries to a [`Write`]r, one per line plus `line_gap`
+/// blank lines.
+#[derive(Debug)]
+pub struct WriterSink<W> {
+    writer: W,
+    line_gap: usize,
+    /// Strip ANSI color codes before writing.
+    plain: bool,
+}
+
+impl<W: Write + Send> WriterSink<W> {
+    /// Sink writing entries to `writer` as rendered.
+    pub const fn new(writer: W, line_gap: usize) -> Self {
+        Self {
+            writer,
+            line_gap,
+            plain: false,
+        }
+    }
+
+    /// Strip ANSI color codes from entries before writing them.
+    #[must_use]
+    pub const fn plain(mut self) -> Self {
+        self.plain = true;
+        self
+    }
+}
+
+impl<W: Write + Send> OutputSink for WriterSink<W> {
+    fn emit(&mut self, record: &FormattedRecord<'_>) -> io::Result<()> {
+        let text = if self.plain {
+            strip_sgr(record.layout.text())
+        } else {
+            Cow::Borrowed(record.text)
+        };
+        let trailing = "\n".repeat(1 + self.line_gap);
+        write!(self.writer, "{text}{trailing}")
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Sink writing plain entries to a new file at `path`.
+pub fn file(path: &Path, line_gap: usize) -> io::Result<WriterSink<BufWriter<File>>> {
+    let file = File::create(path)?;
+    Ok(WriterSink::new(BufWriter::new(file), line_gap).plain())
+}
+
+/// Sink passing every record on to several sinks.
+#[derive(Default)]
+pub struct Tee {
+    sinks: Vec<Box<dyn OutputSink>>,
+}
+
+impl fmt::Debug for Tee {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Tee")
+            .field("sinks", &self.sinks.len())
+            .finish()
+    }
+}
+
+impl Tee {
+    /// Add a sink.
+    pub fn push(&mut self, sink: Box<dyn OutputSink>) {
+        self.sinks.push(sink);
+    }
+
+    /// Whether no sinks were added.
+    pub fn is_empty(&self) -> bool {
+        self.sinks.is_empty()
+    }
+}
+
+impl OutputSink for Tee {
+    /// Every sink gets the record even if an earlier one fails; the first
+    /// error is returned.
+    fn emit(&mut self, record: &FormattedRecord<'_>) -> io::Result<()> {
+        self.sinks
+            .iter_mut()
+            .map(|sink| sink.emit(record))
+            .fold(Ok(()), Result::and)
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.sinks
+            .iter_mut()
+            .map(|sink| sink.finish())
+            .fold(Ok(()), Result::and)
+    }
+}
+
+/// Remove ANSI CSI sequences (colors and styles) from `s`.
+pub(crate) fn strip_sgr(s: &str) -> Cow<'_, str> {
+    if !s.contains('\x1b') {
+        return Cow::Borrowed(s);
+    }
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            out.push(c);
+            continue;
+        }
+        if chars.clone().next() == Some('[') {
+            // Parameters and intermediates up to the final byte.
+            chars.next();
+            for c in chars.by_ref() {
+                if ('@'..='~').contains(&c) {
+                    break;
+                }
+            }
+        }
+    }
+    Cow::Owned(out)
+}
+
+/// An `--output` destination.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SinkSpec {
+    /// `file:PATH` — plain text.
+    File(PathBuf),
+    /// `webhook:http://HOST:PORT/PATH` — NDJSON batches.
+    #[cfg(feature = "net")]
+    Webhook(ListenAddr),
+    /// `loki:http://HOST:PORT` — Loki push API.
+    #[cfg(feature = "net")]
+    Loki(ListenAddr),
+}
+
+impl SinkSpec {
+    /// Parse `file:PATH`, `webhook:URL`, or `loki:URL`.
+    pub fn parse(s: &str) -> Result<Self, String> {
+        #[cfg(feature = "net")]
+        let url = |url: &str| {
+            ListenAddr::parse(url)
+                .ok()
+                .filter(|addr| !addr.tls)
+                .ok_or_else(|| {
+                    format!("invalid output '{s}': expected an http://HOST:PORT[/PATH] URL")
+                })
+        };
+        match s.split_once(':') {
+            Some(("file", path)) if !path.is_empty() => Ok(Self::File(PathBuf::from(path))),
+            #[cfg(feature = "net")]
+            Some(("webhook", rest)) => url(rest).map(Self::Webhook),
+            #[cfg(feature = "net")]
+            Some(("loki", rest)) => url(rest).map(Self::Loki),
+            #[cfg(not(feature = "net"))]
+            Some(("webhook" | "loki", _)) => Err(format!(
+                "invalid output '{s}': network outputs require cor built with the `net` feature"
+            )),
+            _ => Err(format!(
+                "invalid output '{s}': expected file:PATH, webhook:URL, or loki:URL"
+            )),
+        }
+    }
+
+    /// Open the sink, writing `line_gap` blank lines after entries in files.
+    pub fn open(&self, line_gap: usize) -> io::Result<Box<dyn OutputSink>> {
+        Ok(match self {
+            Self::File(path) => Box::new(file(path, line_gap)?),
+            #[cfg(feature = "net")]
+            Self::Webhook(endpoint) => Box::new(WebhookSink::new(endpoint.clone())?),
+            #[cfg(feature = "net")]
+            Self::Loki(endpoint) => Box::new(LokiSink::new(endpoint.clone())?),
+        })
+    }
+}
+
+impl fmt::Display for SinkSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::File(path) => write!(f, "{}", path.display()),
+            #[cfg(feature = "net")]
+            Self::Webhook(endpoint) => {
+                write!(f, "webhook http://{}{}", endpoint.addr, endpoint.path)
+            }
+            #[cfg(feature = "net")]
+            Self::Loki(endpoint) => write!(f, "loki http://{}{}", endpoint.addr, endpoint.path),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::Role;
+    use owo_colors::Style;
+    use std::sync::{Arc, Mutex};
+
+    /// Sink recording the text of every record.
+    #[derive(Clone, Default)]
+    struct Collect(Arc<Mutex<Vec<String>>>);
+
+    impl OutputSink for Collect {
+        fn emit(&mut self, record: &FormattedRecord<'_>) -> io::Result<()> {
+            self.0.lock().unwrap().push(record.text.to_string());
+            Ok(())
+        }
+
+        fn finish(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn raw(text: &str) -> Layout {
+        let mut layout = Layout::default();
+        layout.push(Role::Raw, Style::new(), text);
+        layout
+    }
+
+    fn record(layout: &Layout) -> FormattedRecord<'_> {
+        FormattedRecord {
+            layout,
+            text: layout.text(),
+            json: None,
+            source: "-",
+        }
+    }
+
+    #[test]
+    fn test_strip_sgr() {
+        assert_eq!(strip_sgr("plain"), "plain");
+        assert_eq!(strip_sgr("\x1b[32;1mINFO\x1b[0m hi"), "INFO hi");
+        assert!(matches!(strip_sgr("plain"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_parse_spec() {
+        assert_eq!(
+            SinkSpec::parse("file:out/app.log"),
+            Ok(SinkSpec::File(PathBuf::from("out/app.log")))
+        );
+        assert!(SinkSpec::parse("file:").is_err());
+        assert!(SinkSpec::parse("s3:bucket").is_err());
+    }
+
+    #[cfg(feature = "net")]
+    #[test]
+    fn test_parse_network_spec() {
+        let SinkSpec::Loki(endpoint) = SinkSpec::parse("loki:http://loki:3100").unwrap() else {
+            panic!("expected a loki sink");
+        };
+        assert_eq!(endpoint.addr, "loki:3100");
+        assert!(matches!(
+            SinkSpec::parse("webhook:http://127.0.0.1:9000/hook"),
+            Ok(SinkSpec::Webhook(_))
+        ));
+        assert!(SinkSpec::parse("webhook:https://example.com").is_err());
+    }
+
+    #[cfg(not(feature = "net"))]
+    #[test]
+    fn test_network_spec_requires_feature() {
+        let err = SinkSpec::parse("loki:http://loki:3100").unwrap_err();
+        assert!(err.contains("`net` feature"), "{err}");
+    }
+
+    #[test]
+    fn test_file_sink_writes_plain_text() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.log");
+        let mut sink = file(&path, 1).unwrap();
+        let mut layout = Layout::default();
+        layout.push(Role::Badge, Style::new().red(), "ERROR");
+        layout.push_str(": ");
+        layout.push(Role::Raw, Style::new(), "\x1b[1mboom\x1b[0m");
+        sink.emit(&FormattedRecord {
+            text: "\x1b[31mERROR\x1b[0m: \x1b[1mboom\x1b[0m",
+            ..record(&layout)
+        })
+        .unwrap();
+        sink.finish().unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "ERROR: boom\n\n");
+    }
+
+    #[test]
+    fn test_tee_fans_out() {
+        let (a, b) = (Collect::default(), Collect::default());
+        let mut tee = Tee::default();
+        assert!(tee.is_empty());
+        tee.push(Box::new(a.clone()));
+        tee.push(Box::new(b.clone()));
+        tee.emit(&record(&raw("one"))).unwrap();
+        tee.finish().unwrap();
+        assert_eq!(*a.0.lock().unwrap(), ["one"]);
+        assert_eq!(*b.0.lock().unwrap(), ["one"]);
+    }
+}
@@ -0,0 +1,128 @@
+//! Timestamp ordering of whole inputs (`--sort`).
+//!
+//! Aggregated exports often arrive out of order. [`sort_lines`] reorders
+//! the lines of an input by the timestamps of their records, keeping ties
+//! in input order. Lines without a timestamp — raw text, stack traces,
+//! records without one — move with the line they followed, and lines
+//! before the first timestamp stay first. Each line keeps its position in
+//! the input and the parse done to find its timestamp.
+
+use std::io;
+use std::time::Instant;
+
+use crate::config::Config;
+use crate::input::{self, LineSource, Next, Position};
+use crate::parallel::Prepared;
+use crate::parser::{self, LineKind};
+
+/// A line with where it was read and, if it could be read, its parse.
+type Line = (Position, io::Result<String>, Option<LineKind>);
+
+/// [`LineSource`] over the sorted lines of an input. Deadlines are ignored.
+#[derive(Debug)]
+pub struct SortedLines {
+    lines: std::vec::IntoIter<Line>,
+    pos: Option<Position>,
+    prepared: Option<Prepared>,
+}
+
+/// `lines` ordered by record timestamp. Unreadable lines are kept in place
+/// like lines without a timestamp.
+pub fn sort_lines(lines: Vec<io::Result<String>>, config: &Config) -> SortedLines {
+    let mut groups: Vec<(Option<jiff::Timestamp>, Vec<Line>)> = Vec::new();
+    let mut next_pos = Position::start();
+    for mut line in lines {
+        let pos = next_pos;
+        let kind = match line {
+            Ok(ref mut text) => {
+                next_pos.advance(text);
+                input::strip_cr(text);
+                Some(parser::parse_line(text, config))
+            }
+            Err(ref e) => {
+                next_pos.skip(e);
+                None
+            }
+        };
+        let timestamp = kind.as_ref().and_then(timestamp);
+        match groups.last_mut() {
+            Some((_, group)) if timestamp.is_none() => group.push((pos, line, kind)),
+            _ => groups.push((timestamp, vec![(pos, line, kind)])),
+        }
+    }
+    // Stable, and only a leading group can lack a timestamp.
+    groups.sort_by_key(|&(timestamp, _)| timestamp);
+    let lines: Vec<Line> = groups.into_iter().flat_map(|(_, group)| group).collect();
+    SortedLines {
+        lines: lines.into_iter(),
+        pos: None,
+        prepared: None,
+    }
+}
+
+/// Timestamp of the record parsed as `kind`, if it has one.
+fn timestamp(kind: &LineKind) -> Option<jiff::Timestamp> {
+    match kind {
+        LineKind::Json(record) | LineKind::EmbeddedJson { record, .. } => {
+            record.timestamp.as_ref().map(|ts| ts.value)
+        }
+        LineKind::Raw(_) => None,
+    }
+}
+
+impl LineSource for SortedLines {
+    fn next_line(&mut self, _deadline: Option<Instant>) -> Next {
+        let Some((pos, line, kind)) = self.lines.next() else {
+            return Next::Eof;
+        };
+        self.pos = Some(pos);
+        self.prepared = kind.map(|kind| Prepared {
+            kind,
+            formatted: None,
+        });
+        Next::Line(line)
+    }
+
+    fn take_prepared(&mut self) -> Option<Prepared> {
+        self.prepared.take()
+    }
+
+    fn position(&self) -> Option<Position> {
+        self.pos
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sorts_by_timestamp_keeping_raw_lines_attached() {
+        let lines = [
+            "starting",
+            r#"{"ts":"2024-01-01T00:00:03Z","msg":"c"}"#,
+            "  at trace for c",
+            r#"{"ts":"2024-01-01T00:00:01Z","msg":"a1"}"#,
+            r#"{"msg":"no timestamp"}"#,
+            r#"{"ts":"2024-01-01T00:00:02Z","msg":"b"}"#,
+            r#"{"ts":"2024-01-01T00:00:01Z","msg":"a2"}"#,
+        ];
+        let mut sorted = sort_lines(
+            lines.iter().map(|l| Ok(l.to_string())).collect(),
+            &Config::default(),
+        );
+        let mut order = Vec::new();
+        while let Next::Line(line) = sorted.next_line(None) {
+            let line = line.unwrap();
+            let pos = sorted.position().unwrap();
+            let index = lines.iter().position(|x| *x == line).unwrap();
+            // Lines keep their input positions and parses.
+            assert_eq!(pos.line, index + 1);
+            let offset: usize = lines[..index].iter().map(|l| l.len() + 1).sum();
+            assert_eq!(pos.offset, offset as u64);
+            assert!(sorted.take_prepared().is_some());
+            order.push(index);
+        }
+        assert_eq!(order, [0, 3, 4, 6, 5, 1, 2]);
+    }
+}
@@ -0,0 +1,102 @@
+//! Inline sparkline of a numeric field (`--sparkline`).
+//!
+//! A [`Sparkline`] keeps the last [`WIDTH`] values of one field and draws
+//! them as a row of block characters scaled between their minimum and
+//! maximum. Appended to every record carrying the field, it gives a live
+//! feel for a metric embedded in the logs, such as request latency, while
+//! following a stream.
+
+use std::collections::VecDeque;
+
+use crate::expr::field_text;
+use crate::parser::LogRecord;
+
+/// Number of values shown.
+pub const WIDTH: usize = 20;
+
+/// The most recent values of the tracked field.
+#[derive(Debug, Clone, Default)]
+pub struct Sparkline {
+    values: VecDeque<f64>,
+}
+
+impl Sparkline {
+    /// Whether no value has been recorded yet.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Record the next value, dropping the oldest past [`WIDTH`].
+    pub fn push(&mut self, value: f64) {
+        if self.values.len() == WIDTH {
+            self.values.pop_front();
+        }
+        self.values.push_back(value);
+    }
+
+    /// Draw the values with `ticks`, lowest to highest. When all values
+    /// are equal they are drawn with the lowest tick.
+    pub fn render(&self, ticks: &[char]) -> String {
+        let (min, max) = self
+            .values
+            .iter()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &v| {
+                (lo.min(v), hi.max(v))
+            });
+        #[allow(clippy::cast_precision_loss)]
+        let top = (ticks.len() - 1) as f64;
+        self.values
+            .iter()
+            .map(|&v| {
+                let scaled = if max > min {
+                    ((v - min) / (max - min) * top).round()
+                } else {
+                    0.0
+                };
+                #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                ticks[scaled as usize]
+            })
+            .collect()
+    }
+}
+
+/// Numeric value of `field` on the record, including string-encoded
+/// numbers like `"120"`.
+pub fn field_value(record: &LogRecord, field: &str) -> Option<f64> {
+    field_text(record, field)?
+        .parse()
+        .ok()
+        .filter(|v: &f64| v.is_finite())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TICKS: [char; 4] = ['_', '.', '-', '#'];
+
+    #[test]
+    fn test_render_scales_between_min_and_max() {
+        let mut spark = Sparkline::default();
+        for v in [10.0, 40.0, 20.0, 30.0] {
+            spark.push(v);
+        }
+        assert_eq!(spark.render(&TICKS), "_#.-");
+    }
+
+    #[test]
+    fn test_keeps_last_values() {
+        let mut spark = Sparkline::default();
+        for v in 0..30 {
+            spark.push(f64::from(v));
+        }
+        let line = spark.render(&TICKS);
+        assert_eq!(line.chars().count(), WIDTH);
+        assert!(line.starts_with('_') && line.ends_with('#'));
+
+        let mut flat = Sparkline::default();
+        flat.push(5.0);
+        flat.push(5.0);
+        assert_eq!(flat.render(&TICKS), "__");
+    }
+}
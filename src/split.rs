@@ -0,0 +1,244 @@
+//! Per-value output files (`--split-by`).
+//!
+//! Demultiplexes a combined stream into one file per value of a field, e.g.
+//! `--split-by service --split-dir out` writes `out/payments.log` and
+//! `out/auth.log`. Records without the field (and non-JSON lines) are left
+//! for the caller to write to stdout.
+//!
+//! At most [`MAX_OPEN_FILES`] files are kept open; on high-cardinality
+//! fields the least recently written one is closed and reopened for
+//! appending when its value shows up again.
+
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::expr::field_text;
+use crate::parser::LogRecord;
+
+/// File extension used for split output files.
+const SPLIT_EXTENSION: &str = "log";
+
+/// Longest file stem kept from a value, in bytes; with the hash and
+/// extension the name stays under common 255-byte limits.
+const MAX_STEM_BYTES: usize = 200;
+
+/// Split files kept open at once.
+pub const MAX_OPEN_FILES: usize = 64;
+
+/// Routes formatted entries to one file per field value.
+#[derive(Debug)]
+pub struct Splitter {
+    field: String,
+    dir: PathBuf,
+    files: HashMap<String, OpenFile>,
+    /// Keys whose file was created by this run, so reopening appends.
+    created: HashSet<String>,
+    /// Write counter ordering `OpenFile::used`.
+    writes: u64,
+}
+
+/// An open split file and when it was last written.
+#[derive(Debug)]
+struct OpenFile {
+    writer: BufWriter<File>,
+    used: u64,
+}
+
+impl Splitter {
+    /// Split on `field`, creating files inside `dir`.
+    pub fn new(field: impl Into<String>, dir: impl Into<PathBuf>) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self {
+            field: field.into(),
+            dir,
+            files: HashMap::new(),
+            created: HashSet::new(),
+            writes: 0,
+        })
+    }
+
+    /// The file stem a record is routed to, or `None` if it lacks the field.
+    pub fn key_for(&self, record: &LogRecord) -> Option<String> {
+        field_text(record, &self.field).map(|value| file_stem(&value))
+    }
+
+    /// Write an already formatted entry to the file for `key`.
+    pub fn write_entry(&mut self, key: &str, entry: &str, line_gap: usize) -> io::Result<()> {
+        self.writes += 1;
+        let file = match self.files.entry(key.to_string()) {
+            Entry::Occupied(file) => file.into_mut(),
+            Entry::Vacant(slot) => {
+                let append = !self.created.insert(key.to_string());
+                slot.insert(OpenFile {
+                    writer: BufWriter::new(open(&self.dir, key, append)?),
+                    used: 0,
+                })
+            }
+        };
+        file.used = self.writes;
+        let trailing = "\n".repeat(1 + line_gap);
+        write!(file.writer, "{entry}{trailing}")?;
+        if self.files.len() > MAX_OPEN_FILES {
+            self.close_least_recent()?;
+        }
+        Ok(())
+    }
+
+    /// Flush and close the file written longest ago.
+    fn close_least_recent(&mut self) -> io::Result<()> {
+        let oldest = self
+            .files
+            .iter()
+            .min_by_key(|(_, file)| file.used)
+            .map(|(key, _)| key.clone());
+        match oldest.and_then(|key| self.files.remove(&key)) {
+            Some(mut file) => file.writer.flush(),
+            None => Ok(()),
+        }
+    }
+
+    /// Flush every open file.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.files
+            .values_mut()
+            .try_for_each(|file| file.writer.flush())
+    }
+}
+
+/// Open the split file for `key` in `dir`: created afresh, or appended to
+/// when it was created earlier in the run and closed since.
+fn open(dir: &Path, key: &str, append: bool) -> io::Result<File> {
+    let path = dir.join(format!("{key}.{SPLIT_EXTENSION}"));
+    if append {
+        OpenOptions::new().append(true).open(path)
+    } else {
+        File::create(path)
+    }
+}
+
+/// Turn a field value into a safe file stem.
+///
+/// Path separators, control characters, and leading dots are replaced with
+/// `_` so values can never escape the output directory, and long values
+/// are cut to [`MAX_STEM_BYTES`]. A value changed by that, or empty, gets a
+/// hash of the original appended, so `a/b` and `a_b` still go to different
+/// files.
+fn file_stem(value: &str) -> String {
+    let mut stem: String = value
+        .chars()
+        .map(|c| {
+            if c == '/' || c == '\\' || c.is_control() {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect();
+    if stem.starts_with('.') {
+        stem.replace_range(..1, "_");
+    }
+    if stem.is_empty() {
+        stem.push('_');
+    }
+    if stem.len() > MAX_STEM_BYTES {
+        let cut = stem.floor_char_boundary(MAX_STEM_BYTES);
+        stem.truncate(cut);
+    }
+    if stem != value {
+        // FNV-1a: stable across runs and releases, unlike std's hasher.
+        let hash = value.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        });
+        let _ = write!(stem, "-{:08x}", hash >> 32);
+    }
+    stem
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::parser::{LineKind, parse_line};
+
+    fn record(line: &str) -> LogRecord {
+        match parse_line(line, &Config::default()) {
+            LineKind::Json(record) => record,
+            other => panic!("expected JSON record, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_file_stem_sanitizes() {
+        assert_eq!(file_stem("payments"), "payments");
+        assert!(file_stem("../etc/passwd").starts_with("_._etc_passwd-"));
+        assert!(file_stem("a\nb").starts_with("a_b-"));
+        assert!(file_stem("").starts_with("_-"));
+    }
+
+    #[test]
+    fn test_file_stem_keeps_sanitized_values_apart() {
+        let stems = [file_stem("a/b"), file_stem("a\\b"), file_stem("a_b")];
+        assert_eq!(stems[2], "a_b");
+        assert_ne!(stems[0], stems[1]);
+        assert_ne!(stems[0], stems[2]);
+    }
+
+    #[test]
+    fn test_file_stem_caps_long_values() {
+        let long = "é".repeat(300);
+        let stem = file_stem(&long);
+        assert!(stem.len() <= MAX_STEM_BYTES + 9, "{}", stem.len());
+        assert_ne!(stem, file_stem(&format!("{long}x")));
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut splitter = Splitter::new("service", dir.path()).unwrap();
+        splitter.write_entry(&stem, "one", 0).unwrap();
+    }
+
+    #[test]
+    fn test_key_for_missing_field() {
+        let dir = tempfile::tempdir().unwrap();
+        let splitter = Splitter::new("service", dir.path()).unwrap();
+        assert_eq!(
+            splitter.key_for(&record(r#"{"msg":"x","service":"auth"}"#)),
+            Some("auth".to_string())
+        );
+        assert_eq!(splitter.key_for(&record(r#"{"msg":"x"}"#)), None);
+    }
+
+    #[test]
+    fn test_writes_one_file_per_value() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut splitter = Splitter::new("service", dir.path().join("out")).unwrap();
+        splitter.write_entry("auth", "first", 0).unwrap();
+        splitter.write_entry("payments", "second", 0).unwrap();
+        splitter.write_entry("auth", "third", 0).unwrap();
+        splitter.flush().unwrap();
+
+        let read = |name: &str| fs::read_to_string(dir.path().join("out").join(name)).unwrap();
+        assert_eq!(read("auth.log"), "first\nthird\n");
+        assert_eq!(read("payments.log"), "second\n");
+    }
+
+    #[test]
+    fn test_closes_least_recent_file_and_appends_on_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut splitter = Splitter::new("service", dir.path()).unwrap();
+        for i in 0..=MAX_OPEN_FILES {
+            splitter.write_entry(&format!("s{i}"), "one", 0).unwrap();
+        }
+        assert_eq!(splitter.files.len(), MAX_OPEN_FILES);
+        assert!(!splitter.files.contains_key("s0"));
+        splitter.write_entry("s0", "two", 0).unwrap();
+        splitter.flush().unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.path().join("s0.log")).unwrap(),
+            "one\ntwo\n"
+        );
+    }
+}
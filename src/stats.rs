@@ -0,0 +1,146 @@
+//! End-of-stream statistics (`--stats`).
+//!
+//! [`Stats`] counts every input entry before filters: lines read, records
+//! and raw lines, malformed JSON, records per level, the time span the
+//! record timestamps cover, and how often each field key appears. The
+//! summary is written to stderr once input ends, so it never mixes with
+//! piped output.
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, Write};
+
+use crate::level::Level;
+use crate::parser::LineKind;
+
+/// Number of field keys listed in the summary.
+const TOP_KEYS: usize = 10;
+
+/// Counts accumulated over the input.
+#[derive(Debug, Clone, Default)]
+pub struct Stats {
+    lines: usize,
+    records: usize,
+    raw: usize,
+    malformed: usize,
+    levels: BTreeMap<Level, usize>,
+    unleveled: usize,
+    first: Option<jiff::Timestamp>,
+    last: Option<jiff::Timestamp>,
+    keys: HashMap<String, usize>,
+}
+
+impl Stats {
+    /// Count one input entry spanning `lines` input lines.
+    pub fn observe(&mut self, parsed: &LineKind, lines: usize, malformed: bool) {
+        self.lines += lines;
+        let (LineKind::Json(record) | LineKind::EmbeddedJson { record, .. }) = parsed else {
+            self.raw += 1;
+            self.malformed += usize::from(malformed);
+            return;
+        };
+        self.records += 1;
+        match record.level {
+            Some(level) => *self.levels.entry(level).or_default() += 1,
+            None => self.unleveled += 1,
+        }
+        if let Some(ref ts) = record.timestamp {
+            let ts = ts.value;
+            self.first = Some(self.first.map_or(ts, |first| first.min(ts)));
+            self.last = Some(self.last.map_or(ts, |last| last.max(ts)));
+        }
+        for key in record.extra.keys() {
+            match self.keys.get_mut(key) {
+                Some(n) => *n += 1,
+                None => {
+                    self.keys.insert(key.clone(), 1);
+                }
+            }
+        }
+    }
+
+    /// Write the summary.
+    pub fn write_summary(&self, out: &mut impl Write) -> io::Result<()> {
+        writeln!(
+            out,
+            "cor: {} lines: {} records, {} raw, {} malformed JSON",
+            self.lines, self.records, self.raw, self.malformed
+        )?;
+        if self.records > 0 {
+            let mut levels: Vec<String> = self
+                .levels
+                .iter()
+                .map(|(level, n)| format!("{} {n}", level.name()))
+                .collect();
+            if self.unleveled > 0 {
+                levels.push(format!("none {}", self.unleveled));
+            }
+            writeln!(out, "cor: levels: {}", levels.join(", "))?;
+        }
+        if let (Some(first), Some(last)) = (self.first, self.last) {
+            let span = last.duration_since(first);
+            writeln!(out, "cor: span: {first} .. {last} ({span:#})")?;
+        }
+        if !self.keys.is_empty() {
+            let mut keys: Vec<(&String, &usize)> = self.keys.iter().collect();
+            keys.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+            writeln!(out, "cor: top fields:")?;
+            write_top(
+                out,
+                keys.iter().map(|&(key, &n)| (n, key.as_str())),
+                TOP_KEYS,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Write the first `limit` of `rows` as a right-aligned count and a name,
+/// then how many rows were left out.
+pub fn write_top<'a>(
+    out: &mut impl Write,
+    rows: impl ExactSizeIterator<Item = (usize, &'a str)>,
+    limit: usize,
+) -> io::Result<()> {
+    let more = rows.len().saturating_sub(limit);
+    for (n, name) in rows.take(limit) {
+        writeln!(out, "{n:>8}  {name}")?;
+    }
+    if more > 0 {
+        writeln!(out, "{:>8}  ({more} more)", "")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::parser::parse_line;
+
+    #[test]
+    fn test_summary() {
+        let config = Config::default();
+        let mut stats = Stats::default();
+        for line in [
+            r#"{"ts":"2026-01-15T10:30:00Z","level":"info","msg":"a","status":200}"#,
+            r#"{"ts":"2026-01-15T10:32:05Z","level":"error","msg":"b","status":500,"path":"/x"}"#,
+            r#"{"ts":"2026-01-15T10:31:00Z","msg":"c"}"#,
+            "plain text",
+        ] {
+            stats.observe(&parse_line(line, &config), 1, false);
+        }
+        stats.observe(&parse_line(r#"{"broken"#, &config), 2, true);
+
+        let mut out = Vec::new();
+        stats.write_summary(&mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "cor: 6 lines: 3 records, 2 raw, 1 malformed JSON\n\
+             cor: levels: info 1, error 1, none 1\n\
+             cor: span: 2026-01-15T10:30:00Z .. 2026-01-15T10:32:05Z (2m 5s)\n\
+             cor: top fields:\n\
+             \x20      2  status\n\
+             \x20      1  path\n"
+        );
+    }
+}
@@ -0,0 +1,242 @@
+//! RFC 5424 syslog lines.
+//!
+//! Lines like `<165>1 2026-01-15T10:30:00Z web-1 api 4242 ID47 [req@32473
+//! id="7"] request done`, as written by rsyslog or `logger --rfc5424`, are
+//! read into a [`LogRecord`]: the severity in the priority becomes the
+//! level, the app name the logger, and the host, process ID, message ID,
+//! facility, and structured data (as `SD-ID.param` keys) extra fields.
+//!
+//! When the message is itself a JSON object, it is parsed as usual and the
+//! header only fills in what the JSON lacks.
+
+use std::collections::BTreeMap;
+
+use serde_json::Value;
+
+use crate::extras::Extras;
+use crate::level::Level;
+use crate::parser::LogRecord;
+use crate::timestamp::Timestamp;
+
+/// Facility names by code (RFC 5424 §6.2.1).
+const FACILITIES: [&str; 24] = [
+    "kern", "user", "mail", "daemon", "auth", "syslog", "lpr", "news", "uucp", "cron", "authpriv",
+    "ftp", "ntp", "audit", "alert", "clock", "local0", "local1", "local2", "local3", "local4",
+    "local5", "local6", "local7",
+];
+
+/// The header of an RFC 5424 line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Header<'a> {
+    /// Facility × 8 + severity.
+    pub priority: u8,
+    pub timestamp: Option<&'a str>,
+    pub hostname: Option<&'a str>,
+    pub app_name: Option<&'a str>,
+    pub procid: Option<&'a str>,
+    pub msgid: Option<&'a str>,
+    /// Structured data params as `SD-ID.param` → value.
+    pub structured: BTreeMap<String, String>,
+}
+
+/// Split an RFC 5424 line into its header and message, or `None` if the
+/// line isn't one.
+pub fn parse(line: &str) -> Option<(Header<'_>, &str)> {
+    let rest = line.strip_prefix('<')?;
+    let (priority, rest) = rest.split_once('>')?;
+    if priority.is_empty() || priority.len() > 3 || !priority.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let priority: u8 = priority.parse().ok().filter(|&p| p < 192)?;
+    let (version, rest) = rest.split_once(' ')?;
+    if !version.starts_with(|c: char| matches!(c, '1'..='9'))
+        || !version.bytes().all(|b| b.is_ascii_digit())
+    {
+        return None;
+    }
+    let mut fields = rest.splitn(6, ' ');
+    let mut next = || fields.next().map(|field| (field != "-").then_some(field));
+    let (timestamp, hostname, app_name, procid, msgid) =
+        (next()?, next()?, next()?, next()?, next()?);
+    let (structured, message) = structured_data(fields.next().unwrap_or(""))?;
+    let message = message.strip_prefix('\u{feff}').unwrap_or(message);
+    Some((
+        Header {
+            priority,
+            timestamp,
+            hostname,
+            app_name,
+            procid,
+            msgid,
+            structured,
+        },
+        message,
+    ))
+}
+
+/// Parse the structured data at the start of `s`, returning its params and
+/// the message after it.
+fn structured_data(s: &str) -> Option<(BTreeMap<String, String>, &str)> {
+    let mut params = BTreeMap::new();
+    if s == "-" || s.is_empty() {
+        return Some((params, ""));
+    }
+    if let Some(message) = s.strip_prefix("- ") {
+        return Some((params, message));
+    }
+    let mut rest = s;
+    while let Some(element) = rest.strip_prefix('[') {
+        let (id, mut body) = element.split_at(element.find([' ', ']'])?);
+        loop {
+            body = body.trim_start_matches(' ');
+            if let Some(after) = body.strip_prefix(']') {
+                rest = after;
+                break;
+            }
+            let (name, after) = body.split_once("=\"")?;
+            let (value, after) = quoted(after)?;
+            params.insert(format!("{id}.{name}"), value);
+            body = after;
+        }
+    }
+    match rest {
+        "" => Some((params, "")),
+        _ => rest.strip_prefix(' ').map(|message| (params, message)),
+    }
+}
+
+/// Read a param value up to its closing quote, unescaping `\"`, `\\`, and
+/// `\]`.
+fn quoted(s: &str) -> Option<(String, &str)> {
+    let mut value = String::new();
+    let mut chars = s.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Some((value, &s[i + 1..])),
+            '\\' => match chars.next()? {
+                (_, escaped @ ('"' | '\\' | ']')) => value.push(escaped),
+                (_, other) => {
+                    value.push('\\');
+                    value.push(other);
+                }
+            },
+            c => value.push(c),
+        }
+    }
+    None
+}
+
+impl Header<'_> {
+    /// Level from the severity in the priority.
+    pub fn level(&self) -> Level {
+        Level::from_syslog(i64::from(self.priority % 8))
+    }
+
+    /// Facility name from the priority.
+    pub fn facility(&self) -> &'static str {
+        FACILITIES[usize::from(self.priority / 8)]
+    }
+
+    /// A record of the header and a plain-text `message`.
+    pub fn into_record(self, message: &str) -> LogRecord {
+        let mut record = LogRecord {
+            timestamp: None,
+            level: None,
+            message: (!message.is_empty()).then(|| message.to_string()),
+            logger: None,
+            caller: None,
+            error: None,
+            extra: Extras::new(),
+            raw_json: String::new(),
+        };
+        self.fill(&mut record);
+        record.raw_json = record.to_normalized_json().to_string();
+        record
+    }
+
+    /// Fill in what `record` lacks from the header.
+    pub fn fill(self, record: &mut LogRecord) {
+        if record.timestamp.is_none() {
+            record.timestamp = self
+                .timestamp
+                .and_then(|ts| Timestamp::from_json_value(&Value::from(ts)));
+        }
+        record.level.get_or_insert_with(|| self.level());
+        if record.logger.is_none() {
+            record.logger = self.app_name.map(str::to_string);
+        }
+        let fields = [
+            ("hostname", self.hostname),
+            ("procid", self.procid),
+            ("msgid", self.msgid),
+            ("facility", Some(self.facility())),
+        ];
+        for (key, value) in fields {
+            if let Some(value) = value {
+                record.extra.insert_missing(key.to_string(), value.into());
+            }
+        }
+        for (key, value) in self.structured {
+            record.extra.insert_missing(key, value.into());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_header() {
+        let line = r#"<165>1 2026-01-15T10:30:00.003Z web-1 api 4242 ID47 [exampleSDID@32473 iut="3" eventSource="App\"lication"][meta x="1"] request done"#;
+        let (header, message) = parse(line).unwrap();
+        assert_eq!(header.priority, 165);
+        assert_eq!(header.level(), Level::Info);
+        assert_eq!(header.facility(), "local4");
+        assert_eq!(header.timestamp, Some("2026-01-15T10:30:00.003Z"));
+        assert_eq!(header.hostname, Some("web-1"));
+        assert_eq!(header.app_name, Some("api"));
+        assert_eq!(header.procid, Some("4242"));
+        assert_eq!(header.msgid, Some("ID47"));
+        assert_eq!(
+            header.structured["exampleSDID@32473.eventSource"],
+            "App\"lication"
+        );
+        assert_eq!(header.structured["meta.x"], "1");
+        assert_eq!(message, "request done");
+    }
+
+    #[test]
+    fn test_nil_fields_and_no_message() {
+        let (header, message) = parse("<11>1 - - - - - -").unwrap();
+        assert_eq!(header.level(), Level::Error);
+        assert_eq!(header.timestamp, None);
+        assert_eq!(header.app_name, None);
+        assert!(header.structured.is_empty());
+        assert_eq!(message, "");
+
+        let (_, message) = parse("<14>1 - host app - - - \u{feff}hello").unwrap();
+        assert_eq!(message, "hello");
+    }
+
+    #[test]
+    fn test_rejects_other_lines() {
+        assert!(parse("<html> page").is_none());
+        assert!(parse("<34>Oct 11 22:14:15 mymachine su: 'su root' failed").is_none());
+        assert!(parse("<999>1 - - - - - -").is_none());
+        assert!(parse("<14>1 - - - - - [broken").is_none());
+    }
+
+    #[test]
+    fn test_into_record() {
+        let (header, message) =
+            parse("<12>1 2026-01-15T10:30:00Z web-1 api - - - disk low").unwrap();
+        let record = header.into_record(message);
+        assert_eq!(record.level, Some(Level::Warn));
+        assert_eq!(record.message.as_deref(), Some("disk low"));
+        assert_eq!(record.logger.as_deref(), Some("api"));
+        assert_eq!(record.extra["hostname"], "web-1");
+        assert_eq!(record.extra["facility"], "user");
+        assert!(record.raw_json.contains(r#""message":"disk low""#));
+    }
+}
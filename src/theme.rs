@@ -0,0 +1,852 @@
+//! Color themes (`--theme`).
+//!
+//! A [`Theme`] holds the colors of everything the formatter draws: level
+//! badges, timestamps, field keys, errors, and the elapsed column. Besides
+//! the default palette there are color-blind friendly ones built on the
+//! Okabe–Ito colors, which keep levels apart on the blue–yellow axis that
+//! deuteranopes and protanopes still see.
+//!
+//! The config file's `[theme]` table sets individual elements on top of a
+//! palette, as [`Overrides`] of colors and `bold`/`dimmed`. Colors are ANSI
+//! names, `#rrggbb` hex, or 256-color indexes; on terminals without
+//! truecolor or 256-color support, [`Theme::with_depth`] maps them to the
+//! nearest color the terminal has.
+//!
+//! Flattened field keys are colored by their top-level namespace, so all
+//! `http.*` keys share one hue and `db.*` keys another. The hue is picked
+//! by a stable hash of the namespace, keeping it the same across runs.
+//!
+//! When the terminal background is known, [`Theme::with_background`] picks
+//! the light or dark variant of the theme and replaces colors too close to
+//! the background with lighter or darker ones.
+
+use clap::ValueEnum;
+use owo_colors::{AnsiColors, DynColors, Style};
+
+use owo_colors::XtermColors;
+
+use crate::elapsed::Pace;
+use crate::level::Level;
+
+/// Lowest contrast ratio (WCAG, 1–21) a foreground color may have against
+/// the terminal background before it is adjusted.
+pub const MIN_CONTRAST: f64 = 2.0;
+
+/// Built-in palette.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Palette {
+    /// Standard ANSI colors.
+    #[default]
+    Default,
+    /// Blue/orange levels for red–green (green-weak) color blindness.
+    Deuteranopia,
+    /// Blue/yellow levels for red–green (red-weak) color blindness.
+    Protanopia,
+}
+
+/// Colors a terminal can show.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ColorDepth {
+    /// The 16 ANSI colors.
+    Ansi16,
+    /// The xterm 256-color palette.
+    Xterm256,
+    /// 24-bit colors.
+    TrueColor,
+}
+
+impl ColorDepth {
+    /// Depth announced by the environment: `COLORTERM=truecolor` (or
+    /// `24bit`), then a `TERM` ending in `256color`.
+    pub fn detect(var: impl Fn(&str) -> Option<String>) -> Self {
+        if var("COLORTERM").is_some_and(|v| v == "truecolor" || v == "24bit") {
+            Self::TrueColor
+        } else if var("TERM").is_some_and(|v| v.ends_with("256color")) {
+            Self::Xterm256
+        } else {
+            Self::Ansi16
+        }
+    }
+}
+
+/// A 24-bit color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl Rgb {
+    /// Relative luminance (WCAG 2).
+    fn luminance(self) -> f64 {
+        let channel = |c: u8| {
+            let c = f64::from(c) / 255.0;
+            if c <= 0.039_28 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        0.0722_f64.mul_add(
+            channel(self.2),
+            0.2126_f64.mul_add(channel(self.0), 0.7152 * channel(self.1)),
+        )
+    }
+
+    /// Contrast ratio against `other`, from 1 (none) to 21.
+    pub fn contrast(self, other: Self) -> f64 {
+        let (a, b) = (self.luminance(), other.luminance());
+        (a.max(b) + 0.05) / (a.min(b) + 0.05)
+    }
+
+    /// Whether this is a light color, as a background.
+    pub fn is_light(self) -> bool {
+        self.luminance() > 0.5_f64.powf(2.2)
+    }
+
+    /// Squared distance to `other`, for finding the closest palette color.
+    fn distance(self, other: Self) -> u32 {
+        let channel = |a: u8, b: u8| u32::from(a.abs_diff(b)).pow(2);
+        channel(self.0, other.0) + channel(self.1, other.1) + channel(self.2, other.2)
+    }
+
+    /// Move `amount` (0–1) of the way towards `target`.
+    fn mix(self, target: Self, amount: f64) -> Self {
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let channel = |from: u8, to: u8| {
+            (f64::from(to) - f64::from(from))
+                .mul_add(amount, f64::from(from))
+                .round() as u8
+        };
+        Self(
+            channel(self.0, target.0),
+            channel(self.1, target.1),
+            channel(self.2, target.2),
+        )
+    }
+}
+
+/// Color and emphasis of one element.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Paint {
+    /// Foreground color; the terminal's default when `None`.
+    pub fg: Option<DynColors>,
+    pub bold: bool,
+    pub dimmed: bool,
+}
+
+impl Paint {
+    /// No color or emphasis.
+    pub const PLAIN: Self = Self {
+        fg: None,
+        bold: false,
+        dimmed: false,
+    };
+
+    const fn ansi(color: AnsiColors) -> Self {
+        Self {
+            fg: Some(DynColors::Ansi(color)),
+            ..Self::PLAIN
+        }
+    }
+
+    const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Self {
+            fg: Some(DynColors::Rgb(r, g, b)),
+            ..Self::PLAIN
+        }
+    }
+
+    const fn bold(self) -> Self {
+        Self { bold: true, ..self }
+    }
+
+    const fn dimmed(self) -> Self {
+        Self {
+            dimmed: true,
+            ..self
+        }
+    }
+
+    /// Parse a color spec: whitespace-separated styles (`bold`, `dimmed`,
+    /// `plain`) and a color, given as a name (`red`, `bright_blue`, ...),
+    /// `#rrggbb` hex, or a 256-color index, e.g. `"bold #ff8800"`.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut paint = Self::PLAIN;
+        for word in spec.split_whitespace() {
+            match word.to_ascii_lowercase().as_str() {
+                "bold" => paint.bold = true,
+                "dim" | "dimmed" => paint.dimmed = true,
+                "plain" | "default" => {}
+                color => {
+                    paint.fg = Some(
+                        parse_color(color)
+                            .ok_or_else(|| format!("unknown color or style '{word}'"))?,
+                    );
+                }
+            }
+        }
+        Ok(paint)
+    }
+
+    /// Terminal style drawing this paint.
+    pub fn style(self) -> Style {
+        let mut style = Style::new();
+        if let Some(fg) = self.fg {
+            style = style.color(fg);
+        }
+        if self.bold {
+            style = style.bold();
+        }
+        if self.dimmed {
+            style = style.dimmed();
+        }
+        style
+    }
+
+    /// The variant of this paint for a light background: dimmed text, which
+    /// terminals draw as light grey, becomes a dark grey, and light greys
+    /// are darkened by the same amount.
+    fn for_light_background(self) -> Self {
+        match self.fg.and_then(rgb_of) {
+            None if self.dimmed => Self {
+                fg: Some(DynColors::Rgb(110, 110, 110)),
+                dimmed: false,
+                ..self
+            },
+            Some(Rgb(r, g, b)) if r == g && g == b && r > 128 => Self {
+                fg: Some(DynColors::Rgb(255 - r, 255 - r, 255 - r)),
+                ..self
+            },
+            _ => self,
+        }
+    }
+
+    /// This paint with its color replaced by the closest one available at
+    /// `depth`.
+    fn at_depth(self, depth: ColorDepth) -> Self {
+        let fg = match (self.fg, depth) {
+            (Some(DynColors::Rgb(r, g, b)), ColorDepth::Xterm256) => {
+                DynColors::Xterm(XtermColors::from(nearest_xterm(Rgb(r, g, b))))
+            }
+            (Some(color @ (DynColors::Rgb(..) | DynColors::Xterm(_))), ColorDepth::Ansi16) => {
+                match rgb_of(color) {
+                    Some(rgb) => DynColors::Ansi(nearest_ansi(rgb)),
+                    None => return self,
+                }
+            }
+            _ => return self,
+        };
+        Self {
+            fg: Some(fg),
+            ..self
+        }
+    }
+
+    /// This paint with its color adjusted to contrast with `background`.
+    fn readable_on(self, background: Rgb) -> Self {
+        let Some(fg) = self.fg.and_then(rgb_of) else {
+            return self;
+        };
+        if fg.contrast(background) >= MIN_CONTRAST {
+            return self;
+        }
+        let target = if background.is_light() {
+            Rgb(0, 0, 0)
+        } else {
+            Rgb(255, 255, 255)
+        };
+        let adjusted = (1..=10)
+            .map(|step| fg.mix(target, f64::from(step) / 10.0))
+            .find(|color| color.contrast(background) >= MIN_CONTRAST)
+            .unwrap_or(target);
+        Self {
+            fg: Some(DynColors::Rgb(adjusted.0, adjusted.1, adjusted.2)),
+            ..self
+        }
+    }
+}
+
+/// Color by ANSI name, `#rrggbb`, or 256-color index.
+fn parse_color(spec: &str) -> Option<DynColors> {
+    if let Some(hex) = spec.strip_prefix('#') {
+        if hex.len() != 6 || !hex.is_ascii() {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+        return Some(DynColors::Rgb(channel(0)?, channel(2)?, channel(4)?));
+    }
+    if let Ok(index) = spec.parse::<u8>() {
+        return Some(DynColors::Xterm(XtermColors::from(index)));
+    }
+    ansi_color(spec).map(DynColors::Ansi)
+}
+
+/// ANSI color by name.
+fn ansi_color(name: &str) -> Option<AnsiColors> {
+    Some(match name {
+        "black" => AnsiColors::Black,
+        "red" => AnsiColors::Red,
+        "green" => AnsiColors::Green,
+        "yellow" => AnsiColors::Yellow,
+        "blue" => AnsiColors::Blue,
+        "magenta" | "purple" => AnsiColors::Magenta,
+        "cyan" => AnsiColors::Cyan,
+        "white" => AnsiColors::White,
+        "bright_black" => AnsiColors::BrightBlack,
+        "bright_red" => AnsiColors::BrightRed,
+        "bright_green" => AnsiColors::BrightGreen,
+        "bright_yellow" => AnsiColors::BrightYellow,
+        "bright_blue" => AnsiColors::BrightBlue,
+        "bright_magenta" => AnsiColors::BrightMagenta,
+        "bright_cyan" => AnsiColors::BrightCyan,
+        "bright_white" => AnsiColors::BrightWhite,
+        _ => return None,
+    })
+}
+
+/// The ANSI colors in index order.
+const ANSI_COLORS: [AnsiColors; 16] = {
+    use AnsiColors::{
+        Black, Blue, BrightBlack, BrightBlue, BrightCyan, BrightGreen, BrightMagenta, BrightRed,
+        BrightWhite, BrightYellow, Cyan, Green, Magenta, Red, White, Yellow,
+    };
+    [
+        Black,
+        Red,
+        Green,
+        Yellow,
+        Blue,
+        Magenta,
+        Cyan,
+        White,
+        BrightBlack,
+        BrightRed,
+        BrightGreen,
+        BrightYellow,
+        BrightBlue,
+        BrightMagenta,
+        BrightCyan,
+        BrightWhite,
+    ]
+};
+
+/// Channel levels of the 6×6×6 color cube at xterm indexes 16–231.
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// RGB value of the ANSI color `index` (0–15), using xterm's defaults.
+pub const fn ansi_rgb(index: u8) -> Option<Rgb> {
+    if index as usize >= ANSI_COLORS.len() {
+        return None;
+    }
+    ansi_color_rgb(ANSI_COLORS[index as usize])
+}
+
+/// RGB value of the xterm 256-color `index`.
+fn xterm_rgb(index: u8) -> Option<Rgb> {
+    match index {
+        0..=15 => ansi_rgb(index),
+        16..=231 => {
+            let i = usize::from(index - 16);
+            Some(Rgb(
+                CUBE_LEVELS[i / 36],
+                CUBE_LEVELS[i / 6 % 6],
+                CUBE_LEVELS[i % 6],
+            ))
+        }
+        232..=255 => {
+            let level = 8 + 10 * (index - 232);
+            Some(Rgb(level, level, level))
+        }
+    }
+}
+
+/// Index of the xterm color closest to `rgb`, from the color cube or the
+/// grey ramp.
+fn nearest_xterm(rgb: Rgb) -> u8 {
+    (16..=255)
+        .filter_map(|index| Some((index, xterm_rgb(index)?)))
+        .min_by_key(|&(_, candidate)| candidate.distance(rgb))
+        .map_or(16, |(index, _)| index)
+}
+
+/// The ANSI color closest to `rgb`.
+fn nearest_ansi(rgb: Rgb) -> AnsiColors {
+    ANSI_COLORS
+        .into_iter()
+        .filter_map(|color| Some((color, ansi_color_rgb(color)?)))
+        .min_by_key(|&(_, candidate)| candidate.distance(rgb))
+        .map_or(AnsiColors::Default, |(color, _)| color)
+}
+
+/// RGB value of `color`, using xterm's defaults for the ANSI and 256-color
+/// palettes.
+fn rgb_of(color: DynColors) -> Option<Rgb> {
+    match color {
+        DynColors::Rgb(r, g, b) => Some(Rgb(r, g, b)),
+        DynColors::Ansi(ansi) => ansi_color_rgb(ansi),
+        DynColors::Xterm(xterm) => xterm_rgb(u8::from(xterm)),
+        DynColors::Css(_) => None,
+    }
+}
+
+/// RGB value of the ANSI color `ansi`, using xterm's defaults.
+const fn ansi_color_rgb(ansi: AnsiColors) -> Option<Rgb> {
+    Some(match ansi {
+        AnsiColors::Black => Rgb(0, 0, 0),
+        AnsiColors::Red => Rgb(205, 0, 0),
+        AnsiColors::Green => Rgb(0, 205, 0),
+        AnsiColors::Yellow => Rgb(205, 205, 0),
+        AnsiColors::Blue => Rgb(0, 0, 238),
+        AnsiColors::Magenta => Rgb(205, 0, 205),
+        AnsiColors::Cyan => Rgb(0, 205, 205),
+        AnsiColors::White => Rgb(229, 229, 229),
+        AnsiColors::BrightBlack => Rgb(127, 127, 127),
+        AnsiColors::BrightRed => Rgb(255, 0, 0),
+        AnsiColors::BrightGreen => Rgb(0, 255, 0),
+        AnsiColors::BrightYellow => Rgb(255, 255, 0),
+        AnsiColors::BrightBlue => Rgb(92, 92, 255),
+        AnsiColors::BrightMagenta => Rgb(255, 0, 255),
+        AnsiColors::BrightCyan => Rgb(0, 255, 255),
+        AnsiColors::BrightWhite => Rgb(255, 255, 255),
+        AnsiColors::Default => return None,
+    })
+}
+
+/// Colors of every element the formatter draws.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Theme {
+    pub timestamp: Paint,
+    /// Level badges, from trace to fatal.
+    pub levels: [Paint; 6],
+    pub logger: Paint,
+    pub prefix: Paint,
+    pub tag: Paint,
+    /// Message text, unless a rule colors it.
+    pub message: Paint,
+    pub caller: Paint,
+    /// Keys without a namespace.
+    pub key: Paint,
+    /// Keys with a namespace (`http.status`), picked by [`Theme::key`].
+    pub namespaces: [Paint; 6],
+    pub value: Paint,
+    /// Error text; its key is drawn bold.
+    pub error: Paint,
+    /// Elapsed column for fast, slow, and very slow steps.
+    pub elapsed: [Paint; 3],
+    pub sparkline: Paint,
+    /// Non-JSON lines passed through; lines with their own colors are
+    /// left as is.
+    pub raw: Paint,
+    /// Label of cor's own notes, such as `parse error:`.
+    pub diagnostic: Paint,
+    /// String values in `--json` output.
+    pub json_string: Paint,
+    /// Numbers in `--json` output.
+    pub json_number: Paint,
+    /// `true`, `false`, and `null` in `--json` output.
+    pub json_literal: Paint,
+}
+
+/// Element colors from the config file's `[theme]` table, applied on top
+/// of a palette with [`Theme::with_overrides`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Overrides {
+    pub timestamp: Option<Paint>,
+    pub logger: Option<Paint>,
+    pub prefix: Option<Paint>,
+    pub tag: Option<Paint>,
+    pub message: Option<Paint>,
+    pub caller: Option<Paint>,
+    /// Every field key, replacing the namespace colors.
+    pub keys: Option<Paint>,
+    pub values: Option<Paint>,
+    pub error: Option<Paint>,
+    pub raw: Option<Paint>,
+    pub json_string: Option<Paint>,
+    pub json_number: Option<Paint>,
+    pub json_literal: Option<Paint>,
+    /// Level badges from `[colors]`, from trace to fatal.
+    pub levels: [Option<Paint>; 6],
+}
+
+impl Overrides {
+    /// Set the badge paint of `level`.
+    pub const fn set_level(&mut self, level: Level, paint: Paint) {
+        self.levels[level_index(level)] = Some(paint);
+    }
+}
+
+/// Position of `level` in the level arrays, from trace to fatal.
+const fn level_index(level: Level) -> usize {
+    match level {
+        Level::Trace => 0,
+        Level::Debug => 1,
+        Level::Info => 2,
+        Level::Warn => 3,
+        Level::Error => 4,
+        Level::Fatal => 5,
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::palette(Palette::Default)
+    }
+}
+
+impl Theme {
+    /// The theme of a built-in palette.
+    pub fn palette(palette: Palette) -> Self {
+        use AnsiColors::{Blue, Cyan, Green, Magenta, Red, Yellow};
+
+        let base = Self {
+            timestamp: Paint::PLAIN.bold(),
+            levels: [Cyan, Blue, Green, Yellow, Red, Magenta].map(|c| Paint::ansi(c).bold()),
+            logger: Paint::PLAIN.dimmed(),
+            prefix: Paint::ansi(Cyan).bold(),
+            tag: Paint::PLAIN.bold(),
+            message: Paint::PLAIN,
+            caller: Paint::PLAIN.dimmed(),
+            key: Paint::rgb(150, 150, 150).bold(),
+            namespaces: [
+                (95, 175, 215),
+                (215, 135, 95),
+                (135, 175, 95),
+                (175, 135, 215),
+                (215, 175, 95),
+                (95, 175, 175),
+            ]
+            .map(|(r, g, b)| Paint::rgb(r, g, b).bold()),
+            value: Paint::PLAIN,
+            error: Paint::ansi(Red),
+            elapsed: [Green, Yellow, Red].map(|c| Paint::ansi(c).bold()),
+            sparkline: Paint::ansi(Cyan),
+            raw: Paint::PLAIN,
+            diagnostic: Paint::ansi(Red).bold(),
+            json_string: Paint::ansi(Green),
+            json_number: Paint::ansi(Cyan),
+            json_literal: Paint::ansi(Yellow),
+        };
+        // Okabe–Ito colors.
+        let grey = Paint::rgb(153, 153, 153);
+        let sky_blue = Paint::rgb(86, 180, 233);
+        let blue = Paint::rgb(0, 114, 178);
+        let orange = Paint::rgb(230, 159, 0);
+        let yellow = Paint::rgb(240, 228, 66);
+        let vermillion = Paint::rgb(213, 94, 0);
+        let purple = Paint::rgb(204, 121, 167);
+        match palette {
+            Palette::Default => base,
+            Palette::Deuteranopia => Self {
+                levels: [grey, sky_blue, blue, orange, vermillion, purple].map(Paint::bold),
+                error: vermillion,
+                elapsed: [blue, orange, vermillion].map(Paint::bold),
+                ..base
+            },
+            Palette::Protanopia => Self {
+                levels: [grey, sky_blue, blue, yellow, orange, purple].map(Paint::bold),
+                error: orange,
+                elapsed: [sky_blue, yellow, orange].map(Paint::bold),
+                ..base
+            },
+        }
+    }
+
+    /// This theme with the elements set in `overrides` replaced.
+    #[must_use]
+    pub fn with_overrides(self, overrides: &Overrides) -> Self {
+        Self {
+            timestamp: overrides.timestamp.unwrap_or(self.timestamp),
+            logger: overrides.logger.unwrap_or(self.logger),
+            prefix: overrides.prefix.unwrap_or(self.prefix),
+            tag: overrides.tag.unwrap_or(self.tag),
+            message: overrides.message.unwrap_or(self.message),
+            caller: overrides.caller.unwrap_or(self.caller),
+            key: overrides.keys.unwrap_or(self.key),
+            namespaces: overrides.keys.map_or(self.namespaces, |paint| [paint; 6]),
+            value: overrides.values.unwrap_or(self.value),
+            error: overrides.error.unwrap_or(self.error),
+            raw: overrides.raw.unwrap_or(self.raw),
+            json_string: overrides.json_string.unwrap_or(self.json_string),
+            json_number: overrides.json_number.unwrap_or(self.json_number),
+            json_literal: overrides.json_literal.unwrap_or(self.json_literal),
+            levels: std::array::from_fn(|i| overrides.levels[i].unwrap_or(self.levels[i])),
+            ..self
+        }
+    }
+
+    /// Badge paint of `level`.
+    pub const fn level(&self, level: Level) -> Paint {
+        self.levels[level_index(level)]
+    }
+
+    /// Paint of the field `key`, by its namespace if it has one.
+    pub fn key(&self, key: &str) -> Paint {
+        match key.split_once('.') {
+            Some((namespace, _)) if !namespace.is_empty() => {
+                // FNV-1a: unlike std's hasher, stable across releases.
+                let hash = namespace
+                    .bytes()
+                    .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+                        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+                    });
+                #[allow(clippy::cast_possible_truncation)]
+                self.namespaces[(hash % self.namespaces.len() as u64) as usize]
+            }
+            _ => self.key,
+        }
+    }
+
+    /// Paint of the elapsed column at `pace`.
+    pub const fn elapsed(&self, pace: Pace) -> Paint {
+        self.elapsed[match pace {
+            Pace::Fast => 0,
+            Pace::Warn => 1,
+            Pace::Slow => 2,
+        }]
+    }
+
+    /// This theme adapted to the terminal `background`: the light variant
+    /// on light backgrounds, then [`Theme::with_contrast`].
+    #[must_use]
+    pub fn with_background(self, background: Rgb) -> Self {
+        let theme = if background.is_light() {
+            self.light()
+        } else {
+            self
+        };
+        theme.with_contrast(background)
+    }
+
+    /// The variant of this theme for light backgrounds. Themes are written
+    /// for dark ones, where dimmed text and light greys read well.
+    #[must_use]
+    pub fn light(self) -> Self {
+        self.map(Paint::for_light_background)
+    }
+
+    /// This theme with every color that is hard to read on `background`
+    /// lightened or darkened until it contrasts enough.
+    #[must_use]
+    pub fn with_contrast(self, background: Rgb) -> Self {
+        self.map(|paint| paint.readable_on(background))
+    }
+
+    /// This theme with every color the terminal can't show at `depth`
+    /// replaced by the closest one it can.
+    #[must_use]
+    pub fn with_depth(self, depth: ColorDepth) -> Self {
+        self.map(|paint| paint.at_depth(depth))
+    }
+
+    /// Apply `fix` to every paint.
+    fn map(self, fix: impl Fn(Paint) -> Paint) -> Self {
+        Self {
+            timestamp: fix(self.timestamp),
+            levels: self.levels.map(&fix),
+            logger: fix(self.logger),
+            prefix: fix(self.prefix),
+            tag: fix(self.tag),
+            message: fix(self.message),
+            caller: fix(self.caller),
+            key: fix(self.key),
+            namespaces: self.namespaces.map(&fix),
+            value: fix(self.value),
+            error: fix(self.error),
+            elapsed: self.elapsed.map(&fix),
+            sparkline: fix(self.sparkline),
+            raw: fix(self.raw),
+            diagnostic: fix(self.diagnostic),
+            json_string: fix(self.json_string),
+            json_number: fix(self.json_number),
+            json_literal: fix(self.json_literal),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_contrast_ratio() {
+        let (black, white) = (Rgb(0, 0, 0), Rgb(255, 255, 255));
+        assert!((black.contrast(white) - 21.0).abs() < 0.01);
+        assert!((white.contrast(white) - 1.0).abs() < 0.01);
+        assert!(white.is_light());
+        assert!(!Rgb(30, 30, 30).is_light());
+    }
+
+    #[test]
+    fn test_default_palette_matches_ansi_styles() {
+        let theme = Theme::default();
+        assert_eq!(theme.level(Level::Error).style(), Style::new().red().bold());
+        assert_eq!(theme.elapsed(Pace::Slow).style(), Style::new().red().bold());
+        assert_eq!(theme.logger.style(), Style::new().dimmed());
+    }
+
+    #[test]
+    fn test_color_blind_palettes_avoid_red_and_green() {
+        for palette in [Palette::Deuteranopia, Palette::Protanopia] {
+            let theme = Theme::palette(palette);
+            for paint in theme.levels.iter().chain(&theme.elapsed) {
+                let Some(Rgb(r, g, b)) = paint.fg.and_then(rgb_of) else {
+                    panic!("{palette:?} level colors are RGB");
+                };
+                // Pure red or green hues are what these viewers confuse.
+                assert!(
+                    !(g < 50 && b < 50 && r > 150),
+                    "{palette:?}: red {r},{g},{b}"
+                );
+                assert!(
+                    !(r < 50 && b < 50 && g > 150),
+                    "{palette:?}: green {r},{g},{b}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_contrast_fallback() {
+        let white = Rgb(255, 255, 255);
+        let theme = Theme::default().with_contrast(white);
+        // Yellow on white is adjusted to something readable...
+        let warn = theme.level(Level::Warn);
+        let fg = warn.fg.and_then(rgb_of).unwrap();
+        assert!(fg.contrast(white) >= MIN_CONTRAST);
+        assert!(warn.bold);
+        // ...while red already contrasts and keeps its ANSI color.
+        assert_eq!(
+            theme.level(Level::Error),
+            Theme::default().level(Level::Error)
+        );
+        // Paints without a color are left alone.
+        assert_eq!(theme.logger, Paint::PLAIN.dimmed());
+    }
+
+    #[test]
+    fn test_light_variant_on_light_background() {
+        let white = Rgb(255, 255, 255);
+        let theme = Theme::default().with_background(white);
+        // Dimmed text and the light grey keys turn dark grey.
+        assert_eq!(theme.logger, Paint::rgb(110, 110, 110));
+        assert_eq!(theme.key, Paint::rgb(105, 105, 105).bold());
+        // Colored paints are only contrast-adjusted.
+        assert_eq!(
+            theme.level(Level::Error),
+            Theme::default().level(Level::Error)
+        );
+
+        let dark = Theme::default().with_background(Rgb(30, 30, 46));
+        assert_eq!(dark.logger, Paint::PLAIN.dimmed());
+        assert_eq!(dark.key, Theme::default().key);
+    }
+
+    #[test]
+    fn test_keys_colored_by_namespace() {
+        let theme = Theme::default();
+        assert_eq!(theme.key("status"), theme.key);
+        assert_eq!(theme.key(".hidden"), theme.key);
+        assert_eq!(theme.key("http.status"), theme.key("http.method"));
+        assert_eq!(theme.key("http.request.id"), theme.key("http.status"));
+        assert_ne!(theme.key("http.status"), theme.key);
+        // Hash-picked, but fixed: the same namespace keeps its hue.
+        let http = theme.key("http.x");
+        assert!(
+            ["db", "user", "k8s", "trace"]
+                .iter()
+                .any(|ns| theme.key(&format!("{ns}.x")) != http)
+        );
+    }
+
+    #[test]
+    fn test_parse_paint() {
+        assert_eq!(
+            Paint::parse("bold cyan"),
+            Ok(Paint::ansi(AnsiColors::Cyan).bold())
+        );
+        assert_eq!(Paint::parse("DIM"), Ok(Paint::PLAIN.dimmed()));
+        assert_eq!(Paint::parse("plain"), Ok(Paint::PLAIN));
+        assert!(Paint::parse("bold teal").unwrap_err().contains("'teal'"));
+    }
+
+    #[test]
+    fn test_overrides() {
+        let blue = Paint::ansi(AnsiColors::Blue);
+        let theme = Theme::default().with_overrides(&Overrides {
+            message: Some(blue),
+            keys: Some(blue),
+            ..Overrides::default()
+        });
+        assert_eq!(theme.message, blue);
+        assert_eq!(theme.key("status"), blue);
+        assert_eq!(theme.key("http.status"), blue);
+        assert_eq!(theme.timestamp, Theme::default().timestamp);
+    }
+
+    #[test]
+    fn test_parse_hex_and_256_colors() {
+        assert_eq!(Paint::parse("#FF8800"), Ok(Paint::rgb(255, 136, 0)));
+        assert_eq!(
+            Paint::parse("bold 208"),
+            Ok(Paint {
+                fg: Some(DynColors::Xterm(XtermColors::from(208))),
+                ..Paint::PLAIN.bold()
+            })
+        );
+        assert!(Paint::parse("#ff88").is_err());
+        assert!(Paint::parse("#gg8800").is_err());
+        assert!(Paint::parse("256").is_err());
+    }
+
+    #[test]
+    fn test_downgrade_to_depth() {
+        let orange = Paint::rgb(255, 136, 0).bold();
+        let xterm = orange.at_depth(ColorDepth::Xterm256);
+        assert_eq!(xterm.fg, Some(DynColors::Xterm(XtermColors::from(208))));
+        assert!(xterm.bold);
+        assert_eq!(
+            orange.at_depth(ColorDepth::Ansi16).fg,
+            Some(DynColors::Ansi(AnsiColors::Yellow))
+        );
+        assert_eq!(orange.at_depth(ColorDepth::TrueColor), orange);
+        // 256-color indexes go through their RGB value.
+        let magenta = Paint::parse("201").unwrap();
+        assert_eq!(
+            magenta.at_depth(ColorDepth::Ansi16).fg,
+            Some(DynColors::Ansi(AnsiColors::BrightMagenta))
+        );
+        // ANSI colors are shown everywhere.
+        let theme = Theme::default().with_depth(ColorDepth::Ansi16);
+        assert_eq!(theme.levels, Theme::default().levels);
+        assert_eq!(theme.key.fg, Some(DynColors::Ansi(AnsiColors::BrightBlack)));
+    }
+
+    #[test]
+    fn test_detect_color_depth() {
+        let env = |vars: &'static [(&str, &str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(key, _)| *key == name)
+                    .map(|(_, value)| (*value).to_string())
+            }
+        };
+        assert_eq!(
+            ColorDepth::detect(env(&[("COLORTERM", "truecolor"), ("TERM", "xterm")])),
+            ColorDepth::TrueColor
+        );
+        assert_eq!(
+            ColorDepth::detect(env(&[("TERM", "xterm-256color")])),
+            ColorDepth::Xterm256
+        );
+        assert_eq!(
+            ColorDepth::detect(env(&[("TERM", "xterm")])),
+            ColorDepth::Ansi16
+        );
+    }
+
+    #[test]
+    fn test_ansi_rgb() {
+        assert_eq!(ansi_rgb(0), Some(Rgb(0, 0, 0)));
+        assert_eq!(ansi_rgb(15), Some(Rgb(255, 255, 255)));
+        assert_eq!(ansi_rgb(16), None);
+    }
+}
@@ -0,0 +1,143 @@
+//! Output rate limiting (`--throttle`).
+//!
+//! When a service goes into a log storm, a [`Throttle`] caps how many
+//! entries reach the terminal per window (`100/s`). Entries over the cap are
+//! dropped and counted; the count is shown as a `… N lines suppressed`
+//! marker once their window has run out ([`Throttle::marker_due`]), before
+//! the next entry that gets through, or at the end.
+
+use std::mem;
+use std::time::{Duration, Instant};
+
+/// Entries allowed per window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rate {
+    pub count: usize,
+    pub per: Duration,
+}
+
+impl Rate {
+    /// Parse `COUNT/UNIT` with a unit of `s`, `m`, or `h` (`100/s`,
+    /// `1000/m`); a bare count is per second.
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let input = input.trim();
+        let (count, unit) = input.split_once('/').unwrap_or((input, "s"));
+        let per = match unit.trim() {
+            "s" | "sec" => Duration::from_secs(1),
+            "m" | "min" => Duration::from_mins(1),
+            "h" | "hour" => Duration::from_hours(1),
+            _ => {
+                return Err(format!(
+                    "invalid rate '{input}': expected COUNT/s, COUNT/m, or COUNT/h"
+                ));
+            }
+        };
+        match count.trim().parse() {
+            Ok(count) if count > 0 => Ok(Self { count, per }),
+            _ => Err(format!(
+                "invalid rate '{input}': the count must be a positive integer"
+            )),
+        }
+    }
+}
+
+/// Entries shown and suppressed in the current window.
+#[derive(Debug, Clone)]
+pub struct Throttle {
+    rate: Rate,
+    window_start: Option<Instant>,
+    shown: usize,
+    suppressed: usize,
+    /// End of the window the first unreported suppression happened in.
+    marker_due: Option<Instant>,
+}
+
+impl Throttle {
+    pub const fn new(rate: Rate) -> Self {
+        Self {
+            rate,
+            window_start: None,
+            shown: 0,
+            suppressed: 0,
+            marker_due: None,
+        }
+    }
+
+    /// Whether an entry arriving at `now` is shown. A window opens with
+    /// the first entry after the previous one has run out.
+    pub fn admit(&mut self, now: Instant) -> bool {
+        if self
+            .window_start
+            .is_none_or(|start| now.duration_since(start) >= self.rate.per)
+        {
+            self.window_start = Some(now);
+            self.shown = 0;
+        }
+        if self.shown < self.rate.count {
+            self.shown += 1;
+            true
+        } else {
+            self.suppressed += 1;
+            if self.marker_due.is_none() {
+                self.marker_due = self.window_start.map(|start| start + self.rate.per);
+            }
+            false
+        }
+    }
+
+    /// When the marker for the entries suppressed so far is due: the end of
+    /// their window. `None` when nothing was suppressed.
+    pub const fn marker_due(&self) -> Option<Instant> {
+        self.marker_due
+    }
+
+    /// Entries suppressed since the last call.
+    pub const fn take_suppressed(&mut self) -> usize {
+        self.marker_due = None;
+        mem::replace(&mut self.suppressed, 0)
+    }
+}
+
+/// Marker line standing in for `count` suppressed entries.
+pub fn marker(count: usize, ellipsis: &str) -> String {
+    let noun = if count == 1 { "line" } else { "lines" };
+    format!("{ellipsis} {count} {noun} suppressed")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rate() {
+        assert_eq!(
+            Rate::parse("100/s"),
+            Ok(Rate {
+                count: 100,
+                per: Duration::from_secs(1)
+            })
+        );
+        assert_eq!(Rate::parse("50").unwrap().per, Duration::from_secs(1));
+        assert_eq!(Rate::parse("600/m").unwrap().per, Duration::from_mins(1));
+        for bad in ["0/s", "-1/s", "ten/s", "100/d", ""] {
+            assert!(Rate::parse(bad).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn test_admits_count_per_window() {
+        let mut throttle = Throttle::new(Rate::parse("2/s").unwrap());
+        let start = Instant::now();
+        let admitted: Vec<bool> = [0, 100, 200, 300, 1100, 1200]
+            .into_iter()
+            .map(|ms| throttle.admit(start + Duration::from_millis(ms)))
+            .collect();
+        assert_eq!(admitted, [true, true, false, false, true, true]);
+        // Due at the end of the window that suppressed them.
+        assert_eq!(throttle.marker_due(), Some(start + Duration::from_secs(1)));
+        assert_eq!(throttle.take_suppressed(), 2);
+        assert_eq!(throttle.marker_due(), None);
+        assert_eq!(throttle.take_suppressed(), 0);
+        assert_eq!(marker(1, "…"), "… 1 line suppressed");
+    }
+}
@@ -4,8 +4,11 @@
 //! numeric Unix epochs (seconds, milliseconds, nanoseconds) using a
 //! magnitude-based heuristic for disambiguation.
 
+use std::borrow::Cow;
 use std::fmt;
 
+use crate::locale::Locale;
+
 /// Parsed and normalized timestamp representation.
 ///
 /// Wraps a [`jiff::Timestamp`] for high-precision time handling.
@@ -31,6 +34,37 @@ impl Timestamp {
         zdt.strftime(format).to_string()
     }
 
+    /// Format the timestamp with day and month names in the given locale.
+    pub fn format_localized(
+        &self,
+        format: &str,
+        tz: &jiff::tz::TimeZone,
+        locale: Locale,
+    ) -> String {
+        let zdt = self.value.to_zoned(tz.clone());
+        if locale == Locale::En {
+            return zdt.strftime(format).to_string();
+        }
+        let weekday = usize::from(zdt.weekday().to_monday_zero_offset().unsigned_abs());
+        let month = usize::from(zdt.month().unsigned_abs()) - 1;
+        let localized = rewrite_directives(format, |flags, conv| {
+            let name = match conv {
+                'a' => locale.weekday_abbrev(weekday),
+                'A' => locale.weekday(weekday),
+                'b' | 'h' => locale.month_abbrev(month),
+                'B' => locale.month(month),
+                _ => return None,
+            };
+            let name = if flags.contains('^') {
+                Cow::Owned(name.to_uppercase())
+            } else {
+                Cow::Borrowed(name)
+            };
+            Some(Cow::Owned(name.replace('%', "%%")))
+        });
+        zdt.strftime(&localized).to_string()
+    }
+
     /// Format the timestamp using the default format (`YYYY-MM-DDTHH:MM:SS.mmm`).
     pub fn format_display(&self) -> String {
         self.format_with("%Y-%m-%dT%H:%M:%S%.3f")
@@ -143,6 +177,94 @@ impl Timestamp {
     }
 }
 
+/// Check that a strftime pattern can be rendered.
+///
+/// jiff only reports bad directives (e.g. a trailing `%`) while formatting,
+/// so the pattern is rendered once against a fixed instant.
+pub fn validate_format(format: &str) -> Result<(), String> {
+    use std::fmt::Write;
+
+    let sample = jiff::Timestamp::UNIX_EPOCH.to_zoned(jiff::tz::TimeZone::UTC);
+    let mut rendered = String::new();
+    write!(rendered, "{}", sample.strftime(format)).map_err(|_| {
+        format!("invalid timestamp format '{format}': unsupported or incomplete % directive")
+    })
+}
+
+/// Rewrite a strftime pattern to show the hour on a 12-hour clock.
+///
+/// `%H`/`%k` become `%I`/`%l`, `%T`/`%R` are expanded with `%I`, and an
+/// AM/PM marker (` %p`) is appended unless the pattern already has one.
+/// Patterns without an hour are returned unchanged.
+pub fn to_twelve_hour(format: &str) -> String {
+    let mut has_hour = false;
+    let mut has_meridiem = false;
+    let mut rewritten = rewrite_directives(format, |flags, conv| match conv {
+        'H' | 'I' | 'k' | 'l' => {
+            has_hour = true;
+            let conv = if matches!(conv, 'H' | 'I') { 'I' } else { 'l' };
+            Some(Cow::Owned(format!("%{flags}{conv}")))
+        }
+        'T' => {
+            has_hour = true;
+            Some(Cow::Borrowed("%I:%M:%S"))
+        }
+        'R' => {
+            has_hour = true;
+            Some(Cow::Borrowed("%I:%M"))
+        }
+        'p' | 'P' => {
+            has_meridiem = true;
+            None
+        }
+        _ => None,
+    });
+    if has_hour && !has_meridiem {
+        rewritten.push_str(" %p");
+    }
+    rewritten
+}
+
+/// Walk the `%` directives of a strftime pattern, letting `replace` swap
+/// any of them out.
+///
+/// `replace` receives the directive's flags/width/precision (e.g. `-` in
+/// `%-H`, `.3` in `%.3f`) and its conversion character; returning `None`
+/// keeps the directive as written.
+fn rewrite_directives<'a>(
+    format: &str,
+    mut replace: impl FnMut(&str, char) -> Option<Cow<'a, str>>,
+) -> String {
+    let mut out = String::with_capacity(format.len());
+    let mut chars = format.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        let flags_start = start + 1;
+        let mut flags_end = flags_start;
+        while let Some(&(i, f)) = chars.peek() {
+            if matches!(f, '-' | '_' | '0' | '^' | '#' | '.' | ':') || f.is_ascii_digit() {
+                chars.next();
+                flags_end = i + f.len_utf8();
+            } else {
+                break;
+            }
+        }
+        let Some((end, conv)) = chars.next() else {
+            out.push_str(&format[start..]);
+            break;
+        };
+        let flags = &format[flags_start..flags_end];
+        match replace(flags, conv) {
+            Some(replacement) if conv != '%' => out.push_str(&replacement),
+            _ => out.push_str(&format[start..end + conv.len_utf8()]),
+        }
+    }
+    out
+}
+
 impl fmt::Display for Timestamp {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.format_display())
@@ -219,6 +341,42 @@ mod tests {
         assert_eq!(ts.format_with("%H:%M:%S"), "10:30:00");
     }
 
+    #[test]
+    fn test_validate_format() {
+        assert!(validate_format("%Y-%m-%dT%H:%M:%S%.3f").is_ok());
+        assert!(validate_format("plain text").is_ok());
+        let err = validate_format("%H %").unwrap_err();
+        assert!(err.contains("'%H %'"), "{err}");
+    }
+
+    #[test]
+    fn test_to_twelve_hour() {
+        assert_eq!(to_twelve_hour("%H:%M:%S"), "%I:%M:%S %p");
+        assert_eq!(
+            to_twelve_hour("%Y-%m-%dT%T%.3f"),
+            "%Y-%m-%dT%I:%M:%S%.3f %p"
+        );
+        assert_eq!(to_twelve_hour("%-H:%M %P"), "%-I:%M %P");
+        assert_eq!(to_twelve_hour("%Y-%m-%d"), "%Y-%m-%d");
+        assert_eq!(to_twelve_hour("100%% %H"), "100%% %I %p");
+
+        let ts = Timestamp::from_json_value(&json!("2026-01-15T15:04:05Z")).unwrap();
+        assert_eq!(ts.format_with(&to_twelve_hour("%H:%M:%S")), "03:04:05 PM");
+    }
+
+    #[test]
+    fn test_format_localized() {
+        let ts = Timestamp::from_json_value(&json!("2026-03-02T10:00:00Z")).unwrap();
+        let utc = jiff::tz::TimeZone::UTC;
+        assert_eq!(
+            ts.format_localized("%A %d %B", &utc, Locale::De),
+            "Montag 02 März"
+        );
+        assert_eq!(ts.format_localized("%a %b", &utc, Locale::Fr), "lun. mars");
+        assert_eq!(ts.format_localized("%^b", &utc, Locale::Es), "MAR");
+        assert_eq!(ts.format_localized("%a %b", &utc, Locale::En), "Mon Mar");
+    }
+
     #[test]
     fn test_format_with_full_datetime() {
         let val = json!("2026-01-15T10:30:00.123Z");
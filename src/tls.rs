@@ -0,0 +1,207 @@
+//! TLS for network inputs (`tls://` endpoints and `--listen https://`,
+//! `tls` feature).
+//!
+//! Server certificates are verified against the bundled Mozilla roots, or
+//! against `--tls-ca` when given. `--tls-cert`/`--tls-key` present a client
+//! certificate to producers that require mutual TLS.
+//!
+//! When listening, `--tls-cert`/`--tls-key` are the certificate cor serves,
+//! and `--tls-ca`, if given, must have signed every client's certificate.
+
+use std::io;
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use rustls::server::WebPkiClientVerifier;
+use rustls::{
+    ClientConfig, ClientConnection, RootCertStore, ServerConfig, ServerConnection, StreamOwned,
+};
+use rustls_pki_types::pem::PemObject;
+use rustls_pki_types::{CertificateDer, PrivateKeyDer, ServerName};
+
+/// Certificate files for TLS connections.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TlsFiles {
+    /// PEM bundle of CA certificates replacing the bundled roots.
+    pub ca: Option<PathBuf>,
+    /// PEM client certificate chain.
+    pub cert: Option<PathBuf>,
+    /// PEM private key for `cert`.
+    pub key: Option<PathBuf>,
+}
+
+/// Build the client configuration used for every `tls://` endpoint.
+pub fn client_config(files: &TlsFiles) -> Result<Arc<ClientConfig>, String> {
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let builder = ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .map_err(|e| format!("TLS setup failed: {e}"))?
+        .with_root_certificates(root_store(files.ca.as_deref())?);
+    let config = match (&files.cert, &files.key) {
+        (Some(cert), Some(key)) => builder
+            .with_client_auth_cert(load_certs(cert)?, load_key(key)?)
+            .map_err(|e| format!("{}: {e}", cert.display()))?,
+        (None, None) => builder.with_no_client_auth(),
+        _ => return Err("--tls-cert and --tls-key must be given together".to_string()),
+    };
+    Ok(Arc::new(config))
+}
+
+/// Build the server configuration used for `--listen https://`.
+pub fn server_config(files: &TlsFiles) -> Result<Arc<ServerConfig>, String> {
+    let (Some(cert), Some(key)) = (&files.cert, &files.key) else {
+        return Err("--listen https:// requires --tls-cert and --tls-key".to_string());
+    };
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let builder = ServerConfig::builder_with_provider(Arc::clone(&provider))
+        .with_safe_default_protocol_versions()
+        .map_err(|e| format!("TLS setup failed: {e}"))?;
+    let builder = match files.ca {
+        Some(ref ca) => {
+            let roots = Arc::new(root_store(Some(ca))?);
+            let verifier = WebPkiClientVerifier::builder_with_provider(roots, provider)
+                .build()
+                .map_err(|e| format!("{}: {e}", ca.display()))?;
+            builder.with_client_cert_verifier(verifier)
+        }
+        None => builder.with_no_client_auth(),
+    };
+    let config = builder
+        .with_single_cert(load_certs(cert)?, load_key(key)?)
+        .map_err(|e| format!("{}: {e}", cert.display()))?;
+    Ok(Arc::new(config))
+}
+
+/// Start a TLS session with the client connected on `stream`.
+pub fn accept(
+    stream: TcpStream,
+    config: &Arc<ServerConfig>,
+) -> io::Result<StreamOwned<ServerConnection, TcpStream>> {
+    let conn = ServerConnection::new(Arc::clone(config)).map_err(io::Error::other)?;
+    Ok(StreamOwned::new(conn, stream))
+}
+
+/// Start a TLS session with a client on the shared runtime, like
+/// [`accept`] but completing the handshake.
+#[cfg(feature = "async")]
+pub async fn accept_async(
+    stream: tokio::net::TcpStream,
+    config: &Arc<ServerConfig>,
+) -> io::Result<tokio_rustls::server::TlsStream<tokio::net::TcpStream>> {
+    tokio_rustls::TlsAcceptor::from(Arc::clone(config))
+        .accept(stream)
+        .await
+}
+
+/// Start a TLS session over `stream`, verifying the server as `host`.
+pub fn wrap(
+    stream: TcpStream,
+    host: &str,
+    config: &Arc<ClientConfig>,
+) -> io::Result<StreamOwned<ClientConnection, TcpStream>> {
+    let name = ServerName::try_from(host.to_string())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let conn = ClientConnection::new(Arc::clone(config), name).map_err(io::Error::other)?;
+    Ok(StreamOwned::new(conn, stream))
+}
+
+/// Start a TLS session over `stream` on the shared runtime, like [`wrap`].
+#[cfg(feature = "async")]
+pub async fn wrap_async(
+    stream: tokio::net::TcpStream,
+    host: &str,
+    config: &Arc<ClientConfig>,
+) -> io::Result<tokio_rustls::client::TlsStream<tokio::net::TcpStream>> {
+    let name = ServerName::try_from(host.to_string())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    tokio_rustls::TlsConnector::from(Arc::clone(config))
+        .connect(name, stream)
+        .await
+}
+
+fn root_store(ca: Option<&Path>) -> Result<RootCertStore, String> {
+    let Some(ca) = ca else {
+        return Ok(RootCertStore {
+            roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+        });
+    };
+    let mut store = RootCertStore::empty();
+    for cert in load_certs(ca)? {
+        store
+            .add(cert)
+            .map_err(|e| format!("{}: {e}", ca.display()))?;
+    }
+    Ok(store)
+}
+
+fn load_certs(path: &Path) -> Result<Vec<CertificateDer<'static>>, String> {
+    let certs = CertificateDer::pem_file_iter(path)
+        .and_then(Iterator::collect::<Result<Vec<_>, _>>)
+        .map_err(|e| format!("{}: {e}", path.display()))?;
+    if certs.is_empty() {
+        return Err(format!("{}: no certificates found", path.display()));
+    }
+    Ok(certs)
+}
+
+fn load_key(path: &Path) -> Result<PrivateKeyDer<'static>, String> {
+    PrivateKeyDer::from_pem_file(path).map_err(|e| format!("{}: {e}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_config_uses_bundled_roots() {
+        assert!(client_config(&TlsFiles::default()).is_ok());
+    }
+
+    #[test]
+    fn test_missing_or_empty_files_are_errors() {
+        let missing = TlsFiles {
+            ca: Some(PathBuf::from("/nonexistent/ca.pem")),
+            ..TlsFiles::default()
+        };
+        assert!(client_config(&missing).unwrap_err().contains("ca.pem"));
+
+        let empty = tempfile::NamedTempFile::new().unwrap();
+        let files = TlsFiles {
+            ca: Some(empty.path().to_path_buf()),
+            ..TlsFiles::default()
+        };
+        assert!(
+            client_config(&files)
+                .unwrap_err()
+                .contains("no certificates")
+        );
+    }
+
+    #[test]
+    fn test_server_config_needs_cert_and_key() {
+        let fixture = |name: &str| {
+            Some(
+                PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/tls"))
+                    .join(name),
+            )
+        };
+        let files = TlsFiles {
+            ca: fixture("ca.pem"),
+            cert: fixture("server.pem"),
+            key: fixture("server.key"),
+        };
+        assert!(server_config(&files).is_ok());
+        let files = TlsFiles { key: None, ..files };
+        assert!(server_config(&files).unwrap_err().contains("--tls-key"));
+    }
+
+    #[test]
+    fn test_cert_requires_key() {
+        let files = TlsFiles {
+            cert: Some(PathBuf::from("client.pem")),
+            ..TlsFiles::default()
+        };
+        assert!(client_config(&files).unwrap_err().contains("together"));
+    }
+}
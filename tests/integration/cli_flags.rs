@@ -1,7 +1,7 @@
-//! Integration tests for CLI flags: --line-gap, --verbose, --config errors, --level errors,
-//! --help, --version, and combined flag scenarios.
+//! Integration tests for command-line flags, one section per flag or feature.
 
 use predicates::prelude::*;
+use std::fmt::Write as _;
 use std::io::Write;
 
 use super::cor;
@@ -135,6 +135,25 @@ fn config_nonexistent_path_exits_one() {
         .stderr(predicate::str::contains("config file not found"));
 }
 
+#[test]
+fn config_invalid_timestamp_format_exits_one_with_line() {
+    let mut config_file = tempfile::NamedTempFile::new().unwrap();
+    config_file
+        .write_all(b"line_gap = 0\ntimestamp_format = \"%Y %\"\n")
+        .unwrap();
+
+    let path = config_file.path().display().to_string();
+    cor()
+        .arg(format!("--config={path}"))
+        .write_stdin(r#"{"level":"info","msg":"hello"}"#)
+        .assert()
+        .code(1)
+        .stdout(predicate::str::is_empty())
+        .stderr(predicate::str::contains(format!(
+            "{path}:2: invalid timestamp format"
+        )));
+}
+
 // ── --level with invalid value ──────────────────────────────────────
 
 #[test]
@@ -296,6 +315,17 @@ fn timestamp_format_time_only() {
     );
 }
 
+#[test]
+fn timestamp_format_invalid_rejected_at_startup() {
+    cor()
+        .arg("--timestamp-format=%H %")
+        .write_stdin(r#"{"level":"info","msg":"hello","time":"2026-01-15T10:30:00Z"}"#)
+        .assert()
+        .failure()
+        .stdout(predicate::str::is_empty())
+        .stderr(predicate::str::contains("invalid timestamp format '%H %'"));
+}
+
 // ── --key-min-width ───────────────────────────────────────────────
 
 #[test]
@@ -466,8 +496,68 @@ fn timezone_named_converts_timestamp() {
     );
 }
 
+#[test]
+fn tz_alias_converts_timestamp() {
+    let input = r#"{"level":"info","msg":"hello","time":"2026-01-15T10:30:00Z"}"#;
+    let output = cor()
+        .args(["--color=never", "--tz", "Europe/Madrid"])
+        .write_stdin(input)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    // Madrid is UTC+1 in January.
+    assert!(stdout.contains("11:30:00"), "{stdout}");
+}
+
+#[test]
+fn local_uses_system_timezone() {
+    let input = r#"{"level":"info","msg":"hello","time":"2026-01-15T10:30:00Z"}"#;
+    let output = cor()
+        .args(["--color=never", "--local"])
+        .env("TZ", "America/New_York")
+        .write_stdin(input)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("05:30:00"), "{stdout}");
+}
+
+#[test]
+fn local_conflicts_with_timezone() {
+    cor()
+        .args(["--local", "--tz=UTC"])
+        .write_stdin("")
+        .assert()
+        .code(2);
+}
+
+// ── --capabilities ──────────────────────────────────────────────────
+
+#[test]
+fn capabilities_prints_json_and_exits() {
+    let output = cor().arg("--capabilities").output().unwrap();
+    assert!(output.status.success());
+    let caps: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(caps["name"], "cor");
+    assert_eq!(caps["version"], env!("CARGO_PKG_VERSION"));
+    assert!(
+        caps["input_formats"]
+            .as_array()
+            .unwrap()
+            .contains(&"klog".into())
+    );
+    assert!(
+        caps["output_formats"]
+            .as_array()
+            .unwrap()
+            .contains(&"csv".into())
+    );
+    assert!(caps["features"]["parquet"].is_boolean());
+}
+
 // ── --completions ─────────────────────────────────────────────────
 
+#[cfg(feature = "completions")]
 #[test]
 fn completions_bash_produces_output() {
     cor()
@@ -501,7 +591,7 @@ fn file_argument_nonexistent_errors() {
         .arg("/tmp/cor-test-nonexistent-file.jsonl")
         .assert()
         .failure()
-        .code(1)
+        .code(2)
         .stderr(predicate::str::is_empty().not());
 }
 
@@ -592,6 +682,59 @@ fn grep_invalid_regex_exits_with_error() {
         .stderr(predicate::str::is_empty().not());
 }
 
+#[test]
+fn grep_highlights_match_in_message() {
+    let input = "{\"level\":\"info\",\"msg\":\"connection timeout after 5s\"}\n\
+                 raw line with timeout\n";
+    let output = cor()
+        .args(["--color=always", "--grep=time\\w+"])
+        .write_stdin(input)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("connection \x1b[7mtimeout\x1b[0m after 5s"),
+        "{stdout:?}"
+    );
+    assert!(
+        stdout.contains("raw line with \x1b[7mtimeout\x1b[0m"),
+        "{stdout:?}"
+    );
+}
+
+#[test]
+fn grep_v_hides_matching_lines() {
+    let input = r#"{"level":"info","msg":"GET /healthz","path":"/healthz"}
+{"level":"info","msg":"GET /orders"}
+raw healthz probe
+raw other"#;
+    let output = cor()
+        .args(["--color=never", "--grep-v=healthz"])
+        .write_stdin(input)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("GET /orders"), "{stdout}");
+    assert!(stdout.contains("raw other"), "{stdout}");
+    assert!(!stdout.contains("healthz"), "{stdout}");
+}
+
+#[test]
+fn grep_and_grep_v_combine() {
+    let input = r#"{"msg":"db error: timeout"}
+{"msg":"db error: deadlock"}
+{"msg":"cache miss"}"#;
+    let output = cor()
+        .args(["--color=never", "--grep=error", "--grep-v=deadlock"])
+        .write_stdin(input)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("timeout"), "{stdout}");
+    assert!(!stdout.contains("deadlock"), "{stdout}");
+    assert!(!stdout.contains("cache"), "{stdout}");
+}
+
 // ── --no-extra conflicts ──────────────────────────────────────────
 
 #[test]
@@ -607,3 +750,2006 @@ fn no_extra_conflicts_with_include_fields() {
         .code(2)
         .stderr(predicate::str::contains("cannot be used with"));
 }
+
+// ── --preset ────────────────────────────────────────────────────────
+
+#[test]
+fn preset_ci_compact_uncolored_inline() {
+    let input = r#"{"level":"info","msg":"first","port":8080}
+{"level":"info","msg":"second","port":9090}"#;
+    let output = cor()
+        .arg("--preset=ci")
+        .write_stdin(input)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        !stdout.contains('\x1b'),
+        "ci preset should disable colors.\nGot: {stdout}"
+    );
+    assert!(
+        !stdout.contains("\n\n"),
+        "ci preset should use no line gap.\nGot: {stdout}"
+    );
+    assert!(
+        stdout.contains("first port=8080"),
+        "ci preset should render fields inline.\nGot: {stdout}"
+    );
+}
+
+#[test]
+fn preset_flag_overridden_by_explicit_flag() {
+    let input = r#"{"level":"info","msg":"first"}
+{"level":"info","msg":"second"}"#;
+    let output = cor()
+        .arg("--preset=ci")
+        .arg("--line-gap=1")
+        .write_stdin(input)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("\n\n"),
+        "explicit --line-gap should win over the preset.\nGot: {stdout}"
+    );
+}
+
+#[test]
+fn preset_k8s_turns_kubectl_prefix_into_fields() {
+    let input = concat!(
+        "[pod/api-7f9c4/app] {\"level\":\"error\",\"msg\":\"boom\",\"pid\":1}\n",
+        "[pod/web-1/nginx] {\"level\":\"info\",\"msg\":\"ok\"}\n",
+    );
+    let output = cor()
+        .args([
+            "--preset=k8s",
+            "--color=never",
+            "--where",
+            "container == app",
+        ])
+        .write_stdin(input)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("boom"), "{stdout}");
+    assert!(stdout.contains("pod: api-7f9c4"), "{stdout}");
+    assert!(!stdout.contains("[pod/"), "{stdout}");
+    assert!(!stdout.contains("pid"), "{stdout}");
+    assert!(!stdout.contains("ok"), "{stdout}");
+}
+
+#[test]
+fn preset_invalid_name_fails() {
+    cor()
+        .arg("--preset=bogus")
+        .write_stdin("")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid value"));
+}
+
+// ── --mark-on ───────────────────────────────────────────────────────
+
+#[test]
+fn mark_on_prints_index_to_stderr() {
+    let info = r#"{"level":"info","msg":"ok"}"#;
+    let error = r#"{"level":"error","msg":"boom"}"#;
+    let input = format!("{info}\n{error}\n");
+    let output = cor()
+        .arg("--color=never")
+        .arg("--mark-on=level>=error")
+        .write_stdin(input)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let offset = info.len() + 1;
+    assert_eq!(stderr, format!("-:2\t{offset}\t{error}\n"));
+    // Normal output is unaffected
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("ok") && stdout.contains("boom"));
+}
+
+#[test]
+fn mark_on_appends_to_side_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let log = dir.path().join("app.log");
+    let marks = dir.path().join("marks.tsv");
+    let line = r#"{"level":"info","msg":"done","status":503}"#;
+    std::fs::write(&log, format!("not json\n{line}\n")).unwrap();
+
+    let output = cor()
+        .arg("--color=never")
+        .arg("--mark-on=status >= 500")
+        .arg(format!("--mark-file={}", marks.display()))
+        .arg(&log)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(output.stderr.is_empty(), "index should go to the side file");
+    assert_eq!(
+        std::fs::read_to_string(&marks).unwrap(),
+        format!("{}:2\t9\t{line}\n", log.display())
+    );
+}
+
+#[test]
+fn mark_on_offset_counts_invalid_utf8_lines() {
+    let error = r#"{"level":"error","msg":"boom"}"#;
+    let mut input = b"\xff\xfe bad bytes\n".to_vec();
+    input.extend_from_slice(format!("{error}\n").as_bytes());
+    let output = cor()
+        .arg("--color=never")
+        .arg("--mark-on=level>=error")
+        .write_stdin(input)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        format!("-:2\t13\t{error}\n")
+    );
+}
+
+#[test]
+fn mark_on_offset_counts_crlf_line_endings() {
+    let dir = tempfile::tempdir().unwrap();
+    let log = dir.path().join("app.log");
+    let info = r#"{"level":"info","msg":"ok"}"#;
+    let error = r#"{"level":"error","msg":"boom"}"#;
+    std::fs::write(&log, format!("{info}\r\n{error}\r\n{error}\r\n")).unwrap();
+    let first = info.len() + 2;
+    let second = first + error.len() + 2;
+    let expected = format!(
+        "{log}:2\t{first}\t{error}\n{log}:3\t{second}\t{error}\n",
+        log = log.display()
+    );
+    for jobs in ["1", "2"] {
+        let output = cor()
+            .args(["--color=never", "--mark-on=level>=error", "--jobs", jobs])
+            .arg(&log)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stderr), expected);
+        // The `\r` stays out of the output.
+        assert!(!output.stdout.contains(&b'\r'), "{:?}", output.stdout);
+    }
+}
+
+#[test]
+fn mark_on_invalid_expression_fails() {
+    cor()
+        .arg("--mark-on=level>=loud")
+        .write_stdin("")
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("invalid --mark-on expression"));
+}
+
+#[test]
+fn mark_file_requires_mark_on() {
+    cor()
+        .arg("--mark-file=/tmp/marks.tsv")
+        .write_stdin("")
+        .assert()
+        .failure();
+}
+
+// ── --split-by ──────────────────────────────────────────────────────
+
+#[test]
+fn split_by_writes_one_file_per_value() {
+    let dir = tempfile::tempdir().unwrap();
+    let out = dir.path().join("out");
+    let input = r#"{"level":"info","msg":"charge","service":"payments"}
+{"level":"info","msg":"login","service":"auth"}
+{"level":"info","msg":"refund","service":"payments"}
+{"level":"info","msg":"no service"}
+plain text"#;
+    let output = cor()
+        .arg("--line-gap=0")
+        .arg("--split-by=service")
+        .arg(format!("--split-dir={}", out.display()))
+        .write_stdin(input)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let payments = std::fs::read_to_string(out.join("payments.log")).unwrap();
+    assert!(payments.contains("charge") && payments.contains("refund"));
+    assert!(!payments.contains("login"));
+    assert!(
+        !payments.contains('\x1b'),
+        "split files should not be colored"
+    );
+    let auth = std::fs::read_to_string(out.join("auth.log")).unwrap();
+    assert!(auth.contains("login"));
+
+    // Records without the field and raw lines still go to stdout
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("no service") && stdout.contains("plain text"));
+    assert!(!stdout.contains("charge"));
+}
+
+#[test]
+fn split_by_json_output() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = r#"{"level":"info","msg":"login","service":"auth"}"#;
+    cor()
+        .arg("--json")
+        .arg("--split-by=service")
+        .arg(format!("--split-dir={}", dir.path().display()))
+        .write_stdin(input)
+        .assert()
+        .success();
+    let auth = std::fs::read_to_string(dir.path().join("auth.log")).unwrap();
+    let value: serde_json::Value = serde_json::from_str(auth.trim()).unwrap();
+    assert_eq!(value["msg"], "login");
+}
+
+// ── --quote-values ──────────────────────────────────────────────────
+
+#[test]
+fn quote_values_auto_quotes_ambiguous_values() {
+    let input = r#"{"level":"info","msg":"m","user":"jane doe","id":"abc","n":5}"#;
+    cor()
+        .arg("--color=never")
+        .arg("--single-line")
+        .arg("--quote-values=auto")
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#"user="jane doe""#))
+        .stdout(predicate::str::contains(" id=abc"))
+        .stdout(predicate::str::contains(" n=5"));
+}
+
+#[test]
+fn quote_values_always_quotes_strings_only() {
+    let input = r#"{"level":"info","msg":"m","id":"abc","n":5}"#;
+    cor()
+        .arg("--color=never")
+        .arg("--single-line")
+        .arg("--quote-values=always")
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#"id="abc""#))
+        .stdout(predicate::str::contains(" n=5"));
+}
+
+#[test]
+fn quote_values_never_is_default() {
+    let input = r#"{"level":"info","msg":"m","user":"jane doe"}"#;
+    cor()
+        .arg("--color=never")
+        .arg("--single-line")
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("user=jane doe"));
+}
+
+// ── Control characters ──────────────────────────────────────────────
+
+#[test]
+fn control_chars_escaped_by_default() {
+    let input = r#"{"level":"info","msg":"evil \u001b[2J clear","user":"\u001b[31mroot"}"#;
+    let output = cor()
+        .arg("--color=never")
+        .write_stdin(input)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        !stdout.contains('\x1b'),
+        "ESC must not reach the terminal.\nGot: {stdout}"
+    );
+    assert!(stdout.contains(r"evil \x1b[2J clear"));
+    assert!(stdout.contains(r"\x1b[31mroot"));
+}
+
+#[test]
+fn raw_control_chars_opt_out() {
+    let input = r#"{"level":"info","msg":"keep \u001b[1mbold"}"#;
+    let output = cor()
+        .arg("--color=never")
+        .arg("--raw-control-chars")
+        .write_stdin(input)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("keep \x1b[1mbold"));
+}
+
+#[test]
+fn raw_lines_strip_osc_but_keep_colors() {
+    let input = "plain \x1b]0;pwned\x07text \x1b[32mgreen\x1b[0m\n";
+    let output = cor()
+        .arg("--color=never")
+        .write_stdin(input)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout.trim_end(), "plain text \x1b[32mgreen\x1b[0m");
+}
+
+#[test]
+fn raw_control_chars_keeps_osc_in_raw_lines() {
+    let input = "title \x1b]0;mine\x07\n";
+    let output = cor()
+        .arg("--raw-control-chars")
+        .write_stdin(input)
+        .output()
+        .unwrap();
+    assert!(String::from_utf8_lossy(&output.stdout).contains("\x1b]0;mine\x07"));
+}
+
+// ── --stable ────────────────────────────────────────────────────────
+
+#[test]
+fn stable_output_ignores_ambient_timezone_and_color() {
+    let input = r#"{"ts":"2026-01-15T10:30:00Z","level":"info","msg":"hello","port":8080}"#;
+    let run = |tz: &str| {
+        cor()
+            .arg("--stable")
+            .arg("--color=always")
+            .env("TZ", tz)
+            .arg("--timezone=local")
+            .write_stdin(input)
+            .output()
+            .unwrap()
+            .stdout
+    };
+    let a = run("America/New_York");
+    let b = run("Asia/Tokyo");
+    assert_eq!(a, b);
+    let stdout = String::from_utf8(a).unwrap();
+    assert!(stdout.starts_with("2026-01-15T10:30:00.000"));
+    assert!(!stdout.contains('\x1b'));
+}
+
+#[test]
+fn stable_turns_throttle_off() {
+    cor()
+        .args(["--stable", "--throttle", "2/s", "--line-gap=0"])
+        .write_stdin("line 0\nline 1\nline 2\nline 3\nline 4\n")
+        .assert()
+        .success()
+        .stdout("line 0\nline 1\nline 2\nline 3\nline 4\n");
+}
+
+#[test]
+fn stable_pins_the_klog_year() {
+    cor()
+        .args(["--stable", "--output-format=flat-json"])
+        .write_stdin("I0115 10:30:00.000000 1 server.go:42] started\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("2000-01-15T10:30:00"));
+}
+
+// ── --clock / --locale ──────────────────────────────────────────────
+
+#[test]
+fn clock_12_shows_am_pm() {
+    let input = r#"{"ts":"2026-01-15T15:04:05Z","level":"info","msg":"hello"}"#;
+    cor()
+        .arg("--color=never")
+        .arg("--clock=12")
+        .arg("--timestamp-format=%H:%M:%S")
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("03:04:05 PM"));
+}
+
+#[test]
+fn locale_renders_month_names() {
+    let input = r#"{"ts":"2026-03-02T10:00:00Z","level":"info","msg":"hello"}"#;
+    cor()
+        .arg("--color=never")
+        .arg("--locale=de")
+        .arg("--timestamp-format=%a %d. %B %H:%M")
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("Mo 02. März 10:00"));
+}
+
+// ── --record / replay ───────────────────────────────────────────────
+
+#[test]
+fn record_then_replay_with_different_flags() {
+    let dir = tempfile::tempdir().unwrap();
+    let session = dir.path().join("session.cor");
+    let input = "{\"level\":\"info\",\"msg\":\"started\"}\nplain text\n{\"level\":\"error\",\"msg\":\"boom\"}\n";
+    cor()
+        .arg("--color=never")
+        .arg("--record")
+        .arg(&session)
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("started"));
+
+    let recording = std::fs::read_to_string(&session).unwrap();
+    assert_eq!(
+        recording.lines().count(),
+        4,
+        "header + 3 lines:\n{recording}"
+    );
+
+    cor()
+        .args(["--color=never", "--level=error", "replay", "--speed=0"])
+        .arg(&session)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("boom"))
+        .stdout(predicate::str::contains("plain text"))
+        .stdout(predicate::str::contains("started").not());
+}
+
+#[test]
+fn replay_rejects_non_recordings_and_extra_inputs() {
+    let mut file = tempfile::NamedTempFile::new().unwrap();
+    writeln!(file, r#"{{"level":"info","msg":"hi"}}"#).unwrap();
+    cor()
+        .arg("replay")
+        .arg(file.path())
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("not a cor session recording"));
+    #[cfg(feature = "net")]
+    cor()
+        .arg("--connect=tcp://127.0.0.1:9")
+        .arg("replay")
+        .arg(file.path())
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("replay cannot be combined"));
+}
+
+// ── ##cor:mark annotations ──────────────────────────────────────────
+
+#[test]
+fn mark_control_line_prints_banner() {
+    let input = "{\"level\":\"info\",\"msg\":\"before\"}\n##cor:mark deploy v1.2.3\n{\"level\":\"info\",\"msg\":\"after\"}\n";
+    let output = cor()
+        .args(["--stable", "--line-gap=0"])
+        .write_stdin(input)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 3, "got: {stdout}");
+    assert!(lines[0].contains("before"));
+    assert_eq!(lines[1], "──── deploy v1.2.3 ────");
+    assert!(lines[2].contains("after"));
+}
+
+// ── --discover-fields ───────────────────────────────────────────────
+
+#[test]
+fn discover_fields_reports_redacted_examples() {
+    let input = "{\"level\":\"info\",\"msg\":\"hi\",\"user\":\"bob-42\"}\nplain text\n{\"level\":\"warn\",\"msg\":\"x\",\"user\":\"Al\"}\n";
+    let output = cor()
+        .args(["--discover-fields", "--examples=2"])
+        .write_stdin(input)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with("FIELD"), "got: {stdout}");
+    assert!(stdout.contains("\"aaa-99\", \"Aa\""), "got: {stdout}");
+    assert!(!stdout.contains("bob"), "values must be redacted: {stdout}");
+    assert!(
+        !stdout.contains("plain text"),
+        "records are not printed: {stdout}"
+    );
+    assert!(stdout.ends_with("3 fields in 2 records\n"), "got: {stdout}");
+}
+
+#[test]
+fn examples_requires_discover_fields() {
+    cor().arg("--examples=3").write_stdin("").assert().code(2);
+}
+
+// ── --strict ────────────────────────────────────────────────────────
+
+#[test]
+fn strict_fails_on_malformed_json() {
+    let input = "{\"level\":\"info\",\"msg\":\"ok\"}\n{\"level\": oops}\nfunc() {\n";
+    cor()
+        .args(["--strict", "--color=never"])
+        .write_stdin(input)
+        .assert()
+        .code(4)
+        .stdout(predicate::str::contains("ok"))
+        .stderr(predicate::str::contains(
+            "strict: 1 malformed JSON line(s) at -:2",
+        ));
+}
+
+#[test]
+fn strict_passes_on_clean_input() {
+    cor()
+        .arg("--strict")
+        .write_stdin("{\"level\":\"info\",\"msg\":\"ok\"}\nplain text\n")
+        .assert()
+        .success();
+}
+
+#[test]
+fn exit_code_map_remaps_strict_failure() {
+    cor()
+        .args(["--strict", "--exit-code-map", "strict=20,io=21"])
+        .write_stdin("{\"level\": oops}\n")
+        .assert()
+        .code(20);
+}
+
+#[test]
+fn exit_code_map_rejects_unknown_names() {
+    cor()
+        .args(["--exit-code-map", "parse=3"])
+        .write_stdin("")
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("unknown exit code 'parse'"));
+}
+
+// ── --fail-on ───────────────────────────────────────────────────────
+
+#[test]
+fn fail_on_stops_after_matching_record() {
+    cor()
+        .args(["--fail-on", "level>=error", "--color=never", "--line-gap=0"])
+        .write_stdin(concat!(
+            "{\"level\":\"info\",\"msg\":\"first\"}\n",
+            "{\"level\":\"error\",\"msg\":\"boom\"}\n",
+            "{\"level\":\"info\",\"msg\":\"after\"}\n",
+        ))
+        .assert()
+        .code(3)
+        .stdout(predicate::str::contains("boom"))
+        .stdout(predicate::str::contains("after").not())
+        .stderr(predicate::str::contains("fail-on: matched at -:2"));
+}
+
+#[test]
+fn fail_on_ignores_display_filters() {
+    cor()
+        .args([
+            "--fail-on",
+            "status>=500",
+            "--level",
+            "error",
+            "--jobs",
+            "2",
+        ])
+        .write_stdin("{\"level\":\"info\",\"msg\":\"x\",\"status\":503}\n")
+        .assert()
+        .code(3)
+        .stdout("");
+}
+
+#[test]
+fn fail_on_grep_matches_raw_lines() {
+    cor()
+        .args([
+            "--fail-on-grep",
+            "^panic:",
+            "--exit-code-map",
+            "fail-on-level=9",
+        ])
+        .write_stdin("ok\npanic: nil map\n")
+        .assert()
+        .code(9);
+    cor()
+        .args(["--fail-on-grep", "^panic:"])
+        .write_stdin("ok\n")
+        .assert()
+        .success();
+}
+
+// ── --diag ──────────────────────────────────────────────────────────
+
+#[test]
+fn diag_json_reports_events_and_summary() {
+    let input = "{\"level\":\"info\",\"msg\":\"ok\"}\n{\"level\":\"debug\",\"msg\":\"quiet\"}\n{\"level\": oops}\n";
+    let output = cor()
+        .args(["--diag=json", "--level=info", "--color=never"])
+        .write_stdin(input)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let events: Vec<serde_json::Value> = String::from_utf8_lossy(&output.stderr)
+        .lines()
+        .map(|l| serde_json::from_str(l).expect("stderr should be NDJSON"))
+        .collect();
+    assert!(
+        events
+            .iter()
+            .any(|e| e["event"] == "parse_error" && e["line"] == 3)
+    );
+    let summary = events.last().unwrap();
+    assert_eq!(summary["event"], "summary");
+    assert_eq!(summary["records"], 2);
+    assert_eq!(summary["filtered"], 1);
+    assert_eq!(summary["malformed"], 1);
+}
+
+// ── --debug-cor ─────────────────────────────────────────────────────
+
+#[cfg(feature = "trace")]
+#[test]
+fn debug_cor_prints_pipeline_spans() {
+    let output = cor()
+        .args(["--debug-cor", "--color=never"])
+        .write_stdin("{\"level\":\"info\",\"msg\":\"ok\"}\n")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    for span in ["read", "parse", "format", "write"] {
+        assert!(
+            stderr.contains(&format!("{span}: close time.busy=")),
+            "missing {span} span: {stderr}"
+        );
+    }
+}
+
+#[cfg(not(feature = "trace"))]
+#[test]
+fn debug_cor_requires_trace_feature() {
+    cor().arg("--debug-cor").write_stdin("").assert().code(2);
+}
+
+// ── bench ───────────────────────────────────────────────────────────
+
+#[test]
+fn bench_reports_throughput() {
+    cor()
+        .args(["bench", "--lines=600"])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("cor bench: 600 lines ("))
+        .stdout(predicate::str::contains("lines/s"))
+        .stdout(predicate::str::contains("MB/s"));
+}
+
+// ── index ───────────────────────────────────────────────────────────
+
+#[test]
+fn index_writes_sidecar_and_keeps_filtered_output() {
+    let dir = tempfile::tempdir().unwrap();
+    let log = dir.path().join("big.log");
+    let mut text = "{\"level\":\"info\",\"msg\":\"ok\"}\n".repeat(5000);
+    text.push_str("{\"level\":\"error\",\"msg\":\"boom\",\"status\":500}\n");
+    std::fs::write(&log, text).unwrap();
+    let filtered = |args: &[&str]| {
+        cor()
+            .args(["--color=never", "--line-gap=0"])
+            .args(args)
+            .arg(&log)
+            .output()
+            .unwrap()
+            .stdout
+    };
+    let unindexed = filtered(&["--level=error"]);
+
+    cor()
+        .arg("index")
+        .arg(&log)
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("cor index: 2 blocks"));
+    assert!(dir.path().join("big.log.coridx").exists());
+    assert_eq!(filtered(&["--level=error"]), unindexed);
+    assert_eq!(
+        String::from_utf8(unindexed).unwrap(),
+        "ERROR: boom\n                   status: 500\n"
+    );
+    assert_eq!(
+        filtered(&["--where", "status >= 500"]),
+        filtered(&["--level=error"])
+    );
+    // Line numbers and counts cover the skipped blocks too.
+    let provenance = filtered(&["--level=error", "--provenance", "--output-format=flat-json"]);
+    assert!(
+        String::from_utf8(provenance)
+            .unwrap()
+            .contains("\"line\":5001")
+    );
+    cor()
+        .args(["--level=error", "--stats"])
+        .arg(&log)
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("5001 lines"));
+}
+
+#[test]
+fn index_keeps_elapsed_origin_at_first_record() {
+    let dir = tempfile::tempdir().unwrap();
+    let log = dir.path().join("big.log");
+    let start = 1_700_000_000;
+    let mut text = String::new();
+    for i in 0..5000 {
+        writeln!(
+            text,
+            "{{\"ts\":{},\"level\":\"info\",\"msg\":\"ok\"}}",
+            start + i
+        )
+        .unwrap();
+    }
+    writeln!(
+        text,
+        "{{\"ts\":{},\"level\":\"error\",\"msg\":\"boom\"}}",
+        start + 5000
+    )
+    .unwrap();
+    std::fs::write(&log, text).unwrap();
+    let elapsed = || {
+        cor()
+            .args(["--color=never", "--level=error", "--elapsed"])
+            .arg(&log)
+            .output()
+            .unwrap()
+            .stdout
+    };
+    let unindexed = elapsed();
+    assert!(
+        String::from_utf8_lossy(&unindexed).contains("+1:23:20.000"),
+        "got: {}",
+        String::from_utf8_lossy(&unindexed)
+    );
+    cor().arg("index").arg(&log).assert().success();
+    assert_eq!(elapsed(), unindexed);
+}
+
+#[cfg(feature = "mmap")]
+#[test]
+fn mmap_skips_filtered_lines_with_the_same_output() {
+    let dir = tempfile::tempdir().unwrap();
+    let log = dir.path().join("app.log");
+    let info = r#"{"level":"info","msg":"ok"}"#;
+    // The info record after the open one is a continuation line, not a
+    // record the level filter drops.
+    let text = format!(
+        "{info}\n{{\"level\":\"error\",\"msg\":\"open\n{info}\n\
+         {info}\r\n{{\"level\":\"error\",\"msg\":\"boom\"}}\n"
+    );
+    std::fs::write(&log, text).unwrap();
+    for args in [&["--level=error"][..], &["--level=error", "--provenance"]] {
+        let run = |mmap: bool| {
+            cor()
+                .args(["--color=never", "--output-format=flat-json"])
+                .args(args)
+                .args(mmap.then_some("--mmap"))
+                .arg(&log)
+                .output()
+                .unwrap()
+                .stdout
+        };
+        let expected = run(false);
+        assert!(String::from_utf8_lossy(&expected).contains("boom"));
+        assert_eq!(
+            String::from_utf8_lossy(&run(true)),
+            String::from_utf8_lossy(&expected)
+        );
+    }
+}
+
+// ── --ascii ─────────────────────────────────────────────────────────
+
+#[test]
+fn ascii_truncation_marker() {
+    cor()
+        .args(["--ascii", "--max-field-length=3", "--color=never"])
+        .write_stdin("{\"msg\":\"hi\",\"id\":\"abcdef\"}\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("abc..."))
+        .stdout(predicate::str::contains('…').not());
+}
+
+#[test]
+fn ascii_from_non_utf8_locale() {
+    cor()
+        .env("LC_ALL", "C")
+        .args(["--max-field-length=3", "--color=never"])
+        .write_stdin("{\"msg\":\"hi\",\"id\":\"abcdef\"}\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("abc..."));
+}
+
+// ── --buffer-mode ───────────────────────────────────────────────────
+
+#[test]
+fn buffer_modes_produce_identical_output() {
+    let input =
+        "{\"level\":\"info\",\"msg\":\"a\",\"n\":1}\nplain\n{\"level\":\"warn\",\"msg\":\"b\"}\n";
+    let expected = cor().arg("--stable").write_stdin(input).output().unwrap();
+    for mode in ["line", "block", "none"] {
+        let output = cor()
+            .args(["--stable", &format!("--buffer-mode={mode}")])
+            .write_stdin(input)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        assert_eq!(output.stdout, expected.stdout, "--buffer-mode={mode}");
+    }
+}
+
+#[test]
+fn buffer_mode_rejects_unknown_value() {
+    cor()
+        .arg("--buffer-mode=full")
+        .write_stdin("")
+        .assert()
+        .code(2);
+}
+
+// ── --icons ─────────────────────────────────────────────────────────
+
+#[test]
+fn icons_prefix_levels_and_field_clusters() {
+    cor()
+        .args(["--icons=emoji", "--color=never"])
+        .write_stdin("{\"level\":\"warn\",\"msg\":\"slow\",\"db.query_ms\":900}\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("🔶  WARN: slow"))
+        .stdout(predicate::str::contains("💾 db.query_ms: 900"));
+}
+
+#[test]
+fn icons_suppressed_in_ascii_mode() {
+    cor()
+        .args(["--icons=nerd", "--ascii", "--color=never"])
+        .write_stdin("{\"level\":\"warn\",\"msg\":\"slow\"}\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with(" WARN: slow"));
+}
+
+// ── --with-filename ─────────────────────────────────────────────────
+
+#[test]
+fn with_filename_prefixes_each_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let a = dir.path().join("a.log");
+    let b = dir.path().join("b.log");
+    std::fs::write(&a, "{\"level\":\"info\",\"msg\":\"from a\"}\n").unwrap();
+    std::fs::write(&b, "plain from b\n").unwrap();
+    let output = cor()
+        .args(["-H", "--color=never", "--line-gap=0"])
+        .arg(&a)
+        .arg(&b)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines[0], format!("[{}]  INFO: from a", a.display()));
+    assert_eq!(lines[1], format!("[{}] plain from b", b.display()));
+}
+
+// ── --elapsed ───────────────────────────────────────────────────────
+
+#[test]
+fn elapsed_replaces_timestamp() {
+    let input = "{\"ts\":\"2026-01-15T10:30:00Z\",\"level\":\"info\",\"msg\":\"build\"}\n\
+                 {\"ts\":\"2026-01-15T10:32:05.250Z\",\"level\":\"info\",\"msg\":\"test\"}\n";
+    let output = cor()
+        .args(["--elapsed", "--color=never", "--line-gap=0"])
+        .write_stdin(input)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "+00:00.000   INFO: build\n+02:05.250   INFO: test\n"
+    );
+}
+
+#[test]
+fn relative_to_previous_record() {
+    let input = "{\"ts\":\"2026-01-15T10:30:00Z\",\"msg\":\"boot\"}\n\
+                 {\"ts\":\"2026-01-15T10:30:01.234Z\",\"msg\":\"config\"}\n\
+                 {\"ts\":\"2026-01-15T10:30:04Z\",\"msg\":\"ready\"}\n";
+    let run = |args: &[&str]| {
+        let output = cor()
+            .args(args)
+            .args(["--color=never", "--line-gap=0"])
+            .write_stdin(input)
+            .output()
+            .unwrap();
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+    assert_eq!(
+        run(&["--relative=previous"]),
+        "+00:00.000       : boot\n+00:01.234       : config\n+00:02.766       : ready\n"
+    );
+    assert_eq!(
+        run(&["--relative"]),
+        "+00:00.000       : boot\n+00:01.234       : config\n+00:04.000       : ready\n"
+    );
+    assert!(run(&["--relative=previous", "--elapsed=beside"]).contains("10:30:04.000  +00:02.766"));
+}
+
+#[test]
+fn elapsed_colors_slow_steps() {
+    let input = "{\"ts\":\"2026-01-15T10:30:00Z\",\"msg\":\"a\"}\n\
+                 {\"ts\":\"2026-01-15T10:30:20Z\",\"msg\":\"b\"}\n";
+    let output = cor()
+        .args(["--elapsed", "--color=always", "--line-gap=0"])
+        .write_stdin(input)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout.lines().collect();
+    // Green for the first record, red after a 20s gap.
+    assert!(lines[0].contains("\x1b[32;1m+00:00.000"), "{stdout:?}");
+    assert!(lines[1].contains("\x1b[31;1m+00:20.000"), "{stdout:?}");
+}
+
+#[test]
+fn elapsed_does_not_swallow_file_arguments() {
+    // `--elapsed app.log` must not parse the file name as the mode.
+    cor()
+        .args(["--elapsed", "missing.log"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("missing.log"));
+}
+
+// ── --sort ──────────────────────────────────────────────────────────
+
+#[test]
+fn sort_orders_records_by_timestamp() {
+    cor()
+        .args(["--sort", "--color=never", "--line-gap=0", "--tz", "UTC"])
+        .write_stdin(concat!(
+            "{\"ts\":\"2024-01-01T00:00:02Z\",\"msg\":\"second\"}\n",
+            "  trace of second\n",
+            "{\"ts\":\"2024-01-01T00:00:01Z\",\"msg\":\"first\"}\n",
+        ))
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match("(?s)first.*second\n  trace of second\n$").unwrap());
+}
+
+#[test]
+fn sort_keeps_input_line_numbers() {
+    cor()
+        .args(["--sort", "--provenance", "--output-format=flat-json"])
+        .write_stdin(concat!(
+            "{\"ts\":\"2024-01-01T00:00:02Z\",\"msg\":\"second\"}\n",
+            "{\"ts\":\"2024-01-01T00:00:01Z\",\"msg\":\"first\"}\n",
+        ))
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::is_match(r#"(?s)"msg":"first".*"line":2.*"msg":"second".*"line":1"#)
+                .unwrap(),
+        );
+}
+
+#[test]
+fn sort_conflicts_with_follow() {
+    cor()
+        .args(["--sort", "--follow", "app.log"])
+        .assert()
+        .code(2);
+}
+
+// ── --follow ────────────────────────────────────────────────────────
+
+#[test]
+fn follow_reads_file_then_waits_for_more() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("app.log");
+    std::fs::write(&path, "{\"level\":\"info\",\"msg\":\"first\"}\n").unwrap();
+    // Follow never reaches end of input; the idle timeout ends the run.
+    cor()
+        .args(["-f", "--timeout=300ms", "--color=never"])
+        .arg(&path)
+        .assert()
+        .code(124)
+        .stdout(predicate::str::contains("INFO: first"));
+}
+
+#[test]
+fn follow_reassembles_per_file_past_invalid_utf8() {
+    let dir = tempfile::tempdir().unwrap();
+    let a = dir.path().join("a.log");
+    let b = dir.path().join("b.log");
+    std::fs::write(
+        &a,
+        b"{\"level\":\"info\",\n\"msg\":\"from a\"}\nbad \xff\n{\"msg\":\"a again\"}\n",
+    )
+    .unwrap();
+    std::fs::write(&b, "{\"level\":\"warn\",\n\"msg\":\"from b\"}\n").unwrap();
+    cor()
+        .args(["-f", "--timeout=500ms", "--color=never"])
+        .arg(&a)
+        .arg(&b)
+        .assert()
+        .code(124)
+        .stdout(predicate::str::contains("INFO: from a"))
+        .stdout(predicate::str::contains("WARN: from b"))
+        .stdout(predicate::str::contains("bad \u{fffd}"))
+        .stdout(predicate::str::contains("a again"));
+}
+
+// ── --prefix-pattern ────────────────────────────────────────────────
+
+#[test]
+fn prefix_pattern_turns_prefix_into_fields() {
+    let pattern = r"^(?P<pod>\S+) (?P<stream>stdout|stderr) ";
+    let input = "api-1 stdout {\"level\":\"info\",\"msg\":\"up\"}\n";
+    cor()
+        .args(["--prefix-pattern", pattern, "--color=never"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with(" INFO: up"))
+        .stdout(predicate::str::contains("stream: stdout"));
+
+    // Captured fields can drive --split-by.
+    let dir = tempfile::tempdir().unwrap();
+    cor()
+        .args(["--prefix-pattern", pattern, "--split-by=pod", "--split-dir"])
+        .arg(dir.path())
+        .write_stdin(input)
+        .assert()
+        .success();
+    let split = std::fs::read_to_string(dir.path().join("api-1.log")).unwrap();
+    assert!(split.contains("INFO: up"), "{split}");
+}
+
+#[test]
+fn prefix_pattern_requires_named_groups() {
+    cor()
+        .args(["--prefix-pattern", r"^(\S+) "])
+        .write_stdin("")
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("no named groups"));
+}
+
+// ── --where ─────────────────────────────────────────────────────────
+
+#[test]
+fn where_filters_by_field_expression() {
+    let input = "{\"msg\":\"a\",\"status\":500,\"method\":\"POST\"}\n\
+                 {\"msg\":\"b\",\"status\":200,\"method\":\"POST\"}\n\
+                 {\"msg\":\"c\",\"status\":404,\"method\":\"GET\"}\n";
+    let output = cor()
+        .args([
+            "--where",
+            "status >= 500 || status == 404",
+            "-w",
+            "method == POST",
+            "--color=never",
+            "--no-extra",
+            "--line-gap=0",
+        ])
+        .write_stdin(input)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "     : a\n");
+}
+
+#[test]
+fn where_rejects_invalid_expression() {
+    cor()
+        .args(["--where", "status"])
+        .write_stdin("")
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("missing comparison operator"));
+}
+
+// ── --sparkline ─────────────────────────────────────────────────────
+
+#[test]
+fn sparkline_tracks_numeric_field() {
+    let input = "{\"msg\":\"a\",\"latency_ms\":10}\n\
+                 {\"msg\":\"b\"}\n\
+                 {\"msg\":\"c\",\"latency_ms\":\"80\"}\n\
+                 {\"msg\":\"d\",\"latency_ms\":45}\n";
+    let output = cor()
+        .args([
+            "--sparkline=latency_ms",
+            "--color=never",
+            "--no-extra",
+            "--line-gap=0",
+        ])
+        .write_stdin(input)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "     : a ▁\n     : b\n     : c ▁█\n     : d ▁█▅\n"
+    );
+}
+
+#[test]
+fn sparkline_ascii_ticks() {
+    let output = cor()
+        .args([
+            "--sparkline=n",
+            "--ascii",
+            "--color=never",
+            "--no-extra",
+            "--line-gap=0",
+        ])
+        .write_stdin("{\"msg\":\"a\",\"n\":1}\n{\"msg\":\"b\",\"n\":2}\n")
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "     : a _\n     : b _#\n"
+    );
+}
+
+// ── --filter ────────────────────────────────────────────────────────
+
+#[test]
+fn filter_keeps_records_matching_all_key_values() {
+    let input = "{\"msg\":\"a\",\"service\":\"payments\",\"region\":\"eu\"}\n\
+                 {\"msg\":\"b\",\"service\":\"payments\",\"region\":\"us\"}\n\
+                 {\"msg\":\"c\",\"service\":\"auth\",\"region\":\"eu\"}\n";
+    let output = cor()
+        .args([
+            "--filter",
+            "service=payments",
+            "--filter",
+            "region!=us",
+            "--color=never",
+            "--no-extra",
+            "--line-gap=0",
+        ])
+        .write_stdin(input)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "     : a\n");
+}
+
+#[test]
+fn filter_rejects_other_operators() {
+    cor()
+        .args(["--filter", "status>=500"])
+        .write_stdin("")
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("use --where"));
+}
+
+// ── --watch-field ───────────────────────────────────────────────────
+
+#[test]
+fn watch_field_shows_value_changes() {
+    let input = "{\"msg\":\"poll\",\"config_version\":1}\n\
+                 {\"msg\":\"poll\",\"config_version\":1}\n\
+                 {\"msg\":\"no version\"}\n\
+                 not json\n\
+                 {\"msg\":\"reload\",\"config_version\":2}\n\
+                 {\"msg\":\"poll\",\"config_version\":2}\n\
+                 {\"msg\":\"rollback\",\"config_version\":1}\n";
+    let output = cor()
+        .args([
+            "--watch-field=config_version",
+            "--color=never",
+            "--no-extra",
+            "--line-gap=0",
+        ])
+        .write_stdin(input)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "     : poll\nnot json\n     : reload\n     : rollback\n"
+    );
+}
+
+// ── --first-only ────────────────────────────────────────────────────
+
+#[test]
+fn first_only_suppresses_repeats_and_summarizes() {
+    let input = "{\"msg\":\"retry 1 of 3\"}\n\
+                 {\"msg\":\"connection refused\"}\n\
+                 {\"msg\":\"retry 2 of 3\"}\n\
+                 {\"msg\":\"retry 3 of 3\"}\n\
+                 {\"msg\":\"connection refused\"}\n";
+    let output = cor()
+        .args(["--first-only", "--color=never", "--line-gap=0"])
+        .write_stdin(input)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "     : retry 1 of 3\n     : connection refused\n     : retry 2 of 3\n     : retry 3 of 3\n"
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "cor: suppressed 1 repeats of 1 entries\n       1  connection refused\n"
+    );
+
+    let output = cor()
+        .args(["--first-only=fingerprint", "--color=never", "--line-gap=0"])
+        .write_stdin(input)
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "     : retry 1 of 3\n     : connection refused\n"
+    );
+    assert!(String::from_utf8_lossy(&output.stderr).contains("       2  retry # of #\n"));
+}
+
+#[test]
+fn first_only_repeats_do_not_move_the_elapsed_column() {
+    let input = "{\"ts\":\"2026-01-15T10:30:00Z\",\"msg\":\"a\"}\n\
+                 {\"ts\":\"2026-01-15T10:30:05Z\",\"msg\":\"a\"}\n\
+                 {\"ts\":\"2026-01-15T10:30:06Z\",\"msg\":\"b\"}\n";
+    cor()
+        .args([
+            "--first-only",
+            "--relative=previous",
+            "--color=never",
+            "--line-gap=0",
+        ])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("+00:06.000       : b"));
+}
+
+// ── --in / --not-in ─────────────────────────────────────────────────
+
+#[test]
+fn in_and_not_in_filter_by_value_sets() {
+    let input = "{\"msg\":\"a\",\"status\":500,\"path\":\"/api\"}\n\
+                 {\"msg\":\"b\",\"status\":503,\"path\":\"/health\"}\n\
+                 {\"msg\":\"c\",\"status\":200,\"path\":\"/api\"}\n\
+                 {\"msg\":\"d\",\"status\":\"404\",\"path\":\"/login\"}\n";
+    let output = cor()
+        .args([
+            "--in",
+            "status=404,500,503",
+            "--not-in",
+            "path=/health,/metrics",
+            "--color=never",
+            "--no-extra",
+            "--line-gap=0",
+        ])
+        .write_stdin(input)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "     : a\n     : d\n"
+    );
+}
+
+// ── --min / --max ───────────────────────────────────────────────────
+
+#[test]
+fn min_and_max_bound_numeric_fields() {
+    let input = "{\"msg\":\"a\",\"status\":500,\"latency_ms\":\"80\"}\n\
+                 {\"msg\":\"b\",\"status\":503,\"latency_ms\":250}\n\
+                 {\"msg\":\"c\",\"status\":200,\"latency_ms\":10}\n\
+                 {\"msg\":\"d\",\"status\":\"n/a\",\"latency_ms\":10}\n";
+    let output = cor()
+        .args([
+            "--min",
+            "status=500",
+            "--max",
+            "latency_ms=100",
+            "--color=never",
+            "--no-extra",
+            "--line-gap=0",
+        ])
+        .write_stdin(input)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "     : a\n");
+}
+
+#[test]
+fn min_rejects_non_numeric_bound() {
+    cor()
+        .args(["--min", "status=high"])
+        .write_stdin("")
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("'high' is not a number"));
+}
+
+// ── --output ────────────────────────────────────────────────────────
+
+#[test]
+fn output_file_gets_plain_copy_of_stdout() {
+    let dir = tempfile::tempdir().unwrap();
+    let (first, second) = (dir.path().join("a.log"), dir.path().join("b.log"));
+    cor()
+        .args(["--color=always", "--line-gap=0", "--level=warn"])
+        .arg(format!("--output=file:{}", first.display()))
+        .arg(format!("--output=file:{}", second.display()))
+        .write_stdin(
+            "{\"level\":\"info\",\"msg\":\"skip\"}\n{\"level\":\"error\",\"msg\":\"boom\"}\n",
+        )
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\x1b["));
+    for path in [first, second] {
+        let copy = std::fs::read_to_string(path).unwrap();
+        assert_eq!(copy, "ERROR: boom\n");
+    }
+}
+
+#[test]
+fn output_rejects_unknown_sink() {
+    cor()
+        .args(["--output", "s3:bucket"])
+        .write_stdin("")
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("expected file:PATH"));
+}
+
+// ── --output-format ─────────────────────────────────────────────────
+
+#[test]
+fn output_format_flat_json_normalizes_and_flattens() {
+    cor()
+        .args(["--output-format=flat-json", "--level=warn"])
+        .write_stdin(concat!(
+            "{\"severity\":\"info\",\"message\":\"skip\"}\n",
+            "{\"severity\":\"ERROR\",\"message\":\"boom\",\"http\":{\"status\":503}}\n",
+            "plain text\n",
+        ))
+        .assert()
+        .success()
+        .stdout("{\"level\":\"error\",\"msg\":\"boom\",\"http.status\":503}\n");
+}
+
+#[test]
+fn output_format_csv_writes_header_and_selected_columns() {
+    cor()
+        .args(["--output-format=csv", "--fields=ts,level,msg,status"])
+        .write_stdin(concat!(
+            "{\"ts\":\"2026-01-15T10:30:00Z\",\"level\":\"info\",\"msg\":\"a, b\",\"status\":200}\n",
+            "plain text\n",
+            "{\"level\":\"warn\",\"msg\":\"say \\\"hi\\\"\"}\n",
+        ))
+        .assert()
+        .success()
+        .stdout(concat!(
+            "ts,level,msg,status\n",
+            "2026-01-15T10:30:00Z,info,\"a, b\",200\n",
+            ",warn,\"say \"\"hi\"\"\",\n",
+        ));
+}
+
+#[test]
+fn output_format_tsv_defaults_to_ts_level_msg() {
+    cor()
+        .args(["--output-format=tsv"])
+        .write_stdin("{\"level\":\"error\",\"msg\":\"boom\"}\n")
+        .assert()
+        .success()
+        .stdout("ts\tlevel\tmsg\n\terror\tboom\n");
+}
+
+#[test]
+fn output_format_conflicts_with_json() {
+    cor()
+        .args(["--json", "--output-format=text"])
+        .write_stdin("")
+        .assert()
+        .code(2);
+}
+
+// ── --json coloring ─────────────────────────────────────────────────
+
+#[test]
+fn json_colors_tokens_in_place_with_color() {
+    cor()
+        .args(["--json", "--color=always"])
+        .write_stdin("{\"msg\": \"hi\", \"n\":1}\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\x1b[36m1\x1b[0m"))
+        .stdout(predicate::str::contains("\x1b[32m\"hi\"\x1b[0m, "));
+}
+
+#[test]
+fn json_without_color_is_byte_exact() {
+    let line = "{ \"msg\" : \"hi\",\"tags\":[ 1,2 ] }";
+    cor()
+        .args(["--json", "--line-gap=0"])
+        .write_stdin(format!("{line}\n"))
+        .assert()
+        .success()
+        .stdout(format!("{line}\n"));
+}
+
+// ── --json-pretty ───────────────────────────────────────────────────
+
+#[test]
+fn json_pretty_indents_records_and_drops_raw_lines() {
+    cor()
+        .args(["--json-pretty", "--line-gap=0"])
+        .write_stdin("{\"msg\":\"hi\",\"tags\":[],\"n\":{\"a\":1}}\nplain text\n")
+        .assert()
+        .success()
+        .stdout("{\n  \"msg\": \"hi\",\n  \"tags\": [],\n  \"n\": {\n    \"a\": 1\n  }\n}\n");
+}
+
+#[test]
+fn json_pretty_highlights_with_color() {
+    cor()
+        .args(["--json-pretty", "--color=always"])
+        .write_stdin("{\"n\":1}\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\x1b[36m1\x1b[0m"));
+}
+
+// ── --provenance ────────────────────────────────────────────────────
+
+#[test]
+fn provenance_adds_cor_object_to_flat_json() {
+    cor()
+        .args(["--output-format=flat-json", "--provenance", "--preset=dev"])
+        .write_stdin("{\"level\":\"info\",\"msg\":\"a\nb\"}\n{\"msg\":\"x\"}\n")
+        .assert()
+        .success()
+        .stdout(concat!(
+            "{\"level\":\"info\",\"msg\":\"a\\nb\",\"_cor\":{\"line\":1,\"parser\":\"JSON object\",",
+            "\"profile\":\"dev\",\"reassembled\":true,\"source\":\"-\"}}\n",
+            "{\"msg\":\"x\",\"_cor\":{\"line\":3,\"parser\":\"JSON object\",",
+            "\"profile\":\"dev\",\"reassembled\":false,\"source\":\"-\"}}\n",
+        ));
+}
+
+// ── --theme ─────────────────────────────────────────────────────────
+
+#[test]
+fn theme_switches_level_palette() {
+    let input = "{\"level\":\"error\",\"msg\":\"boom\"}\n";
+    let default = cor()
+        .args(["--color=always"])
+        .write_stdin(input)
+        .output()
+        .unwrap();
+    let default = String::from_utf8_lossy(&default.stdout);
+    assert!(default.contains("\x1b[31;1mERROR"), "{default:?}");
+
+    let output = cor()
+        .args(["--color=always", "--theme=deuteranopia"])
+        .write_stdin(input)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    // Okabe-Ito vermillion instead of red.
+    assert!(stdout.contains("\x1b[38;2;213;94;0;1mERROR"), "{stdout:?}");
+}
+
+#[test]
+fn theme_colors_downgrade_without_truecolor() {
+    let input = "{\"level\":\"error\",\"msg\":\"boom\"}\n";
+    let run = |term: &str| {
+        let output = cor()
+            .args(["--color=always", "--theme=deuteranopia"])
+            .env_remove("COLORTERM")
+            .env("TERM", term)
+            .write_stdin(input)
+            .output()
+            .unwrap();
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+    let xterm256 = run("xterm-256color");
+    assert!(xterm256.contains("\x1b[38;5;166;1mERROR"), "{xterm256:?}");
+    let ansi = run("xterm");
+    assert!(ansi.contains("\x1b[31;1mERROR"), "{ansi:?}");
+}
+
+#[test]
+fn level_colors_accept_hex() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = dir.path().join("config.toml");
+    std::fs::write(&config, "[colors]\nerror = \"#ff8800\"\n").unwrap();
+    let output = cor()
+        .args(["--color=always"])
+        .arg(format!("--config={}", config.display()))
+        .write_stdin("{\"level\":\"error\",\"msg\":\"boom\"}\n")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("\x1b[38;2;255;136;0;1mERROR"), "{stdout:?}");
+}
+
+// ── --stats ─────────────────────────────────────────────────────────
+
+#[test]
+fn stats_summarizes_input_on_stderr() {
+    let input = "{\"level\":\"info\",\"msg\":\"a\",\"status\":200}\n\
+                 {\"level\":\"error\",\"msg\":\"b\",\"status\":500}\n\
+                 not json\n";
+    let output = cor()
+        .args(["--stats", "--level=error", "--color=never", "--line-gap=0"])
+        .write_stdin(input)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "ERROR: b\n                   status: 500\nnot json\n"
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "cor: 3 lines: 2 records, 1 raw, 0 malformed JSON\n\
+         cor: levels: info 1, error 1\n\
+         cor: top fields:\n       2  status\n"
+    );
+}
+
+// ── --live-counts ───────────────────────────────────────────────────
+
+#[test]
+fn live_counts_need_a_terminal() {
+    let output = cor()
+        .args(["--live-counts=stderr", "--color=never"])
+        .write_stdin("{\"level\":\"warn\",\"msg\":\"a\"}\n{\"level\":\"error\",\"msg\":\"b\"}\n")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(output.stderr.is_empty());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("ERROR: b"));
+}
+
+// ── --bell-on / --notify-on ─────────────────────────────────────────
+
+#[test]
+fn bell_on_is_silent_when_stderr_is_not_a_terminal() {
+    let output = cor()
+        .args(["--bell-on", "error", "--level", "info"])
+        .write_stdin(concat!(
+            "{\"level\":\"info\",\"msg\":\"a\"}\n",
+            "{\"level\":\"error\",\"msg\":\"b\"}\n",
+            "{\"level\":\"fatal\",\"msg\":\"c\"}\n",
+        ))
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(output.stderr.is_empty());
+    assert!(!output.stdout.contains(&0x07));
+}
+
+#[test]
+fn notify_on_without_notifier_warns_once() {
+    let output = cor()
+        .args(["--notify-on", "error"])
+        .env("PATH", "")
+        .write_stdin("{\"level\":\"error\",\"msg\":\"a\"}\n{\"level\":\"fatal\",\"msg\":\"b\"}\n")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(
+        stderr.matches("cor: desktop notification failed").count(),
+        1,
+        "{stderr:?}"
+    );
+    assert!(String::from_utf8_lossy(&output.stdout).contains('b'));
+}
+
+// ── --focus ─────────────────────────────────────────────────────────
+
+#[test]
+fn focus_dims_records_outside_the_focus() {
+    let input = "{\"msg\":\"picked\",\"request_id\":\"abc\"}\n\
+                 {\"msg\":\"other\",\"request_id\":\"xyz\"}\n";
+    let output = cor()
+        .args(["--focus", "request_id=abc", "--color=always"])
+        .write_stdin(input)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("\x1b[96;1mpicked"), "{stdout:?}");
+    assert!(stdout.contains("\x1b[2mother"), "{stdout:?}");
+}
+
+// ── --highlight ─────────────────────────────────────────────────────
+
+#[test]
+fn highlight_colors_matches_in_message_and_values() {
+    let output = cor()
+        .args([
+            "--highlight",
+            "req-[0-9]+:cyan",
+            "--highlight",
+            r"\w+@example\.com",
+            "--color=always",
+        ])
+        .write_stdin("{\"msg\":\"handling req-42\",\"user\":\"ann@example.com\"}\n")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("\x1b[36;1mreq-42\x1b[0m"), "{stdout:?}");
+    assert!(
+        stdout.contains("\x1b[33;1mann@example.com\x1b[0m"),
+        "{stdout:?}"
+    );
+}
+
+#[test]
+fn highlight_invalid_regex_exits_with_error() {
+    cor()
+        .args(["--highlight", "(:red"])
+        .write_stdin("")
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("invalid highlight pattern"));
+}
+
+// ── --sample ────────────────────────────────────────────────────────
+
+#[test]
+fn sample_thins_records_below_level_and_keeps_errors() {
+    use std::fmt::Write as _;
+    let mut input = String::new();
+    for n in 0..1000 {
+        let _ = writeln!(input, "{{\"level\":\"debug\",\"msg\":\"tick {n}\"}}");
+    }
+    input.push_str("{\"level\":\"error\",\"msg\":\"boom\"}\nplain text\n");
+    let output = cor()
+        .args(["--sample", "10%", "--single-line", "--line-gap=0"])
+        .write_stdin(input)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let ticks = stdout.matches("tick").count();
+    assert!((40..200).contains(&ticks), "kept {ticks} of 1000");
+    assert!(stdout.contains("boom"), "{stdout}");
+    assert!(stdout.contains("plain text"), "{stdout}");
+}
+
+#[test]
+fn sample_level_exempts_lower_levels() {
+    cor()
+        .args(["--sample", "0.001", "--sample-level", "info"])
+        .write_stdin("{\"level\":\"info\",\"msg\":\"kept\"}\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("kept"));
+}
+
+#[test]
+fn sample_rejects_out_of_range_rate() {
+    cor()
+        .args(["--sample", "1.5"])
+        .write_stdin("")
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("at most 1"));
+}
+
+// ── --throttle ──────────────────────────────────────────────────────
+
+#[test]
+fn throttle_caps_entries_and_reports_suppressed() {
+    cor()
+        .args(["--throttle", "3/m", "--line-gap=0"])
+        .write_stdin(
+            "line 0\nline 1\nline 2\nline 3\nline 4\nline 5\nline 6\nline 7\nline 8\nline 9\n",
+        )
+        .assert()
+        .success()
+        .stdout("line 0\nline 1\nline 2\n… 7 lines suppressed\n");
+}
+
+#[test]
+fn throttle_applies_to_output_sinks() {
+    let dir = tempfile::tempdir().unwrap();
+    let copy = dir.path().join("copy.log");
+    cor()
+        .args(["--throttle", "1/m", "--color=never"])
+        .arg(format!("--output=file:{}", copy.display()))
+        .write_stdin("{\"msg\":\"first\"}\n{\"msg\":\"second\"}\n")
+        .assert()
+        .success();
+    let copied = std::fs::read_to_string(&copy).unwrap();
+    assert!(
+        copied.contains("first") && !copied.contains("second"),
+        "{copied:?}"
+    );
+}
+
+#[test]
+fn throttle_rejects_invalid_rate() {
+    cor()
+        .args(["--throttle", "100/d"])
+        .write_stdin("")
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("COUNT/s"));
+}
+
+// ── --jobs ──────────────────────────────────────────────────────────
+
+#[test]
+fn jobs_keep_output_identical_and_ordered() {
+    let input: String = (0..2000)
+        .map(|n| match n % 50 {
+            0 => format!("plain text {n}\n"),
+            1 => format!("{{\"level\":\"info\",\"msg\":\"split\nline {n}\"}}\n"),
+            _ => format!("{{\"level\":\"warn\",\"msg\":\"record {n}\",\"n\":{n}}}\n"),
+        })
+        .collect();
+    let run = |jobs: &str| {
+        cor()
+            .args(["--color=always", "--jobs", jobs])
+            .write_stdin(input.clone())
+            .output()
+            .unwrap()
+    };
+    let serial = run("1");
+    let parallel = run("4");
+    assert!(parallel.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&parallel.stdout),
+        String::from_utf8_lossy(&serial.stdout)
+    );
+}
+
+#[test]
+fn jobs_keep_stateful_formatting() {
+    let input: String = (0..600)
+        .map(|n| match n % 3 {
+            0 => format!(
+                "{{\"ts\":\"2024-01-01T00:{:02}:{:02}Z\",\"msg\":\"r{n}\"}}\n",
+                n / 60,
+                n % 60
+            ),
+            _ => format!("{{\"msg\":\"untimed {n}\",\"user\":\"u{}\"}}\n", n % 7),
+        })
+        .collect();
+    let run = |jobs: &str| {
+        cor()
+            .args([
+                "--color=never",
+                "--elapsed",
+                "--seen-counts=user",
+                "--jobs",
+                jobs,
+            ])
+            .write_stdin(input.clone())
+            .output()
+            .unwrap()
+            .stdout
+    };
+    assert_eq!(
+        String::from_utf8_lossy(&run("4")),
+        String::from_utf8_lossy(&run("1"))
+    );
+}
+
+#[test]
+fn jobs_ignored_input_is_reported() {
+    cor()
+        .args(["--jobs", "2", "--sort"])
+        .write_stdin("{\"msg\":\"x\"}\n")
+        .assert()
+        .success()
+        .stderr("cor: --jobs ignored for --sort, processed on one thread\n");
+}
+
+// ── --dim-unmatched ─────────────────────────────────────────────────
+
+#[test]
+fn dim_unmatched_keeps_filtered_records_in_grey() {
+    let input = "{\"msg\":\"first\",\"status\":200}\n\
+                 {\"msg\":\"boom\",\"status\":503}\n\
+                 {\"msg\":\"last\",\"status\":200}\n";
+    let output = cor()
+        .args([
+            "--where",
+            "status>=500",
+            "--dim-unmatched",
+            "--color=always",
+        ])
+        .write_stdin(input)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let first = stdout.find("\x1b[90mfirst").expect("dimmed first record");
+    let boom = stdout.find("boom").expect("matching record");
+    let last = stdout.find("\x1b[90mlast").expect("dimmed last record");
+    assert!(first < boom && boom < last, "{stdout:?}");
+    assert!(!stdout.contains("\x1b[90mboom"), "{stdout:?}");
+}
+
+#[test]
+fn dim_unmatched_records_stay_out_of_sinks() {
+    let dir = tempfile::tempdir().unwrap();
+    let copy = dir.path().join("copy.log");
+    cor()
+        .args(["--where", "status>=500", "--dim-unmatched", "--color=never"])
+        .arg(format!("--output=file:{}", copy.display()))
+        .write_stdin("{\"msg\":\"ok\",\"status\":200}\n{\"msg\":\"boom\",\"status\":503}\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("ok"));
+    let copied = std::fs::read_to_string(&copy).unwrap();
+    assert!(
+        copied.contains("boom") && !copied.contains("ok"),
+        "{copied:?}"
+    );
+
+    let split = dir.path().join("split");
+    cor()
+        .args(["--level=error", "--dim-unmatched", "--color=never"])
+        .args(["--split-by", "service", "--split-dir"])
+        .arg(&split)
+        .write_stdin(
+            "{\"level\":\"info\",\"msg\":\"ok\",\"service\":\"a\"}\n\
+             {\"level\":\"error\",\"msg\":\"boom\",\"service\":\"a\"}\n",
+        )
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("ok"));
+    let split = std::fs::read_to_string(split.join("a.log")).unwrap();
+    assert!(split.contains("boom") && !split.contains("ok"), "{split:?}");
+}
+
+// ── --max-fields / --expand-all ─────────────────────────────────────
+
+#[test]
+fn max_fields_folds_extra_fields() {
+    let input = r#"{"msg":"dense","a":1,"b":2,"c":3}"#;
+    cor()
+        .args(["--max-fields", "1", "--color=never"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("… +2 more fields"))
+        .stdout(predicate::str::contains("b: 2").not());
+}
+
+#[test]
+fn expand_all_overrides_config_max_fields() {
+    let mut config_file = tempfile::NamedTempFile::new().unwrap();
+    config_file.write_all(b"max_fields = 1\n").unwrap();
+    cor()
+        .arg(format!("--config={}", config_file.path().display()))
+        .args(["--expand-all", "--color=never"])
+        .write_stdin(r#"{"msg":"dense","a":1,"b":2,"c":3}"#)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("c: 3"))
+        .stdout(predicate::str::contains("more fields").not());
+}
+
+// ── --truncate-mode ─────────────────────────────────────────────────
+
+#[test]
+fn truncate_mode_tail_keeps_end_of_value() {
+    cor()
+        .args([
+            "--max-field-length",
+            "11",
+            "--truncate-mode",
+            "tail",
+            "--color=never",
+        ])
+        .write_stdin(r#"{"msg":"load","path":"/var/lib/app/releases/42/config.yaml"}"#)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("path: …config.yaml"));
+}
+
+// ── --seen-counts ───────────────────────────────────────────────────
+
+#[test]
+fn seen_counts_marks_repeated_values() {
+    let input = [
+        r#"{"level":"error","msg":"upstream failed","error":"connection refused"}"#,
+        r#"{"level":"error","msg":"upstream failed","error":"timeout"}"#,
+        r#"{"level":"error","msg":"retry failed","error":"connection refused"}"#,
+    ]
+    .join("\n");
+    cor()
+        .args(["--seen-counts", "--color=never"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("upstream failed (seen 2×)"))
+        .stdout(predicate::str::contains("connection refused (seen 2×)"))
+        .stdout(predicate::str::contains("timeout (seen").not());
+}
+
+#[test]
+fn seen_counts_on_named_field() {
+    cor()
+        .args([
+            "--seen-counts=user",
+            "--single-line",
+            "--color=never",
+            "--ascii",
+        ])
+        .write_stdin("{\"msg\":\"a\",\"user\":\"ann\"}\n{\"msg\":\"a\",\"user\":\"ann\"}\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("user=ann (seen 2x)"))
+        .stdout(predicate::str::contains("a (seen").not());
+}
+
+// ── pipeline ────────────────────────────────────────────────────────
+
+#[test]
+fn pipeline_runs_configured_stages() {
+    let mut config_file = tempfile::NamedTempFile::new().unwrap();
+    config_file
+        .write_all(b"pipeline = [\"redact\", \"infer_level\"]\n")
+        .unwrap();
+    cor()
+        .arg(format!("--config={}", config_file.path().display()))
+        .args(["--json"])
+        .write_stdin(r#"{"msg":"ERROR: login failed","password":"hunter2"}"#)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""password":"[REDACTED]""#))
+        .stdout(predicate::str::contains(r#""level":"error""#))
+        .stdout(predicate::str::contains("hunter2").not());
+}
+
+#[test]
+fn pipeline_rejects_unknown_stage() {
+    let mut config_file = tempfile::NamedTempFile::new().unwrap();
+    config_file.write_all(b"pipeline = [\"redcat\"]\n").unwrap();
+    cor()
+        .arg(format!("--config={}", config_file.path().display()))
+        .write_stdin("{}")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown variant `redcat`"));
+}
+
+// ── --explain ───────────────────────────────────────────────────────
+
+#[test]
+fn explain_describes_first_entries_on_stderr() {
+    cor()
+        .args(["--explain=1", "--level=warn", "--color=never"])
+        .write_stdin(
+            "{\"severity\":\"info\",\"event\":\"started\"}\n{\"severity\":\"info\",\"event\":\"again\"}\n",
+        )
+        .assert()
+        .success()
+        .stdout("")
+        .stderr(predicate::str::contains("cor: explain line 1: JSON object"))
+        .stderr(predicate::str::contains("level=severity message=event"))
+        .stderr(predicate::str::contains("dropped: level info is below warn"))
+        .stderr(predicate::str::contains("line 2").not());
+}
@@ -212,3 +212,129 @@ message = "event"
         .success()
         .stdout(predicate::str::contains("from body"));
 }
+
+#[test]
+fn message_key_fallback_list_for_mixed_streams() {
+    let input = r#"{"level":"info","event":"from service a"}
+{"level":"info","description":"from service b"}"#;
+    cor()
+        .arg("--color=never")
+        .arg("--message-key=event,description")
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("INFO: from service a"))
+        .stdout(predicate::str::contains("INFO: from service b"));
+}
+
+#[test]
+fn message_fields_compose_message() {
+    let input = r#"{"level":"error","message":"upload failed","error_detail":"quota exceeded","bucket":"b1"}"#;
+    let output = cor()
+        .arg("--color=never")
+        .arg("--message-fields=message,error_detail")
+        .write_stdin(input)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("ERROR: upload failed: quota exceeded"),
+        "Got: {stdout}"
+    );
+    assert!(
+        !stdout.contains("error_detail"),
+        "consumed fields leave extras.\nGot: {stdout}"
+    );
+    assert!(stdout.contains("bucket: b1"));
+}
+
+#[test]
+fn keep_extracted_shows_detected_keys_as_extras() {
+    let input = r#"{"level":"info","msg":"hello","port":8080}"#;
+    cor()
+        .arg("--color=never")
+        .arg("--keep-extracted")
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("INFO: hello"))
+        .stdout(predicate::str::contains("level: info"))
+        .stdout(predicate::str::contains("msg: hello"));
+}
+
+#[test]
+fn per_level_field_rules_from_config() {
+    let mut config_file = tempfile::NamedTempFile::new().unwrap();
+    config_file
+        .write_all(
+            br#"
+[fields]
+exclude = ["payload"]
+
+[fields.error]
+include = ["payload"]
+"#,
+        )
+        .unwrap();
+    let input = r#"{"level":"info","msg":"ok","payload":"info-body","port":1}
+{"level":"error","msg":"boom","payload":"error-body","port":2}"#;
+    let output = cor()
+        .arg("--color=never")
+        .arg(format!("--config={}", config_file.path().display()))
+        .write_stdin(input)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!stdout.contains("info-body"), "Got: {stdout}");
+    assert!(stdout.contains("port: 1"));
+    assert!(stdout.contains("payload: error-body"));
+    assert!(!stdout.contains("port: 2"), "Got: {stdout}");
+}
+
+#[test]
+fn rules_hide_tag_and_route_to_stderr() {
+    let mut config_file = tempfile::NamedTempFile::new().unwrap();
+    config_file
+        .write_all(
+            br#"
+[[rules]]
+match = 'path == "/healthz"'
+hide = true
+
+[[rules]]
+match = 'status >= 500'
+tag = "5XX"
+stderr = true
+"#,
+        )
+        .unwrap();
+    let input = r#"{"level":"info","msg":"probe","path":"/healthz"}
+{"level":"info","msg":"served","path":"/api","status":200}
+{"level":"error","msg":"crashed","path":"/api","status":503}"#;
+    let output = cor()
+        .arg("--color=never")
+        .arg(format!("--config={}", config_file.path().display()))
+        .write_stdin(input)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!stdout.contains("probe") && !stderr.contains("probe"));
+    assert!(stdout.contains("served"));
+    assert!(!stdout.contains("crashed"), "Got: {stdout}");
+    assert!(stderr.contains("ERROR: [5XX] crashed"), "Got: {stderr}");
+}
+
+#[test]
+fn rules_invalid_expression_exits_one() {
+    let mut config_file = tempfile::NamedTempFile::new().unwrap();
+    config_file
+        .write_all(b"[[rules]]\nmatch = 'level >= loud'\n")
+        .unwrap();
+    cor()
+        .arg(format!("--config={}", config_file.path().display()))
+        .write_stdin("")
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("rules[0]"));
+}
@@ -10,9 +10,13 @@ mod streaming;
 
 use assert_cmd::Command;
 
-/// Shared helper: build a `cor` command with config isolation.
+/// Shared helper: build a `cor` command with config and locale isolation.
 pub fn cor() -> Command {
     let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("cor"));
     cmd.env("XDG_CONFIG_HOME", "/tmp/cor-test-no-config");
+    // A non-UTF-8 locale switches to ASCII markers.
+    cmd.env("LC_ALL", "C.UTF-8");
+    // Theme colors are downgraded on terminals without truecolor.
+    cmd.env("COLORTERM", "truecolor");
     cmd
 }
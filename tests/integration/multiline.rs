@@ -1,5 +1,7 @@
 //! Integration tests for multi-line JSON reassembly edge cases.
 
+use std::fmt::Write;
+
 use super::cor;
 
 #[test]
@@ -119,3 +121,146 @@ fn successful_reassembly_after_two_lines() {
         "Subsequent line should also be formatted.\nGot: {stdout}"
     );
 }
+
+/// Input where a garbage line exhausts the continuation buffer, followed by
+/// a genuinely split JSON record of the same shape.
+fn garbage_then_split_record() -> String {
+    let mut input = String::new();
+    for i in 0..=200 {
+        writeln!(input, "junk {{\"msg\":\"xxxxxxxxxxxxxxxx garbage {i}").unwrap();
+    }
+    input.push_str("junk {\"msg\":\"xxxxxxxxxxxxxxxx first half\n");
+    input.push_str("second half\"}\n");
+    input
+}
+
+#[test]
+fn failed_shape_cache_skips_reassembly_for_same_shape() {
+    let output = cor()
+        .arg("--color=never")
+        .write_stdin(garbage_then_split_record())
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("junk {\"msg\":\"xxxxxxxxxxxxxxxx first half\n"),
+        "line with a recently failed shape should pass through raw.\nGot: {stdout}"
+    );
+    assert!(
+        stdout.contains("second half\"}"),
+        "following line should pass through raw.\nGot: {stdout}"
+    );
+}
+
+#[test]
+fn no_reassembly_cache_always_reassembles() {
+    let output = cor()
+        .arg("--color=never")
+        .arg("--no-reassembly-cache")
+        .write_stdin(garbage_then_split_record())
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("first half\nsecond half"),
+        "split record should be reassembled when the cache is disabled.\nGot: {stdout}"
+    );
+    assert!(
+        !stdout.contains("second half\"}"),
+        "reassembled record should not leak raw JSON.\nGot: {stdout}"
+    );
+}
+
+#[test]
+fn record_over_the_continuation_limit_does_not_disable_reassembly() {
+    // A traceback longer than the limit fails reassembly without being
+    // malformed, so the next split record of the same shape still parses.
+    let mut input = String::from("{\"level\":\"error\",\"msg\":\"first\n");
+    for i in 0..250 {
+        writeln!(input, "  at frame {i}").unwrap();
+    }
+    input.push_str("\"}\n{\"level\":\"error\",\"msg\":\"second\n  at foo\n  at bar\"}\n");
+
+    let output = cor()
+        .arg("--color=never")
+        .write_stdin(input)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("second\n  at foo\n  at bar"),
+        "record after an over-limit one should be reassembled.\nGot: {stdout}"
+    );
+    assert!(
+        !stdout.contains("at bar\"}"),
+        "reassembled record should not leak raw JSON.\nGot: {stdout}"
+    );
+}
+
+#[test]
+fn max_continuation_lines_limits_reassembly() {
+    let input = "{\"level\":\"info\",\"msg\":\"a\nb\nc\nd\"}";
+
+    let output = cor()
+        .arg("--color=never")
+        .arg("--max-continuation-lines=2")
+        .write_stdin(input)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        !stdout.contains("INFO"),
+        "record spanning more lines than the limit should not be reassembled.\nGot: {stdout}"
+    );
+    assert!(
+        stdout.contains("d\"}"),
+        "lines should pass through raw.\nGot: {stdout}"
+    );
+}
+
+#[test]
+fn max_continuation_bytes_limits_reassembly() {
+    let filler = "x".repeat(64);
+    let input = format!("{{\"level\":\"info\",\"msg\":\"{filler}\n{filler}\n{filler}\"}}");
+
+    let output = cor()
+        .arg("--color=never")
+        .arg("--max-continuation-bytes=100")
+        .write_stdin(input.clone())
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        !stdout.contains("INFO"),
+        "record larger than the byte cap should not be reassembled.\nGot: {stdout}"
+    );
+
+    // Without the cap, the same input reassembles.
+    let output = cor()
+        .arg("--color=never")
+        .write_stdin(input)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("INFO"),
+        "expected reassembly.\nGot: {stdout}"
+    );
+}
+
+#[test]
+fn continuation_idle_still_reassembles_prompt_input() {
+    let input = "{\"level\":\"error\",\"msg\":\"boom\",\"trace\":\"line1\nline2\"}";
+
+    let output = cor()
+        .arg("--color=never")
+        .arg("--continuation-idle=2s")
+        .write_stdin(input)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("ERROR") && stdout.contains("boom"),
+        "lines arriving within the idle window should be reassembled.\nGot: {stdout}"
+    );
+}
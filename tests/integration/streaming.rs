@@ -187,3 +187,381 @@ fn raw_passthrough_line_flushed_before_stdin_closes() {
         "expected raw passthrough, got: {line:?}"
     );
 }
+
+#[test]
+fn incomplete_json_released_after_continuation_timeout() {
+    // An unterminated JSON object would otherwise hold the reassembly
+    // buffer open until more input (or EOF) arrives.
+    let input = r#"{"level":"info","msg":"never closes"#;
+    let output = output_within(
+        input,
+        &["--color=never", "--continuation-timeout=100ms"],
+        input.len(),
+        Duration::from_secs(5),
+    )
+    .expect("cor held back an incomplete JSON line past the continuation timeout");
+
+    assert!(
+        output.contains(input),
+        "expected the buffered line as raw output, got: {output:?}"
+    );
+}
+
+#[test]
+fn held_continuation_lines_flushed_when_input_goes_idle() {
+    // The opening line of a traceback arrives, then the producer stalls.
+    // With an idle window, the held line is flushed as raw text instead
+    // of appearing to be swallowed.
+    let input = "{\"level\":\"error\",\"msg\":\"boom\",\"trace\":\"line1";
+    let output = output_within(
+        input,
+        &["--color=never", "--continuation-idle=100ms"],
+        input.len(),
+        Duration::from_secs(5),
+    )
+    .expect("cor held back continuation lines after input went idle");
+
+    assert!(
+        output.contains(input),
+        "expected the held line as raw output, got: {output:?}"
+    );
+}
+
+/// Wait up to `timeout` for `child` to exit, killing it otherwise.
+fn wait_within(child: &mut std::process::Child, timeout: Duration) -> Option<i32> {
+    let deadline = std::time::Instant::now() + timeout;
+    while std::time::Instant::now() < deadline {
+        if let Some(status) = child.try_wait().expect("poll cor") {
+            return status.code();
+        }
+        thread::sleep(Duration::from_millis(20));
+    }
+    let _ = child.kill();
+    None
+}
+
+#[test]
+fn timeout_exits_when_producer_hangs() {
+    let mut child = spawn_cor(&["--color=never", "--timeout=200ms"]);
+    write_input(&mut child, r#"{"level":"info","msg":"last words"}"#);
+
+    // stdin stays open: only the timeout can end the run.
+    let code = wait_within(&mut child, Duration::from_secs(5));
+    assert_eq!(code, Some(124), "cor should exit with the timeout code");
+
+    let mut stdout = String::new();
+    child
+        .stdout
+        .take()
+        .unwrap()
+        .read_to_string(&mut stdout)
+        .unwrap();
+    assert!(stdout.contains("last words"), "got: {stdout:?}");
+    let mut stderr = String::new();
+    child
+        .stderr
+        .take()
+        .unwrap()
+        .read_to_string(&mut stderr)
+        .unwrap();
+    assert!(stderr.contains("no input received"), "got: {stderr:?}");
+}
+
+#[test]
+fn timeout_exit_code_is_configurable() {
+    let mut child = spawn_cor(&["--timeout=100ms", "--timeout-exit-code=3"]);
+    let code = wait_within(&mut child, Duration::from_secs(5));
+    drop(child.stdin.take());
+    assert_eq!(code, Some(3));
+}
+
+#[test]
+fn exit_on_eof_false_keeps_waiting() {
+    let mut child = spawn_cor(&["--color=never", "--exit-on-eof=false", "--timeout=500ms"]);
+    write_input(&mut child, r#"{"level":"info","msg":"before eof"}"#);
+    drop(child.stdin.take());
+
+    // Closing stdin no longer ends the run; the timeout does.
+    let code = wait_within(&mut child, Duration::from_secs(5));
+    assert_eq!(code, Some(124));
+}
+
+#[test]
+fn exit_on_eof_false_waits_on_last_input_only() {
+    let dir = tempfile::tempdir().unwrap();
+    let first = dir.path().join("first.log");
+    std::fs::write(&first, "{\"level\":\"info\",\"msg\":\"from file\"}\n").unwrap();
+    let first = first.display().to_string();
+    let mut child = spawn_cor(&[
+        "--color=never",
+        "--exit-on-eof=false",
+        "--timeout=500ms",
+        &first,
+        "-",
+    ]);
+    write_input(&mut child, r#"{"level":"info","msg":"from stdin"}"#);
+    drop(child.stdin.take());
+
+    let code = wait_within(&mut child, Duration::from_secs(5));
+    assert_eq!(code, Some(124));
+    let mut stdout = String::new();
+    child
+        .stdout
+        .take()
+        .unwrap()
+        .read_to_string(&mut stdout)
+        .unwrap();
+    assert!(
+        stdout.contains("from file") && stdout.contains("from stdin"),
+        "got: {stdout:?}"
+    );
+}
+
+#[cfg(feature = "net")]
+#[test]
+fn connect_reconnects_and_labels_lines() {
+    use std::net::TcpListener;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    thread::spawn(move || {
+        // Two short-lived connections: cor must reconnect to see the second.
+        for msg in ["first session", "second session"] {
+            let (mut conn, _) = listener.accept().unwrap();
+            writeln!(conn, r#"{{"level":"info","msg":"{msg}"}}"#).unwrap();
+        }
+    });
+
+    let endpoint = format!("--connect=api=tcp://{addr}");
+    let mut child = spawn_cor(&["--color=never", &endpoint, "--timeout=2s"]);
+    let code = wait_within(&mut child, Duration::from_secs(10));
+    assert_eq!(code, Some(124));
+
+    let mut stdout = String::new();
+    child
+        .stdout
+        .take()
+        .unwrap()
+        .read_to_string(&mut stdout)
+        .unwrap();
+    assert!(stdout.contains("[api]"), "got: {stdout:?}");
+    assert!(stdout.contains("first session"), "got: {stdout:?}");
+    assert!(stdout.contains("second session"), "got: {stdout:?}");
+}
+
+#[cfg(feature = "net")]
+#[test]
+fn connect_reassembles_per_connection() {
+    use std::net::TcpListener;
+
+    let a = TcpListener::bind("127.0.0.1:0").unwrap();
+    let b = TcpListener::bind("127.0.0.1:0").unwrap();
+    let endpoints = [
+        format!("--connect=a=tcp://{}", a.local_addr().unwrap()),
+        format!("--connect=b=tcp://{}", b.local_addr().unwrap()),
+    ];
+    thread::spawn(move || {
+        let (mut a, _) = a.accept().unwrap();
+        let (mut b, _) = b.accept().unwrap();
+        let pause = || thread::sleep(Duration::from_millis(200));
+        // A record split across lines on `a`, with a line from `b` and an
+        // invalid line on `a` arriving in between.
+        a.write_all(b"{\"level\":\"error\",\"msg\":\"first\n")
+            .unwrap();
+        pause();
+        writeln!(b, r#"{{"level":"info","msg":"from b"}}"#).unwrap();
+        pause();
+        a.write_all(b"\xff\xfe\nsecond\"}\n").unwrap();
+        writeln!(a, r#"{{"level":"info","msg":"after invalid"}}"#).unwrap();
+        pause();
+        pause();
+    });
+
+    let mut child = spawn_cor(&[
+        "--color=never",
+        &endpoints[0],
+        &endpoints[1],
+        "--timeout=2s",
+    ]);
+    assert_eq!(wait_within(&mut child, Duration::from_secs(10)), Some(124));
+    let mut stdout = String::new();
+    child
+        .stdout
+        .take()
+        .unwrap()
+        .read_to_string(&mut stdout)
+        .unwrap();
+    assert!(stdout.contains("[b]  INFO: from b"), "got: {stdout:?}");
+    assert!(stdout.contains("[a] ERROR: first"), "got: {stdout:?}");
+    assert!(stdout.contains("second"), "got: {stdout:?}");
+    assert!(!stdout.contains("\"level\""), "got: {stdout:?}");
+    assert!(
+        stdout.contains("[a]  INFO: after invalid"),
+        "got: {stdout:?}"
+    );
+}
+
+#[cfg(feature = "net")]
+#[test]
+fn connect_rejects_invalid_endpoint() {
+    super::cor()
+        .arg("--connect=localhost:5170")
+        .assert()
+        .code(2)
+        .stderr(predicates::str::contains("tcp://HOST:PORT"));
+}
+
+#[cfg(all(feature = "net", not(feature = "tls")))]
+#[test]
+fn connect_tls_requires_feature() {
+    super::cor()
+        .arg("--connect=tls://localhost:6514")
+        .assert()
+        .code(2)
+        .stderr(predicates::str::contains("`tls` feature"));
+}
+
+#[cfg(all(feature = "net", not(feature = "tls")))]
+#[test]
+fn listen_https_requires_feature() {
+    super::cor()
+        .arg("--listen=https://127.0.0.1:0/ingest")
+        .assert()
+        .code(2)
+        .stderr(predicates::str::contains("`tls` feature"));
+}
+
+#[cfg(feature = "tls")]
+#[test]
+fn listen_https_requires_certificate() {
+    super::cor()
+        .arg("--listen=https://127.0.0.1:0/ingest")
+        .assert()
+        .code(1)
+        .stderr(predicates::str::contains("--tls-cert and --tls-key"));
+}
+
+#[cfg(feature = "tls")]
+#[test]
+fn connect_tls_reports_bad_ca_file() {
+    super::cor()
+        .args([
+            "--connect=tls://localhost:6514",
+            "--tls-ca=/nonexistent/ca.pem",
+        ])
+        .assert()
+        .code(1)
+        .stderr(predicates::str::contains("ca.pem"));
+}
+
+#[cfg(feature = "net")]
+#[test]
+fn listen_ingests_posted_ndjson() {
+    let mut child = spawn_cor(&[
+        "--color=never",
+        "--listen=http://127.0.0.1:0/ingest",
+        "--timeout=1s",
+    ]);
+    let mut stderr = BufReader::new(child.stderr.take().unwrap());
+    let mut banner = String::new();
+    stderr.read_line(&mut banner).unwrap();
+    let addr = banner
+        .trim()
+        .strip_prefix("cor: listening on http://")
+        .and_then(|rest| rest.strip_suffix("/ingest"))
+        .unwrap_or_else(|| panic!("unexpected banner: {banner:?}"))
+        .to_string();
+
+    // A chunked upload, as sent by `curl -T -` or a streaming client.
+    // Control lines from network clients are shown as text, not banners.
+    let body = "{\"level\":\"warn\",\"msg\":\"from lambda\"}\n##cor:mark injected\n";
+    let mut conn = std::net::TcpStream::connect(&addr).unwrap();
+    write!(
+        conn,
+        "POST /ingest HTTP/1.1\r\nHost: {addr}\r\nTransfer-Encoding: chunked\r\n\r\n{:x}\r\n{body}\r\n0\r\n\r\n",
+        body.len()
+    )
+    .unwrap();
+    let mut response = String::new();
+    conn.read_to_string(&mut response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 202"), "got: {response:?}");
+
+    assert_eq!(wait_within(&mut child, Duration::from_secs(10)), Some(124));
+    let mut stdout = String::new();
+    child
+        .stdout
+        .take()
+        .unwrap()
+        .read_to_string(&mut stdout)
+        .unwrap();
+    assert!(
+        stdout.contains("WARN") && stdout.contains("from lambda"),
+        "got: {stdout:?}"
+    );
+    assert!(stdout.contains("##cor:mark injected"), "got: {stdout:?}");
+}
+
+#[cfg(unix)]
+#[test]
+fn sigint_flushes_sinks_before_exiting() {
+    let dir = tempfile::tempdir().unwrap();
+    let copy = dir.path().join("copy.log");
+    let mut child = spawn_cor(&[
+        "--color=never",
+        &format!("--output=file:{}", copy.display()),
+    ]);
+    write_input(&mut child, r#"{"level":"info","msg":"kept"}"#);
+    // Once the line is on stdout it has been handed to the buffered sink.
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+    let mut line = String::new();
+    stdout.read_line(&mut line).unwrap();
+    assert!(line.contains("kept"), "got: {line:?}");
+
+    // stdin stays open: only the signal ends the run.
+    let status = Command::new("kill")
+        .args(["-INT", &child.id().to_string()])
+        .status()
+        .unwrap();
+    assert!(status.success());
+    assert_eq!(wait_within(&mut child, Duration::from_secs(5)), Some(130));
+    let copied = std::fs::read_to_string(&copy).unwrap();
+    assert!(copied.contains("kept"), "got: {copied:?}");
+}
+
+#[cfg(unix)]
+#[test]
+fn sigint_ends_waiting_at_end_of_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let log = dir.path().join("app.log");
+    std::fs::write(&log, "{\"level\":\"info\",\"msg\":\"kept\"}\n").unwrap();
+    let mut child = spawn_cor(&[
+        "--color=never",
+        "--exit-on-eof=false",
+        "--jobs=2",
+        log.to_str().unwrap(),
+    ]);
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+    let mut line = String::new();
+    stdout.read_line(&mut line).unwrap();
+    assert!(line.contains("kept"), "got: {line:?}");
+
+    let status = Command::new("kill")
+        .args(["-INT", &child.id().to_string()])
+        .status()
+        .unwrap();
+    assert!(status.success());
+    assert_eq!(wait_within(&mut child, Duration::from_secs(5)), Some(130));
+}
+
+#[test]
+fn throttle_marker_shown_once_the_window_runs_out() {
+    // stdin stays open: the marker must not wait for the next entry.
+    let expected = "a\nb\n… 3 lines suppressed\n";
+    let output = output_within(
+        "a\nb\nc\nd\ne",
+        &["--throttle=2/s", "--line-gap=0"],
+        expected.len(),
+        Duration::from_secs(5),
+    );
+    assert_eq!(output.as_deref(), Some(expected));
+}